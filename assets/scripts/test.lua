@@ -1,9 +1,24 @@
 --
--- Created by IntelliJ IDEA.
--- User: Aaron
--- Date: 12/19/2016
--- Time: 4:08 PM
--- To change this template use File | Settings | File Templates.
+-- Example script. Attach it to an entity with `Script::new("scripts/test.lua")`.
 --
 
-print "Hello, World!"
\ No newline at end of file
+-- `state` is kept when the script is reloaded
+state.elapsed = state.elapsed or 0
+
+function init()
+    print("Started on " .. tostring(entity))
+end
+
+events.on("input", function(event)
+    if event.type == "key" and event.action == "press" then
+        print("Key pressed: " .. event.key)
+    end
+end)
+
+function update(dt)
+    state.elapsed = state.elapsed + dt
+end
+
+function destroy()
+    print(tostring(entity) .. " ran for " .. state.elapsed .. " seconds")
+end
@@ -0,0 +1,58 @@
+//! Material asset implementation
+
+use std::ops::{Deref, DerefMut};
+
+use protocols::traits::Storage;
+use protocols::material::protocol;
+use protocols::material::{MaterialMap, EXTENSION};
+
+use ::error::AssetResult;
+use ::asset::{Asset, AssetMedium};
+use ::assets::native::generic::{NativeAssetQuery, NativeAssetSaveArgs, query_native, load_native, save_native};
+
+/// Material Asset
+#[derive(Serialize, Deserialize)]
+pub struct MaterialAsset(MaterialMap);
+
+impl<'a> Asset<'a> for MaterialAsset {
+    type LoadArgs = ();
+    type SaveArgs = NativeAssetSaveArgs;
+
+    type Query = NativeAssetQuery<'a>;
+
+    fn query(query: NativeAssetQuery<'a>) -> AssetResult<bool> {
+        query_native(query, EXTENSION)
+    }
+
+    fn load(medium: AssetMedium<'a>, _: ()) -> AssetResult<MaterialAsset> {
+        load_native(medium, EXTENSION, |message| {
+            let material_reader = try_throw!(message.get_root::<protocol::material_map::Reader>());
+
+            Ok(MaterialAsset(try_rethrow!(MaterialMap::load_from_reader(material_reader))))
+        })
+    }
+
+    fn save(&self, medium: AssetMedium<'a>, args: NativeAssetSaveArgs) -> AssetResult<()> {
+        save_native(self, medium, EXTENSION, args, |message| {
+            let material_builder = message.init_root::<protocol::material_map::Builder>();
+
+            try_rethrow!(self.0.save_to_builder(material_builder));
+
+            Ok(())
+        })
+    }
+}
+
+impl Deref for MaterialAsset {
+    type Target = MaterialMap;
+
+    fn deref(&self) -> &MaterialMap {
+        &self.0
+    }
+}
+
+impl DerefMut for MaterialAsset {
+    fn deref_mut(&mut self) -> &mut MaterialMap {
+        &mut self.0
+    }
+}
\ No newline at end of file
@@ -0,0 +1,7 @@
+//! Material asset
+
+pub mod asset;
+
+pub use self::asset::MaterialAsset;
+pub use ::assets::native::formats::NativeFileFormat as MaterialFileFormat;
+pub use ::assets::native::generic::{NativeAssetQuery as MaterialAssetQuery, NativeAssetSaveArgs as MaterialAssetSaveArgs};
\ No newline at end of file
@@ -1,8 +1,12 @@
 //! All assets which can be loaded and saved
 
 pub mod standard;
+pub mod native;
 pub mod texture;
 pub mod model;
+pub mod scene;
+pub mod prefab;
+pub mod material;
 
 /// TODO
 pub enum GenericAsset {
@@ -10,4 +14,10 @@ pub enum GenericAsset {
     Texture(texture::TextureAsset),
     /// Model asset
     Model(model::ModelAsset),
+    /// Scene asset
+    Scene(scene::SceneAsset),
+    /// Prefab asset
+    Prefab(prefab::PrefabAsset),
+    /// Material asset
+    Material(material::MaterialAsset),
 }
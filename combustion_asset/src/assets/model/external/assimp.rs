@@ -1,13 +1,18 @@
 //! Routines for converting Assimp structures to Combustion structures
 
-use nalgebra::Vector3;
+use std::collections::HashMap;
+
+use nalgebra::{Vector3, Quaternion, Matrix4, Eye};
 
 use assimp::{self, Named};
 
-use protocols::math::data::Transform;
+use common::traits::DefaultName;
+
+use protocols::math::data::{Transform, decompose_trs, multiply_quaternions};
 use protocols::mesh::protocol::MeshPrimitive;
 use protocols::mesh::data::{Mesh, MeshVertices, Vertices, TexCoord};
-use protocols::model::data::{Model, Node};
+use protocols::model::data::{Model, Node, Joint, Skeleton, VertexWeights, Skin, Interpolation,
+                             Keyframe, ChannelKeyframes, Channel, AnimationClip};
 
 use ::error::{AssetResult, AssetError};
 
@@ -17,19 +22,221 @@ pub fn scene_to_model(scene: assimp::Scene) -> AssetResult<Model> {
 
     let mut meshes = Vec::new();
 
+    // Inverse bind matrices of every bone referenced by any mesh, by name
+    let mut bones = HashMap::new();
+
     for raw_mesh in raw_meshes {
+        if let Some(raw_bones) = raw_mesh.bones() {
+            for bone in raw_bones {
+                bones.insert(bone.name().to_string(), Matrix4::from(bone.offset_matrix().clone()));
+            }
+        }
+
         meshes.push(assimp_mesh_to_mesh(raw_mesh)?);
     }
 
     let root = try_rethrow!(assimp_node_to_node(scene.root()));
 
-    Ok(Model {
+    let mut skeletons = Vec::new();
+    let mut skins = Vec::new();
+    let mut animations = Vec::new();
+
+    if !bones.is_empty() {
+        // Assimp has no concept of skeletons, so all bones of the scene are gathered into a single one
+        let (skeleton, offsets) = assimp_bones_to_skeleton(scene.root(), &bones);
+
+        if let Some(raw_meshes) = scene.meshes() {
+            for (i, raw_mesh) in raw_meshes.enumerate() {
+                if let Some(skin) = assimp_mesh_to_skin(raw_mesh, i as u32, &skeleton) {
+                    skins.push(skin);
+                }
+            }
+        }
+
+        if let Some(raw_animations) = scene.animations() {
+            for raw_animation in raw_animations {
+                animations.push(assimp_animation_to_clip(raw_animation, &skeleton, &offsets));
+            }
+        }
+
+        skeletons.push(skeleton);
+    }
+
+    let mut model = Model {
         meshes: meshes,
         root: root,
         materials: Vec::new(),
+        lods: Vec::new(),
+        skeletons: skeletons,
+        skins: skins,
+        animations: animations,
+    };
+
+    model.update_bounds();
+
+    Ok(model)
+}
+
+/// Gather every bone into a skeleton.
+///
+/// Also returns the combined transform of the skipped nodes above each joint, which has been folded into the
+/// joint's rest pose and also has to be applied to its animation keys.
+fn assimp_bones_to_skeleton(root: assimp::Node, bones: &HashMap<String, Matrix4<f32>>) -> (Skeleton, Vec<Matrix4<f32>>) {
+    fn collect_joints(node: assimp::Node,
+                      parent: Option<u32>,
+                      offset: Matrix4<f32>,
+                      bones: &HashMap<String, Matrix4<f32>>,
+                      joints: &mut Vec<Joint>,
+                      offsets: &mut Vec<Matrix4<f32>>) {
+        let name = node.name().to_string();
+
+        let transformation: Matrix4<f32> = node.transformation().clone().into();
+
+        // Nodes which aren't bones are skipped, and their children attached to the nearest bone above them.
+        // The transforms of skipped nodes are carried down into the local transforms of those children.
+        let (parent, offset) = if let Some(inverse_bind) = bones.get(&name) {
+            let (translation, rotation, scale) = decompose_trs(&(offset * transformation));
+
+            joints.push(Joint {
+                name: name,
+                parent: parent,
+                inverse_bind: *inverse_bind,
+                translation: translation,
+                rotation: rotation,
+                scale: scale,
+            });
+
+            offsets.push(offset);
+
+            (Some(joints.len() as u32 - 1), Matrix4::new_identity(4))
+        } else {
+            (parent, offset * transformation)
+        };
+
+        if let Some(children) = node.children() {
+            for child_node in children {
+                collect_joints(child_node, parent, offset, bones, joints, offsets);
+            }
+        }
+    }
+
+    let mut joints = Vec::with_capacity(bones.len());
+    let mut offsets = Vec::with_capacity(bones.len());
+
+    collect_joints(root, None, Matrix4::new_identity(4), bones, &mut joints, &mut offsets);
+
+    (Skeleton {
+        name: Skeleton::default_name(),
+        joints: joints,
+    }, offsets)
+}
+
+fn assimp_mesh_to_skin(mesh: assimp::Mesh, index: u32, skeleton: &Skeleton) -> Option<Skin> {
+    let raw_bones = match mesh.bones() {
+        Some(raw_bones) => raw_bones,
+        None => return None,
+    };
+
+    let num_vertices = mesh.vertices().map_or(0, |vertices| vertices.len());
+
+    let mut weights = vec![VertexWeights::default(); num_vertices];
+
+    for bone in raw_bones {
+        if let Some(joint) = skeleton.find_joint(bone.name()) {
+            for weight in bone.weights() {
+                if let Some(vertex_weights) = weights.get_mut(weight.vertex_id as usize) {
+                    vertex_weights.joints.push(joint);
+                    vertex_weights.weights.push(weight.weight);
+                }
+            }
+        }
+    }
+
+    for vertex_weights in &mut weights {
+        vertex_weights.limit(4);
+    }
+
+    Some(Skin {
+        mesh: index,
+        skeleton: 0,
+        weights: weights,
     })
 }
 
+/// Convert an Assimp animation into a clip for the skeleton created by `assimp_bones_to_skeleton`.
+///
+/// Assimp doesn't expose how keys are meant to be interpolated, so every channel is linearly interpolated,
+/// which is what Assimp itself assumes.
+///
+/// Keys of joints below skipped nodes have the skipped transforms applied, the same way as their rest pose.
+/// Translation and rotation stay exact, but since each property is animated separately, scale is only exact
+/// when the skipped nodes scale uniformly.
+fn assimp_animation_to_clip(animation: assimp::Animation, skeleton: &Skeleton, offsets: &[Matrix4<f32>]) -> AnimationClip {
+    // Assimp keyframe times are given in ticks, where zero ticks per second means unspecified
+    let ticks_per_second = match animation.ticks_per_second() {
+        tps if tps > 0.0 => tps,
+        _ => 25.0,
+    };
+
+    let to_seconds = |ticks: f64| (ticks / ticks_per_second) as f32;
+
+    let mut channels = Vec::new();
+
+    if let Some(raw_channels) = animation.channels() {
+        for raw_channel in raw_channels {
+            let joint = match skeleton.find_joint(raw_channel.node_name()) {
+                Some(joint) => joint,
+                None => continue,
+            };
+
+            let offset = offsets[joint as usize];
+
+            let (_, offset_rotation, offset_scale) = decompose_trs(&offset);
+
+            let translation = raw_channel.position_keys().iter().map(|key| {
+                let t = Vector3::from(key.value);
+
+                Keyframe::new(to_seconds(key.time), Vector3::new(
+                    offset.m11 * t.x + offset.m12 * t.y + offset.m13 * t.z + offset.m14,
+                    offset.m21 * t.x + offset.m22 * t.y + offset.m23 * t.z + offset.m24,
+                    offset.m31 * t.x + offset.m32 * t.y + offset.m33 * t.z + offset.m34,
+                ))
+            }).collect();
+
+            let rotation = raw_channel.rotation_keys().iter().map(|key| {
+                let q = Quaternion::new(key.value.w, key.value.x, key.value.y, key.value.z);
+
+                Keyframe::new(to_seconds(key.time), multiply_quaternions(&offset_rotation, &q))
+            }).collect();
+
+            let scale = raw_channel.scaling_keys().iter().map(|key| {
+                let s = Vector3::from(key.value);
+
+                Keyframe::new(to_seconds(key.time), Vector3::new(s.x * offset_scale.x, s.y * offset_scale.y, s.z * offset_scale.z))
+            }).collect();
+
+            for keyframes in vec![ChannelKeyframes::Translation(translation),
+                                  ChannelKeyframes::Rotation(rotation),
+                                  ChannelKeyframes::Scale(scale)] {
+                if !keyframes.is_empty() {
+                    channels.push(Channel {
+                        skeleton: 0,
+                        joint: joint,
+                        interpolation: Interpolation::Linear,
+                        keyframes: keyframes,
+                    });
+                }
+            }
+        }
+    }
+
+    AnimationClip {
+        name: animation.name().to_string(),
+        duration: to_seconds(animation.duration()),
+        channels: channels,
+    }
+}
+
 fn assimp_mesh_to_mesh(mesh: assimp::Mesh) -> AssetResult<Mesh> {
     let vertices = MeshVertices::Discrete({
         let raw_positions = try_throw!(mesh.vertices().ok_or(AssetError::UnsupportedFormat));
@@ -54,6 +261,9 @@ fn assimp_mesh_to_mesh(mesh: assimp::Mesh) -> AssetResult<Mesh> {
         indices: indices,
         materials: Vec::new(),
         primitive: MeshPrimitive::Triangles,
+        morph_targets: Vec::new(),
+        morph_weights: Vec::new(),
+        bounds: None,
     })
 }
 
@@ -77,5 +287,6 @@ fn assimp_node_to_node(node: assimp::Node) -> AssetResult<Node> {
         // Create a single-element Vec with the converted node transform
         transforms: vec![Transform::Matrix(node.transformation().clone().into())],
         children: children,
+        bounds: None,
     })
 }
\ No newline at end of file
@@ -0,0 +1,44 @@
+//! File formats for native assets
+
+use ::asset::AssetFileFormat;
+use ::assets::standard::formats::StandardFileFormat;
+
+/// Supported file formats
+#[derive(Debug, Clone, Copy, PartialEq, Hash, PartialOrd)]
+pub enum NativeFileFormat {
+    /// Native Combustion file format
+    Native,
+    /// Any standard file format
+    Standard(StandardFileFormat)
+}
+
+impl NativeFileFormat {
+    /// Determine file format from extension, where `native` is the extension of the native format.
+    ///
+    /// `None` is returned if no format exists for that file extension.
+    pub fn from_extension(ext: &str, native: &str) -> Option<NativeFileFormat> {
+        Some(if ext == native {
+            NativeFileFormat::Native
+        } else if let Some(standard_format) = StandardFileFormat::from_extension(ext) {
+            NativeFileFormat::Standard(standard_format)
+        } else {
+            return None;
+        })
+    }
+
+    /// Check if the format can be imported
+    pub fn can_import(&self) -> bool {
+        match *self {
+            NativeFileFormat::Standard(standard_format) => standard_format.can_import(),
+            _ => true,
+        }
+    }
+
+    /// Check if the format can be exported
+    pub fn can_export(&self) -> bool {
+        match *self {
+            NativeFileFormat::Standard(standard_format) => standard_format.can_export(),
+            _ => true,
+        }
+    }
+}
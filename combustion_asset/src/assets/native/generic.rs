@@ -0,0 +1,139 @@
+//! Load and Save routines for native assets
+
+use std::ascii::AsciiExt;
+use std::io::BufReader;
+
+use capnp::serialize_packed;
+use capnp::message::{self, ReaderOptions, HeapAllocator};
+use capnp::serialize::OwnedSegments;
+
+use serde::{Serialize, Deserialize};
+
+use ::error::{AssetResult, AssetError};
+use ::asset::{Asset, AssetMedium, AssetQuery};
+
+use super::formats::NativeFileFormat;
+
+/// Native Asset queries
+#[derive(Debug, Clone, Copy)]
+pub enum NativeAssetQuery<'a> {
+    /// Check if a file extension is supported for importing
+    SupportedImportExtension(&'a str),
+    /// Check if a file extension is supported for exporting
+    SupportedExportExtension(&'a str),
+    /// Check if a file extension is supported for both importing and exporting
+    SupportedExtension(&'a str),
+}
+
+impl<'a> AssetQuery for NativeAssetQuery<'a> {
+    type Arguments = NativeAssetQuery<'a>;
+    type Result = bool;
+}
+
+/// Arguments for native asset save routines
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NativeAssetSaveArgs {
+    /// For serialization formats that support "pretty-printing", pretty-print the data
+    pub pretty: bool,
+}
+
+/// Answer a query for an asset whose native format uses the `native` extension
+pub fn query_native(query: NativeAssetQuery, native: &str) -> AssetResult<bool> {
+    Ok(match query {
+        NativeAssetQuery::SupportedImportExtension(ext) => {
+            match NativeFileFormat::from_extension(ext, native) {
+                Some(format) if format.can_import() => true,
+                _ => false
+            }
+        },
+        NativeAssetQuery::SupportedExportExtension(ext) => {
+            match NativeFileFormat::from_extension(ext, native) {
+                Some(format) if format.can_export() => true,
+                _ => false
+            }
+        },
+        NativeAssetQuery::SupportedExtension(ext) => {
+            match NativeFileFormat::from_extension(ext, native) {
+                Some(format) if format.can_import() && format.can_export() => true,
+                _ => false
+            }
+        },
+    })
+}
+
+/// Load any `T: Asset` from its native format or a standard format, chosen by the file extension.
+///
+/// `load` reads the asset from the root of the native message.
+pub fn load_native<'a, T: 'a, F>(medium: AssetMedium<'a>, native: &str, load: F) -> AssetResult<T>
+    where T: Asset<'a> + Deserialize, F: FnOnce(&message::Reader<OwnedSegments>) -> AssetResult<T>
+{
+    if let AssetMedium::File(path, vfs) = medium {
+        if let Some(ext) = path.extension() {
+            let ext = try_throw!(ext.to_str().ok_or(AssetError::InvalidValue)).to_ascii_lowercase();
+
+            let format = match NativeFileFormat::from_extension(ext.as_str(), native) {
+                Some(format) if format.can_import() => format,
+                _ => throw!(AssetError::UnsupportedFormat),
+            };
+
+            match format {
+                NativeFileFormat::Native => {
+                    let mut reader = BufReader::new(try_throw!(vfs.open(path)));
+
+                    let message_reader = try_throw!(serialize_packed::read_message(&mut reader, ReaderOptions {
+                        traversal_limit_in_words: u64::max_value(),
+                        nesting_limit: 1024,
+                    }));
+
+                    return load(&message_reader);
+                },
+                NativeFileFormat::Standard(standard_format) => {
+                    let reader = BufReader::new(try_throw!(vfs.open(path)));
+
+                    return ::assets::standard::generic::load_standard_format(reader, standard_format);
+                },
+            }
+        }
+    }
+
+    throw!(AssetError::UnsupportedMedium)
+}
+
+/// Save any `T: Asset` to its native format or a standard format, chosen by the file extension.
+///
+/// `save` builds the root of the native message from the asset.
+pub fn save_native<'a, T: 'a, F>(asset: &T, medium: AssetMedium<'a>, native: &str, args: NativeAssetSaveArgs, save: F) -> AssetResult<()>
+    where T: Asset<'a> + Serialize, F: FnOnce(&mut message::Builder<HeapAllocator>) -> AssetResult<()>
+{
+    if let AssetMedium::File(path, vfs) = medium {
+        if let Some(ext) = path.extension() {
+            let ext = try_throw!(ext.to_str().ok_or(AssetError::InvalidValue)).to_ascii_lowercase();
+
+            let format = match NativeFileFormat::from_extension(ext.as_str(), native) {
+                Some(format) if format.can_export() => format,
+                _ => throw!(AssetError::UnsupportedFormat),
+            };
+
+            match format {
+                NativeFileFormat::Native => {
+                    let mut writer = try_throw!(vfs.create_or_truncate(path));
+
+                    let mut message = message::Builder::new_default();
+
+                    try!(save(&mut message));
+
+                    try_throw!(serialize_packed::write_message(&mut writer, &message));
+
+                    return Ok(());
+                },
+                NativeFileFormat::Standard(standard_format) => {
+                    let writer = try_throw!(vfs.create_or_truncate(path));
+
+                    return ::assets::standard::generic::save_standard_format(writer, standard_format, asset, args.pretty);
+                },
+            }
+        }
+    }
+
+    throw!(AssetError::UnsupportedMedium)
+}
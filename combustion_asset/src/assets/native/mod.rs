@@ -0,0 +1,7 @@
+//! Generic native assets
+//!
+//! Assets stored as a single Cap'n Proto message from `combustion_protocols`, or in any standard format.
+//! Each asset only provides its native extension and how to read and build its message root.
+
+pub mod formats;
+pub mod generic;
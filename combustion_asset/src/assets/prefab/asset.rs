@@ -0,0 +1,58 @@
+//! Prefab asset implementation
+
+use std::ops::{Deref, DerefMut};
+
+use protocols::traits::Storage;
+use protocols::scene::protocol;
+use protocols::scene::{Prefab, PREFAB_EXTENSION};
+
+use ::error::AssetResult;
+use ::asset::{Asset, AssetMedium};
+use ::assets::native::generic::{NativeAssetQuery, NativeAssetSaveArgs, query_native, load_native, save_native};
+
+/// Prefab Asset
+#[derive(Serialize, Deserialize)]
+pub struct PrefabAsset(Prefab);
+
+impl<'a> Asset<'a> for PrefabAsset {
+    type LoadArgs = ();
+    type SaveArgs = NativeAssetSaveArgs;
+
+    type Query = NativeAssetQuery<'a>;
+
+    fn query(query: NativeAssetQuery<'a>) -> AssetResult<bool> {
+        query_native(query, PREFAB_EXTENSION)
+    }
+
+    fn load(medium: AssetMedium<'a>, _: ()) -> AssetResult<PrefabAsset> {
+        load_native(medium, PREFAB_EXTENSION, |message| {
+            let prefab_reader = try_throw!(message.get_root::<protocol::prefab::Reader>());
+
+            Ok(PrefabAsset(try_rethrow!(Prefab::load_from_reader(prefab_reader))))
+        })
+    }
+
+    fn save(&self, medium: AssetMedium<'a>, args: NativeAssetSaveArgs) -> AssetResult<()> {
+        save_native(self, medium, PREFAB_EXTENSION, args, |message| {
+            let prefab_builder = message.init_root::<protocol::prefab::Builder>();
+
+            try_rethrow!(self.0.save_to_builder(prefab_builder));
+
+            Ok(())
+        })
+    }
+}
+
+impl Deref for PrefabAsset {
+    type Target = Prefab;
+
+    fn deref(&self) -> &Prefab {
+        &self.0
+    }
+}
+
+impl DerefMut for PrefabAsset {
+    fn deref_mut(&mut self) -> &mut Prefab {
+        &mut self.0
+    }
+}
\ No newline at end of file
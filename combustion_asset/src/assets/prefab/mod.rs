@@ -0,0 +1,7 @@
+//! Prefab asset
+
+pub mod asset;
+
+pub use self::asset::PrefabAsset;
+pub use ::assets::native::formats::NativeFileFormat as PrefabFileFormat;
+pub use ::assets::native::generic::{NativeAssetQuery as PrefabAssetQuery, NativeAssetSaveArgs as PrefabAssetSaveArgs};
\ No newline at end of file
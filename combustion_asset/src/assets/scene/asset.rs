@@ -0,0 +1,58 @@
+//! Scene asset implementation
+
+use std::ops::{Deref, DerefMut};
+
+use protocols::traits::Storage;
+use protocols::scene::protocol;
+use protocols::scene::{Scene, EXTENSION};
+
+use ::error::AssetResult;
+use ::asset::{Asset, AssetMedium};
+use ::assets::native::generic::{NativeAssetQuery, NativeAssetSaveArgs, query_native, load_native, save_native};
+
+/// Scene Asset
+#[derive(Serialize, Deserialize)]
+pub struct SceneAsset(Scene);
+
+impl<'a> Asset<'a> for SceneAsset {
+    type LoadArgs = ();
+    type SaveArgs = NativeAssetSaveArgs;
+
+    type Query = NativeAssetQuery<'a>;
+
+    fn query(query: NativeAssetQuery<'a>) -> AssetResult<bool> {
+        query_native(query, EXTENSION)
+    }
+
+    fn load(medium: AssetMedium<'a>, _: ()) -> AssetResult<SceneAsset> {
+        load_native(medium, EXTENSION, |message| {
+            let scene_reader = try_throw!(message.get_root::<protocol::scene::Reader>());
+
+            Ok(SceneAsset(try_rethrow!(Scene::load_from_reader(scene_reader))))
+        })
+    }
+
+    fn save(&self, medium: AssetMedium<'a>, args: NativeAssetSaveArgs) -> AssetResult<()> {
+        save_native(self, medium, EXTENSION, args, |message| {
+            let scene_builder = message.init_root::<protocol::scene::Builder>();
+
+            try_rethrow!(self.0.save_to_builder(scene_builder));
+
+            Ok(())
+        })
+    }
+}
+
+impl Deref for SceneAsset {
+    type Target = Scene;
+
+    fn deref(&self) -> &Scene {
+        &self.0
+    }
+}
+
+impl DerefMut for SceneAsset {
+    fn deref_mut(&mut self) -> &mut Scene {
+        &mut self.0
+    }
+}
\ No newline at end of file
@@ -0,0 +1,7 @@
+//! Scene asset
+
+pub mod asset;
+
+pub use self::asset::SceneAsset;
+pub use ::assets::native::formats::NativeFileFormat as SceneFileFormat;
+pub use ::assets::native::generic::{NativeAssetQuery as SceneAssetQuery, NativeAssetSaveArgs as SceneAssetSaveArgs};
\ No newline at end of file
@@ -3,6 +3,7 @@
 use std::io::prelude::*;
 
 use serde::{Serialize, Deserialize};
+use serde::de::DeserializeSeed;
 
 use ::error::{AssetResult, AssetError};
 use ::asset::Asset;
@@ -41,36 +42,81 @@ pub fn load_standard_format<'a, T: 'a, R>(mut reader: R, format: StandardFileFor
 }
 
 /// Save any `T: Asset` to a standard serializable format
+pub fn save_standard_format<'a, T: 'a, W>(writer: W, format: StandardFileFormat, asset: &T, pretty: bool) -> AssetResult<()>
+    where W: Write, T: Asset<'a> + Serialize
+{
+    save_standard_format_value(writer, format, asset, pretty)
+}
+
+/// Load a value from a standard deserializable format with a `DeserializeSeed`,
+/// for data which needs some state to be deserialized
+#[cfg_attr(not(feature = "bincode"), allow(unused_mut))]
+#[cfg_attr(not(any(feature = "json", feature = "yaml", feature = "bincode")), allow(unused_variables, unreachable_code))]
+pub fn load_standard_format_seed<S, R>(mut reader: R, format: StandardFileFormat, seed: S) -> AssetResult<S::Value>
+    where R: Read, S: DeserializeSeed
+{
+    let value = match format {
+        #[cfg(feature = "bincode")]
+        StandardFileFormat::Bincode => {
+            use bincode::{Deserializer, SizeLimit};
+
+            let mut deserializer = Deserializer::new(&mut reader, SizeLimit::Infinite);
+
+            try_throw!(seed.deserialize(&mut deserializer))
+        },
+        #[cfg(feature = "json")]
+        StandardFileFormat::Json => {
+            use json::{from_reader, Value};
+
+            let value: Value = try_throw!(from_reader(reader));
+
+            try_throw!(seed.deserialize(value))
+        },
+        #[cfg(feature = "yaml")]
+        StandardFileFormat::Yaml => {
+            use yaml::{from_reader, Value};
+
+            let value: Value = try_throw!(from_reader(reader));
+
+            try_throw!(seed.deserialize(value))
+        },
+        _ => throw!(AssetError::UnsupportedFormat),
+    };
+
+    Ok(value)
+}
+
+/// Save any serializable value to a standard format, for data which is not an asset by itself
 #[cfg_attr(not(feature = "json"), allow(unused_variables))]
 #[cfg_attr(not(any(feature = "json", feature = "yaml", feature = "bincode")), allow(unused_mut, unreachable_code))]
-pub fn save_standard_format<'a, T: 'a, W>(mut writer: W, format: StandardFileFormat, asset: &T, pretty: bool) -> AssetResult<()>
-    where W: Write, T: Asset<'a> + Serialize
+pub fn save_standard_format_value<T: ?Sized, W>(mut writer: W, format: StandardFileFormat, value: &T, pretty: bool) -> AssetResult<()>
+    where W: Write, T: Serialize
 {
     match format {
         #[cfg(feature = "bincode")]
         StandardFileFormat::Bincode => {
             use bincode::{serialize_into, SizeLimit};
 
-            try_throw!(serialize_into(&mut writer, asset, SizeLimit::Infinite));
+            try_throw!(serialize_into(&mut writer, value, SizeLimit::Infinite));
         },
         #[cfg(feature = "json")]
         StandardFileFormat::Json => {
             use json::{to_writer, to_writer_pretty};
 
             if pretty {
-                try_throw!(to_writer_pretty(&mut writer, asset));
+                try_throw!(to_writer_pretty(&mut writer, value));
             } else {
-                try_throw!(to_writer(&mut writer, asset));
+                try_throw!(to_writer(&mut writer, value));
             }
         },
         #[cfg(feature = "yaml")]
         StandardFileFormat::Yaml => {
             use yaml::to_writer;
 
-            try_throw!(to_writer(&mut writer, asset));
+            try_throw!(to_writer(&mut writer, value));
         },
         _ => throw!(AssetError::UnsupportedFormat),
     }
 
     Ok(())
-}
\ No newline at end of file
+}
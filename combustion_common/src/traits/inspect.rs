@@ -0,0 +1,435 @@
+//! Runtime field introspection, for editor inspectors and console commands
+//!
+//! Fields are addressed by dot-separated paths, such as `color.r` or `zdistance.1`. An empty path refers to the value itself.
+//!
+//! `Inspect` is usually implemented with `#[derive(Inspect)]`. Fields can be hidden with `#[inspect(skip)]`,
+//! and `#[inspect(path = "::core::common")]` changes the path used to find this module.
+
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::path::PathBuf;
+
+use nalgebra::{Vector2, Vector3, Vector4, Point2, Point3};
+
+use color::Color;
+
+/// Generic value of an inspectable field
+#[derive(Debug, Clone, PartialEq)]
+pub enum InspectValue {
+    /// Missing optional value
+    None,
+    /// Boolean value
+    Bool(bool),
+    /// Any integer
+    Integer(i64),
+    /// Any floating point number
+    Float(f64),
+    /// String or path
+    String(String),
+    /// Name of an enum variant
+    Enum(String),
+    /// Two-component vector
+    Vector2(Vector2<f32>),
+    /// Three-component vector
+    Vector3(Vector3<f32>),
+    /// Four-component vector
+    Vector4(Vector4<f32>),
+    /// Two-dimensional point
+    Point2(Point2<f32>),
+    /// Three-dimensional point
+    Point3(Point3<f32>),
+    /// Color
+    Color(Color),
+}
+
+/// Error when setting a field
+#[derive(Debug, Clone, PartialEq)]
+pub enum InspectError {
+    /// No field exists at the given path
+    UnknownField(String),
+    /// The value is the wrong type for the field, which has the given type
+    InvalidValue(&'static str),
+    /// The field can't be set as a whole, only its own fields can be
+    Unsupported,
+}
+
+/// Result type for setting fields
+pub type InspectResult<T> = Result<T, InspectError>;
+
+impl Display for InspectError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            InspectError::UnknownField(ref path) => write!(f, "Unknown field: {}", path),
+            InspectError::InvalidValue(type_name) => write!(f, "Invalid value for type {}", type_name),
+            InspectError::Unsupported => write!(f, "{}", self.description()),
+        }
+    }
+}
+
+impl Error for InspectError {
+    fn description(&self) -> &str {
+        match *self {
+            InspectError::UnknownField(_) => "Unknown Field",
+            InspectError::InvalidValue(_) => "Invalid Value",
+            InspectError::Unsupported => "Unsupported Operation",
+        }
+    }
+}
+
+/// Name and type of an inspectable field
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldInfo {
+    /// Field name, used in paths
+    pub name: &'static str,
+    /// Type of the field, as written in the source
+    pub type_name: &'static str,
+}
+
+/// Type whose fields can be listed, read and written at runtime
+pub trait Inspect {
+    /// Name of the type
+    fn type_name(&self) -> &'static str;
+
+    /// Fields directly inside this value. Empty for plain values.
+    fn fields(&self) -> Vec<FieldInfo> { Vec::new() }
+
+    /// Get the value at the path, or `None` if there is no such field or it has no single value
+    fn get(&self, path: &str) -> Option<InspectValue>;
+
+    /// Set the value at the path
+    fn set(&mut self, path: &str, value: InspectValue) -> InspectResult<()>;
+
+    /// Create a new instance from a value, used to fill in missing optional fields
+    fn from_value(_value: &InspectValue) -> Option<Self> where Self: Sized { None }
+}
+
+/// Split a path into the first field name and the rest of the path
+pub fn split_path(path: &str) -> (&str, &str) {
+    match path.find('.') {
+        Some(index) => (&path[..index], &path[index + 1..]),
+        None => (path, ""),
+    }
+}
+
+/// Join the field name back onto the rest of a path, for error messages
+pub fn unknown_field(field: &str, rest: &str) -> InspectError {
+    if rest.is_empty() {
+        InspectError::UnknownField(field.to_string())
+    } else {
+        InspectError::UnknownField(format!("{}.{}", field, rest))
+    }
+}
+
+impl Inspect for bool {
+    fn type_name(&self) -> &'static str { "bool" }
+
+    fn get(&self, path: &str) -> Option<InspectValue> {
+        if path.is_empty() { Some(InspectValue::Bool(*self)) } else { None }
+    }
+
+    fn set(&mut self, path: &str, value: InspectValue) -> InspectResult<()> {
+        if !path.is_empty() {
+            return Err(InspectError::UnknownField(path.to_string()));
+        }
+
+        *self = try!(bool::from_value(&value).ok_or(InspectError::InvalidValue("bool")));
+
+        Ok(())
+    }
+
+    fn from_value(value: &InspectValue) -> Option<bool> {
+        match *value {
+            InspectValue::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_inspect_integer {
+    ($in_range:expr; $($t:ident),*) => {$(
+        impl Inspect for $t {
+            fn type_name(&self) -> &'static str { stringify!($t) }
+
+            fn get(&self, path: &str) -> Option<InspectValue> {
+                if path.is_empty() { Some(InspectValue::Integer(*self as i64)) } else { None }
+            }
+
+            fn set(&mut self, path: &str, value: InspectValue) -> InspectResult<()> {
+                if !path.is_empty() {
+                    return Err(InspectError::UnknownField(path.to_string()));
+                }
+
+                *self = try!($t::from_value(&value).ok_or(InspectError::InvalidValue(stringify!($t))));
+
+                Ok(())
+            }
+
+            fn from_value(value: &InspectValue) -> Option<$t> {
+                match *value {
+                    InspectValue::Integer(value) if $in_range(value, $t::min_value() as i64, $t::max_value() as u64) => Some(value as $t),
+                    _ => None,
+                }
+            }
+        }
+    )*}
+}
+
+impl_inspect_integer!(|value: i64, min: i64, max: u64| value >= min && value <= max as i64; i8, i16, i32, i64, isize);
+impl_inspect_integer!(|value: i64, _: i64, max: u64| value >= 0 && value as u64 <= max; u8, u16, u32, u64, usize);
+
+macro_rules! impl_inspect_float {
+    ($($t:ident),*) => {$(
+        impl Inspect for $t {
+            fn type_name(&self) -> &'static str { stringify!($t) }
+
+            fn get(&self, path: &str) -> Option<InspectValue> {
+                if path.is_empty() { Some(InspectValue::Float(*self as f64)) } else { None }
+            }
+
+            fn set(&mut self, path: &str, value: InspectValue) -> InspectResult<()> {
+                if !path.is_empty() {
+                    return Err(InspectError::UnknownField(path.to_string()));
+                }
+
+                *self = try!($t::from_value(&value).ok_or(InspectError::InvalidValue(stringify!($t))));
+
+                Ok(())
+            }
+
+            fn from_value(value: &InspectValue) -> Option<$t> {
+                match *value {
+                    InspectValue::Float(value) => Some(value as $t),
+                    InspectValue::Integer(value) => Some(value as $t),
+                    _ => None,
+                }
+            }
+        }
+    )*}
+}
+
+impl_inspect_float!(f32, f64);
+
+impl Inspect for String {
+    fn type_name(&self) -> &'static str { "String" }
+
+    fn get(&self, path: &str) -> Option<InspectValue> {
+        if path.is_empty() { Some(InspectValue::String(self.clone())) } else { None }
+    }
+
+    fn set(&mut self, path: &str, value: InspectValue) -> InspectResult<()> {
+        if !path.is_empty() {
+            return Err(InspectError::UnknownField(path.to_string()));
+        }
+
+        *self = try!(String::from_value(&value).ok_or(InspectError::InvalidValue("String")));
+
+        Ok(())
+    }
+
+    fn from_value(value: &InspectValue) -> Option<String> {
+        match *value {
+            InspectValue::String(ref value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl Inspect for PathBuf {
+    fn type_name(&self) -> &'static str { "PathBuf" }
+
+    fn get(&self, path: &str) -> Option<InspectValue> {
+        if path.is_empty() { Some(InspectValue::String(self.to_string_lossy().into_owned())) } else { None }
+    }
+
+    fn set(&mut self, path: &str, value: InspectValue) -> InspectResult<()> {
+        if !path.is_empty() {
+            return Err(InspectError::UnknownField(path.to_string()));
+        }
+
+        *self = try!(PathBuf::from_value(&value).ok_or(InspectError::InvalidValue("PathBuf")));
+
+        Ok(())
+    }
+
+    fn from_value(value: &InspectValue) -> Option<PathBuf> {
+        match *value {
+            InspectValue::String(ref value) => Some(PathBuf::from(value)),
+            _ => None,
+        }
+    }
+}
+
+/// Vectors and points can be set as a whole, or by their components
+macro_rules! impl_inspect_vector {
+    ($t:ident, $variant:ident, [$($field:ident),*]) => {
+        impl Inspect for $t<f32> {
+            fn type_name(&self) -> &'static str { concat!(stringify!($t), "<f32>") }
+
+            fn fields(&self) -> Vec<FieldInfo> {
+                vec![$(FieldInfo { name: stringify!($field), type_name: "f32" }),*]
+            }
+
+            fn get(&self, path: &str) -> Option<InspectValue> {
+                match path {
+                    "" => Some(InspectValue::$variant(*self)),
+                    $(stringify!($field) => Some(InspectValue::Float(self.$field as f64)),)*
+                    _ => None,
+                }
+            }
+
+            fn set(&mut self, path: &str, value: InspectValue) -> InspectResult<()> {
+                match path {
+                    "" => {
+                        *self = try!($t::from_value(&value).ok_or(InspectError::InvalidValue(concat!(stringify!($t), "<f32>"))));
+
+                        Ok(())
+                    }
+                    $(stringify!($field) => self.$field.set("", value),)*
+                    _ => Err(InspectError::UnknownField(path.to_string())),
+                }
+            }
+
+            fn from_value(value: &InspectValue) -> Option<$t<f32>> {
+                match *value {
+                    InspectValue::$variant(value) => Some(value),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+impl_inspect_vector!(Vector2, Vector2, [x, y]);
+impl_inspect_vector!(Vector3, Vector3, [x, y, z]);
+impl_inspect_vector!(Vector4, Vector4, [x, y, z, w]);
+impl_inspect_vector!(Point2, Point2, [x, y]);
+impl_inspect_vector!(Point3, Point3, [x, y, z]);
+
+impl Inspect for Color {
+    fn type_name(&self) -> &'static str { "Color" }
+
+    fn fields(&self) -> Vec<FieldInfo> {
+        vec![
+            FieldInfo { name: "r", type_name: "f32" },
+            FieldInfo { name: "g", type_name: "f32" },
+            FieldInfo { name: "b", type_name: "f32" },
+            FieldInfo { name: "a", type_name: "f32" },
+        ]
+    }
+
+    fn get(&self, path: &str) -> Option<InspectValue> {
+        match path {
+            "" => Some(InspectValue::Color(*self)),
+            "r" => self.r.get(""),
+            "g" => self.g.get(""),
+            "b" => self.b.get(""),
+            "a" => self.a.get(""),
+            _ => None,
+        }
+    }
+
+    fn set(&mut self, path: &str, value: InspectValue) -> InspectResult<()> {
+        match path {
+            "" => {
+                *self = try!(Color::from_value(&value).ok_or(InspectError::InvalidValue("Color")));
+
+                Ok(())
+            }
+            "r" => self.r.set("", value),
+            "g" => self.g.set("", value),
+            "b" => self.b.set("", value),
+            "a" => self.a.set("", value),
+            _ => Err(InspectError::UnknownField(path.to_string())),
+        }
+    }
+
+    fn from_value(value: &InspectValue) -> Option<Color> {
+        match *value {
+            InspectValue::Color(color) => Some(color),
+            InspectValue::Vector4(ref v) => Some(Color::new(v.x, v.y, v.z, v.w)),
+            InspectValue::Vector3(ref v) => Some(Color::new(v.x, v.y, v.z, 1.0)),
+            _ => None,
+        }
+    }
+}
+
+/// Optional fields read as `InspectValue::None` when missing, and can be cleared by setting them to it
+impl<T: Inspect> Inspect for Option<T> {
+    fn type_name(&self) -> &'static str {
+        match *self {
+            Some(ref value) => value.type_name(),
+            None => "Option",
+        }
+    }
+
+    fn fields(&self) -> Vec<FieldInfo> {
+        match *self {
+            Some(ref value) => value.fields(),
+            None => Vec::new(),
+        }
+    }
+
+    fn get(&self, path: &str) -> Option<InspectValue> {
+        match *self {
+            Some(ref value) => value.get(path),
+            None if path.is_empty() => Some(InspectValue::None),
+            None => None,
+        }
+    }
+
+    fn set(&mut self, path: &str, value: InspectValue) -> InspectResult<()> {
+        if path.is_empty() && value == InspectValue::None {
+            *self = None;
+
+            return Ok(());
+        }
+
+        if let Some(ref mut inner) = *self {
+            return inner.set(path, value);
+        }
+
+        if !path.is_empty() {
+            return Err(InspectError::UnknownField(path.to_string()));
+        }
+
+        *self = Some(try!(T::from_value(&value).ok_or(InspectError::InvalidValue("Option"))));
+
+        Ok(())
+    }
+
+    fn from_value(value: &InspectValue) -> Option<Option<T>> {
+        match *value {
+            InspectValue::None => Some(None),
+            _ => T::from_value(value).map(Some),
+        }
+    }
+}
+
+/// Pairs are accessed by index, like tuple fields
+impl<A: Inspect, B: Inspect> Inspect for (A, B) {
+    fn type_name(&self) -> &'static str { "tuple" }
+
+    fn fields(&self) -> Vec<FieldInfo> {
+        vec![
+            FieldInfo { name: "0", type_name: self.0.type_name() },
+            FieldInfo { name: "1", type_name: self.1.type_name() },
+        ]
+    }
+
+    fn get(&self, path: &str) -> Option<InspectValue> {
+        match split_path(path) {
+            ("0", rest) => self.0.get(rest),
+            ("1", rest) => self.1.get(rest),
+            _ => None,
+        }
+    }
+
+    fn set(&mut self, path: &str, value: InspectValue) -> InspectResult<()> {
+        match split_path(path) {
+            ("", _) => Err(InspectError::Unsupported),
+            ("0", rest) => self.0.set(rest, value),
+            ("1", rest) => self.1.set(rest, value),
+            (field, rest) => Err(unknown_field(field, rest)),
+        }
+    }
+}
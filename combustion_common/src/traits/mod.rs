@@ -1,5 +1,7 @@
 //! Common traits
 
 pub mod named;
+pub mod inspect;
 
-pub use self::named::{Named, DefaultName};
\ No newline at end of file
+pub use self::named::{Named, DefaultName};
+pub use self::inspect::{Inspect, InspectValue, InspectError, InspectResult, FieldInfo};
\ No newline at end of file
@@ -42,6 +42,40 @@ pub enum RenderSignal {
     Event(WindowEvent)
 }
 
+/// Convert a window event into an input event, if it is one
+fn input_event(event: WindowEvent) -> Option<::events::builtin::InputEvent> {
+    use ::events::builtin::{InputEvent, Action};
+
+    fn action(action: glfw::Action) -> Action {
+        match action {
+            glfw::Action::Press => Action::Press,
+            glfw::Action::Release => Action::Release,
+            glfw::Action::Repeat => Action::Repeat,
+        }
+    }
+
+    Some(match event {
+        WindowEvent::Key(key, scancode, key_action, modifiers) => InputEvent::Key {
+            key: key as i32,
+            scancode: scancode as i32,
+            action: action(key_action),
+            modifiers: modifiers.bits() as u32,
+        },
+        WindowEvent::Char(c) => InputEvent::Char(c),
+        WindowEvent::MouseButton(button, button_action, modifiers) => InputEvent::MouseButton {
+            button: button as i32,
+            action: action(button_action),
+            modifiers: modifiers.bits() as u32,
+        },
+        WindowEvent::CursorPos(x, y) => InputEvent::CursorMoved(x, y),
+        WindowEvent::Scroll(x, y) => InputEvent::Scroll(x, y),
+        WindowEvent::Size(width, height) => InputEvent::Resized(width, height),
+        WindowEvent::Focus(focused) => InputEvent::Focus(focused),
+        WindowEvent::Close => InputEvent::Close,
+        _ => return None,
+    })
+}
+
 pub struct RenderLoopState {
     total_frames: u64,
     refresh_rate: f64,
@@ -187,9 +221,13 @@ pub fn start(mut state: &mut RenderLoopState, mut context: glfw::RenderContext,
 
         // Step one: process events
         if scene.with_world(|world| -> bool {
-            use resources::event_queue::{Event, Resource as EventQueue};
+            use ::events::EventChannel;
+            use ::events::builtin::InputEvent;
 
-            let mut event_queue = world.write_resource::<EventQueue>();
+            //Start a new frame for the event channels, dropping the events from two frames ago
+            ::events::system::maintain_channels(world);
+
+            let mut input_events = world.write_resource::<EventChannel<InputEvent>>();
 
             for signal in rx.try_iter() {
                 match signal {
@@ -209,7 +247,9 @@ pub fn start(mut state: &mut RenderLoopState, mut context: glfw::RenderContext,
                         info!("Pausing...");
                     }
                     RenderSignal::Event(event) => {
-                        event_queue.push(Event::WindowEvent(event));
+                        if let Some(event) = input_event(event) {
+                            input_events.single_write(event);
+                        }
                     }
                 }
             }
@@ -235,21 +275,68 @@ pub fn start(mut state: &mut RenderLoopState, mut context: glfw::RenderContext,
                 use components::gpu_buffer::Component as GPU_Buffer;
                 use components::renderable::Component as Renderable;
                 use components::camera::Component as Camera;
+                use components::occluder::Component as Occluder;
 
                 use resources::camera::Resource as CameraResource;
 
+                use ::scene::culling::Culling;
+                use ::scene::spatial::Bvh;
+
                 let ref transforms = world.read::<Transform>();
                 let ref positions = world.read::<Position>();
                 let ref meshes = world.read::<Mesh>();
+                let ref occluders = world.read::<Occluder>();
 
                 let ref mut gpu_buffers = world.write::<GPU_Buffer>();
                 let ref mut renderables = world.write::<Renderable>();
 
                 let ref entities = world.entities();
 
+                let mut cameras = world.write::<Camera>();
+
+                let camera_entity = world.read_resource::<CameraResource>().entity();
+
+                let mut view_position = Point3::new(0.0, 0.0, 0.0);
+                let mut view_matrix = Matrix4::new_identity(4);
+                let mut projection_matrix = Matrix4::new_identity(4);
+
+                if let Some(position) = positions.get(camera_entity) {
+                    view_position = position.0;
+                }
+
+                if let Some(transform) = transforms.get(camera_entity) {
+                    view_matrix = transform.world;
+                }
+
+                let mut view_projection = projection_matrix * view_matrix;
+
+                if let Some(mut camera) = cameras.get_mut(camera_entity) {
+                    use components::camera::Kind;
+
+                    //If the window was resized, adjust the projection accordingly.
+                    if let Some((width, height)) = viewport_size {
+                        camera.kind.resize(width as f32, height as f32, None);
+                    }
+
+                    projection_matrix = camera.kind.to_homogeneous();
+                    view_projection = camera.kind.view_projection(&view_matrix);
+                }
+
+                //Cull against the camera frustum before filling the render queue, so only visible items are enqueued
+                let mut culling = world.write_resource::<Culling>();
+
+                culling.cull(&world.read_resource::<Bvh>(), &view_projection,
+                             (occluders, transforms, entities).iter().map(|(occluder, transform, entity)| {
+                                 (entity, &occluder.volume, &transform.world)
+                             }));
+
                 let mut render_queue = world.write_resource::<RenderQueue>();
 
                 for (_, ref mut gpu_buffer, entity) in (renderables, gpu_buffers, entities).iter() {
+                    if !culling.is_visible(entity) {
+                        continue;
+                    }
+
                     if gpu_buffer.dirty {
                         if let Some(mesh) = meshes.get(entity) {
                             if let Some(ref mesh) = sources.mesh(mesh.source, mesh.index)? {
@@ -266,7 +353,7 @@ pub fn start(mut state: &mut RenderLoopState, mut context: glfw::RenderContext,
                     }
 
                     let (matrix, inverse) = if let Some(transform) = transforms.get(entity) {
-                        (transform.matrix, transform.inverse)
+                        (transform.world, transform.world.inverse())
                     } else {
                         (Matrix4::new_identity(4), Some(Matrix4::new_identity(4)))
                     };
@@ -278,33 +365,6 @@ pub fn start(mut state: &mut RenderLoopState, mut context: glfw::RenderContext,
                     });
                 }
 
-                let mut cameras = world.write::<Camera>();
-
-                let camera_entity = world.read_resource::<CameraResource>().entity();
-
-                let mut view_position = Point3::new(0.0, 0.0, 0.0);
-                let mut view_matrix = Matrix4::new_identity(4);
-                let mut projection_matrix = Matrix4::new_identity(4);
-
-                if let Some(mut camera) = cameras.get_mut(camera_entity) {
-                    use components::camera::Kind;
-
-                    //If the window was resized, adjust the projection accordingly.
-                    if let Some((width, height)) = viewport_size {
-                        camera.kind.resize(width as f32, height as f32, None);
-                    }
-
-                    projection_matrix = camera.kind.to_homogeneous();
-                }
-
-                if let Some(position) = positions.get(camera_entity) {
-                    view_position = position.0;
-                }
-
-                if let Some(transform) = transforms.get(camera_entity) {
-                    view_matrix = transform.matrix;
-                }
-
                 render_queue.swap(&mut final_render_queue);
 
                 Ok((view_position, view_matrix, projection_matrix))
@@ -14,6 +14,12 @@ extern crate num_cpus;
 extern crate vec_map;
 extern crate lazy;
 
+#[cfg(test)]
+extern crate serde;
+#[cfg(test)]
+#[macro_use]
+extern crate serde_derive;
+
 #[macro_use]
 pub extern crate combustion_common as common;
 
@@ -27,6 +33,7 @@ pub extern crate combustion_events as events;
 pub extern crate combustion_ecs as ecs;
 
 pub mod error;
+pub mod snapshot;
 
 #[macro_use]
 pub mod scheduler;
@@ -9,6 +9,15 @@ use error::*;
 use ::common::structures::freelist::FreelistVecMap;
 pub use ::scene::sourcemap::SourceMap;
 pub use ::scene::graph::SceneGraph;
+pub use ::scene::instance::SceneInstance;
+pub use ::scene::spatial::Bvh;
+pub use ::scene::culling::Culling;
+pub use ::scene::prefab::{PrefabLibrary, PrefabInstance, PrefabOverrides};
+
+use ::scene::instance;
+use ::events::EventChannel;
+use ::events::builtin::InputEvent;
+use ::protocols::scene::{Scene as SceneDescription, Prefab};
 
 use resources;
 use entities::camera::Entity as Camera;
@@ -19,6 +28,7 @@ use entities::Entity;
 pub struct Scene<'a> {
     pub planner: specs::Planner<systems::Delta>,
     pub sources: SourceMap<'a>,
+    pub prefabs: PrefabLibrary,
 }
 
 impl<'a> Scene<'a> {
@@ -34,8 +44,8 @@ impl<'a> Scene<'a> {
             //Cursor position helper resource
             world.add_resource(resources::cursor::Resource::new());
 
-            //Event queue resource
-            world.add_resource(resources::event_queue::Resource::new());
+            //Event channels, which the render loop maintains once per frame
+            ::events::system::register_channels(&mut world);
 
             //Render queue resource
             world.add_resource(resources::render_queue::Resource::new());
@@ -46,23 +56,47 @@ impl<'a> Scene<'a> {
             let graph = SceneGraph::new(&world);
             world.add_resource::<resources::scene_graph::Resource>(graph.into());
 
+            //Spatial index of entity bounds, kept up to date by the spatial system
+            world.add_resource(Bvh::new());
+
+            //Visibility from the camera, updated by the renderer as it fills the render queue
+            world.add_resource(Culling::new());
+
             specs::Planner::new(world, num_cpus::get())
         };
 
+        let cursor = systems::cursor::System::new(&planner.mut_world().read_resource::<EventChannel<InputEvent>>());
+
+        planner.add_system(cursor, "CursorSystem", systems::Priorities::Input as specs::Priority);
+
         planner.add_system(systems::constraints::System, "ConstrainSystem",
                            systems::Priorities::Constraints as specs::Priority);
 
         planner.add_system(systems::transform::System, "TransformSystem",
                            systems::Priorities::Transforms as specs::Priority);
 
+        planner.add_system(systems::spatial::System::new(), "SpatialSystem",
+                           systems::Priorities::Spatial as specs::Priority);
+
+        planner.add_system(systems::animation::System, "AnimationSystem",
+                           systems::Priorities::Animation as specs::Priority);
+
+        planner.add_system(systems::skinning::System, "SkinningSystem",
+                           systems::Priorities::Skinning as specs::Priority);
+
         ::game::scene::add_systems(&mut planner);
 
         planner.dispatch(0.0);
         planner.wait();
 
+        let mut prefabs = PrefabLibrary::new();
+
+        ::game::components::register_prefabs(&mut prefabs.components);
+
         Ok(Scene {
             planner: planner,
             sources: SourceMap::new(),
+            prefabs: prefabs,
         })
     }
 
@@ -86,6 +120,57 @@ impl<'a> Scene<'a> {
         self.planner.mut_world().write_resource()
     }
 
+    /// Spawn the nodes and lights of a scene description into the world and scene graph
+    pub fn instantiate(&mut self, description: &SceneDescription) -> AppResult<SceneInstance> {
+        use ::components::light::Component as Light;
+
+        let world = self.planner.mut_world();
+
+        //The graph is moved out of its resource so it can be modified alongside the world
+        let mut graph = world.write_resource::<resources::scene_graph::Resource>().take();
+
+        let result = instance::instantiate::<Light>(world, &mut graph, description);
+
+        *world.write_resource::<resources::scene_graph::Resource>() = graph.into();
+
+        result.map_err(|_| AppError::InvalidScene)
+    }
+
+    /// Save an instantiated scene back into a scene description
+    pub fn snapshot(&mut self, instance: &SceneInstance) -> SceneDescription {
+        use ::components::light::Component as Light;
+
+        let world = self.planner.mut_world();
+
+        let graph = world.read_resource::<resources::scene_graph::Resource>();
+
+        instance::snapshot::<Light>(world, &graph, instance)
+    }
+
+    /// Spawn an instance of a prefab from the prefab library, attached to the root of the scene graph.
+    ///
+    /// Returns `None` if there is no prefab with that name.
+    pub fn instantiate_prefab(&mut self, name: &str, overrides: &PrefabOverrides) -> AppResult<Option<PrefabInstance>> {
+        let world = self.planner.mut_world();
+
+        let mut graph = world.write_resource::<resources::scene_graph::Resource>().take();
+
+        let root = graph.root();
+
+        let result = self.prefabs.instantiate(world, &mut graph, name, root, overrides);
+
+        *world.write_resource::<resources::scene_graph::Resource>() = graph.into();
+
+        result.map_err(|_| AppError::InvalidScene)
+    }
+
+    /// Replace a prefab in the prefab library and propagate the changes to all of its instances.
+    ///
+    /// Returns the number of updated entities.
+    pub fn update_prefab(&mut self, prefab: Prefab) -> usize {
+        self.prefabs.update(self.planner.mut_world(), prefab)
+    }
+
     #[inline(always)]
     pub fn world(&mut self) -> &mut specs::World {
         self.planner.mut_world()
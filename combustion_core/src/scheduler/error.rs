@@ -8,6 +8,12 @@ pub type SchedulerResult<T> = Result<T, SchedulerError>;
 #[derive(Debug)]
 pub enum SchedulerError {
     Io(io::Error),
+    /// A thread panicked with the given message
+    Panicked { thread: String, message: String },
+    /// A thread stopped responding, usually because another one panicked
+    Disconnected,
+    /// The renderer or event source could not be created
+    Init(String),
 }
 
 impl From<io::Error> for SchedulerError {
@@ -18,7 +24,11 @@ impl From<io::Error> for SchedulerError {
 
 impl Display for SchedulerError {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        write!(f, "{}", self.description())
+        match *self {
+            SchedulerError::Panicked { ref thread, ref message } => write!(f, "{} thread panicked: {}", thread, message),
+            SchedulerError::Init(ref message) => write!(f, "{}: {}", self.description(), message),
+            _ => write!(f, "{}", self.description()),
+        }
     }
 }
 
@@ -26,6 +36,9 @@ impl Error for SchedulerError {
     fn description(&self) -> &str {
         match *self {
             SchedulerError::Io(ref err) => err.description(),
+            SchedulerError::Panicked { .. } => "Thread Panicked",
+            SchedulerError::Disconnected => "Thread Disconnected",
+            SchedulerError::Init(_) => "Initialization Failed",
         }
     }
 }
\ No newline at end of file
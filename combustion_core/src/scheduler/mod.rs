@@ -1,34 +1,839 @@
+//! Multi-threaded engine scheduler
+//!
+//! The scheduler thread drives the engine frame by frame. Each frame it tells the ECS thread to simulate frame N+1
+//! while the render thread draws frame N, then waits for both before starting the next frame. The ECS thread writes its
+//! render data into one of two buffers while the render thread reads the other, and the buffers are swapped every frame,
+//! so neither thread ever waits on the other mid-frame.
+//!
+//! Input is gathered by the event thread and handed to the simulation at the start of its next frame.
+//!
+//! Without a renderer the scheduler runs headless, which is useful for tests and dedicated servers.
+//!
+//! If any thread panics, the scheduler shuts everything else down and `Scheduler::join` returns the panic as an error.
+
+use std::any::Any;
+use std::mem;
 use std::sync::mpsc;
-use std::sync::{Arc, RwLock};
-use std::thread::{self, Thread};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle, Thread};
+use std::time::{Duration, Instant};
 
 pub mod error;
 
 pub use self::error::*;
 
+/// Whether the engine should keep running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    /// Keep running
+    Continue,
+    /// Stop after the current frame
+    Exit,
+}
+
+/// Information about the frame being simulated or rendered
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frame {
+    /// Frame number, starting at zero
+    pub index: u64,
+    /// Time since the previous frame, in seconds
+    pub delta: f64,
+}
+
+/// Game state updated on the ECS thread
+pub trait Simulation: Send + 'static {
+    /// Events given to the simulation from the event thread
+    type Event: Send + 'static;
+    /// Everything the renderer needs to draw a frame
+    type RenderData: Default + Send + 'static;
+
+    /// Simulate a frame, given all events received since the last one
+    fn update(&mut self, frame: &Frame, events: Vec<Self::Event>) -> Control;
+
+    /// Write the data for rendering the frame just simulated
+    fn extract(&mut self, frame: &Frame, data: &mut Self::RenderData);
+
+    /// Called on the ECS thread after the last frame
+    fn shutdown(&mut self) {}
+}
+
+/// Draws frames on the render thread
+pub trait Renderer<D> {
+    fn render(&mut self, frame: &Frame, data: &D) -> Control;
+}
+
+/// Collects events on the event thread
+pub trait EventSource<E> {
+    /// Add any new events to `events`.
+    ///
+    /// This may block until there are some, but should return every so often so the thread can notice a shutdown.
+    fn poll(&mut self, events: &mut Vec<E>) -> Control;
+}
+
+/// Creates the renderer on the render thread, since graphics contexts usually can't be moved between threads
+pub type RendererFactory<D> = Box<FnMut() -> SchedulerResult<Box<Renderer<D>>> + Send>;
+
+/// Creates the event source on the event thread
+pub type EventSourceFactory<E> = Box<FnMut() -> SchedulerResult<Box<EventSource<E>>> + Send>;
+
+/// Messages from the threads back to the scheduler thread
 pub enum SchedulerEvent {
+    /// A thread started and is ready for work
     Bootstrap(Thread),
+    /// The simulation finished a frame
+    Simulated(Control),
+    /// The renderer finished a frame
+    Rendered(Control),
+    /// A thread failed to start
+    Failed(SchedulerError),
+}
+
+enum Command<D> {
+    Frame(Frame, Arc<Mutex<D>>),
+}
+
+/// State shared by all threads
+#[derive(Default)]
+struct Flags {
+    /// Set when the engine should stop after the current frame
+    shutdown: AtomicBool,
+    /// Set when any thread panicked
+    panicked: AtomicBool,
+}
+
+impl Flags {
+    #[inline]
+    fn is_shutdown(&self) -> bool {
+        self.shutdown.load(Ordering::SeqCst)
+    }
+
+    #[inline]
+    fn shutdown(&self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Shuts everything down if the thread it lives on panics
+struct PanicGuard(Arc<Flags>);
+
+impl Drop for PanicGuard {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.0.panicked.store(true, Ordering::SeqCst);
+            self.0.shutdown();
+        }
+    }
+}
+
+/// How often to check for panicked threads while waiting on a frame
+const PANIC_POLL_INTERVAL_MS: u64 = 10;
+
+fn panic_message(payload: Box<Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&'static str>() {
+        return message.to_string();
+    }
+
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(_) => "Unknown panic".to_string(),
+    }
+}
+
+fn join_thread<T>(name: &str, handle: JoinHandle<T>) -> SchedulerResult<T> {
+    handle.join().map_err(|payload| SchedulerError::Panicked { thread: name.to_string(), message: panic_message(payload) })
+}
+
+/// Configures and starts a `Scheduler`
+pub struct SchedulerBuilder<S: Simulation> {
+    simulation: S,
+    renderer: Option<RendererFactory<S::RenderData>>,
+    events: Option<EventSourceFactory<S::Event>>,
+    frame_limit: Option<u64>,
+    frame_time: Option<Duration>,
+    fixed_delta: Option<f64>,
+}
+
+impl<S: Simulation> SchedulerBuilder<S> {
+    pub fn new(simulation: S) -> SchedulerBuilder<S> {
+        SchedulerBuilder {
+            simulation: simulation,
+            renderer: None,
+            events: None,
+            frame_limit: None,
+            frame_time: None,
+            fixed_delta: None,
+        }
+    }
+
+    /// Render frames on a render thread. Without a renderer the scheduler runs headless.
+    pub fn renderer(mut self, factory: RendererFactory<S::RenderData>) -> SchedulerBuilder<S> {
+        self.renderer = Some(factory);
+        self
+    }
+
+    /// Collect events on an event thread
+    pub fn events(mut self, factory: EventSourceFactory<S::Event>) -> SchedulerBuilder<S> {
+        self.events = Some(factory);
+        self
+    }
+
+    /// Stop after simulating this many frames
+    pub fn frame_limit(mut self, frames: u64) -> SchedulerBuilder<S> {
+        self.frame_limit = Some(frames);
+        self
+    }
+
+    /// Wait until at least this long has passed before starting the next frame
+    pub fn frame_time(mut self, frame_time: Duration) -> SchedulerBuilder<S> {
+        self.frame_time = Some(frame_time);
+        self
+    }
+
+    /// Give every frame the same delta instead of the measured time, for deterministic runs
+    pub fn fixed_delta(mut self, delta: f64) -> SchedulerBuilder<S> {
+        self.fixed_delta = Some(delta);
+        self
+    }
+
+    /// Start the scheduler thread, which starts all the others
+    pub fn spawn(self) -> SchedulerResult<Scheduler> {
+        let flags = Arc::new(Flags::default());
+
+        let scheduler_flags = flags.clone();
+
+        let handle = try!(thread::Builder::new().name("Scheduler".to_string()).spawn(move || {
+            let _guard = PanicGuard(scheduler_flags.clone());
+
+            self.run(scheduler_flags)
+        }));
+
+        Ok(Scheduler {
+            flags: flags,
+            scheduler_thread: handle.thread().clone(),
+            handle: Some(handle),
+        })
+    }
+
+    fn run(self, flags: Arc<Flags>) -> SchedulerResult<u64> {
+        let SchedulerBuilder { simulation, renderer, events, frame_limit, frame_time, fixed_delta } = self;
+
+        let (reply_tx, reply_rx) = mpsc::channel();
+        let (event_tx, event_rx) = mpsc::channel();
+
+        let mut threads = Vec::new();
+
+        let ecs_tx = try!(spawn_ecs_thread(simulation, event_rx, reply_tx.clone(), flags.clone(), &mut threads));
+
+        let render_tx = match renderer {
+            Some(factory) => Some(try!(spawn_render_thread(factory, reply_tx.clone(), flags.clone(), &mut threads))),
+            None => None,
+        };
+
+        if let Some(factory) = events {
+            try!(spawn_event_thread(factory, event_tx, reply_tx.clone(), flags.clone(), &mut threads));
+        }
+
+        drop(reply_tx);
+
+        let result = run_frames(&reply_rx, &ecs_tx, render_tx.as_ref(), threads.len(), &flags, frame_limit, frame_time, fixed_delta);
+
+        flags.shutdown();
+
+        // Closing the command channels stops the ECS and render threads
+        drop(ecs_tx);
+        drop(render_tx);
+
+        let mut first_error = None;
+
+        for (name, handle) in threads {
+            if let Err(err) = join_thread(name, handle) {
+                first_error = first_error.or(Some(err));
+            }
+        }
+
+        // A panic is more interesting than whatever the frame loop noticed because of it
+        match first_error {
+            Some(err) => Err(err),
+            None => result,
+        }
+    }
+}
+
+type NamedThread = (&'static str, JoinHandle<()>);
+
+fn spawn_ecs_thread<S: Simulation>(mut simulation: S, event_rx: mpsc::Receiver<Vec<S::Event>>, reply_tx: mpsc::Sender<SchedulerEvent>,
+                                   flags: Arc<Flags>, threads: &mut Vec<NamedThread>) -> SchedulerResult<mpsc::Sender<Command<S::RenderData>>> {
+    let (command_tx, command_rx) = mpsc::channel::<Command<S::RenderData>>();
+
+    let handle = try!(thread::Builder::new().name("ECS".to_string()).spawn(move || {
+        let _guard = PanicGuard(flags);
+
+        let _ = reply_tx.send(SchedulerEvent::Bootstrap(thread::current()));
+
+        while let Ok(Command::Frame(frame, buffer)) = command_rx.recv() {
+            let events: Vec<S::Event> = event_rx.try_iter().flat_map(|events| events).collect();
+
+            let control = simulation.update(&frame, events);
+
+            {
+                let mut data = buffer.lock().unwrap_or_else(|err| err.into_inner());
+
+                simulation.extract(&frame, &mut data);
+            }
+
+            if reply_tx.send(SchedulerEvent::Simulated(control)).is_err() {
+                break;
+            }
+        }
+
+        simulation.shutdown();
+    }));
+
+    threads.push(("ECS", handle));
+
+    Ok(command_tx)
+}
+
+fn spawn_render_thread<D: Send + 'static>(mut factory: RendererFactory<D>, reply_tx: mpsc::Sender<SchedulerEvent>,
+                                          flags: Arc<Flags>, threads: &mut Vec<NamedThread>) -> SchedulerResult<mpsc::Sender<Command<D>>> {
+    let (command_tx, command_rx) = mpsc::channel::<Command<D>>();
+
+    let handle = try!(thread::Builder::new().name("Render".to_string()).spawn(move || {
+        let _guard = PanicGuard(flags);
+
+        let mut renderer = match factory() {
+            Ok(renderer) => renderer,
+            Err(err) => {
+                let _ = reply_tx.send(SchedulerEvent::Failed(err));
+                return;
+            }
+        };
+
+        let _ = reply_tx.send(SchedulerEvent::Bootstrap(thread::current()));
+
+        while let Ok(Command::Frame(frame, buffer)) = command_rx.recv() {
+            let control = {
+                let data = buffer.lock().unwrap_or_else(|err| err.into_inner());
+
+                renderer.render(&frame, &data)
+            };
+
+            if reply_tx.send(SchedulerEvent::Rendered(control)).is_err() {
+                break;
+            }
+        }
+    }));
+
+    threads.push(("Render", handle));
+
+    Ok(command_tx)
+}
+
+fn spawn_event_thread<E: Send + 'static>(mut factory: EventSourceFactory<E>, event_tx: mpsc::Sender<Vec<E>>, reply_tx: mpsc::Sender<SchedulerEvent>,
+                                         flags: Arc<Flags>, threads: &mut Vec<NamedThread>) -> SchedulerResult<()> {
+    let handle = try!(thread::Builder::new().name("Events".to_string()).spawn(move || {
+        let _guard = PanicGuard(flags.clone());
+
+        let mut source = match factory() {
+            Ok(source) => source,
+            Err(err) => {
+                let _ = reply_tx.send(SchedulerEvent::Failed(err));
+                return;
+            }
+        };
+
+        let _ = reply_tx.send(SchedulerEvent::Bootstrap(thread::current()));
+
+        // Nothing else is sent from here, so the scheduler shouldn't wait on this thread
+        drop(reply_tx);
+
+        let mut events = Vec::new();
+
+        while !flags.is_shutdown() {
+            let control = source.poll(&mut events);
+
+            if !events.is_empty() && event_tx.send(mem::replace(&mut events, Vec::new())).is_err() {
+                break;
+            }
+
+            if control == Control::Exit {
+                flags.shutdown();
+            }
+        }
+    }));
+
+    threads.push(("Events", handle));
+
+    Ok(())
+}
+
+/// Run frames until something asks to exit, returning the number of frames simulated
+fn run_frames<D: Default>(reply_rx: &mpsc::Receiver<SchedulerEvent>, ecs_tx: &mpsc::Sender<Command<D>>, render_tx: Option<&mpsc::Sender<Command<D>>>,
+                          thread_count: usize, flags: &Flags, frame_limit: Option<u64>, frame_time: Option<Duration>,
+                          fixed_delta: Option<f64>) -> SchedulerResult<u64> {
+    // Wait for a reply, giving up if any thread panics since it may have been the one to reply
+    let receive = || -> SchedulerResult<SchedulerEvent> {
+        loop {
+            match reply_rx.recv_timeout(Duration::from_millis(PANIC_POLL_INTERVAL_MS)) {
+                Ok(event) => return Ok(event),
+                Err(mpsc::RecvTimeoutError::Timeout) if !flags.panicked.load(Ordering::SeqCst) => {}
+                Err(_) => return Err(SchedulerError::Disconnected),
+            }
+        }
+    };
+
+    // Wait for every thread to start
+    for _ in 0..thread_count {
+        match try!(receive()) {
+            SchedulerEvent::Bootstrap(_) => {}
+            SchedulerEvent::Failed(err) => return Err(err),
+            _ => return Err(SchedulerError::Disconnected),
+        }
+    }
+
+    let buffers = [Arc::new(Mutex::new(D::default())), Arc::new(Mutex::new(D::default()))];
+
+    let mut write = 0;
+    let mut frames = 0;
+    let mut last = Instant::now();
+
+    // The frame which was simulated last, and is rendered alongside the next one
+    let mut previous: Option<Frame> = None;
+
+    // Set when the renderer asks to exit, so it isn't given the final frame
+    let mut renderer_exited = false;
+
+    while !flags.is_shutdown() && frame_limit.map_or(true, |limit| frames < limit) {
+        let now = Instant::now();
+        let elapsed = now - last;
+
+        last = now;
+
+        let frame = Frame {
+            index: frames,
+            delta: fixed_delta.unwrap_or_else(|| elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 * 1e-9),
+        };
+
+        if ecs_tx.send(Command::Frame(frame, buffers[write].clone())).is_err() {
+            return Err(SchedulerError::Disconnected);
+        }
+
+        let mut waiting = 1;
+
+        if let (Some(render_tx), Some(previous)) = (render_tx, previous) {
+            if render_tx.send(Command::Frame(previous, buffers[1 - write].clone())).is_err() {
+                return Err(SchedulerError::Disconnected);
+            }
+
+            waiting += 1;
+        }
+
+        let mut control = Control::Continue;
+
+        while waiting > 0 {
+            match try!(receive()) {
+                SchedulerEvent::Simulated(result) => {
+                    if result == Control::Exit {
+                        control = Control::Exit;
+                    }
+
+                    waiting -= 1;
+                }
+                SchedulerEvent::Rendered(result) => {
+                    if result == Control::Exit {
+                        control = Control::Exit;
+                        renderer_exited = true;
+                    }
+
+                    waiting -= 1;
+                }
+                _ => {}
+            }
+        }
+
+        frames += 1;
+
+        previous = Some(frame);
+        write = 1 - write;
+
+        if control == Control::Exit {
+            break;
+        }
+
+        if let Some(frame_time) = frame_time {
+            let elapsed = last.elapsed();
+
+            if elapsed < frame_time {
+                thread::sleep(frame_time - elapsed);
+            }
+        }
+    }
+
+    // The renderer is always a frame behind, so the last simulated frame still has to be drawn
+    if let (Some(render_tx), Some(previous)) = (render_tx, previous) {
+        if !renderer_exited && !flags.panicked.load(Ordering::SeqCst) {
+            if render_tx.send(Command::Frame(previous, buffers[1 - write].clone())).is_err() {
+                return Err(SchedulerError::Disconnected);
+            }
+
+            loop {
+                if let SchedulerEvent::Rendered(_) = try!(receive()) {
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(frames)
 }
 
+/// Handle to the running engine threads
 pub struct Scheduler {
-    render_thread: Thread,
-    ecs_thread: Thread,
-    event_thread: Thread,
+    flags: Arc<Flags>,
     scheduler_thread: Thread,
+    handle: Option<JoinHandle<SchedulerResult<u64>>>,
 }
 
 impl Scheduler {
-    //pub fn new() -> SchedulerResult<Arc<Scheduler>> {
-    //    let (bootstrap_tx, bootstrap_rx) = mpsc::sync_channel();
-//
-    //    let scheduler_join_handle = thread::Builder::new().name("Scheduler").spawn(move || {
-    //        let scheduler = bootstrap_rx.recv().unwrap();
-//
-    //        // Do scheduling stuff
-    //    })?;
-//
-//
-//
-    //    Ok(())
-    //}
-}
\ No newline at end of file
+    /// Start a scheduler with the default configuration and no renderer or event source
+    pub fn new<S: Simulation>(simulation: S) -> SchedulerResult<Scheduler> {
+        SchedulerBuilder::new(simulation).spawn()
+    }
+
+    /// The thread driving the frames
+    #[inline]
+    pub fn thread(&self) -> &Thread {
+        &self.scheduler_thread
+    }
+
+    /// Ask all threads to stop after the current frame
+    pub fn shutdown(&self) {
+        self.flags.shutdown();
+    }
+
+    /// Returns `true` until the scheduler has been asked to stop, or stopped by itself
+    pub fn is_running(&self) -> bool {
+        !self.flags.is_shutdown()
+    }
+
+    /// Wait for all threads to stop, returning the number of frames simulated,
+    /// or the first panic or error from any thread.
+    pub fn join(mut self) -> SchedulerResult<u64> {
+        match self.handle.take() {
+            Some(handle) => try!(join_thread("Scheduler", handle)),
+            None => Ok(0),
+        }
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.shutdown();
+
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    use super::*;
+
+    /// Everything the test simulation and renderer saw
+    #[derive(Default)]
+    struct Log {
+        simulated: Vec<Frame>,
+        rendered: Vec<(u64, u64)>,
+        events: Vec<u32>,
+        shutdown: bool,
+    }
+
+    type SharedLog = Arc<Mutex<Log>>;
+
+    fn shared_log() -> SharedLog {
+        Arc::new(Mutex::new(Log::default()))
+    }
+
+    struct TestSimulation {
+        log: SharedLog,
+        exit_at: Option<u64>,
+        panic_at: Option<u64>,
+        exit_after_events: Option<usize>,
+    }
+
+    impl TestSimulation {
+        fn new(log: &SharedLog) -> TestSimulation {
+            TestSimulation { log: log.clone(), exit_at: None, panic_at: None, exit_after_events: None }
+        }
+    }
+
+    impl Simulation for TestSimulation {
+        type Event = u32;
+        type RenderData = u64;
+
+        fn update(&mut self, frame: &Frame, events: Vec<u32>) -> Control {
+            if self.panic_at == Some(frame.index) {
+                panic!("Simulation exploded");
+            }
+
+            let mut log = self.log.lock().unwrap();
+
+            log.simulated.push(*frame);
+            log.events.extend(events);
+
+            if self.exit_at == Some(frame.index) || self.exit_after_events.map_or(false, |count| log.events.len() >= count) {
+                Control::Exit
+            } else {
+                Control::Continue
+            }
+        }
+
+        fn extract(&mut self, frame: &Frame, data: &mut u64) {
+            *data = frame.index;
+        }
+
+        fn shutdown(&mut self) {
+            self.log.lock().unwrap().shutdown = true;
+        }
+    }
+
+    struct TestRenderer {
+        log: SharedLog,
+        exit_at: Option<u64>,
+        panic_at: Option<u64>,
+    }
+
+    impl Renderer<u64> for TestRenderer {
+        fn render(&mut self, frame: &Frame, data: &u64) -> Control {
+            if self.panic_at == Some(frame.index) {
+                panic!("Renderer exploded");
+            }
+
+            self.log.lock().unwrap().rendered.push((frame.index, *data));
+
+            if self.exit_at == Some(frame.index) { Control::Exit } else { Control::Continue }
+        }
+    }
+
+    fn renderer(log: &SharedLog, exit_at: Option<u64>, panic_at: Option<u64>) -> RendererFactory<u64> {
+        let log = log.clone();
+
+        Box::new(move || -> SchedulerResult<Box<Renderer<u64>>> {
+            Ok(Box::new(TestRenderer { log: log.clone(), exit_at: exit_at, panic_at: panic_at }) as Box<Renderer<u64>>)
+        })
+    }
+
+    /// Sends one event per poll until it has sent `count`
+    struct TestEvents {
+        next: u32,
+        count: u32,
+    }
+
+    impl EventSource<u32> for TestEvents {
+        fn poll(&mut self, events: &mut Vec<u32>) -> Control {
+            if self.next < self.count {
+                events.push(self.next);
+
+                self.next += 1;
+            }
+
+            thread::sleep(Duration::from_millis(1));
+
+            Control::Continue
+        }
+    }
+
+    fn indices(frames: &[Frame]) -> Vec<u64> {
+        frames.iter().map(|frame| frame.index).collect()
+    }
+
+    #[test]
+    fn headless_frame_limit() {
+        let log = shared_log();
+
+        let frames = SchedulerBuilder::new(TestSimulation::new(&log)).frame_limit(5).fixed_delta(0.25).spawn().unwrap().join().unwrap();
+
+        assert_eq!(frames, 5);
+
+        let log = log.lock().unwrap();
+
+        assert_eq!(indices(&log.simulated), vec![0, 1, 2, 3, 4]);
+        assert!(log.simulated.iter().all(|frame| frame.delta == 0.25));
+        assert!(log.rendered.is_empty());
+        assert!(log.shutdown);
+    }
+
+    #[test]
+    fn every_frame_is_rendered() {
+        let log = shared_log();
+
+        let frames = SchedulerBuilder::new(TestSimulation::new(&log))
+            .renderer(renderer(&log, None, None))
+            .frame_limit(4)
+            .spawn().unwrap().join().unwrap();
+
+        assert_eq!(frames, 4);
+
+        // Each frame is drawn with the render data extracted for it, including the last one
+        assert_eq!(log.lock().unwrap().rendered, vec![(0, 0), (1, 1), (2, 2), (3, 3)]);
+    }
+
+    #[test]
+    fn exit_from_the_simulation_renders_its_frame() {
+        let log = shared_log();
+
+        let mut simulation = TestSimulation::new(&log);
+
+        simulation.exit_at = Some(2);
+
+        let frames = SchedulerBuilder::new(simulation).renderer(renderer(&log, None, None)).spawn().unwrap().join().unwrap();
+
+        assert_eq!(frames, 3);
+
+        let log = log.lock().unwrap();
+
+        assert_eq!(indices(&log.simulated), vec![0, 1, 2]);
+        assert_eq!(log.rendered, vec![(0, 0), (1, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn exit_from_the_renderer() {
+        let log = shared_log();
+
+        let frames = SchedulerBuilder::new(TestSimulation::new(&log)).renderer(renderer(&log, Some(1), None)).spawn().unwrap().join().unwrap();
+
+        // Frame 1 is rendered alongside the simulation of frame 2, which is never drawn
+        assert_eq!(frames, 3);
+        assert_eq!(log.lock().unwrap().rendered, vec![(0, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn simulation_panics_are_returned_from_join() {
+        let log = shared_log();
+
+        let mut simulation = TestSimulation::new(&log);
+
+        simulation.panic_at = Some(2);
+
+        let result = SchedulerBuilder::new(simulation).renderer(renderer(&log, None, None)).spawn().unwrap().join();
+
+        match result {
+            Err(SchedulerError::Panicked { ref thread, ref message }) => {
+                assert_eq!(thread, "ECS");
+                assert_eq!(message, "Simulation exploded");
+            }
+            other => panic!("Expected a panic, got {:?}", other),
+        }
+
+        assert_eq!(indices(&log.lock().unwrap().simulated), vec![0, 1]);
+    }
+
+    #[test]
+    fn renderer_panics_are_returned_from_join() {
+        let log = shared_log();
+
+        let result = SchedulerBuilder::new(TestSimulation::new(&log)).renderer(renderer(&log, None, Some(0))).spawn().unwrap().join();
+
+        match result {
+            Err(SchedulerError::Panicked { ref thread, ref message }) => {
+                assert_eq!(thread, "Render");
+                assert_eq!(message, "Renderer exploded");
+            }
+            other => panic!("Expected a panic, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn renderer_init_failures_are_returned_from_join() {
+        let log = shared_log();
+
+        let factory: RendererFactory<u64> = Box::new(|| -> SchedulerResult<Box<Renderer<u64>>> {
+            Err(SchedulerError::Init("No window".to_string()))
+        });
+
+        match SchedulerBuilder::new(TestSimulation::new(&log)).renderer(factory).spawn().unwrap().join() {
+            Err(SchedulerError::Init(ref message)) => assert_eq!(message, "No window"),
+            other => panic!("Expected an init error, got {:?}", other),
+        }
+
+        assert!(log.lock().unwrap().simulated.is_empty());
+    }
+
+    #[test]
+    fn shutdown_stops_after_the_current_frame() {
+        let log = shared_log();
+
+        let scheduler = SchedulerBuilder::new(TestSimulation::new(&log))
+            .renderer(renderer(&log, None, None))
+            .frame_time(Duration::from_millis(1))
+            .spawn().unwrap();
+
+        while log.lock().unwrap().simulated.len() < 3 {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        assert!(scheduler.is_running());
+
+        scheduler.shutdown();
+
+        assert!(!scheduler.is_running());
+
+        let frames = scheduler.join().unwrap();
+
+        let log = log.lock().unwrap();
+
+        assert!(frames >= 3);
+        assert_eq!(log.simulated.len() as u64, frames);
+        assert_eq!(log.rendered.len() as u64, frames);
+        assert_eq!(log.rendered.last(), Some(&(frames - 1, frames - 1)));
+        assert!(log.shutdown);
+    }
+
+    #[test]
+    fn dropping_the_scheduler_shuts_it_down() {
+        let log = shared_log();
+
+        let scheduler = Scheduler::new(TestSimulation::new(&log)).unwrap();
+
+        while log.lock().unwrap().simulated.is_empty() {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        drop(scheduler);
+
+        assert!(log.lock().unwrap().shutdown);
+    }
+
+    #[test]
+    fn events_reach_the_simulation() {
+        let log = shared_log();
+
+        let mut simulation = TestSimulation::new(&log);
+
+        simulation.exit_after_events = Some(3);
+
+        let started = Arc::new(AtomicBool::new(false));
+        let factory_started = started.clone();
+
+        let factory: EventSourceFactory<u32> = Box::new(move || -> SchedulerResult<Box<EventSource<u32>>> {
+            factory_started.store(true, Ordering::SeqCst);
+
+            Ok(Box::new(TestEvents { next: 0, count: 3 }) as Box<EventSource<u32>>)
+        });
+
+        SchedulerBuilder::new(simulation)
+            .events(factory)
+            .frame_time(Duration::from_millis(1))
+            .spawn().unwrap().join().unwrap();
+
+        assert!(started.load(Ordering::SeqCst));
+        assert_eq!(log.lock().unwrap().events, vec![0, 1, 2]);
+    }
+}
@@ -0,0 +1,191 @@
+//! Saving and loading whole worlds, for save games
+//!
+//! Every component registered with the `ComponentRegistry` is saved for every entity. Loading creates new entities,
+//! and returns the map from saved entity IDs to them so anything else referring to the saved entities can be fixed up.
+
+use std::io::prelude::*;
+
+use ecs::World;
+use ecs::serialize::{ComponentRegistry, EntityMap};
+
+use asset::error::AssetResult;
+use asset::assets::standard::formats::StandardFileFormat;
+use asset::assets::standard::generic::{save_standard_format_value, load_standard_format_seed};
+
+/// Save all entities and registered components of the world
+pub fn save_world<W: Write>(registry: &ComponentRegistry, world: &World, writer: W,
+                            format: StandardFileFormat, pretty: bool) -> AssetResult<()> {
+    save_standard_format_value(writer, format, &registry.serializer(world), pretty)
+}
+
+/// Load entities and components saved with `save_world` into the world, alongside any existing entities.
+///
+/// The registered components must already have their storages in the world, see `ComponentRegistry::register_storages`.
+pub fn load_world<R: Read>(registry: &ComponentRegistry, world: &mut World, reader: R,
+                           format: StandardFileFormat) -> AssetResult<EntityMap> {
+    load_standard_format_seed(reader, format, registry.deserializer(world))
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use ecs::{self, World, Join};
+    use ecs::serialize::{ComponentRegistry, SerializeComponent};
+
+    use asset::assets::standard::formats::StandardFileFormat;
+
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    struct Health {
+        current: u32,
+        max: u32,
+    }
+
+    impl ecs::Component for Health {
+        type Storage = ecs::VecStorage<Health>;
+    }
+
+    impl SerializeComponent for Health {
+        fn serialize_name() -> &'static str { "Health" }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    struct Label(String);
+
+    impl ecs::Component for Label {
+        type Storage = ecs::VecStorage<Label>;
+    }
+
+    impl SerializeComponent for Label {
+        fn serialize_name() -> &'static str { "Label" }
+    }
+
+    /// Not registered, so never saved
+    #[derive(Clone, Debug)]
+    struct Scratch;
+
+    impl ecs::Component for Scratch {
+        type Storage = ecs::VecStorage<Scratch>;
+    }
+
+    fn registry() -> ComponentRegistry {
+        let mut registry = ComponentRegistry::new();
+
+        registry.register::<Health>();
+        registry.register::<Label>();
+
+        registry
+    }
+
+    fn world(registry: &ComponentRegistry) -> World {
+        let mut world = World::new();
+
+        registry.register_storages(&mut world);
+
+        world.register::<Scratch>();
+
+        world
+    }
+
+    fn save(registry: &ComponentRegistry, world: &World, format: StandardFileFormat) -> Vec<u8> {
+        let mut buffer = Vec::new();
+
+        save_world(registry, world, &mut buffer, format, false).unwrap();
+
+        buffer
+    }
+
+    fn round_trip(format: StandardFileFormat) {
+        let registry = registry();
+
+        let mut source = world(&registry);
+
+        let player = source.create_now().with(Health { current: 50, max: 100 }).with(Label("player".to_string())).build();
+        let crate_ = source.create_now().with(Label("crate".to_string())).with(Scratch).build();
+        let _empty = source.create_now().build();
+
+        let saved = save(&registry, &source, format);
+
+        // Loading next to existing entities forces new IDs
+        let mut target = world(&registry);
+
+        let existing = target.create_now().with(Label("existing".to_string())).build();
+
+        let map = load_world(&registry, &mut target, Cursor::new(saved), format).unwrap();
+
+        assert_eq!(map.len(), 3);
+
+        let loaded_player = map[&player.get_id()];
+        let loaded_crate = map[&crate_.get_id()];
+
+        assert!(loaded_player != existing && loaded_crate != existing);
+
+        let health = target.read::<Health>();
+        let labels = target.read::<Label>();
+
+        assert_eq!(health.get(loaded_player), Some(&Health { current: 50, max: 100 }));
+        assert_eq!(labels.get(loaded_player), Some(&Label("player".to_string())));
+        assert_eq!(health.get(loaded_crate), None);
+        assert_eq!(labels.get(loaded_crate), Some(&Label("crate".to_string())));
+        assert_eq!(labels.get(existing), Some(&Label("existing".to_string())));
+
+        assert!(target.read::<Scratch>().get(loaded_crate).is_none());
+
+        assert_eq!((&target.entities()).iter().count(), 4);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        round_trip(StandardFileFormat::Json);
+    }
+
+    #[test]
+    fn bincode_round_trip() {
+        round_trip(StandardFileFormat::Bincode);
+    }
+
+    #[test]
+    fn yaml_round_trip() {
+        round_trip(StandardFileFormat::Yaml);
+    }
+
+    #[test]
+    fn loading_twice_creates_separate_entities() {
+        let registry = registry();
+
+        let mut source = world(&registry);
+
+        let entity = source.create_now().with(Health { current: 1, max: 2 }).build();
+
+        let saved = save(&registry, &source, StandardFileFormat::Json);
+
+        let mut target = world(&registry);
+
+        let first = load_world(&registry, &mut target, Cursor::new(&saved[..]), StandardFileFormat::Json).unwrap();
+        let second = load_world(&registry, &mut target, Cursor::new(&saved[..]), StandardFileFormat::Json).unwrap();
+
+        assert!(first[&entity.get_id()] != second[&entity.get_id()]);
+        assert_eq!((&target.read::<Health>()).iter().count(), 2);
+    }
+
+    #[test]
+    fn unknown_components_fail_to_load() {
+        let registry = registry();
+
+        let mut source = world(&registry);
+
+        source.create_now().with(Health { current: 1, max: 2 }).build();
+
+        let saved = save(&registry, &source, StandardFileFormat::Json);
+
+        let mut partial = ComponentRegistry::new();
+
+        partial.register::<Label>();
+
+        let mut target = world(&registry);
+
+        assert!(load_world(&partial, &mut target, Cursor::new(saved), StandardFileFormat::Json).is_err());
+    }
+}
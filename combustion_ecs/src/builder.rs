@@ -1,6 +1,21 @@
-//! Acyclic Directed graph dependency builder for
-
+//! Acyclic Directed graph dependency builder for systems
+//!
+//! Systems declare which other systems they depend on, and optionally which components and resources
+//! they read and write. The builder sorts them topologically into stages, where every system in a stage
+//! has had all of its dependencies run in earlier stages and no two systems in a stage access the same data
+//! with at least one of them writing it.
+//!
+//! Each stage is given a single specs priority, from highest to lowest, so systems in the same stage
+//! are free to run in parallel.
+//!
+//! Systems can also be put in groups with their own update policy, such as a fixed timestep.
+//! See the `group` module for details.
+
+use std::any::{Any, TypeId};
+use std::collections::BTreeSet;
 use std::collections::hash_map::Entry;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::intrinsics::type_name;
 
 use fnv::FnvHashMap;
 use specs;
@@ -10,27 +25,195 @@ use petgraph::algo::*;
 use petgraph::visit::*;
 
 use error::*;
+use group::{GroupDispatcher, GroupHandle, UpdatePolicy};
+
+pub type SystemConstructor = Box<FnMut(&mut SystemPlanner, specs::Priority) -> SystemResult<()>>;
+
+/// Planner given to system constructors, which puts every system it adds in the group of the constructor
+pub struct SystemPlanner<'a> {
+    planner: &'a mut super::Planner,
+    group: &'a GroupHandle,
+}
+
+impl<'a> SystemPlanner<'a> {
+    /// Add a system to the planner, wrapped so it only runs when its group does
+    pub fn add_system<S>(&mut self, system: S, name: &str, priority: specs::Priority) where S: specs::System<super::Delta> + 'static {
+        self.planner.add_system(self.group.wrap(system), name, priority);
+    }
+
+    #[inline]
+    pub fn mut_world(&mut self) -> &mut specs::World { self.planner.mut_world() }
+}
+
+/// A single component or resource type accessed by a system
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Access {
+    id: TypeId,
+    name: &'static str,
+}
+
+impl Access {
+    /// Access for the given component or resource type
+    pub fn of<T: Any>() -> Access {
+        Access { id: TypeId::of::<T>(), name: unsafe { type_name::<T>() } }
+    }
+
+    /// Name of the accessed type
+    #[inline]
+    pub fn name(&self) -> &'static str { self.name }
+}
+
+/// Set of components and resources a system reads and writes
+#[derive(Debug, Clone, Default)]
+pub struct SystemAccess {
+    reads: Vec<Access>,
+    writes: Vec<Access>,
+    exclusive: bool,
+}
+
+impl SystemAccess {
+    /// Access to nothing, which never conflicts with other systems
+    pub fn new() -> SystemAccess {
+        SystemAccess::default()
+    }
+
+    /// Access to everything, which conflicts with every other system.
+    ///
+    /// This is assumed for systems which don't declare their access.
+    pub fn exclusive() -> SystemAccess {
+        SystemAccess { exclusive: true, ..SystemAccess::default() }
+    }
+
+    /// Declare a component or resource the system reads
+    pub fn read<T: Any>(mut self) -> SystemAccess {
+        self.reads.push(Access::of::<T>());
+        self
+    }
+
+    /// Declare a component or resource the system writes
+    pub fn write<T: Any>(mut self) -> SystemAccess {
+        self.writes.push(Access::of::<T>());
+        self
+    }
+
+    #[inline]
+    pub fn is_exclusive(&self) -> bool { self.exclusive }
+
+    #[inline]
+    pub fn reads(&self) -> &[Access] { &self.reads }
+
+    #[inline]
+    pub fn writes(&self) -> &[Access] { &self.writes }
+
+    /// Find the name of something both systems access with at least one of them writing it, if any
+    pub fn conflict(&self, other: &SystemAccess) -> Option<&'static str> {
+        if self.exclusive || other.exclusive {
+            return Some("<exclusive>");
+        }
+
+        for write in &self.writes {
+            if other.writes.contains(write) || other.reads.contains(write) {
+                return Some(write.name);
+            }
+        }
+
+        for write in &other.writes {
+            if self.reads.contains(write) {
+                return Some(write.name);
+            }
+        }
+
+        None
+    }
+}
+
+struct SystemNode {
+    name: String,
+    constructor: Option<SystemConstructor>,
+    access: SystemAccess,
+    group: Option<GroupHandle>,
+}
+
+type SystemGraph = Graph<SystemNode, (), Directed, usize>;
+
+/// Systems given the same priority, which may run in parallel
+#[derive(Debug, Clone)]
+pub struct Stage {
+    /// Specs priority of every system in the stage
+    pub priority: specs::Priority,
+    /// Names of the systems in the stage
+    pub systems: Vec<String>,
+}
+
+/// Two systems without a dependency between them which access the same data, so were put in different stages.
+///
+/// The order they run in is only decided by the order they were added, so a dependency should be declared
+/// if it matters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    /// System which runs first
+    pub first: String,
+    /// System which runs second
+    pub second: String,
+    /// Name of the data both systems access
+    pub access: &'static str,
+}
+
+/// Final order of systems, for debugging
+#[derive(Debug, Clone, Default)]
+pub struct Schedule {
+    /// Stages, in the order they run
+    pub stages: Vec<Stage>,
+    /// Conflicts which forced systems into separate stages
+    pub conflicts: Vec<Conflict>,
+}
 
-pub type SystemConstructor = Box<FnMut(&mut super::Planner, specs::Priority) -> SystemResult<()>>;
+impl Schedule {
+    /// Index of the stage containing the named system
+    pub fn stage_of(&self, name: &str) -> Option<usize> {
+        self.stages.iter().position(|stage| stage.systems.iter().any(|system| system == name))
+    }
+}
+
+impl Display for Schedule {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for (i, stage) in self.stages.iter().enumerate() {
+            try!(writeln!(f, "Stage {} (priority {}): {}", i, stage.priority, stage.systems.join(", ")));
+        }
+
+        for conflict in &self.conflicts {
+            try!(writeln!(f, "Conflict: {} runs before {}, both access {}", conflict.first, conflict.second, conflict.access));
+        }
 
-type SystemGraph = Graph<Option<SystemConstructor>, (), Directed, usize>;
+        Ok(())
+    }
+}
 
 pub struct SystemBuilder {
     node_table: FnvHashMap<String, NodeIndex<usize>>,
     root: NodeIndex<usize>,
     graph: SystemGraph,
     cycle_state: DfsSpace<NodeIndex<usize>, < SystemGraph as Visitable >::Map>,
+    groups: GroupDispatcher,
+    group_handles: FnvHashMap<String, GroupHandle>,
 }
 
 impl SystemBuilder {
     pub fn new() -> SystemBuilder {
         let mut graph = Graph::default();
-        let root = graph.add_node(None);
-
-        SystemBuilder { node_table: FnvHashMap::default(), root: root, graph: graph, cycle_state: DfsSpace::default() }
+        let root = graph.add_node(SystemNode { name: String::new(), constructor: None, access: SystemAccess::new(), group: None });
+
+        SystemBuilder {
+            node_table: FnvHashMap::default(),
+            root: root,
+            graph: graph,
+            cycle_state: DfsSpace::default(),
+            groups: GroupDispatcher::new(),
+            group_handles: FnvHashMap::default(),
+        }
     }
 
-    fn add_system_impl(&mut self, name: String, constructor: SystemConstructor) -> SystemResult<NodeIndex<usize>> {
+    fn add_system_impl(&mut self, name: String, constructor: SystemConstructor, access: SystemAccess, group: Option<GroupHandle>) -> SystemResult<NodeIndex<usize>> {
         Ok(match self.node_table.entry(name.into()) {
             Entry::Occupied(occupied_entry) => {
                 let node = occupied_entry.get().clone();
@@ -38,7 +221,9 @@ impl SystemBuilder {
                 // We already have the node index for this system, so it definitely exists.
                 // Overwrite the previous constructor with the new one
                 if let Some(mut weight) = self.graph.node_weight_mut(node) {
-                    *weight = Some(constructor);
+                    weight.constructor = Some(constructor);
+                    weight.access = access;
+                    weight.group = group;
                 } else {
                     // If for some really weird reason the system existed in the node_table but not in the graph, complain about it.
                     throw!(SystemError::DuplicateSystem(occupied_entry.key().clone()));
@@ -48,7 +233,12 @@ impl SystemBuilder {
             },
             Entry::Vacant(vacant_entry) => {
                 // If the system didn't exist, add it to the graph and place the node index in the vacant entry in the node_table
-                let node = self.graph.add_node(Some(constructor));
+                let node = self.graph.add_node(SystemNode {
+                    name: vacant_entry.key().clone(),
+                    constructor: Some(constructor),
+                    access: access,
+                    group: group,
+                });
 
                 vacant_entry.insert(node);
 
@@ -57,28 +247,37 @@ impl SystemBuilder {
         })
     }
 
+    /// Add a system without dependencies, which is assumed to access everything
     pub fn add_system<S: Into<String>>(&mut self, name: S, constructor: SystemConstructor) -> SystemResult<NodeIndex<usize>> {
-        let node = try_rethrow!(self.add_system_impl(name.into(), constructor));
+        self.add_system_with_access(name, constructor, None, SystemAccess::exclusive())
+    }
 
-        // Connect a system with zero dependencies to the root node
-        self.graph.add_edge(self.root, node, ());
+    /// Add a system which runs after all of its dependencies, and is assumed to access everything
+    pub fn add_system_with_deps<S: Into<String>, D: IntoIterator<Item = String>>(&mut self, name: S, constructor: SystemConstructor, deps: D) -> SystemResult<NodeIndex<usize>> {
+        self.add_system_with_access(name, constructor, deps, SystemAccess::exclusive())
+    }
 
-        Ok(node)
+    /// Add a system which runs after all of its dependencies, and only accesses the given data
+    pub fn add_system_with_access<S: Into<String>, D: IntoIterator<Item = String>>(&mut self, name: S, constructor: SystemConstructor, deps: D, access: SystemAccess) -> SystemResult<NodeIndex<usize>> {
+        self.add_system_in_group(name.into(), constructor, deps, access, None)
     }
 
-    pub fn add_system_with_deps<S: Into<String>, D: IntoIterator<Item = String>>(&mut self, name: S, constructor: SystemConstructor, deps: D) -> SystemResult<NodeIndex<usize>> {
-        let node = try_rethrow!(self.add_system_impl(name.into(), constructor));
+    fn add_system_in_group<D: IntoIterator<Item = String>>(&mut self, name: String, constructor: SystemConstructor, deps: D, access: SystemAccess, group: Option<GroupHandle>) -> SystemResult<NodeIndex<usize>> {
+        let node = try_rethrow!(self.add_system_impl(name, constructor, access, group));
 
         for dep in deps.into_iter() {
             let dep_node = match self.node_table.entry(dep) {
                 Entry::Vacant(vacant_entry) => {
                     let dep_name = vacant_entry.key().clone();
 
-                    let dep_node = self.graph.add_node(Some(box move |_, _| {
-                        throw!(SystemError::MissingDependentSystem(dep_name.clone()))
-                    }));
-
-                    self.graph.add_edge(self.root, dep_node, ());
+                    let dep_node = self.graph.add_node(SystemNode {
+                        name: dep_name.clone(),
+                        constructor: Some(box move |_, _| {
+                            throw!(SystemError::MissingDependentSystem(dep_name.clone()))
+                        }),
+                        access: SystemAccess::new(),
+                        group: None,
+                    });
 
                     vacant_entry.insert(dep_node);
 
@@ -87,7 +286,8 @@ impl SystemBuilder {
                 Entry::Occupied(occupied_entry) => {
                     let dep_node = occupied_entry.get().clone();
 
-                    if has_path_connecting(&self.graph, dep_node, node, Some(&mut self.cycle_state)) {
+                    // If the system already leads to its dependency, depending on it would close a loop
+                    if has_path_connecting(&self.graph, node, dep_node, Some(&mut self.cycle_state)) {
                         throw!(SystemError::WouldCycle);
                     }
 
@@ -95,34 +295,175 @@ impl SystemBuilder {
                 }
             };
 
-            self.graph.add_edge(dep_node, node, ());
+            self.graph.update_edge(dep_node, node, ());
         }
 
         Ok(node)
     }
 
-    pub fn build(mut self, mut planner: &mut super::Planner) -> SystemResult<()> {
-        // Since specs has a higher-number = higher-priority sorting policy, we need to start from the max value and go from highest priority systems to lowest.
-        let mut priority = specs::Priority::max_value();
+    /// Add a group of systems with its own update policy, or change the policy of an existing group
+    pub fn add_group<S: Into<String>>(&mut self, name: S, policy: UpdatePolicy) -> SystemResult<GroupHandle> {
+        let name = name.into();
+
+        // Checked before the handle is stored, so systems can't be added to a group that was never created
+        if !policy.is_valid() {
+            throw!(SystemError::InvalidPolicy(name));
+        }
+
+        let handle = self.group_handles.entry(name.clone()).or_insert_with(|| GroupHandle::new(name)).clone();
 
-        let mut dfs = Dfs::new(&self.graph, self.root);
+        try_rethrow!(self.groups.add_group(handle.clone(), policy));
 
-        while let Some(node) = dfs.next(&self.graph) {
-            if let &mut Some(ref mut cb) = self.graph.node_weight_mut(node).unwrap() {
-                try_rethrow!(cb(planner, priority));
+        Ok(handle)
+    }
 
-                priority -= 1;
+    /// Add a system to a group, which runs after all of its dependencies and only accesses the given data.
+    ///
+    /// The system only runs when the group does, and is given the group's delta instead of the frame delta.
+    pub fn add_system_to_group<S, N, D>(&mut self, group: &str, name: N, system: S, deps: D, access: SystemAccess) -> SystemResult<NodeIndex<usize>>
+        where S: specs::System<super::Delta> + 'static,
+              N: Into<String>,
+              D: IntoIterator<Item = String> {
+        let handle = match self.group_handles.get(group) {
+            Some(handle) => handle.clone(),
+            None => throw!(SystemError::MissingGroup(group.to_string())),
+        };
+
+        let name = name.into();
+        let system_name = name.clone();
+
+        let mut system = Some(system);
+
+        let constructor: SystemConstructor = box move |planner, priority| {
+            if let Some(system) = system.take() {
+                planner.add_system(system, &system_name, priority);
             }
+
+            Ok(())
+        };
+
+        self.add_system_in_group(name, constructor, deps, access, Some(handle))
+    }
+
+    /// Compute the stages systems will run in, without building them
+    pub fn schedule(&self) -> SystemResult<Schedule> {
+        let count = self.graph.node_count();
+
+        let mut in_degree = vec![0usize; count];
+
+        for edge in self.graph.raw_edges() {
+            in_degree[edge.target().index()] += 1;
         }
 
-        Ok(())
+        // Earliest stage each system can go in, given the stages of its dependencies
+        let mut earliest = vec![0usize; count];
+
+        // Visiting ready systems in the order they were added keeps the schedule deterministic
+        let mut ready: BTreeSet<NodeIndex<usize>> = self.graph.node_indices()
+                                                        .filter(|node| *node != self.root && in_degree[node.index()] == 0)
+                                                        .collect();
+
+        let mut stages: Vec<Vec<NodeIndex<usize>>> = Vec::new();
+        let mut conflicts = Vec::new();
+        let mut visited = 0;
+
+        while let Some(node) = ready.iter().next().cloned() {
+            ready.remove(&node);
+
+            visited += 1;
+
+            let access = &self.graph[node].access;
+
+            let mut stage = earliest[node.index()];
+
+            loop {
+                if stage == stages.len() {
+                    stages.push(Vec::new());
+                }
+
+                let mut conflict = None;
+
+                for other in &stages[stage] {
+                    if let Some(name) = access.conflict(&self.graph[*other].access) {
+                        conflict = Some((*other, name));
+                        break;
+                    }
+                }
+
+                match conflict {
+                    Some((other, name)) => {
+                        conflicts.push(Conflict {
+                            first: self.graph[other].name.clone(),
+                            second: self.graph[node].name.clone(),
+                            access: name,
+                        });
+
+                        stage += 1;
+                    }
+                    None => break,
+                }
+            }
+
+            stages[stage].push(node);
+
+            for dependent in self.graph.neighbors_directed(node, Outgoing) {
+                let index = dependent.index();
+
+                earliest[index] = earliest[index].max(stage + 1);
+
+                in_degree[index] -= 1;
+
+                if in_degree[index] == 0 {
+                    ready.insert(dependent);
+                }
+            }
+        }
+
+        // Anything not visited is stuck waiting on a cycle
+        if visited != count - 1 {
+            throw!(SystemError::WouldCycle);
+        }
+
+        // Since specs has a higher-number = higher-priority sorting policy, start from the max value and count down
+        Ok(Schedule {
+            stages: stages.into_iter().enumerate().map(|(i, stage)| Stage {
+                priority: specs::Priority::max_value() - i as specs::Priority,
+                systems: stage.into_iter().map(|node| self.graph[node].name.clone()).collect(),
+            }).collect(),
+            conflicts: conflicts,
+        })
+    }
+
+    /// Add all systems to the planner in stage order, returning the schedule that was used
+    /// and the dispatcher which runs the planner according to the group update policies.
+    ///
+    /// Systems only run when their group is active, so the planner should always be run through the dispatcher.
+    pub fn build(mut self, mut planner: &mut super::Planner) -> SystemResult<(Schedule, GroupDispatcher)> {
+        let schedule = try_rethrow!(self.schedule());
+
+        for stage in &schedule.stages {
+            for name in &stage.systems {
+                let node = &mut self.graph[self.node_table[name]];
+
+                let group = node.group.as_ref().unwrap_or(self.groups.ungrouped());
+
+                if let Some(ref mut cb) = node.constructor {
+                    try_rethrow!(cb(&mut SystemPlanner { planner: &mut *planner, group: group }, stage.priority));
+                }
+            }
+        }
+
+        Ok((schedule, self.groups))
     }
 }
 
 #[cfg(test)]
 pub mod test {
+    use std::sync::{Arc, Mutex};
+
     use super::*;
     use ::Planner;
+    use ::group::UpdatePolicy;
 
     macro_rules! dummy {
         ($name:expr) => {box |_, p| {
@@ -136,6 +477,40 @@ pub mod test {
         [$($dep:expr),*] => {[$($dep),*].iter().map(|s| s.to_string())}
     }
 
+    struct A;
+    struct B;
+
+    struct Ticks(u32);
+
+    /// Records the delta of every run
+    struct Record(Arc<Mutex<Vec<::Delta>>>);
+
+    impl specs::System<::Delta> for Record {
+        fn run(&mut self, arg: specs::RunArg, delta: ::Delta) {
+            arg.fetch(|_| ());
+
+            self.0.lock().unwrap().push(delta);
+        }
+    }
+
+    fn record(name: &'static str, runs: &Arc<Mutex<Vec<::Delta>>>) -> SystemConstructor {
+        let runs = runs.clone();
+
+        box move |planner, priority| {
+            planner.add_system(Record(runs.clone()), name, priority);
+
+            Ok(())
+        }
+    }
+
+    impl specs::System<::Delta> for Ticks {
+        fn run(&mut self, arg: specs::RunArg, _: ::Delta) {
+            arg.fetch(|_| ());
+
+            self.0 += 1;
+        }
+    }
+
     #[test]
     fn basic() {
         let mut builder = SystemBuilder::new();
@@ -171,4 +546,119 @@ pub mod test {
 
         builder.build(&mut planner).unwrap();
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn dependencies_run_first() {
+        let mut builder = SystemBuilder::new();
+
+        // "last" depends on both, and "second" is added after "last" so a depth-first walk would visit it too late
+        builder.add_system("first", dummy!("first")).unwrap();
+        builder.add_system_with_deps("last", dummy!("last"), deps!["first", "second"]).unwrap();
+        builder.add_system_with_deps("second", dummy!("second"), deps!["first"]).unwrap();
+
+        let schedule = builder.schedule().unwrap();
+
+        assert!(schedule.stage_of("first") < schedule.stage_of("second"));
+        assert!(schedule.stage_of("second") < schedule.stage_of("last"));
+    }
+
+    #[test]
+    fn parallel_stages() {
+        let mut builder = SystemBuilder::new();
+
+        builder.add_system_with_access("write_a", dummy!("write_a"), None, SystemAccess::new().write::<A>()).unwrap();
+        builder.add_system_with_access("write_b", dummy!("write_b"), None, SystemAccess::new().write::<B>()).unwrap();
+        builder.add_system_with_access("read_both", dummy!("read_both"), deps!["write_a", "write_b"], SystemAccess::new().read::<A>().read::<B>()).unwrap();
+        builder.add_system_with_access("read_a", dummy!("read_a"), deps!["write_a"], SystemAccess::new().read::<A>()).unwrap();
+
+        let schedule = builder.schedule().unwrap();
+
+        assert_eq!(schedule.stages.len(), 2);
+        assert_eq!(schedule.stages[0].systems, vec!["write_a".to_string(), "write_b".to_string()]);
+        assert_eq!(schedule.stages[1].systems, vec!["read_both".to_string(), "read_a".to_string()]);
+        assert!(schedule.conflicts.is_empty());
+
+        assert!(schedule.stages[0].priority > schedule.stages[1].priority);
+
+        let mut planner = Planner::new(specs::World::new(), 4);
+
+        builder.build(&mut planner).unwrap();
+    }
+
+    #[test]
+    fn conflicting_systems_are_split() {
+        let mut builder = SystemBuilder::new();
+
+        builder.add_system_with_access("first", dummy!("first"), None, SystemAccess::new().write::<A>()).unwrap();
+        builder.add_system_with_access("second", dummy!("second"), None, SystemAccess::new().read::<A>()).unwrap();
+        builder.add_system("exclusive", dummy!("exclusive")).unwrap();
+
+        let schedule = builder.schedule().unwrap();
+
+        assert_eq!(schedule.stages.len(), 3);
+        assert_eq!(schedule.conflicts.len(), 3);
+        assert_eq!(schedule.conflicts[0].first, "first");
+        assert_eq!(schedule.conflicts[0].second, "second");
+    }
+
+    #[test]
+    fn grouped_systems() {
+        let mut builder = SystemBuilder::new();
+
+        builder.add_group("physics", UpdatePolicy::Fixed { step: 0.01, max_steps: 4 }).unwrap();
+        builder.add_group("render", UpdatePolicy::Variable).unwrap();
+
+        builder.add_system_to_group("physics", "physics", Ticks(0), None, SystemAccess::new().write::<A>()).unwrap();
+        builder.add_system_to_group("render", "render", Ticks(0), deps!["physics"], SystemAccess::new().read::<A>()).unwrap();
+
+        assert!(builder.add_system_to_group("missing", "missing", Ticks(0), None, SystemAccess::new()).is_err());
+
+        assert!(builder.add_group("broken", UpdatePolicy::Fixed { step: 0.0, max_steps: 4 }).is_err());
+        assert!(builder.add_system_to_group("broken", "broken", Ticks(0), None, SystemAccess::new()).is_err());
+
+        let mut planner = Planner::new(specs::World::new(), 4);
+
+        let (_, mut dispatcher) = builder.build(&mut planner).unwrap();
+
+        assert_eq!(dispatcher.dispatch(&mut planner, 0.035), 3);
+        assert_eq!(dispatcher.dispatch(&mut planner, 0.001), 1);
+
+        dispatcher.set_policy("physics", UpdatePolicy::Paused).unwrap();
+        assert_eq!(dispatcher.policy("physics"), Some(UpdatePolicy::Paused));
+    }
+
+    #[test]
+    fn ungrouped_systems_run_once_per_frame() {
+        let mut builder = SystemBuilder::new();
+
+        let fixed = Arc::new(Mutex::new(Vec::new()));
+        let ungrouped = Arc::new(Mutex::new(Vec::new()));
+        let with_deps = Arc::new(Mutex::new(Vec::new()));
+
+        builder.add_group("physics", UpdatePolicy::Fixed { step: 0.01, max_steps: 4 }).unwrap();
+
+        builder.add_system_to_group("physics", "physics", Record(fixed.clone()), None, SystemAccess::new().write::<A>()).unwrap();
+        builder.add_system("ungrouped", record("ungrouped", &ungrouped)).unwrap();
+        builder.add_system_with_deps("with_deps", record("with_deps", &with_deps), deps!["physics"]).unwrap();
+
+        let mut planner = Planner::new(specs::World::new(), 4);
+
+        let (_, mut dispatcher) = builder.build(&mut planner).unwrap();
+
+        assert_eq!(dispatcher.dispatch(&mut planner, 0.035), 3);
+
+        assert_eq!(fixed.lock().unwrap().len(), 3);
+        assert!(fixed.lock().unwrap().iter().all(|&delta| delta == 0.01));
+
+        // Only once, with the whole frame delta
+        assert_eq!(*ungrouped.lock().unwrap(), vec![0.035]);
+        assert_eq!(*with_deps.lock().unwrap(), vec![0.035]);
+
+        // Frames without any fixed ticks still run them
+        assert_eq!(dispatcher.dispatch(&mut planner, 0.001), 1);
+
+        assert_eq!(fixed.lock().unwrap().len(), 3);
+        assert_eq!(*ungrouped.lock().unwrap(), vec![0.035, 0.001]);
+        assert_eq!(*with_deps.lock().unwrap(), vec![0.035, 0.001]);
+    }
+}
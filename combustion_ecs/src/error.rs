@@ -10,6 +10,8 @@ pub enum SystemError {
     WouldCycle,
     MissingDependentSystem(String),
     DuplicateSystem(String),
+    MissingGroup(String),
+    InvalidPolicy(String),
 }
 
 impl Display for SystemError {
@@ -24,6 +26,8 @@ impl Error for SystemError {
             SystemError::WouldCycle => "Dependency Would Cycle",
             SystemError::MissingDependentSystem(_) => "Missing Dependent System",
             SystemError::DuplicateSystem(_) => "Duplicate System",
+            SystemError::MissingGroup(_) => "Missing System Group",
+            SystemError::InvalidPolicy(_) => "Invalid Group Update Policy",
         }
     }
 }
\ No newline at end of file
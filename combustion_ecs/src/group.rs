@@ -0,0 +1,346 @@
+//! System groups with their own update rates
+//!
+//! Every system in the specs `Planner` runs once per `dispatch`, so systems needing a different rate are put
+//! in a group with an `UpdatePolicy`. Each frame, `GroupDispatcher::dispatch` works out how many ticks every group
+//! needs and dispatches the planner enough times to run the busiest group, with the other groups skipping the
+//! passes they don't need. Ticks are aligned to the end of the frame, so per-frame systems see the latest results of
+//! fixed-rate systems.
+//!
+//! Systems added outside of a group run once per frame with the frame delta, on the last pass,
+//! as if they were in a variable group.
+//!
+//! The timing of each group, including the interpolation alpha for rendering between fixed ticks,
+//! is available as the `GroupTimings` resource.
+
+use std::sync::{Arc, RwLock};
+
+use fnv::FnvHashMap;
+use specs;
+
+use super::{Delta, Planner};
+use super::error::{SystemError, SystemResult};
+
+/// How often the systems of a group run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UpdatePolicy {
+    /// Once per frame with the frame delta
+    Variable,
+    /// At a fixed rate, running up to `max_steps` ticks per frame to catch up.
+    ///
+    /// Any time beyond that is dropped, so a slow frame can't cause ever longer frames.
+    Fixed {
+        /// Time between ticks
+        step: Delta,
+        /// Maximum number of ticks in a single frame
+        max_steps: u32,
+    },
+    /// Once every N frames, with the combined delta of those frames
+    EveryNFrames(u32),
+    /// Not at all
+    Paused,
+}
+
+impl UpdatePolicy {
+    /// Checks the policy can actually advance, so a fixed step has to be positive.
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            // Also rejects NaN
+            UpdatePolicy::Fixed { step, .. } => step > 0.0,
+            _ => true,
+        }
+    }
+}
+
+/// Timing of a group for the current frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GroupTiming {
+    /// Number of times the group runs this frame
+    pub ticks: u32,
+    /// Delta given to the systems of the group for each tick
+    pub delta: Delta,
+    /// How far between the last tick and the next one the frame is, from `0.0` to `1.0`.
+    ///
+    /// Rendering systems can use this to interpolate the results of fixed-rate groups.
+    pub alpha: Delta,
+}
+
+/// Resource holding the timing of every group for the current frame
+#[derive(Debug, Clone, Default)]
+pub struct GroupTimings(FnvHashMap<String, GroupTiming>);
+
+impl GroupTimings {
+    /// Timing of the named group
+    pub fn get(&self, group: &str) -> Option<&GroupTiming> {
+        self.0.get(group)
+    }
+
+    /// Interpolation alpha of the named group, or `1.0` if there is no such group
+    pub fn alpha(&self, group: &str) -> Delta {
+        self.0.get(group).map_or(1.0, |timing| timing.alpha)
+    }
+}
+
+#[derive(Debug, Default)]
+struct GroupState {
+    active: bool,
+    delta: Delta,
+}
+
+/// Shared handle to a group, used to put systems in it
+#[derive(Debug, Clone)]
+pub struct GroupHandle {
+    name: String,
+    state: Arc<RwLock<GroupState>>,
+}
+
+impl GroupHandle {
+    pub fn new<S: Into<String>>(name: S) -> GroupHandle {
+        GroupHandle { name: name.into(), state: Arc::default() }
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str { &self.name }
+
+    /// Wrap a system so it only runs when the group does
+    pub fn wrap<S>(&self, system: S) -> GroupedSystem<S> where S: specs::System<Delta> {
+        GroupedSystem { system: system, state: self.state.clone() }
+    }
+}
+
+/// System which only runs on the passes its group is active, with the group's delta
+pub struct GroupedSystem<S> {
+    system: S,
+    state: Arc<RwLock<GroupState>>,
+}
+
+impl<S> specs::System<Delta> for GroupedSystem<S> where S: specs::System<Delta> {
+    fn run(&mut self, arg: specs::RunArg, _: Delta) {
+        let (active, delta) = {
+            let state = self.state.read().unwrap();
+
+            (state.active, state.delta)
+        };
+
+        if active {
+            self.system.run(arg, delta);
+        } else {
+            // The planner still expects the system to fetch its data
+            arg.fetch(|_| ());
+        }
+    }
+}
+
+struct Group {
+    handle: GroupHandle,
+    policy: UpdatePolicy,
+    accumulator: Delta,
+    frames: u32,
+    alpha: Delta,
+}
+
+impl Group {
+    fn new(handle: GroupHandle, policy: UpdatePolicy) -> Group {
+        Group { handle: handle, policy: policy, accumulator: 0.0, frames: 0, alpha: 1.0 }
+    }
+
+    /// Advance the group clock by a frame
+    fn advance(&mut self, frame_delta: Delta) -> GroupTiming {
+        match self.policy {
+            UpdatePolicy::Variable => {
+                self.alpha = 1.0;
+
+                GroupTiming { ticks: 1, delta: frame_delta, alpha: 1.0 }
+            }
+            UpdatePolicy::Fixed { step, max_steps } => {
+                self.accumulator += frame_delta;
+
+                let mut ticks = 0;
+
+                while self.accumulator >= step && ticks < max_steps {
+                    self.accumulator -= step;
+                    ticks += 1;
+                }
+
+                // Too far behind to catch up, so drop the extra time
+                if self.accumulator >= step {
+                    self.accumulator %= step;
+                }
+
+                self.alpha = self.accumulator / step;
+
+                GroupTiming { ticks: ticks, delta: step, alpha: self.alpha }
+            }
+            UpdatePolicy::EveryNFrames(frames) => {
+                self.frames += 1;
+                self.accumulator += frame_delta;
+
+                if self.frames >= frames {
+                    let delta = self.accumulator;
+
+                    self.frames = 0;
+                    self.accumulator = 0.0;
+                    self.alpha = 1.0;
+
+                    GroupTiming { ticks: 1, delta: delta, alpha: 1.0 }
+                } else {
+                    self.alpha = self.frames as Delta / frames as Delta;
+
+                    GroupTiming { ticks: 0, delta: 0.0, alpha: self.alpha }
+                }
+            }
+            UpdatePolicy::Paused => {
+                GroupTiming { ticks: 0, delta: 0.0, alpha: self.alpha }
+            }
+        }
+    }
+}
+
+/// Runs the planner according to the update policies of all groups
+pub struct GroupDispatcher {
+    groups: Vec<Group>,
+    ungrouped: GroupHandle,
+}
+
+impl GroupDispatcher {
+    pub fn new() -> GroupDispatcher {
+        GroupDispatcher { groups: Vec::new(), ungrouped: GroupHandle::new("") }
+    }
+
+    /// Handle of the implicit group holding every system added outside of a group
+    #[inline]
+    pub fn ungrouped(&self) -> &GroupHandle { &self.ungrouped }
+
+    /// Add a group, or change the policy of an existing group with the same name
+    pub fn add_group(&mut self, handle: GroupHandle, policy: UpdatePolicy) -> SystemResult<()> {
+        if !policy.is_valid() {
+            throw!(SystemError::InvalidPolicy(handle.name.clone()));
+        }
+
+        if let Some(group) = self.groups.iter_mut().find(|group| group.handle.name == handle.name) {
+            group.policy = policy;
+
+            return Ok(());
+        }
+
+        self.groups.push(Group::new(handle, policy));
+
+        Ok(())
+    }
+
+    /// Change the policy of an existing group
+    pub fn set_policy(&mut self, name: &str, policy: UpdatePolicy) -> SystemResult<()> {
+        if !policy.is_valid() {
+            throw!(SystemError::InvalidPolicy(name.to_string()));
+        }
+
+        match self.groups.iter_mut().find(|group| group.handle.name == name) {
+            Some(group) => {
+                group.policy = policy;
+
+                Ok(())
+            }
+            None => throw!(SystemError::MissingGroup(name.to_string())),
+        }
+    }
+
+    /// Current policy of a group
+    pub fn policy(&self, name: &str) -> Option<UpdatePolicy> {
+        self.groups.iter().find(|group| group.handle.name == name).map(|group| group.policy)
+    }
+
+    /// Advance all groups by the frame delta and run the planner as many times as the busiest group needs.
+    ///
+    /// Returns the number of passes.
+    pub fn dispatch(&mut self, planner: &mut Planner, frame_delta: Delta) -> u32 {
+        let timings: Vec<GroupTiming> = self.groups.iter_mut().map(|group| group.advance(frame_delta)).collect();
+
+        let passes = timings.iter().fold(1, |passes, timing| passes.max(timing.ticks));
+
+        planner.mut_world().add_resource(GroupTimings(self.groups.iter().zip(timings.iter()).map(|(group, timing)| {
+            (group.handle.name.clone(), *timing)
+        }).collect()));
+
+        for pass in 0..passes {
+            for (group, timing) in self.groups.iter().zip(timings.iter()) {
+                let mut state = group.handle.state.write().unwrap();
+
+                // Align ticks to the end of the frame
+                state.active = pass >= passes - timing.ticks;
+                state.delta = timing.delta;
+            }
+
+            {
+                let mut state = self.ungrouped.state.write().unwrap();
+
+                state.active = pass == passes - 1;
+                state.delta = frame_delta;
+            }
+
+            planner.dispatch(frame_delta);
+            planner.wait();
+        }
+
+        passes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fixed_timestep() {
+        let mut group = Group::new(GroupHandle::new("physics"), UpdatePolicy::Fixed { step: 0.01, max_steps: 5 });
+
+        assert_eq!(group.advance(0.025).ticks, 2);
+
+        // The remaining half step carries over
+        let timing = group.advance(0.005);
+
+        assert_eq!(timing.ticks, 1);
+        assert!(timing.alpha.abs() < 1e-9);
+
+        // A long frame only catches up by the maximum number of steps
+        let timing = group.advance(1.0);
+
+        assert_eq!(timing.ticks, 5);
+        assert!(timing.alpha < 1.0);
+        assert_eq!(group.advance(0.0).ticks, 0);
+    }
+
+    #[test]
+    fn every_n_frames() {
+        let mut group = Group::new(GroupHandle::new("network"), UpdatePolicy::EveryNFrames(3));
+
+        assert_eq!(group.advance(0.1).ticks, 0);
+        assert_eq!(group.advance(0.1).ticks, 0);
+
+        let timing = group.advance(0.1);
+
+        assert_eq!(timing.ticks, 1);
+        assert!((timing.delta - 0.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn invalid_fixed_step() {
+        let mut dispatcher = GroupDispatcher::new();
+
+        assert!(dispatcher.add_group(GroupHandle::new("zero"), UpdatePolicy::Fixed { step: 0.0, max_steps: 5 }).is_err());
+        assert!(dispatcher.add_group(GroupHandle::new("negative"), UpdatePolicy::Fixed { step: -0.01, max_steps: 5 }).is_err());
+        assert!(dispatcher.add_group(GroupHandle::new("nan"), UpdatePolicy::Fixed { step: ::std::f64::NAN, max_steps: 5 }).is_err());
+        assert_eq!(dispatcher.policy("zero"), None);
+
+        dispatcher.add_group(GroupHandle::new("physics"), UpdatePolicy::Fixed { step: 0.01, max_steps: 5 }).unwrap();
+
+        assert!(dispatcher.set_policy("physics", UpdatePolicy::Fixed { step: 0.0, max_steps: 5 }).is_err());
+        assert_eq!(dispatcher.policy("physics"), Some(UpdatePolicy::Fixed { step: 0.01, max_steps: 5 }));
+        assert!(dispatcher.set_policy("missing", UpdatePolicy::Paused).is_err());
+    }
+
+    #[test]
+    fn paused() {
+        let mut group = Group::new(GroupHandle::new("paused"), UpdatePolicy::Paused);
+
+        assert_eq!(group.advance(1.0).ticks, 0);
+    }
+}
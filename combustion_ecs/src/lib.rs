@@ -1,15 +1,19 @@
-#![feature(box_syntax)]
+#![feature(box_syntax, core_intrinsics)]
 
 extern crate specs;
 extern crate petgraph;
 //extern crate num_cpus;
 extern crate fnv;
+extern crate serde;
+extern crate erased_serde;
 
 #[macro_use]
 extern crate trace_error;
 
 pub mod error;
 pub mod builder;
+pub mod group;
+pub mod serialize;
 pub mod macros;
 
 pub type Delta = f64;
@@ -18,11 +22,16 @@ pub type Planner = specs::Planner<Delta>;
 pub use specs::{
     Entity,
     Component,
+    Join,
     System,
     World,
     VecStorage,
     NullStorage,
     HashMapStorage,
     UnprotectedStorage,
+    MaskedStorage,
+    Storage,
+    Allocator,
+    Entities,
     RunArg
 };
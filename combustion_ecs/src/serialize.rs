@@ -0,0 +1,359 @@
+//! Serialization of components and whole worlds
+//!
+//! Components opt in with `#[derive(Component)]` and `#[ecs(serialize)]`, which gives them a stable name and ID,
+//! and are then registered with a `ComponentRegistry`. The registry can serialize every registered component of every
+//! entity in a world with any serde format, and load them back into another world.
+//!
+//! Entities are stored by their ID at the time they were saved. Loading creates new entities, so the IDs are remapped
+//! and the `EntityMap` from saved IDs to the new entities is returned. Components which store entities themselves
+//! must be fixed up with it afterwards.
+//!
+//! A snapshot looks like this, in whatever format is used:
+//!
+//! ```text
+//! {
+//!     "entities": [0, 1, 4],
+//!     "components": {
+//!         "Health": [[0, { "current": 50, "max": 100 }], [4, { "current": 10, "max": 10 }]]
+//!     }
+//! }
+//! ```
+
+use std::fmt;
+
+use fnv::FnvHashMap;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::ser::{SerializeMap, SerializeStruct};
+use serde::de::{DeserializeSeed, Visitor, MapVisitor, SeqVisitor};
+use serde::de::Error as DeError;
+use erased_serde;
+
+use specs::Join;
+
+use super::{Component, Entity, World};
+
+/// Map from entity IDs in a snapshot to the entities created for them
+pub type EntityMap = FnvHashMap<u32, Entity>;
+
+/// Component with a stable name and ID, which can be saved and loaded with serde
+pub trait SerializeComponent: Component + Serialize + Deserialize {
+    /// Stable name of the component, used as its key in snapshots
+    fn serialize_name() -> &'static str;
+
+    /// Stable ID of the component, derived from its name by default
+    fn serialize_id() -> u64 {
+        stable_id(Self::serialize_name())
+    }
+}
+
+/// 64-bit FNV-1a hash of a component name, which stays the same across builds and platforms
+pub fn stable_id(name: &str) -> u64 {
+    name.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+struct ComponentEntry {
+    name: &'static str,
+    id: u64,
+    register: fn(&mut World),
+    save: fn(&World, &mut FnMut(&erased_serde::Serialize)),
+    load: fn(&World, &EntityMap, &mut erased_serde::Deserializer) -> Result<(), erased_serde::Error>,
+}
+
+fn register_storage<C: SerializeComponent>(world: &mut World) {
+    world.register::<C>();
+}
+
+fn save_storage<C: SerializeComponent>(world: &World, f: &mut FnMut(&erased_serde::Serialize)) {
+    let storage = world.read::<C>();
+    let entities = world.entities();
+
+    let components: Vec<(u32, &C)> = (&storage, &entities).iter().map(|(component, entity)| {
+        (entity.get_id() as u32, component)
+    }).collect();
+
+    f(&components);
+}
+
+fn load_storage<C: SerializeComponent>(world: &World, entities: &EntityMap, deserializer: &mut erased_serde::Deserializer) -> Result<(), erased_serde::Error> {
+    let components: Vec<(u32, C)> = try!(erased_serde::deserialize(deserializer));
+
+    let mut storage = world.write::<C>();
+
+    for (id, component) in components {
+        match entities.get(&id) {
+            Some(entity) => { storage.insert(*entity, component); }
+            None => return Err(erased_serde::Error::custom(format!("Component {} on unknown entity {}", C::serialize_name(), id))),
+        }
+    }
+
+    Ok(())
+}
+
+/// Set of component types which are saved in world snapshots
+#[derive(Default)]
+pub struct ComponentRegistry {
+    entries: Vec<ComponentEntry>,
+}
+
+impl ComponentRegistry {
+    pub fn new() -> ComponentRegistry {
+        ComponentRegistry::default()
+    }
+
+    /// Register a component type. Registering the same name twice replaces the previous type.
+    ///
+    /// # Panics
+    ///
+    /// If a component with a different name already has the same ID.
+    pub fn register<C: SerializeComponent>(&mut self) {
+        let entry = ComponentEntry {
+            name: C::serialize_name(),
+            id: C::serialize_id(),
+            register: register_storage::<C>,
+            save: save_storage::<C>,
+            load: load_storage::<C>,
+        };
+
+        if let Some(existing) = self.entries.iter_mut().find(|existing| existing.id == entry.id) {
+            assert_eq!(existing.name, entry.name, "Component ID collision");
+
+            *existing = entry;
+
+            return;
+        }
+
+        self.entries.push(entry);
+    }
+
+    /// Returns `true` if a component type is registered with the given name
+    pub fn is_registered(&self, name: &str) -> bool {
+        self.entries.iter().any(|entry| entry.name == name)
+    }
+
+    /// Name of the component registered with the given ID
+    pub fn name_of(&self, id: u64) -> Option<&'static str> {
+        self.entries.iter().find(|entry| entry.id == id).map(|entry| entry.name)
+    }
+
+    /// ID of the component registered with the given name
+    pub fn id_of(&self, name: &str) -> Option<u64> {
+        self.entries.iter().find(|entry| entry.name == name).map(|entry| entry.id)
+    }
+
+    /// Register the storage of every registered component type with a world.
+    ///
+    /// Storages which are already registered are replaced, so this should be done before adding any components.
+    pub fn register_storages(&self, world: &mut World) {
+        for entry in &self.entries {
+            (entry.register)(world);
+        }
+    }
+
+    /// Serializable snapshot of all entities and registered components in the world
+    pub fn serializer<'a>(&'a self, world: &'a World) -> WorldSerializer<'a> {
+        WorldSerializer { registry: self, world: world }
+    }
+
+    /// Seed which loads a snapshot into the world, creating new entities for it.
+    ///
+    /// The component storages must already be registered with the world.
+    pub fn deserializer<'a>(&'a self, world: &'a mut World) -> WorldDeserializer<'a> {
+        WorldDeserializer { registry: self, world: world }
+    }
+}
+
+/// Serializes all entities and registered components of a world
+pub struct WorldSerializer<'a> {
+    registry: &'a ComponentRegistry,
+    world: &'a World,
+}
+
+struct ComponentsSerializer<'a> {
+    registry: &'a ComponentRegistry,
+    world: &'a World,
+}
+
+struct StorageSerializer<'a> {
+    entry: &'a ComponentEntry,
+    world: &'a World,
+}
+
+impl<'a> Serialize for WorldSerializer<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entities: Vec<u32> = {
+            let entities = self.world.entities();
+
+            (&entities).iter().map(|entity| entity.get_id() as u32).collect()
+        };
+
+        let mut state = try!(serializer.serialize_struct("WorldSnapshot", 2));
+
+        try!(state.serialize_field("entities", &entities));
+        try!(state.serialize_field("components", &ComponentsSerializer { registry: self.registry, world: self.world }));
+
+        state.end()
+    }
+}
+
+impl<'a> Serialize for ComponentsSerializer<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = try!(serializer.serialize_map(Some(self.registry.entries.len())));
+
+        for entry in &self.registry.entries {
+            try!(state.serialize_key(entry.name));
+            try!(state.serialize_value(&StorageSerializer { entry: entry, world: self.world }));
+        }
+
+        state.end()
+    }
+}
+
+impl<'a> Serialize for StorageSerializer<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut serializer = Some(serializer);
+        let mut result = None;
+
+        (self.entry.save)(self.world, &mut |components| {
+            if let Some(serializer) = serializer.take() {
+                result = Some(erased_serde::serialize(components, serializer));
+            }
+        });
+
+        result.expect("Component storage was not serialized")
+    }
+}
+
+/// Loads a snapshot into a world, returning the map from saved entity IDs to new entities
+pub struct WorldDeserializer<'a> {
+    registry: &'a ComponentRegistry,
+    world: &'a mut World,
+}
+
+const FIELDS: &'static [&'static str] = &["entities", "components"];
+
+impl<'a> DeserializeSeed for WorldDeserializer<'a> {
+    type Value = EntityMap;
+
+    fn deserialize<D: Deserializer>(self, deserializer: D) -> Result<EntityMap, D::Error> {
+        deserializer.deserialize_struct("WorldSnapshot", FIELDS, self)
+    }
+}
+
+impl<'a> WorldDeserializer<'a> {
+    fn create_entities(&mut self, ids: Vec<u32>) -> EntityMap {
+        ids.into_iter().map(|id| (id, self.world.create_now().build())).collect()
+    }
+}
+
+impl<'a> Visitor for WorldDeserializer<'a> {
+    type Value = EntityMap;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a world snapshot")
+    }
+
+    fn visit_seq<V: SeqVisitor>(mut self, mut visitor: V) -> Result<EntityMap, V::Error> {
+        let ids: Vec<u32> = match try!(visitor.visit()) {
+            Some(ids) => ids,
+            None => return Err(V::Error::invalid_length(0, &self)),
+        };
+
+        let entities = self.create_entities(ids);
+
+        let seed = ComponentsDeserializer { registry: self.registry, world: &*self.world, entities: &entities };
+
+        if try!(visitor.visit_seed(seed)).is_none() {
+            return Err(V::Error::invalid_length(1, &"a world snapshot"));
+        }
+
+        Ok(entities)
+    }
+
+    fn visit_map<V: MapVisitor>(mut self, mut visitor: V) -> Result<EntityMap, V::Error> {
+        let mut entities = None;
+        let mut components = false;
+
+        while let Some(key) = try!(visitor.visit_key::<String>()) {
+            match key.as_str() {
+                "entities" => {
+                    if entities.is_some() {
+                        return Err(V::Error::duplicate_field("entities"));
+                    }
+
+                    let ids: Vec<u32> = try!(visitor.visit_value());
+
+                    entities = Some(self.create_entities(ids));
+                }
+                "components" => {
+                    // Components refer to entities, so those have to come first
+                    let entities = match entities {
+                        Some(ref entities) => entities,
+                        None => return Err(V::Error::custom("Components must come after entities in a world snapshot")),
+                    };
+
+                    if components {
+                        return Err(V::Error::duplicate_field("components"));
+                    }
+
+                    try!(visitor.visit_value_seed(ComponentsDeserializer { registry: self.registry, world: &*self.world, entities: entities }));
+
+                    components = true;
+                }
+                field => return Err(V::Error::unknown_field(field, FIELDS)),
+            }
+        }
+
+        entities.ok_or_else(|| V::Error::missing_field("entities"))
+    }
+}
+
+struct ComponentsDeserializer<'a> {
+    registry: &'a ComponentRegistry,
+    world: &'a World,
+    entities: &'a EntityMap,
+}
+
+struct StorageDeserializer<'a> {
+    entry: &'a ComponentEntry,
+    world: &'a World,
+    entities: &'a EntityMap,
+}
+
+impl<'a> DeserializeSeed for ComponentsDeserializer<'a> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'a> Visitor for ComponentsDeserializer<'a> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of component names to components")
+    }
+
+    fn visit_map<V: MapVisitor>(self, mut visitor: V) -> Result<(), V::Error> {
+        while let Some(name) = try!(visitor.visit_key::<String>()) {
+            let entry = match self.registry.entries.iter().find(|entry| entry.name == name) {
+                Some(entry) => entry,
+                None => return Err(V::Error::custom(format!("Unregistered component {}", name))),
+            };
+
+            try!(visitor.visit_value_seed(StorageDeserializer { entry: entry, world: self.world, entities: self.entities }));
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> DeserializeSeed for StorageDeserializer<'a> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer>(self, deserializer: D) -> Result<(), D::Error> {
+        let mut deserializer = erased_serde::Deserializer::erase(deserializer);
+
+        (self.entry.load)(self.world, self.entities, &mut deserializer).map_err(D::Error::custom)
+    }
+}
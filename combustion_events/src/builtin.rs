@@ -0,0 +1,104 @@
+//! Event types sent by the engine itself
+
+use std::path::PathBuf;
+
+use ecs::Entity;
+
+/// Entity lifecycle events
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntityEvent {
+    /// Entity was created
+    Created(Entity),
+    /// Entity was destroyed
+    Destroyed(Entity),
+    /// Component with the given name was added to the entity
+    ComponentAdded(Entity, &'static str),
+    /// Component with the given name was removed from the entity
+    ComponentRemoved(Entity, &'static str),
+}
+
+/// Collision events between two entities
+#[derive(Debug, Clone, PartialEq)]
+pub enum CollisionEvent {
+    /// The entities started touching
+    Started {
+        /// First entity
+        a: Entity,
+        /// Second entity
+        b: Entity,
+        /// Contact point, in world space
+        point: [f32; 3],
+        /// Contact normal pointing from `a` to `b`
+        normal: [f32; 3],
+        /// Penetration depth
+        depth: f32,
+    },
+    /// The entities stopped touching
+    Ended {
+        /// First entity
+        a: Entity,
+        /// Second entity
+        b: Entity,
+    },
+}
+
+/// Key or button state change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// Pressed down
+    Press,
+    /// Released
+    Release,
+    /// Held down long enough to repeat
+    Repeat,
+}
+
+/// Input events from the window
+///
+/// Keys and buttons use the codes of the window backend.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEvent {
+    /// Keyboard key
+    Key {
+        /// Backend key code
+        key: i32,
+        /// Platform-specific scancode
+        scancode: i32,
+        /// What happened to the key
+        action: Action,
+        /// Bitmask of held modifier keys
+        modifiers: u32,
+    },
+    /// Text input
+    Char(char),
+    /// Mouse button
+    MouseButton {
+        /// Backend button code
+        button: i32,
+        /// What happened to the button
+        action: Action,
+        /// Bitmask of held modifier keys
+        modifiers: u32,
+    },
+    /// Cursor moved to the given position, in screen coordinates
+    CursorMoved(f64, f64),
+    /// Scroll wheel or touchpad scrolling
+    Scroll(f64, f64),
+    /// Window was resized to the given size, in pixels
+    Resized(i32, i32),
+    /// Window gained or lost focus
+    Focus(bool),
+    /// Window was asked to close
+    Close,
+}
+
+/// Asset events, for hot reloading
+#[derive(Debug, Clone, PartialEq)]
+pub enum AssetEvent {
+    /// Asset was changed on disk and has been reloaded
+    Reloaded(PathBuf),
+    /// Asset was removed
+    Removed(PathBuf),
+    /// Asset was changed on disk, but failed to reload
+    Failed(PathBuf, String),
+}
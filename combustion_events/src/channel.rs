@@ -0,0 +1,251 @@
+//! Typed, double-buffered event channels
+//!
+//! An `EventChannel<T>` is meant to be used as an ECS resource. Systems write events into it at any point in a frame,
+//! and every reader keeps its own cursor, so any number of systems can read the same events without consuming them.
+//!
+//! Events are kept for two frames. `maintain` should be called once at the start of every frame, which drops the events
+//! from two frames ago. This way a reader which runs before a writer in the same frame still sees its events the next frame.
+
+use std::iter::Chain;
+use std::slice::Iter;
+
+/// Cursor of a single reader into an `EventChannel`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ReaderId {
+    cursor: u64,
+}
+
+/// Iterator over events not yet seen by a reader
+pub type EventIter<'a, T> = Chain<Iter<'a, T>, Iter<'a, T>>;
+
+/// Double-buffered channel of events of a single type
+#[derive(Debug, Clone)]
+pub struct EventChannel<T> {
+    /// Events from the previous frame
+    previous: Vec<T>,
+    /// Events from this frame
+    current: Vec<T>,
+    /// Sequence number of the first event in `previous`
+    offset: u64,
+}
+
+impl<T> Default for EventChannel<T> {
+    #[inline(always)]
+    fn default() -> EventChannel<T> { EventChannel::new() }
+}
+
+impl<T> EventChannel<T> {
+    /// Create a new empty channel
+    pub fn new() -> EventChannel<T> {
+        EventChannel { previous: Vec::new(), current: Vec::new(), offset: 0 }
+    }
+
+    /// Sequence number one past the last event written
+    #[inline]
+    fn end(&self) -> u64 {
+        self.offset + (self.previous.len() + self.current.len()) as u64
+    }
+
+    /// Create a reader which will see every event written from now on
+    pub fn register_reader(&self) -> ReaderId {
+        ReaderId { cursor: self.end() }
+    }
+
+    /// Write a single event
+    #[inline]
+    pub fn single_write(&mut self, event: T) {
+        self.current.push(event);
+    }
+
+    /// Write every event from an iterator
+    pub fn iter_write<I>(&mut self, events: I) where I: IntoIterator<Item = T> {
+        self.current.extend(events);
+    }
+
+    /// Move every event out of a vector into the channel
+    pub fn drain_vec_write(&mut self, events: &mut Vec<T>) {
+        self.current.extend(events.drain(..));
+    }
+
+    /// Read all events the reader hasn't seen yet, and move its cursor past them.
+    ///
+    /// Events dropped by `maintain` before the reader got to them are skipped. See `missed`.
+    pub fn read(&self, reader: &mut ReaderId) -> EventIter<T> {
+        let start = if reader.cursor > self.offset { (reader.cursor - self.offset) as usize } else { 0 };
+
+        reader.cursor = self.end();
+
+        let previous_len = self.previous.len();
+
+        let (previous, current) = if start < previous_len {
+            (&self.previous[start..], &self.current[..])
+        } else {
+            (&self.previous[previous_len..], &self.current[(start - previous_len).min(self.current.len())..])
+        };
+
+        previous.iter().chain(current.iter())
+    }
+
+    /// Number of events the reader hasn't seen yet
+    pub fn pending(&self, reader: &ReaderId) -> usize {
+        (self.end() - reader.cursor.max(self.offset).min(self.end())) as usize
+    }
+
+    /// Number of events which were dropped before the reader could see them
+    pub fn missed(&self, reader: &ReaderId) -> u64 {
+        self.offset.saturating_sub(reader.cursor)
+    }
+
+    /// Number of events currently held, from this frame and the previous one
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    /// Returns `true` if there are no events from this frame or the previous one
+    pub fn is_empty(&self) -> bool {
+        self.previous.is_empty() && self.current.is_empty()
+    }
+
+    /// Start a new frame, dropping the events from two frames ago
+    pub fn maintain(&mut self) {
+        self.offset += self.previous.len() as u64;
+
+        self.previous.clear();
+
+        ::std::mem::swap(&mut self.previous, &mut self.current);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn read(channel: &EventChannel<u32>, reader: &mut ReaderId) -> Vec<u32> {
+        channel.read(reader).cloned().collect()
+    }
+
+    #[test]
+    fn readers_only_see_new_events() {
+        let mut channel = EventChannel::new();
+
+        channel.single_write(1);
+
+        let mut reader = channel.register_reader();
+
+        assert_eq!(channel.pending(&reader), 0);
+
+        channel.iter_write(vec![2, 3]);
+
+        assert_eq!(channel.pending(&reader), 2);
+        assert_eq!(read(&channel, &mut reader), vec![2, 3]);
+        assert_eq!(read(&channel, &mut reader), Vec::<u32>::new());
+
+        channel.drain_vec_write(&mut vec![4]);
+
+        assert_eq!(read(&channel, &mut reader), vec![4]);
+    }
+
+    #[test]
+    fn readers_have_their_own_cursors() {
+        let mut channel = EventChannel::new();
+
+        let mut first = channel.register_reader();
+        let mut second = channel.register_reader();
+
+        channel.iter_write(vec![1, 2]);
+
+        assert_eq!(read(&channel, &mut first), vec![1, 2]);
+
+        channel.single_write(3);
+
+        assert_eq!(read(&channel, &mut first), vec![3]);
+        assert_eq!(read(&channel, &mut second), vec![1, 2, 3]);
+        assert_eq!(channel.pending(&first), 0);
+        assert_eq!(channel.pending(&second), 0);
+    }
+
+    #[test]
+    fn events_are_kept_for_two_frames() {
+        let mut channel = EventChannel::new();
+
+        let mut reader = channel.register_reader();
+
+        channel.single_write(1);
+
+        channel.maintain();
+
+        assert_eq!(channel.len(), 1);
+
+        channel.single_write(2);
+
+        // A reader running before the writer sees last frame's events along with this frame's
+        assert_eq!(read(&channel, &mut reader), vec![1, 2]);
+
+        channel.maintain();
+
+        assert_eq!(channel.len(), 1);
+
+        channel.maintain();
+
+        assert!(channel.is_empty());
+        assert_eq!(channel.missed(&reader), 0);
+    }
+
+    #[test]
+    fn reading_across_a_swap() {
+        let mut channel = EventChannel::new();
+
+        let mut reader = channel.register_reader();
+
+        channel.iter_write(vec![1, 2]);
+
+        assert_eq!(read(&channel, &mut reader), vec![1, 2]);
+
+        channel.single_write(3);
+        channel.maintain();
+        channel.single_write(4);
+
+        // The cursor is inside the previous frame
+        assert_eq!(channel.pending(&reader), 2);
+        assert_eq!(read(&channel, &mut reader), vec![3, 4]);
+    }
+
+    #[test]
+    fn lagging_readers_skip_dropped_events() {
+        let mut channel = EventChannel::new();
+
+        let mut lagging = channel.register_reader();
+        let mut current = channel.register_reader();
+
+        channel.iter_write(vec![1, 2]);
+        channel.maintain();
+        channel.single_write(3);
+
+        assert_eq!(read(&channel, &mut current), vec![1, 2, 3]);
+
+        channel.maintain();
+        channel.single_write(4);
+
+        assert_eq!(channel.missed(&lagging), 2);
+        assert_eq!(channel.pending(&lagging), 2);
+        assert_eq!(read(&channel, &mut lagging), vec![3, 4]);
+        assert_eq!(channel.missed(&lagging), 0);
+
+        assert_eq!(read(&channel, &mut current), vec![4]);
+    }
+
+    #[test]
+    fn new_readers_after_maintain() {
+        let mut channel = EventChannel::new();
+
+        channel.single_write(1);
+        channel.maintain();
+
+        let mut reader = channel.register_reader();
+
+        channel.single_write(2);
+
+        assert_eq!(read(&channel, &mut reader), vec![2]);
+        assert_eq!(channel.missed(&reader), 0);
+    }
+}
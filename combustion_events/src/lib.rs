@@ -6,6 +6,13 @@
 extern crate combustion_common as common;
 
 pub extern crate parallel_event_emitter;
+extern crate combustion_ecs as ecs;
+
+pub mod channel;
+pub mod builtin;
+pub mod system;
+
+pub use channel::{EventChannel, ReaderId, EventIter};
 
 /// Use my Parallel Event Emitter crate
 pub use parallel_event_emitter::*;
\ No newline at end of file
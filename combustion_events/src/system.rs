@@ -0,0 +1,116 @@
+//! Event system
+//!
+//! Starts a new frame for every built-in event channel. It should run before any system which writes or reads events,
+//! so that events written during a frame are kept until every reader has had a chance to see them.
+//! Game loops which don't run it as a system call `maintain_channels` once per frame instead.
+//!
+//! Window backends write their input directly into the `InputEvent` channel.
+
+use ecs::{self, World, RunArg, Delta};
+
+use channel::EventChannel;
+use builtin::{EntityEvent, CollisionEvent, InputEvent, AssetEvent};
+
+/// Add a channel for every built-in event type to the world
+pub fn register_channels(world: &mut World) {
+    world.add_resource(EventChannel::<EntityEvent>::new());
+    world.add_resource(EventChannel::<CollisionEvent>::new());
+    world.add_resource(EventChannel::<InputEvent>::new());
+    world.add_resource(EventChannel::<AssetEvent>::new());
+}
+
+/// Start a new frame for every built-in event channel
+pub fn maintain_channels(world: &World) {
+    world.write_resource::<EventChannel<EntityEvent>>().maintain();
+    world.write_resource::<EventChannel<CollisionEvent>>().maintain();
+    world.write_resource::<EventChannel<InputEvent>>().maintain();
+    world.write_resource::<EventChannel<AssetEvent>>().maintain();
+}
+
+/// Maintains the built-in event channels
+pub struct System;
+
+impl ecs::System<Delta> for System {
+    fn run(&mut self, arg: RunArg, _: Delta) {
+        arg.fetch(|world| maintain_channels(world));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ecs::{World, Planner};
+
+    use channel::EventChannel;
+    use builtin::{InputEvent, AssetEvent};
+
+    use super::*;
+
+    #[test]
+    fn channels_are_maintained_every_frame() {
+        let mut world = World::new();
+
+        register_channels(&mut world);
+
+        let mut reader = world.read_resource::<EventChannel<InputEvent>>().register_reader();
+
+        world.write_resource::<EventChannel<InputEvent>>().single_write(InputEvent::Close);
+        world.write_resource::<EventChannel<AssetEvent>>().single_write(AssetEvent::Removed("a.png".into()));
+
+        let mut planner = Planner::new(world, 1);
+
+        planner.add_system(System, "events", 0);
+
+        planner.dispatch(0.0);
+        planner.wait();
+
+        {
+            let world = planner.mut_world();
+
+            // Events from the last frame are still readable after one frame
+            assert_eq!(world.read_resource::<EventChannel<InputEvent>>().len(), 1);
+            assert_eq!(world.read_resource::<EventChannel<AssetEvent>>().len(), 1);
+        }
+
+        planner.dispatch(0.0);
+        planner.wait();
+
+        let world = planner.mut_world();
+
+        let input_events = world.read_resource::<EventChannel<InputEvent>>();
+
+        assert!(input_events.is_empty());
+        assert!(world.read_resource::<EventChannel<AssetEvent>>().is_empty());
+
+        assert_eq!(input_events.missed(&reader), 1);
+        assert_eq!(input_events.read(&mut reader).count(), 0);
+    }
+
+    #[test]
+    fn channels_are_maintained_from_the_game_loop() {
+        let mut world = World::new();
+
+        register_channels(&mut world);
+
+        let mut reader = world.read_resource::<EventChannel<InputEvent>>().register_reader();
+
+        world.write_resource::<EventChannel<InputEvent>>().single_write(InputEvent::Focus(true));
+
+        maintain_channels(&world);
+
+        world.write_resource::<EventChannel<InputEvent>>().single_write(InputEvent::Close);
+
+        {
+            let input_events = world.read_resource::<EventChannel<InputEvent>>();
+
+            assert_eq!(input_events.read(&mut reader).cloned().collect::<Vec<_>>(), vec![InputEvent::Focus(true), InputEvent::Close]);
+        }
+
+        maintain_channels(&world);
+        maintain_channels(&world);
+
+        let input_events = world.read_resource::<EventChannel<InputEvent>>();
+
+        assert!(input_events.is_empty());
+        assert_eq!(input_events.missed(&reader), 0);
+    }
+}
@@ -1,10 +0,0 @@
-//! Instanced rendering component
-
-use specs;
-
-#[derive(Default)]
-pub struct Component;
-
-impl specs::Component for Component {
-    type Storage = specs::NullStorage<Component>;
-}
\ No newline at end of file
@@ -1,9 +1,15 @@
 //! Lighted component
 
 use specs;
-use nalgebra::Point3;
+use nalgebra::{Point3, Vector3};
 
-#[derive(Copy, Clone, Debug)]
+use ::core::common::color::Color;
+use ::core::protocols::scene::{Light, LightKind};
+
+use ::scene::instance::SceneLight;
+
+#[derive(Copy, Clone, Debug, Inspect)]
+#[inspect(path = "::core::common")]
 pub enum Kind {
     /// Directional light infinitely far away, with all rays parallel
     Directional,
@@ -53,7 +59,8 @@ pub enum Kind {
     Shape,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Inspect)]
+#[inspect(path = "::core::common")]
 pub struct Component {
     /// Pretty obvious
     pub enabled: bool,
@@ -61,8 +68,178 @@ pub struct Component {
     pub kind: Kind,
     /// Light intensity
     pub intensity: f32,
+    /// Light color
+    pub color: Color,
+    /// Ambient color added into the scene by the light
+    pub ambient: Color,
+    /// Direction the light is facing, for directional lights and spotlights
+    pub direction: Vector3<f32>,
+    /// Minimum and maximum distances the light can affect
+    pub zdistance: (f32, f32),
 }
 
 impl specs::Component for Component {
     type Storage = specs::VecStorage<Component>;
 }
+
+/// Only directional, point and spotlights can be saved into scene descriptions
+impl SceneLight for Component {
+    fn from_light(light: &Light) -> Component {
+        let kind = match light.kind {
+            LightKind::Directional => Kind::Directional,
+            LightKind::Point => Kind::Point { radius: light.effect_radius },
+            LightKind::Spotlight => Kind::Spotlight {
+                radius: light.effect_radius,
+                inner_cone: light.inner_cone,
+                outer_cone: light.outer_cone,
+                reflector_efficiency: 1.0,
+            },
+        };
+
+        Component {
+            enabled: true,
+            kind: kind,
+            intensity: light.intensity,
+            color: light.color,
+            ambient: light.ambient,
+            direction: light.direction,
+            zdistance: light.zdistance,
+        }
+    }
+
+    fn to_light(&self, light: &mut Light) -> bool {
+        match self.kind {
+            Kind::Directional => {
+                light.kind = LightKind::Directional;
+            },
+            Kind::Point { radius } => {
+                light.kind = LightKind::Point;
+                light.effect_radius = radius;
+            },
+            Kind::Spotlight { radius, inner_cone, outer_cone, .. } => {
+                light.kind = LightKind::Spotlight;
+                light.effect_radius = radius;
+                light.inner_cone = inner_cone;
+                light.outer_cone = outer_cone;
+            },
+            _ => return false,
+        }
+
+        light.direction = self.direction;
+        light.color = self.color;
+        light.ambient = self.ambient;
+        light.zdistance = self.zdistance;
+        light.intensity = self.intensity;
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::Vector3;
+
+    use ::core::common::color::Color;
+    use ::core::common::traits::inspect::{Inspect, InspectValue, InspectError};
+
+    use super::*;
+
+    fn light() -> Component {
+        Component {
+            enabled: true,
+            kind: Kind::Point { radius: 5.0 },
+            intensity: 1.0,
+            color: Color::new(1.0, 1.0, 1.0, 1.0),
+            ambient: Color::new(0.0, 0.0, 0.0, 1.0),
+            direction: Vector3::new(0.0, -1.0, 0.0),
+            zdistance: (0.1, 100.0),
+        }
+    }
+
+    #[test]
+    fn inspect_fields() {
+        let light = light();
+
+        let fields = light.fields();
+
+        assert_eq!(light.type_name(), "Component");
+        assert!(fields.iter().any(|field| field.name == "kind" && field.type_name == "Kind"));
+        assert!(fields.iter().any(|field| field.name == "zdistance" && field.type_name == "(f32,f32)"));
+
+        assert_eq!(light.kind.fields().len(), 1);
+        assert_eq!(light.kind.fields()[0].name, "radius");
+    }
+
+    #[test]
+    fn inspect_get_set() {
+        let mut light = light();
+
+        assert_eq!(light.get("kind"), Some(InspectValue::Enum("Point".to_string())));
+        assert_eq!(light.get("kind.radius"), Some(InspectValue::Float(5.0)));
+        assert_eq!(light.get("zdistance.1"), Some(InspectValue::Float(100.0)));
+
+        light.set("kind.radius", InspectValue::Float(10.0)).unwrap();
+        light.set("color.g", InspectValue::Float(0.5)).unwrap();
+        light.set("direction", InspectValue::Vector3(Vector3::new(1.0, 0.0, 0.0))).unwrap();
+        light.set("direction.y", InspectValue::Float(2.0)).unwrap();
+        light.set("enabled", InspectValue::Bool(false)).unwrap();
+
+        assert_eq!(light.get("kind.radius"), Some(InspectValue::Float(10.0)));
+        assert_eq!(light.color.g, 0.5);
+        assert_eq!(light.direction, Vector3::new(1.0, 2.0, 0.0));
+        assert!(!light.enabled);
+
+        assert_eq!(light.set("intensity", InspectValue::String("bright".to_string())), Err(InspectError::InvalidValue("f32")));
+        assert_eq!(light.set("kind.inner_cone", InspectValue::Float(1.0)), Err(InspectError::UnknownField("inner_cone".to_string())));
+    }
+
+    #[test]
+    fn inspect_switch_kind() {
+        let mut light = light();
+
+        light.set("kind", InspectValue::Enum("Directional".to_string())).unwrap();
+
+        assert_eq!(light.get("kind"), Some(InspectValue::Enum("Directional".to_string())));
+        assert_eq!(light.get("kind.radius"), None);
+
+        // Variants with fields have no values to switch to
+        assert!(light.set("kind", InspectValue::Enum("Spotlight".to_string())).is_err());
+    }
+
+    #[test]
+    fn scene_light_round_trip() {
+        let original = Light {
+            name: "Lamp".to_string(),
+            kind: LightKind::Spotlight,
+            direction: Vector3::new(0.0, 0.0, -1.0),
+            intensity: 2.0,
+            effect_radius: 5.0,
+            inner_cone: 0.25,
+            outer_cone: 0.5,
+            ..Light::default()
+        };
+
+        let component = Component::from_light(&original);
+
+        let mut saved = Light { name: original.name.clone(), ..Light::default() };
+
+        assert!(component.to_light(&mut saved));
+
+        match saved.kind {
+            LightKind::Spotlight => {}
+            kind => panic!("expected a spotlight, got {:?}", kind),
+        }
+
+        assert_eq!(saved.direction, original.direction);
+        assert_eq!(saved.intensity, 2.0);
+        assert_eq!(saved.effect_radius, 5.0);
+        assert_eq!((saved.inner_cone, saved.outer_cone), (0.25, 0.5));
+
+        // Kinds the scene description has no equivalent for are left out
+        let mut emitter = light();
+
+        emitter.kind = Kind::Emitter;
+
+        assert!(!emitter.to_light(&mut saved));
+    }
+}
@@ -2,19 +2,21 @@
 
 use specs;
 
-pub mod node;
+pub use ::scene::components::{node, name};
 pub mod renderable;
 pub mod effector;
-pub mod model;
+pub use ::scene::components::{model, instanced, properties};
 pub mod mesh;
 pub mod material;
-pub mod instanced;
 pub mod position;
 pub mod isometry;
 pub mod rotation;
 pub mod quaternion_rotation;
 pub mod scale;
-pub mod transform;
+pub use ::scene::components::transform;
+pub use ::scene::components::{animator, joint_palette};
+pub use ::scene::components::{bounds, occluder};
+pub use ::scene::components::prefab_instance;
 pub mod camera;
 pub mod light;
 pub mod physics;
@@ -23,18 +25,25 @@ pub mod constraints;
 
 pub fn register_all(world: &mut specs::World) {
     ecs_register_mod!(world, node);
+    ecs_register_mod!(world, name);
     ecs_register_mod!(world, renderable);
     ecs_register_mod!(world, effector);
     ecs_register_mod!(world, mesh);
     ecs_register_mod!(world, model);
     ecs_register_mod!(world, material);
     ecs_register_mod!(world, instanced);
+    ecs_register_mod!(world, properties);
     ecs_register_mod!(world, position);
     ecs_register_mod!(world, isometry);
     ecs_register_mod!(world, rotation);
     ecs_register_mod!(world, quaternion_rotation);
     ecs_register_mod!(world, scale);
     ecs_register_mod!(world, transform);
+    ecs_register_mod!(world, animator);
+    ecs_register_mod!(world, joint_palette);
+    ecs_register_mod!(world, bounds);
+    ecs_register_mod!(world, occluder);
+    ecs_register_mod!(world, prefab_instance);
     ecs_register_mod!(world, camera);
     ecs_register_mod!(world, light);
     ecs_register_mod!(world, physics);
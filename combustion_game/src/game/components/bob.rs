@@ -1,15 +1,10 @@
-use specs;
-
-#[derive(Default)]
+#[derive(Default, Component)]
+#[ecs(prefab = "bob", path = "::core::ecs")]
 pub struct Component {
     pub up: bool,
     pub value: f32
 }
 
-impl specs::Component for Component {
-    type Storage = specs::VecStorage<Component>;
-}
-
 impl Component {
     pub fn new() -> Component {
         Component { up: true, value: 0.0 }
@@ -2,10 +2,18 @@
 
 use specs;
 
+use ::scene::PrefabRegistry;
+
 pub mod turntable;
 pub mod bob;
 
 pub fn register_all(world: &mut specs::World) {
     register_mod!(world, turntable);
     register_mod!(world, bob);
-}
\ No newline at end of file
+}
+
+/// Register the game components which can be stored in prefabs
+pub fn register_prefabs(registry: &mut PrefabRegistry) {
+    registry.register::<turntable::Component>();
+    registry.register::<bob::Component>();
+}
@@ -20,7 +20,7 @@ impl specs::System<super::Delta> for System {
         });
 
         for (mut bob, mut position) in (bobs, positions).iter() {
-            bob.value += delta;
+            bob.value += delta as f32;
 
             position.0.y = (bob.value / 3.0).sin() / 3.0;
 
@@ -1,13 +1,11 @@
 //! Systems specific to the game
 
 pub mod turntable;
-pub mod blackhole;
 pub mod bob;
 
 pub use systems::Delta;
 
 pub enum Priorities {
-    Blackhole,
     Turntable,
     Bob,
 }
\ No newline at end of file
@@ -20,7 +20,7 @@ impl specs::System<super::Delta> for System {
         });
 
         for (turntable, mut rotation) in (turntables, rotations).iter() {
-            rotation.0.append_rotation_mut(&Vector3::new(0.0, turntable.rate * delta, 0.0));
+            rotation.0.append_rotation_mut(&Vector3::new(0.0, turntable.rate * delta as f32, 0.0));
         }
     }
 }
\ No newline at end of file
@@ -13,11 +13,13 @@ extern crate time;
 extern crate num_cpus;
 extern crate vec_map;
 extern crate lazy;
+extern crate petgraph;
 
 #[macro_use]
 extern crate combustion_macros;
 
 pub extern crate combustion_core as core;
+pub extern crate combustion_scene as scene;
 
 //#[macro_use]
 //pub mod components;
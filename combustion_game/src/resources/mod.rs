@@ -1,6 +1,5 @@
 pub mod scene_graph;
 pub mod cursor;
 pub mod camera;
-pub mod event_queue;
 pub mod render_queue;
 pub mod projection;
\ No newline at end of file
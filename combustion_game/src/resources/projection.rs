@@ -1,6 +1,10 @@
 use specs;
 use nalgebra::*;
 
+use ::core::common::traits::inspect::{Inspect, InspectValue, InspectError, InspectResult, FieldInfo};
+
+use ::scene::spatial::Frustum;
+
 #[derive(Copy, Clone, Debug)]
 pub enum Kind {
     Perspective(Perspective3<f32>),
@@ -38,6 +42,16 @@ impl Kind {
             }
         }
     }
+
+    /// Combined projection and view matrix, where `view` transforms world space into view space
+    pub fn view_projection(&self, view: &Matrix4<f32>) -> Matrix4<f32> {
+        self.to_homogeneous() * *view
+    }
+
+    /// World space frustum seen through the projection, used for culling
+    pub fn frustum(&self, view: &Matrix4<f32>) -> Frustum {
+        Frustum::from_matrix(&self.view_projection(view))
+    }
 }
 
 impl ToHomogeneous<Matrix4<f32>> for Kind {
@@ -53,6 +67,67 @@ impl ToHomogeneous<Matrix4<f32>> for Kind {
     }
 }
 
+/// Perspective projections expose `fovy`, `aspect`, `znear` and `zfar`,
+/// and orthographic projections expose `left`, `right`, `bottom`, `top`, `znear` and `zfar`.
+impl Inspect for Kind {
+    fn type_name(&self) -> &'static str { "Kind" }
+
+    fn fields(&self) -> Vec<FieldInfo> {
+        let names: &[&'static str] = match *self {
+            Kind::Perspective(_) => &["fovy", "aspect", "znear", "zfar"],
+            Kind::Orthographic(_) => &["left", "right", "bottom", "top", "znear", "zfar"],
+        };
+
+        names.iter().map(|&name| FieldInfo { name: name, type_name: "f32" }).collect()
+    }
+
+    fn get(&self, path: &str) -> Option<InspectValue> {
+        let value = match (*self, path) {
+            (Kind::Perspective(_), "") => return Some(InspectValue::Enum("Perspective".to_string())),
+            (Kind::Orthographic(_), "") => return Some(InspectValue::Enum("Orthographic".to_string())),
+            (Kind::Perspective(projection), "fovy") => projection.fovy(),
+            (Kind::Perspective(projection), "aspect") => projection.aspect(),
+            (Kind::Perspective(projection), "znear") => projection.znear(),
+            (Kind::Perspective(projection), "zfar") => projection.zfar(),
+            (Kind::Orthographic(projection), "left") => projection.left(),
+            (Kind::Orthographic(projection), "right") => projection.right(),
+            (Kind::Orthographic(projection), "bottom") => projection.bottom(),
+            (Kind::Orthographic(projection), "top") => projection.top(),
+            (Kind::Orthographic(projection), "znear") => projection.znear(),
+            (Kind::Orthographic(projection), "zfar") => projection.zfar(),
+            _ => return None,
+        };
+
+        Some(InspectValue::Float(value as f64))
+    }
+
+    fn set(&mut self, path: &str, value: InspectValue) -> InspectResult<()> {
+        if path.is_empty() {
+            return Err(InspectError::Unsupported);
+        }
+
+        let value = try!(f32::from_value(&value).ok_or(InspectError::InvalidValue("f32")));
+
+        match (&mut *self, path) {
+            (&mut Kind::Perspective(ref mut projection), "fovy") => projection.set_fovy(value),
+            (&mut Kind::Perspective(ref mut projection), "aspect") => projection.set_aspect(value),
+            (&mut Kind::Perspective(ref mut projection), "znear") => projection.set_znear(value),
+            (&mut Kind::Perspective(ref mut projection), "zfar") => projection.set_zfar(value),
+            (&mut Kind::Orthographic(ref mut projection), "left") => projection.set_left(value),
+            (&mut Kind::Orthographic(ref mut projection), "right") => projection.set_right(value),
+            (&mut Kind::Orthographic(ref mut projection), "bottom") => projection.set_bottom(value),
+            (&mut Kind::Orthographic(ref mut projection), "top") => projection.set_top(value),
+            (&mut Kind::Orthographic(ref mut projection), "znear") => projection.set_znear(value),
+            (&mut Kind::Orthographic(ref mut projection), "zfar") => projection.set_zfar(value),
+            _ => return Err(InspectError::UnknownField(path.to_string())),
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Inspect)]
+#[inspect(path = "::core::common")]
 pub struct Resource {
     pub kind: Kind
 }
@@ -77,4 +152,121 @@ impl Resource {
     pub fn new_orthographic_window(width: f32, height: f32, znear: f32, zfar: f32) -> Resource {
         Resource::new_orthographic(0.0, width, height, 0.0, znear, zfar)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::PI;
+
+    use ::core::common::traits::inspect::{Inspect, InspectValue, InspectError};
+    use ::core::protocols::math::data::Aabb;
+
+    use ::scene::spatial::Containment;
+
+    use super::*;
+
+    /// Projections may be stored as matrices, so values read back are only approximately what was set
+    fn float(projection: &Resource, path: &str) -> f64 {
+        match projection.get(path) {
+            Some(InspectValue::Float(value)) => value,
+            value => panic!("Expected float at {}, got {:?}", path, value),
+        }
+    }
+
+    fn assert_near(a: f64, b: f64) {
+        assert!((a - b).abs() <= 1e-4 * b.abs().max(1.0), "{} != {}", a, b);
+    }
+
+    #[test]
+    fn inspect_perspective() {
+        let mut projection = Resource::new_perspective(2.0, 1.0, 0.1, 100.0);
+
+        let names: Vec<&str> = projection.kind.fields().iter().map(|field| field.name).collect();
+
+        assert_eq!(names, vec!["fovy", "aspect", "znear", "zfar"]);
+
+        assert_eq!(projection.get("kind"), Some(InspectValue::Enum("Perspective".to_string())));
+        assert_near(float(&projection, "kind.aspect"), 2.0);
+        assert_eq!(projection.get("kind.left"), None);
+
+        projection.set("kind.zfar", InspectValue::Float(50.0)).unwrap();
+        projection.set("kind.fovy", InspectValue::Integer(2)).unwrap();
+
+        assert_near(float(&projection, "kind.zfar"), 50.0);
+        assert_near(float(&projection, "kind.fovy"), 2.0);
+
+        assert_eq!(projection.set("kind.top", InspectValue::Float(1.0)), Err(InspectError::UnknownField("top".to_string())));
+        assert_eq!(projection.set("kind.znear", InspectValue::Bool(true)), Err(InspectError::InvalidValue("f32")));
+        assert_eq!(projection.set("kind", InspectValue::Enum("Orthographic".to_string())), Err(InspectError::Unsupported));
+    }
+
+    #[test]
+    fn inspect_orthographic() {
+        let mut projection = Resource::new_orthographic_window(800.0, 600.0, -1.0, 1.0);
+
+        assert_eq!(projection.get("kind"), Some(InspectValue::Enum("Orthographic".to_string())));
+        assert_near(float(&projection, "kind.right"), 800.0);
+        assert_near(float(&projection, "kind.bottom"), 600.0);
+
+        projection.set("kind.right", InspectValue::Float(1024.0)).unwrap();
+
+        assert_near(float(&projection, "kind.right"), 1024.0);
+        assert_eq!(projection.get("kind.fovy"), None);
+    }
+
+    /// Small box around the point
+    fn point(x: f32, y: f32, z: f32) -> Aabb {
+        Aabb::new(Point3::new(x - 0.01, y - 0.01, z - 0.01), Point3::new(x + 0.01, y + 0.01, z + 0.01))
+    }
+
+    fn translation(x: f32, y: f32, z: f32) -> Matrix4<f32> {
+        let mut matrix = Matrix4::new_identity(4);
+
+        matrix.m14 = x;
+        matrix.m24 = y;
+        matrix.m34 = z;
+
+        matrix
+    }
+
+    #[test]
+    fn perspective_frustum() {
+        // 90 degree field of view, looking down -z
+        let projection = Resource::new_perspective(1.0, PI / 2.0, 0.1, 100.0);
+
+        let frustum = projection.kind.frustum(&Matrix4::new_identity(4));
+
+        assert_eq!(frustum.test_aabb(&point(0.0, 0.0, -10.0)), Containment::Inside);
+        assert_eq!(frustum.test_aabb(&point(9.0, 0.0, -10.0)), Containment::Inside);
+        assert_eq!(frustum.test_aabb(&point(11.0, 0.0, -10.0)), Containment::Outside);
+        assert_eq!(frustum.test_aabb(&point(0.0, 0.0, 10.0)), Containment::Outside);
+        assert_eq!(frustum.test_aabb(&point(0.0, 0.0, -0.05)), Containment::Outside);
+        assert_eq!(frustum.test_aabb(&point(0.0, 0.0, -200.0)), Containment::Outside);
+
+        // Moving the camera back by 20 brings the point behind it into view
+        let frustum = projection.kind.frustum(&translation(0.0, 0.0, -20.0));
+
+        assert_eq!(frustum.test_aabb(&point(0.0, 0.0, 10.0)), Containment::Inside);
+        assert_eq!(frustum.test_aabb(&point(0.0, 0.0, 30.0)), Containment::Outside);
+    }
+
+    #[test]
+    fn orthographic_frustum() {
+        let projection = Resource::new_orthographic_window(800.0, 600.0, 0.1, 10.0);
+
+        let frustum = projection.kind.frustum(&Matrix4::new_identity(4));
+
+        assert_eq!(frustum.test_aabb(&point(400.0, 300.0, -1.0)), Containment::Inside);
+        assert_eq!(frustum.test_aabb(&point(0.0, 300.0, -1.0)), Containment::Intersecting);
+        assert_eq!(frustum.test_aabb(&point(900.0, 300.0, -1.0)), Containment::Outside);
+        assert_eq!(frustum.test_aabb(&point(400.0, 700.0, -1.0)), Containment::Outside);
+        assert_eq!(frustum.test_aabb(&point(400.0, 300.0, 1.0)), Containment::Outside);
+        assert_eq!(frustum.test_aabb(&point(400.0, 300.0, -20.0)), Containment::Outside);
+
+        // Objects don't shrink with distance, so only the view offset moves them
+        let frustum = projection.kind.frustum(&translation(-500.0, 0.0, 0.0));
+
+        assert_eq!(frustum.test_aabb(&point(400.0, 300.0, -9.0)), Containment::Outside);
+        assert_eq!(frustum.test_aabb(&point(900.0, 300.0, -9.0)), Containment::Inside);
+    }
+}
@@ -0,0 +1,37 @@
+//! Cursor system
+//!
+//! Reads cursor movement from the input event channel into the cursor resource.
+
+use specs;
+
+use ::core::events::{EventChannel, ReaderId};
+use ::core::events::builtin::InputEvent;
+
+use ::resources::cursor::Resource as Cursor;
+
+pub struct System {
+    reader: ReaderId,
+}
+
+impl System {
+    pub fn new(input_events: &EventChannel<InputEvent>) -> System {
+        System { reader: input_events.register_reader() }
+    }
+}
+
+impl specs::System<super::Delta> for System {
+    fn run(&mut self, arg: specs::RunArg, _: super::Delta) {
+        let (input_events, mut cursor) = arg.fetch(|world| {
+            (
+                world.read_resource::<EventChannel<InputEvent>>(),
+                world.write_resource::<Cursor>(),
+            )
+        });
+
+        for event in input_events.read(&mut self.reader) {
+            if let InputEvent::CursorMoved(x, y) = *event {
+                cursor.set((x, y));
+            }
+        }
+    }
+}
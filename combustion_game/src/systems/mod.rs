@@ -3,15 +3,21 @@ pub mod clean;
 pub mod physics;
 pub mod transform;
 pub mod constraints;
+pub mod cursor;
+pub use ::scene::systems::{animation, skinning, spatial};
 
-pub type Delta = f32;
+pub use ::core::ecs::Delta;
 
 pub enum Priorities {
     LAST = 0,
     Render,
+    Spatial,
     Constraints,
     Transforms,
+    Skinning,
+    Animation,
     Physics,
     Clean,
+    Input,
     FIRST
 }
\ No newline at end of file
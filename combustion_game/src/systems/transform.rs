@@ -1,10 +1,17 @@
+//! Transform system
+//!
+//! Solves the local matrix of every transform from its position, rotation and scale components,
+//! then propagates them through the scene graph to get the world matrices.
+
 use specs;
 use specs::Join;
 
 use nalgebra::{Rotation3, Isometry3, Vector3, Matrix4};
-use nalgebra::{ToHomogeneous, Eye, Unit, Norm, Inverse};
+use nalgebra::{ToHomogeneous, Eye, Unit, Norm};
 use nalgebra::to_rotation_matrix;
 
+use ::scene::systems::hierarchy;
+
 pub struct System;
 
 impl System {
@@ -16,9 +23,12 @@ impl System {
         use ::components::scale::Component as Scale;
         use ::components::transform::Component as Transform;
 
+        use ::resources::scene_graph::Resource as SceneGraph;
+
         //Get entity ids and all the necessary component storage structures
-        let (ref positions, ref isometries, ref rotations, ref quat_rotations, ref scales, ref mut transforms, ref entities) = arg.fetch(|world| {
+        let (ref graph, ref positions, ref isometries, ref rotations, ref quat_rotations, ref scales, ref mut transforms, ref entities) = arg.fetch(|world| {
             (
+                world.read_resource::<SceneGraph>(),
                 world.read::<Position>(),
                 world.read::<Isometry>(),
                 world.read::<Rotation>(),
@@ -29,10 +39,7 @@ impl System {
             )
         });
 
-        for (mut transform, entity) in (transforms, entities).iter() {
-            //Start off with the identity matrix
-            transform.matrix = Matrix4::new_identity(4);
-
+        for (mut transform, entity) in (&mut *transforms, entities).iter() {
             ///TODO: Joint these together
             let mut scale_matrix = Matrix4::new_identity(4);
             let mut rotation_matrix = Matrix4::new_identity(4);
@@ -67,10 +74,12 @@ impl System {
                 scale_matrix.m33 = scale.0.z;
             }
 
-            transform.matrix = isometry_matrix * translation_matrix * rotation_matrix * quat_rotation_matrix * scale_matrix;
-
-            transform.inverse = transform.matrix.inverse();
+            //Only marks the transform as dirty if the matrix actually changed
+            transform.set_matrix(isometry_matrix * translation_matrix * rotation_matrix * quat_rotation_matrix * scale_matrix);
         }
+
+        //Combine the local matrices of dirty transforms with their parents to get the world matrices
+        hierarchy::propagate(graph, transforms, entities);
     }
 }
 
@@ -5,6 +5,9 @@ struct ECSComponentProperties {
     storage_path: Option<syn::Path>,
     builtin_storage: bool,
     ecs_path: Option<syn::Path>,
+    prefab_name: Option<String>,
+    scene_path: Option<syn::Path>,
+    serialize_name: Option<String>,
 }
 
 pub fn expand(ast: &syn::MacroInput) -> Result<quote::Tokens, String> {
@@ -12,6 +15,9 @@ pub fn expand(ast: &syn::MacroInput) -> Result<quote::Tokens, String> {
         storage_path: None,
         builtin_storage: true,
         ecs_path: None,
+        prefab_name: None,
+        scene_path: None,
+        serialize_name: None,
     };
 
     let name = &ast.ident;
@@ -43,9 +49,35 @@ pub fn expand(ast: &syn::MacroInput) -> Result<quote::Tokens, String> {
                                                 props.ecs_path = Some(syn::parse_path(s.as_str())?);
                                             }
                                         }
+                                        // #[ecs(prefab = "name")]
+                                        "prefab" => {
+                                            if let &syn::Lit::Str(ref s, _) = lit {
+                                                props.prefab_name = Some(s.clone());
+                                            }
+                                        }
+                                        // #[ecs(serialize = "name")]
+                                        "serialize" => {
+                                            if let &syn::Lit::Str(ref s, _) = lit {
+                                                props.serialize_name = Some(s.clone());
+                                            }
+                                        }
+                                        // #[ecs(scene_path = "combustion_scene")]
+                                        "scene_path" => {
+                                            if let &syn::Lit::Str(ref s, _) = lit {
+                                                props.scene_path = Some(syn::parse_path(s.as_str())?);
+                                            }
+                                        }
                                         _ => {}
                                     }
                                 }
+                                // #[ecs(prefab)]
+                                &syn::MetaItem::Word(ref ident) if ident == "prefab" => {
+                                    props.prefab_name = Some(name.to_string());
+                                }
+                                // #[ecs(serialize)]
+                                &syn::MetaItem::Word(ref ident) if ident == "serialize" => {
+                                    props.serialize_name = Some(name.to_string());
+                                }
                                 _ => {}
                             }
                         }
@@ -77,9 +109,115 @@ pub fn expand(ast: &syn::MacroInput) -> Result<quote::Tokens, String> {
         storage_path.global = ecs_path.global;
     }
 
+    let prefab_impl = match props.prefab_name {
+        Some(ref prefab_name) => {
+            let scene_path = props.scene_path.unwrap_or_else(|| { syn::parse_path("::scene").unwrap() });
+
+            try!(expand_prefab(ast, prefab_name, &scene_path))
+        }
+        None => quote! {},
+    };
+
+    // The component must also implement `Serialize` and `Deserialize`, usually by deriving them
+    let serialize_impl = match props.serialize_name {
+        Some(ref serialize_name) => quote! {
+            impl #ecs_path::serialize::SerializeComponent for #name {
+                fn serialize_name() -> &'static str { #serialize_name }
+            }
+        },
+        None => quote! {},
+    };
+
     Ok(quote! {
         impl #component_path for #name {
             type Storage = #storage_path<#name>;
         }
+
+        #prefab_impl
+
+        #serialize_impl
+    })
+}
+
+/// Implement `PrefabComponent`, converting each field with `PropertyValue` and using the field name as the property name.
+///
+/// Tuple struct fields are named by their index.
+fn expand_prefab(ast: &syn::MacroInput, prefab_name: &str, scene_path: &syn::Path) -> Result<quote::Tokens, String> {
+    let name = &ast.ident;
+
+    let fields = match ast.body {
+        syn::Body::Struct(ref data) => data.fields(),
+        syn::Body::Enum(_) => return Err("Prefab components must be structs".to_string()),
+    };
+
+    // Property key and field accessor for each field
+    let fields: Vec<(String, syn::Ident)> = fields.iter().enumerate().map(|(i, field)| {
+        match field.ident {
+            Some(ref ident) => (ident.to_string(), ident.clone()),
+            None => (i.to_string(), syn::Ident::new(i.to_string())),
+        }
+    }).collect();
+
+    let save_fields: Vec<quote::Tokens> = fields.iter().map(|&(ref key, ref accessor)| {
+        quote! {
+            component.properties.insert(#key.to_string(), #scene_path::prefab::PropertyValue::to_value(&self.#accessor));
+        }
+    }).collect();
+
+    let apply_fields: Vec<quote::Tokens> = fields.iter().map(|&(ref key, ref accessor)| {
+        quote! {
+            if let Some(value) = component.get(#key) {
+                match #scene_path::prefab::PropertyValue::from_value(value) {
+                    Some(value) => self.#accessor = value,
+                    None => return false,
+                }
+            }
+        }
+    }).collect();
+
+    let load_fields: Vec<quote::Tokens> = fields.iter().map(|&(ref key, _)| {
+        quote! {
+            match component.get(#key).and_then(#scene_path::prefab::PropertyValue::from_value) {
+                Some(value) => value,
+                None => return None,
+            }
+        }
+    }).collect();
+
+    let constructor = match ast.body {
+        syn::Body::Struct(syn::VariantData::Struct(_)) => {
+            let idents = fields.iter().map(|&(_, ref accessor)| accessor);
+
+            quote! { #name { #(#idents: #load_fields),* } }
+        }
+        syn::Body::Struct(syn::VariantData::Tuple(_)) => quote! { #name(#(#load_fields),*) },
+        _ => quote! { #name },
+    };
+
+    Ok(quote! {
+        impl #scene_path::prefab::PrefabComponent for #name {
+            fn prefab_name() -> &'static str { #prefab_name }
+
+            #[allow(unused_mut)]
+            fn to_node_component(&self) -> #scene_path::prefab::NodeComponent {
+                let mut component = #scene_path::prefab::NodeComponent::new(#prefab_name);
+
+                #(#save_fields)*
+
+                component
+            }
+
+            #[allow(unused_variables)]
+            fn from_node_component(component: &#scene_path::prefab::NodeComponent) -> Option<#name> {
+                Some(#constructor)
+            }
+
+            #[allow(unused_variables)]
+            fn apply_node_component(&mut self, component: &#scene_path::prefab::NodeComponent) -> bool {
+                #(#apply_fields)*
+
+                true
+            }
+        }
     })
 }
\ No newline at end of file
@@ -0,0 +1,248 @@
+use syn;
+use quote;
+
+struct InspectField {
+    /// Name used in paths
+    name: String,
+    /// How the field is accessed or bound
+    accessor: syn::Ident,
+    /// Type as written in the source
+    type_name: String,
+}
+
+fn is_skipped(field: &syn::Field) -> bool {
+    field.attrs.iter().any(|attr| match attr.value {
+        // #[inspect(skip)]
+        syn::MetaItem::List(ref ident, ref nested) if ident == "inspect" => {
+            nested.iter().any(|item| matches!(*item, syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(ref word)) if word == "skip"))
+        }
+        _ => false,
+    })
+}
+
+/// Collect the inspectable fields, with tuple fields named by their index and bound as `__field<index>` in patterns
+fn collect_fields(fields: &[syn::Field], bind: bool) -> Vec<InspectField> {
+    fields.iter().enumerate().filter(|&(_, field)| !is_skipped(field)).map(|(i, field)| {
+        let ty = &field.ty;
+
+        let type_name = quote!(#ty).to_string().replace(" ", "");
+
+        match field.ident {
+            Some(ref ident) => InspectField { name: ident.to_string(), accessor: ident.clone(), type_name: type_name },
+            None => InspectField {
+                name: i.to_string(),
+                accessor: if bind { syn::Ident::new(format!("__field{}", i)) } else { syn::Ident::new(i.to_string()) },
+                type_name: type_name,
+            },
+        }
+    }).collect()
+}
+
+pub fn expand(ast: &syn::MacroInput) -> Result<quote::Tokens, String> {
+    let mut common_path = None;
+
+    for attr in &ast.attrs {
+        match attr.value {
+            // #[inspect(path = "::core::common")]
+            syn::MetaItem::List(ref ident, ref nested) if ident == "inspect" => {
+                for item in nested {
+                    if let &syn::NestedMetaItem::MetaItem(syn::MetaItem::NameValue(ref ident, syn::Lit::Str(ref s, _))) = item {
+                        if ident == "path" {
+                            common_path = Some(syn::parse_path(s.as_str())?);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let common_path = common_path.unwrap_or_else(|| { syn::parse_path("::common").unwrap() });
+
+    let inspect = quote! { #common_path::traits::inspect };
+
+    match ast.body {
+        syn::Body::Struct(ref data) => expand_struct(ast, data, &inspect),
+        syn::Body::Enum(ref variants) => expand_enum(ast, variants, &inspect),
+    }
+}
+
+fn expand_struct(ast: &syn::MacroInput, data: &syn::VariantData, inspect: &quote::Tokens) -> Result<quote::Tokens, String> {
+    let name = &ast.ident;
+    let type_name = name.to_string();
+
+    let fields = collect_fields(data.fields(), false);
+
+    let infos: Vec<quote::Tokens> = fields.iter().map(|field| {
+        let (name, type_name) = (&field.name, &field.type_name);
+
+        quote! { #inspect::FieldInfo { name: #name, type_name: #type_name } }
+    }).collect();
+
+    let get_arms: Vec<quote::Tokens> = fields.iter().map(|field| {
+        let (name, accessor) = (&field.name, &field.accessor);
+
+        quote! { (#name, rest) => #inspect::Inspect::get(&self.#accessor, rest), }
+    }).collect();
+
+    let set_arms: Vec<quote::Tokens> = fields.iter().map(|field| {
+        let (name, accessor) = (&field.name, &field.accessor);
+
+        quote! { (#name, rest) => #inspect::Inspect::set(&mut self.#accessor, rest, value), }
+    }).collect();
+
+    Ok(quote! {
+        impl #inspect::Inspect for #name {
+            fn type_name(&self) -> &'static str { #type_name }
+
+            fn fields(&self) -> Vec<#inspect::FieldInfo> {
+                vec![#(#infos),*]
+            }
+
+            #[allow(unused_variables)]
+            fn get(&self, path: &str) -> Option<#inspect::InspectValue> {
+                match #inspect::split_path(path) {
+                    #(#get_arms)*
+                    _ => None,
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn set(&mut self, path: &str, value: #inspect::InspectValue) -> #inspect::InspectResult<()> {
+                match #inspect::split_path(path) {
+                    ("", _) => Err(#inspect::InspectError::Unsupported),
+                    #(#set_arms)*
+                    (field, rest) => Err(#inspect::unknown_field(field, rest)),
+                }
+            }
+        }
+    })
+}
+
+/// Enums read and write their variant by name, and the fields of the current variant by path.
+///
+/// Only unit variants can be switched to, since there are no values for the fields of the others.
+fn expand_enum(ast: &syn::MacroInput, variants: &[syn::Variant], inspect: &quote::Tokens) -> Result<quote::Tokens, String> {
+    let name = &ast.ident;
+    let type_name = name.to_string();
+
+    let mut variant_arms = Vec::new();
+    let mut field_arms = Vec::new();
+    let mut get_arms = Vec::new();
+    let mut set_arms = Vec::new();
+    let mut switch_arms = Vec::new();
+    let mut from_arms = Vec::new();
+
+    for variant in variants {
+        let ident = &variant.ident;
+        let variant_name = ident.to_string();
+
+        let fields = collect_fields(variant.data.fields(), true);
+
+        // Every field is bound, so each arm can use any of them
+        let (pattern, pattern_mut) = match variant.data {
+            syn::VariantData::Struct(ref data) => {
+                let idents: Vec<&syn::Ident> = data.iter().filter_map(|field| field.ident.as_ref()).collect();
+                let idents_mut = idents.clone();
+
+                (quote! { #name::#ident { #(ref #idents),* } }, quote! { #name::#ident { #(ref mut #idents_mut),* } })
+            }
+            syn::VariantData::Tuple(ref data) => {
+                let idents: Vec<syn::Ident> = (0..data.len()).map(|i| syn::Ident::new(format!("__field{}", i))).collect();
+                let idents_mut = idents.clone();
+
+                (quote! { #name::#ident(#(ref #idents),*) }, quote! { #name::#ident(#(ref mut #idents_mut),*) })
+            }
+            syn::VariantData::Unit => {
+                switch_arms.push(quote! { #variant_name => { *self = #name::#ident; Ok(()) } });
+                from_arms.push(quote! { #variant_name => Some(#name::#ident), });
+
+                (quote! { #name::#ident }, quote! { #name::#ident })
+            }
+        };
+
+        variant_arms.push(quote! { #pattern => #variant_name, });
+
+        let infos: Vec<quote::Tokens> = fields.iter().map(|field| {
+            let (name, type_name) = (&field.name, &field.type_name);
+
+            quote! { #inspect::FieldInfo { name: #name, type_name: #type_name } }
+        }).collect();
+
+        field_arms.push(quote! { #pattern => vec![#(#infos),*], });
+
+        for field in &fields {
+            let (field_name, accessor) = (&field.name, &field.accessor);
+
+            get_arms.push(quote! { (&#pattern, (#field_name, rest)) => #inspect::Inspect::get(#accessor, rest), });
+            set_arms.push(quote! { (&mut #pattern_mut, (#field_name, rest)) => #inspect::Inspect::set(#accessor, rest, value), });
+        }
+    }
+
+    Ok(quote! {
+        impl #inspect::Inspect for #name {
+            fn type_name(&self) -> &'static str { #type_name }
+
+            #[allow(unused_variables)]
+            fn fields(&self) -> Vec<#inspect::FieldInfo> {
+                match *self {
+                    #(#field_arms)*
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn get(&self, path: &str) -> Option<#inspect::InspectValue> {
+                if path.is_empty() {
+                    let variant = match *self {
+                        #(#variant_arms)*
+                    };
+
+                    return Some(#inspect::InspectValue::Enum(variant.to_string()));
+                }
+
+                match (self, #inspect::split_path(path)) {
+                    #(#get_arms)*
+                    _ => None,
+                }
+            }
+
+            #[allow(unused_variables, unreachable_patterns)]
+            fn set(&mut self, path: &str, value: #inspect::InspectValue) -> #inspect::InspectResult<()> {
+                if path.is_empty() {
+                    let current = match *self {
+                        #(#variant_arms)*
+                    };
+
+                    return match value {
+                        #inspect::InspectValue::Enum(ref variant) | #inspect::InspectValue::String(ref variant) => {
+                            match variant.as_str() {
+                                variant if variant == current => Ok(()),
+                                #(#switch_arms)*
+                                _ => Err(#inspect::InspectError::InvalidValue(#type_name)),
+                            }
+                        }
+                        _ => Err(#inspect::InspectError::InvalidValue(#type_name)),
+                    };
+                }
+
+                match (self, #inspect::split_path(path)) {
+                    #(#set_arms)*
+                    (_, (field, rest)) => Err(#inspect::unknown_field(field, rest)),
+                }
+            }
+
+            #[allow(unreachable_patterns)]
+            fn from_value(value: &#inspect::InspectValue) -> Option<#name> {
+                match *value {
+                    #inspect::InspectValue::Enum(ref variant) | #inspect::InspectValue::String(ref variant) => {
+                        match variant.as_str() {
+                            #(#from_arms)*
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1 @@
+pub mod derive_inspect;
\ No newline at end of file
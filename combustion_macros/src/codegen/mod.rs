@@ -1,2 +1,3 @@
 pub mod ecs;
-pub mod named;
\ No newline at end of file
+pub mod named;
+pub mod inspect;
\ No newline at end of file
@@ -31,4 +31,16 @@ pub fn derive_named(input: TokenStream) -> TokenStream {
         .expect("Failed to run codegen")
         .parse()
         .expect("Failed to generate token stream")
-}
\ No newline at end of file
+}
+
+#[proc_macro_derive(Inspect, attributes(inspect))]
+pub fn derive_inspect(input: TokenStream) -> TokenStream {
+    let input = input.to_string();
+
+    let ast = syn::parse_macro_input(&input).unwrap();
+
+    codegen::inspect::derive_inspect::expand(&ast)
+        .expect("Failed to run codegen")
+        .parse()
+        .expect("Failed to generate token stream")
+}
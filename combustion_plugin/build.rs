@@ -0,0 +1,13 @@
+use std::env;
+use std::process::Command;
+
+/// Record the exact compiler version, which plugins have to match
+fn main() {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+
+    let output = Command::new(rustc).arg("--version").output().expect("Unable to run rustc");
+
+    let version = String::from_utf8(output.stdout).expect("Invalid rustc version");
+
+    println!("cargo:rustc-env=COMBUSTION_PLUGIN_RUSTC={}", version.trim());
+}
@@ -0,0 +1,809 @@
+//! Actor-model execution of plugins
+//!
+//! Every actor runs on its own thread with its own mailbox, and never touches the `World` directly.
+//! Instead, at each sync point the `ActorSystem`:
+//!
+//! 1. Applies the commands and delivers the messages of actors which finished late last frame.
+//! 2. Takes a read-only `Snapshot` of the engine state, made of every component and resource type
+//!    added with `snapshot_component` and `snapshot_resource`.
+//! 3. Sends the snapshot, along with any messages from other actors, to every actor which is idle.
+//! 4. Waits for each actor to reply with its commands, but no longer than the actor's time budget.
+//! 5. Applies the commands of every actor which replied, in the order the actors were spawned.
+//!
+//! An actor which runs over its budget keeps running in the background. Its commands are applied at the next
+//! sync point after it finishes, and it skips every frame it is still busy for, so a slow plugin can never stall the engine.
+//!
+//! `ActorSystem::dispatch` should be called once per frame outside of the specs dispatch, such as right after `Planner::wait`.
+
+use std::any::{Any, TypeId};
+use std::marker::PhantomData;
+use std::mem;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender, RecvTimeoutError, TryRecvError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use fnv::FnvHashMap;
+
+use ecs::{Component, Delta, Entity, Join, World};
+
+use ::error::{PluginError, PluginResult};
+use ::library::PluginLibrary;
+
+/// Time budget given to actors which don't ask for another, in milliseconds
+pub const DEFAULT_BUDGET_MS: u64 = 2;
+
+/// Message sent between actors
+pub type Message = Box<Any + Send>;
+
+/// Plugin logic which runs on its own thread
+pub trait Actor: Send + 'static {
+    /// Called once per frame the actor isn't busy for, after any messages have been received
+    fn update(&mut self, frame: &ActorFrame, context: &mut ActorContext);
+
+    /// Called for every message sent to this actor by another
+    #[allow(unused_variables)]
+    fn receive(&mut self, from: &str, message: Message, context: &mut ActorContext) {}
+
+    /// Called on the actor thread before it stops
+    fn stop(&mut self) {}
+}
+
+/// Change to the world, created by an actor and applied at a sync point
+pub trait WorldCommand: Send {
+    fn apply(self: Box<Self>, world: &mut World);
+}
+
+struct InsertComponent<C> {
+    entity: Entity,
+    component: C,
+}
+
+impl<C: Component + Send> WorldCommand for InsertComponent<C> {
+    fn apply(self: Box<Self>, world: &mut World) {
+        let command = *self;
+
+        world.write::<C>().insert(command.entity, command.component);
+    }
+}
+
+struct RemoveComponent<C> {
+    entity: Entity,
+    _component: PhantomData<fn() -> C>,
+}
+
+impl<C: Component> WorldCommand for RemoveComponent<C> {
+    fn apply(self: Box<Self>, world: &mut World) {
+        world.write::<C>().remove(self.entity);
+    }
+}
+
+struct DeleteEntity(Entity);
+
+impl WorldCommand for DeleteEntity {
+    fn apply(self: Box<Self>, world: &mut World) {
+        world.delete_now(self.0);
+    }
+}
+
+struct SetResource<R>(R);
+
+impl<R: Any + Send + Sync> WorldCommand for SetResource<R> {
+    fn apply(self: Box<Self>, world: &mut World) {
+        world.add_resource(self.0);
+    }
+}
+
+struct Execute<F>(F);
+
+impl<F> WorldCommand for Execute<F> where F: FnOnce(&mut World) + Send {
+    fn apply(self: Box<Self>, world: &mut World) {
+        let Execute(f) = *self;
+
+        f(world)
+    }
+}
+
+/// Collects the commands and messages of an actor during a frame
+pub struct ActorContext {
+    commands: Vec<Box<WorldCommand>>,
+    messages: Vec<(String, Message)>,
+    started: Instant,
+    budget: Duration,
+}
+
+impl ActorContext {
+    fn new(budget: Duration) -> ActorContext {
+        ActorContext {
+            commands: Vec::new(),
+            messages: Vec::new(),
+            started: Instant::now(),
+            budget: budget,
+        }
+    }
+
+    /// Add or replace a component of an entity
+    pub fn insert<C: Component + Send>(&mut self, entity: Entity, component: C) {
+        self.commands.push(box InsertComponent { entity: entity, component: component });
+    }
+
+    /// Remove a component from an entity
+    pub fn remove<C: Component>(&mut self, entity: Entity) {
+        self.commands.push(box RemoveComponent::<C> { entity: entity, _component: PhantomData });
+    }
+
+    /// Delete an entity and all of its components
+    pub fn delete(&mut self, entity: Entity) {
+        self.commands.push(box DeleteEntity(entity));
+    }
+
+    /// Add or replace a resource
+    pub fn set_resource<R: Any + Send + Sync>(&mut self, resource: R) {
+        self.commands.push(box SetResource(resource));
+    }
+
+    /// Run any function on the world at the sync point, such as to create entities
+    pub fn execute<F>(&mut self, f: F) where F: FnOnce(&mut World) + Send + 'static {
+        self.commands.push(box Execute(f));
+    }
+
+    /// Add any other command
+    pub fn command<C: WorldCommand + 'static>(&mut self, command: C) {
+        self.commands.push(box command);
+    }
+
+    /// Send a message to another actor, which receives it at its next frame
+    pub fn send<M: Any + Send>(&mut self, to: &str, message: M) {
+        self.messages.push((to.to_string(), box message));
+    }
+
+    /// Time left in this frame's budget, so long-running work can be split up across frames
+    pub fn remaining(&self) -> Duration {
+        let elapsed = self.started.elapsed();
+
+        if elapsed < self.budget { self.budget - elapsed } else { Duration::from_secs(0) }
+    }
+
+    /// Check if the actor has used up its budget for this frame
+    #[inline]
+    pub fn is_over_budget(&self) -> bool {
+        self.started.elapsed() >= self.budget
+    }
+}
+
+/// Copy of every component of a single type at the time of a snapshot
+pub struct ComponentSnapshot<C> {
+    components: FnvHashMap<Entity, C>,
+}
+
+impl<C> ComponentSnapshot<C> {
+    #[inline]
+    pub fn get(&self, entity: Entity) -> Option<&C> {
+        self.components.get(&entity)
+    }
+
+    #[inline]
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.components.contains_key(&entity)
+    }
+
+    /// Iterate over every entity with the component, in no particular order
+    pub fn iter(&self) -> ::std::collections::hash_map::Iter<Entity, C> {
+        self.components.iter()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize { self.components.len() }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.components.is_empty() }
+}
+
+struct ResourceSnapshot<R>(R);
+
+/// Read-only copy of engine state, shared by all actors
+#[derive(Default)]
+pub struct Snapshot {
+    entries: FnvHashMap<TypeId, Box<Any + Send + Sync>>,
+}
+
+impl Snapshot {
+    fn get<T: Any>(&self) -> Option<&T> {
+        self.entries.get(&TypeId::of::<T>()).and_then(|entry| {
+            let entry: &Any = &**entry;
+
+            entry.downcast_ref()
+        })
+    }
+
+    /// Every component of the given type, if it is part of snapshots
+    pub fn components<C: Any>(&self) -> Option<&ComponentSnapshot<C>> {
+        self.get::<ComponentSnapshot<C>>()
+    }
+
+    /// Component of a single entity
+    pub fn component<C: Any>(&self, entity: Entity) -> Option<&C> {
+        self.components::<C>().and_then(|components| components.get(entity))
+    }
+
+    /// Resource of the given type, if it is part of snapshots
+    pub fn resource<R: Any>(&self) -> Option<&R> {
+        self.get::<ResourceSnapshot<R>>().map(|resource| &resource.0)
+    }
+}
+
+/// Frame given to actors
+#[derive(Clone)]
+pub struct ActorFrame {
+    /// Number of sync points before this one
+    pub index: u64,
+    /// Time since the previous sync point
+    pub delta: Delta,
+    pub snapshot: Arc<Snapshot>,
+}
+
+/// Timing of a single actor
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ActorStats {
+    /// Frames the actor was given
+    pub frames: u64,
+    /// Frames the actor missed because it was still busy
+    pub skipped: u64,
+    /// Frames where the actor ran over its budget
+    pub overruns: u64,
+    /// How long the last finished frame took
+    pub last: Duration,
+    /// How long the slowest frame took
+    pub slowest: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ActorState {
+    Idle,
+    Busy,
+    /// The actor thread panicked
+    Failed,
+}
+
+enum Mail {
+    Frame {
+        frame: ActorFrame,
+        messages: Vec<(String, Message)>,
+        budget: Duration,
+    },
+    Stop,
+}
+
+struct Reply {
+    commands: Vec<Box<WorldCommand>>,
+    messages: Vec<(String, Message)>,
+    elapsed: Duration,
+}
+
+struct ActorHandle {
+    name: String,
+    budget: Duration,
+    state: ActorState,
+    stats: ActorStats,
+    /// Messages waiting for the next frame
+    inbox: Vec<(String, Message)>,
+    mailbox: Sender<Mail>,
+    replies: Receiver<Reply>,
+    thread: Option<JoinHandle<()>>,
+    /// Library the actor came from, kept open until the actor thread has stopped
+    library: Option<Arc<PluginLibrary>>,
+}
+
+impl ActorHandle {
+    fn finish(&mut self, reply: &Reply) {
+        self.state = ActorState::Idle;
+        self.stats.last = reply.elapsed;
+
+        if reply.elapsed > self.stats.slowest {
+            self.stats.slowest = reply.elapsed;
+        }
+    }
+
+    fn stop(&mut self) {
+        let _ = self.mailbox.send(Mail::Stop);
+    }
+
+    fn join(&mut self) {
+        // A panic was already reported as a failed actor
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run_actor(mut actor: Box<Actor>, mailbox: Receiver<Mail>, replies: Sender<Reply>) {
+    while let Ok(mail) = mailbox.recv() {
+        match mail {
+            Mail::Frame { frame, messages, budget } => {
+                let mut context = ActorContext::new(budget);
+
+                for (from, message) in messages {
+                    actor.receive(&from, message, &mut context);
+                }
+
+                actor.update(&frame, &mut context);
+
+                let reply = Reply {
+                    elapsed: context.started.elapsed(),
+                    commands: context.commands,
+                    messages: context.messages,
+                };
+
+                if replies.send(reply).is_err() {
+                    break;
+                }
+            }
+            Mail::Stop => break,
+        }
+    }
+
+    actor.stop();
+}
+
+type SnapshotProvider = Box<Fn(&World) -> (TypeId, Box<Any + Send + Sync>)>;
+
+/// Runs actors and applies their commands at sync points
+pub struct ActorSystem {
+    actors: Vec<ActorHandle>,
+    providers: Vec<SnapshotProvider>,
+    frame: u64,
+}
+
+impl Default for ActorSystem {
+    fn default() -> ActorSystem { ActorSystem::new() }
+}
+
+impl ActorSystem {
+    pub fn new() -> ActorSystem {
+        ActorSystem { actors: Vec::new(), providers: Vec::new(), frame: 0 }
+    }
+
+    /// Include every component of the given type in snapshots
+    pub fn snapshot_component<C: Component + Clone + Send + Sync>(&mut self) {
+        self.providers.push(box |world: &World| {
+            let storage = world.read::<C>();
+            let entities = world.entities();
+
+            let components = (&storage, &entities).iter().map(|(component, entity)| (entity, component.clone())).collect();
+
+            (TypeId::of::<ComponentSnapshot<C>>(), box ComponentSnapshot { components: components } as Box<Any + Send + Sync>)
+        });
+    }
+
+    /// Include a resource in snapshots
+    pub fn snapshot_resource<R: Any + Clone + Send + Sync>(&mut self) {
+        self.providers.push(box |world: &World| {
+            let resource = world.read_resource::<R>().clone();
+
+            (TypeId::of::<ResourceSnapshot<R>>(), box ResourceSnapshot(resource) as Box<Any + Send + Sync>)
+        });
+    }
+
+    /// Start an actor on its own thread with the given time budget per frame
+    pub fn spawn<A: Actor>(&mut self, name: &str, actor: A, budget: Duration) -> PluginResult<()> {
+        self.spawn_boxed(name, box actor, budget, None)
+    }
+
+    #[doc(hidden)]
+    pub fn spawn_boxed(&mut self, name: &str, actor: Box<Actor>, budget: Duration, library: Option<Arc<PluginLibrary>>) -> PluginResult<()> {
+        if self.contains(name) {
+            throw!(PluginError::DuplicateActor(name.to_string()));
+        }
+
+        let (mail_tx, mail_rx) = mpsc::channel();
+        let (reply_tx, reply_rx) = mpsc::channel();
+
+        let thread = try_throw!(thread::Builder::new().name(format!("Actor {}", name)).spawn(move || {
+            run_actor(actor, mail_rx, reply_tx)
+        }));
+
+        self.actors.push(ActorHandle {
+            name: name.to_string(),
+            budget: budget,
+            state: ActorState::Idle,
+            stats: ActorStats::default(),
+            inbox: Vec::new(),
+            mailbox: mail_tx,
+            replies: reply_rx,
+            thread: Some(thread),
+            library: library,
+        });
+
+        Ok(())
+    }
+
+    /// Stop an actor and wait for its thread to finish, dropping any commands it hasn't had applied yet
+    pub fn remove(&mut self, name: &str) -> bool {
+        match self.actors.iter().position(|actor| actor.name == name) {
+            Some(index) => {
+                let mut actor = self.actors.remove(index);
+
+                actor.stop();
+                actor.join();
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stop every actor which came from a plugin library, unless `keep` returns `true` for its library
+    #[doc(hidden)]
+    pub fn retain_plugin_actors<F>(&mut self, mut keep: F) where F: FnMut(&Arc<PluginLibrary>) -> bool {
+        let mut removed = Vec::new();
+
+        let mut i = 0;
+
+        while i < self.actors.len() {
+            let remove = match self.actors[i].library {
+                Some(ref library) => !keep(library),
+                None => false,
+            };
+
+            if remove {
+                removed.push(self.actors.remove(i));
+            } else {
+                i += 1;
+            }
+        }
+
+        for actor in &mut removed {
+            actor.stop();
+        }
+
+        for actor in &mut removed {
+            actor.join();
+        }
+    }
+
+    /// Check if any actor came from the given plugin library
+    #[doc(hidden)]
+    pub fn has_plugin_actors(&self, library: &Arc<PluginLibrary>) -> bool {
+        self.actors.iter().any(|actor| match actor.library {
+            Some(ref other) => &**other as *const PluginLibrary == &**library as *const PluginLibrary,
+            None => false,
+        })
+    }
+
+    #[inline]
+    pub fn contains(&self, name: &str) -> bool {
+        self.actors.iter().any(|actor| actor.name == name)
+    }
+
+    /// Names of all actors, in the order their commands are applied
+    pub fn actors(&self) -> Vec<&str> {
+        self.actors.iter().map(|actor| actor.name.as_str()).collect()
+    }
+
+    /// Timing of an actor
+    pub fn stats(&self, name: &str) -> Option<&ActorStats> {
+        self.actors.iter().find(|actor| actor.name == name).map(|actor| &actor.stats)
+    }
+
+    /// Change the time budget of an actor, returning `false` if there is no such actor
+    pub fn set_budget(&mut self, name: &str, budget: Duration) -> bool {
+        match self.actors.iter_mut().find(|actor| actor.name == name) {
+            Some(actor) => {
+                actor.budget = budget;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Names of actors whose threads panicked. They are never given another frame.
+    pub fn failed(&self) -> Vec<&str> {
+        self.actors.iter().filter(|actor| actor.state == ActorState::Failed).map(|actor| actor.name.as_str()).collect()
+    }
+
+    fn take_snapshot(&self, world: &World) -> Snapshot {
+        Snapshot { entries: self.providers.iter().map(|provider| provider(world)).collect() }
+    }
+
+    /// Run a sync point, returning how many actors had their commands applied
+    pub fn dispatch(&mut self, world: &mut World, delta: Delta) -> usize {
+        let mut replies = Vec::new();
+
+        // Collect replies which came in after their deadlines
+        for (i, actor) in self.actors.iter_mut().enumerate() {
+            if actor.state == ActorState::Busy {
+                match actor.replies.try_recv() {
+                    Ok(reply) => {
+                        actor.finish(&reply);
+                        replies.push((i, reply));
+                    }
+                    Err(TryRecvError::Empty) => {}
+                    Err(TryRecvError::Disconnected) => actor.state = ActorState::Failed,
+                }
+            }
+        }
+
+        // Late messages have to be delivered before the next frame starts
+        self.deliver(&mut replies);
+
+        let frame = ActorFrame {
+            index: self.frame,
+            delta: delta,
+            snapshot: Arc::new(self.take_snapshot(world)),
+        };
+
+        self.frame += 1;
+
+        let start = Instant::now();
+
+        let mut started = Vec::new();
+
+        for (i, actor) in self.actors.iter_mut().enumerate() {
+            match actor.state {
+                ActorState::Idle => {
+                    let messages = mem::replace(&mut actor.inbox, Vec::new());
+
+                    let mail = Mail::Frame { frame: frame.clone(), messages: messages, budget: actor.budget };
+
+                    if actor.mailbox.send(mail).is_ok() {
+                        actor.state = ActorState::Busy;
+                        actor.stats.frames += 1;
+
+                        started.push(i);
+                    } else {
+                        actor.state = ActorState::Failed;
+                    }
+                }
+                ActorState::Busy => actor.stats.skipped += 1,
+                ActorState::Failed => {}
+            }
+        }
+
+        // Every actor started at the same time, so waiting on each in turn only takes as long as the largest budget
+        let mut on_time = Vec::new();
+
+        for i in started {
+            let actor = &mut self.actors[i];
+
+            let now = Instant::now();
+            let deadline = start + actor.budget;
+
+            let timeout = if deadline > now { deadline - now } else { Duration::from_secs(0) };
+
+            match actor.replies.recv_timeout(timeout) {
+                Ok(reply) => {
+                    actor.finish(&reply);
+                    on_time.push((i, reply));
+                }
+                Err(RecvTimeoutError::Timeout) => actor.stats.overruns += 1,
+                Err(RecvTimeoutError::Disconnected) => actor.state = ActorState::Failed,
+            }
+        }
+
+        self.deliver(&mut on_time);
+
+        replies.extend(on_time);
+
+        // Late replies were collected first, so put everything back in spawn order.
+        // The sort is stable, so an actor's late reply still comes before its reply for this frame.
+        replies.sort_by_key(|&(i, _)| i);
+
+        let applied = replies.len();
+
+        for (_, reply) in replies {
+            for command in reply.commands {
+                command.apply(world);
+            }
+        }
+
+        applied
+    }
+
+    /// Move the messages of each reply into the inboxes of their recipients. Messages to unknown actors are dropped.
+    fn deliver(&mut self, replies: &mut [(usize, Reply)]) {
+        for &mut (i, ref mut reply) in replies.iter_mut() {
+            let from = self.actors[i].name.clone();
+
+            for (to, message) in mem::replace(&mut reply.messages, Vec::new()) {
+                if let Some(actor) = self.actors.iter_mut().find(|actor| actor.name == to) {
+                    actor.inbox.push((from.clone(), message));
+                }
+            }
+        }
+    }
+}
+
+impl Drop for ActorSystem {
+    fn drop(&mut self) {
+        for actor in &mut self.actors {
+            actor.stop();
+        }
+
+        for actor in &mut self.actors {
+            actor.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+    use std::time::Duration;
+
+    use ecs::{Component, VecStorage, World};
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Position(f32);
+
+    impl Component for Position {
+        type Storage = VecStorage<Position>;
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Velocity(f32);
+
+    impl Component for Velocity {
+        type Storage = VecStorage<Velocity>;
+    }
+
+    /// Moves every entity with a velocity, reading from the snapshot and writing through commands
+    struct Mover;
+
+    impl Actor for Mover {
+        fn update(&mut self, frame: &ActorFrame, context: &mut ActorContext) {
+            let positions = frame.snapshot.components::<Position>().unwrap();
+
+            for (&entity, velocity) in frame.snapshot.components::<Velocity>().unwrap().iter() {
+                let position = positions.get(entity).unwrap();
+
+                context.insert(entity, Position(position.0 + velocity.0 * frame.delta as f32));
+            }
+        }
+    }
+
+    struct Sleeper(Duration);
+
+    impl Actor for Sleeper {
+        fn update(&mut self, _: &ActorFrame, context: &mut ActorContext) {
+            thread::sleep(self.0);
+
+            context.set_resource(self.0);
+        }
+    }
+
+    fn world() -> World {
+        let mut world = World::new();
+
+        world.register::<Position>();
+        world.register::<Velocity>();
+
+        world
+    }
+
+    #[test]
+    fn commands_applied_at_sync_point() {
+        let mut world = world();
+
+        let entity = world.create_now().with(Position(0.0)).with(Velocity(2.0)).build();
+
+        let mut actors = ActorSystem::new();
+
+        actors.snapshot_component::<Position>();
+        actors.snapshot_component::<Velocity>();
+
+        actors.spawn("mover", Mover, Duration::from_secs(5)).unwrap();
+
+        assert!(actors.spawn("mover", Mover, Duration::from_secs(5)).is_err());
+
+        assert_eq!(actors.dispatch(&mut world, 0.5), 1);
+        assert_eq!(actors.dispatch(&mut world, 0.5), 1);
+
+        assert_eq!(world.read::<Position>().get(entity), Some(&Position(2.0)));
+        assert_eq!(actors.stats("mover").unwrap().frames, 2);
+    }
+
+    #[test]
+    fn over_budget() {
+        let mut world = world();
+
+        let mut actors = ActorSystem::new();
+
+        actors.spawn("sleeper", Sleeper(Duration::from_millis(100)), Duration::from_millis(1)).unwrap();
+
+        // The sleeper misses its deadline, so nothing is applied and it skips the next frame
+        assert_eq!(actors.dispatch(&mut world, 0.0), 0);
+        assert_eq!(actors.dispatch(&mut world, 0.0), 0);
+
+        thread::sleep(Duration::from_millis(200));
+
+        // Its late commands are applied at the next sync point, and it is given a new frame
+        assert!(actors.dispatch(&mut world, 0.0) >= 1);
+
+        let stats = *actors.stats("sleeper").unwrap();
+
+        assert!(stats.overruns >= 1);
+        assert_eq!(stats.skipped, 1);
+        assert_eq!(*world.read_resource::<Duration>(), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn messages() {
+        struct Ping;
+        struct Pong(Vec<u32>);
+
+        impl Actor for Ping {
+            fn update(&mut self, frame: &ActorFrame, context: &mut ActorContext) {
+                context.send("pong", frame.index as u32);
+            }
+        }
+
+        impl Actor for Pong {
+            fn update(&mut self, _: &ActorFrame, context: &mut ActorContext) {
+                let received = self.0.clone();
+
+                context.execute(move |world| world.add_resource(received));
+            }
+
+            fn receive(&mut self, from: &str, message: Message, _: &mut ActorContext) {
+                assert_eq!(from, "ping");
+
+                self.0.push(*message.downcast::<u32>().unwrap());
+            }
+        }
+
+        let mut world = world();
+
+        let mut actors = ActorSystem::new();
+
+        actors.spawn("ping", Ping, Duration::from_secs(5)).unwrap();
+        actors.spawn("pong", Pong(Vec::new()), Duration::from_secs(5)).unwrap();
+
+        for _ in 0..3 {
+            actors.dispatch(&mut world, 0.0);
+        }
+
+        // Messages arrive one frame after they are sent
+        assert_eq!(*world.read_resource::<Vec<u32>>(), vec![0, 1]);
+    }
+
+    #[test]
+    fn commands_applied_in_spawn_order() {
+        /// Records its name when its commands are applied, running over its budget on the first frame if slow
+        struct Logger {
+            name: &'static str,
+            slow: bool,
+        }
+
+        impl Actor for Logger {
+            fn update(&mut self, frame: &ActorFrame, context: &mut ActorContext) {
+                if self.slow && frame.index == 0 {
+                    thread::sleep(Duration::from_millis(100));
+                }
+
+                let name = self.name;
+
+                context.execute(move |world| world.write_resource::<Vec<&'static str>>().push(name));
+            }
+        }
+
+        let mut world = world();
+
+        world.add_resource(Vec::<&'static str>::new());
+
+        let mut actors = ActorSystem::new();
+
+        actors.spawn("first", Logger { name: "first", slow: false }, Duration::from_secs(5)).unwrap();
+        actors.spawn("second", Logger { name: "second", slow: true }, Duration::from_millis(10)).unwrap();
+
+        assert_eq!(actors.dispatch(&mut world, 0.0), 1);
+
+        thread::sleep(Duration::from_millis(200));
+
+        // The late reply of the second actor is collected before the first actor replies, but still applied after it
+        actors.dispatch(&mut world, 0.0);
+
+        assert_eq!(&world.read_resource::<Vec<&'static str>>()[..3], &["first", "first", "second"]);
+    }
+}
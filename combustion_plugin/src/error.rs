@@ -0,0 +1,74 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io;
+
+use trace_error::TraceResult;
+
+pub type PluginResult<T> = TraceResult<T, PluginError>;
+
+#[derive(Debug)]
+pub enum PluginError {
+    Io(io::Error),
+    /// The manifest could not be parsed, or is missing a field
+    InvalidManifest(String),
+    /// The plugin requires an engine version other than this one
+    UnsupportedEngine { name: String, required: String },
+    /// The plugin was built against a different plugin API
+    UnsupportedApi { name: String, version: u32 },
+    /// The plugin was built by a different compiler or against a different version of this crate
+    IncompatibleBuild { name: String, build: String },
+    /// The library does not export the given entrypoint
+    MissingEntrypoint(&'static str),
+    /// The registration entrypoint returned an error
+    Registration(String),
+    AlreadyLoaded(String),
+    NotLoaded(String),
+    /// An actor with the same name is already running
+    DuplicateActor(String),
+}
+
+impl From<io::Error> for PluginError {
+    fn from(err: io::Error) -> PluginError {
+        PluginError::Io(err)
+    }
+}
+
+impl Display for PluginError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            PluginError::Io(ref err) => err.fmt(f),
+            PluginError::InvalidManifest(ref message) |
+            PluginError::Registration(ref message) => write!(f, "{}: {}", self.description(), message),
+            PluginError::UnsupportedEngine { ref name, ref required } => {
+                write!(f, "{}: {} requires engine version {}", self.description(), name, required)
+            }
+            PluginError::UnsupportedApi { ref name, version } => {
+                write!(f, "{}: {} was built for plugin API version {}", self.description(), name, version)
+            }
+            PluginError::IncompatibleBuild { ref name, ref build } => {
+                write!(f, "{}: {} was built with {}", self.description(), name, build)
+            }
+            PluginError::MissingEntrypoint(symbol) => write!(f, "{}: {}", self.description(), symbol),
+            PluginError::AlreadyLoaded(ref name) |
+            PluginError::NotLoaded(ref name) |
+            PluginError::DuplicateActor(ref name) => write!(f, "{}: {}", self.description(), name),
+        }
+    }
+}
+
+impl Error for PluginError {
+    fn description(&self) -> &str {
+        match *self {
+            PluginError::Io(ref err) => err.description(),
+            PluginError::InvalidManifest(_) => "Invalid Plugin Manifest",
+            PluginError::UnsupportedEngine { .. } => "Unsupported Engine Version",
+            PluginError::UnsupportedApi { .. } => "Unsupported Plugin API Version",
+            PluginError::IncompatibleBuild { .. } => "Incompatible Plugin Build",
+            PluginError::MissingEntrypoint(_) => "Missing Plugin Entrypoint",
+            PluginError::Registration(_) => "Plugin Registration Failed",
+            PluginError::AlreadyLoaded(_) => "Plugin Already Loaded",
+            PluginError::NotLoaded(_) => "Plugin Not Loaded",
+            PluginError::DuplicateActor(_) => "Duplicate Actor",
+        }
+    }
+}
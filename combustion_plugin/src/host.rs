@@ -0,0 +1,586 @@
+//! Plugin discovery, loading and unloading
+//!
+//! The `PluginHost` finds plugins in subdirectories of a plugin directory, each with its own `plugin.toml`.
+//! Loading a plugin checks that it supports this engine version and plugin API version, and that it was built
+//! by the same compiler as the host, then calls its registration entrypoint, which is declared with the `declare_plugin!` macro.
+//!
+//! Plugins can be unloaded or reloaded at any time. Their components and systems stay in any world
+//! and schedule they were installed into, so those should be rebuilt with `PluginHost::install` afterwards.
+//! Actors are different, since `PluginHost::install_actors` only stops the actors of plugins which were unloaded or reloaded.
+//! Libraries are only closed by `release_unused` once nothing created by them is left,
+//! and are never closed at all if the host is dropped while they are still in use.
+
+use std::env::consts::DLL_SUFFIX;
+use std::ffi::CStr;
+use std::fs;
+use std::mem;
+use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use asset::asset::AssetMedium;
+use asset::error::AssetResult;
+
+use ecs::World;
+use ecs::builder::SystemBuilder;
+use ecs::error::SystemResult;
+use ecs::serialize::ComponentRegistry;
+
+use ::actor::ActorSystem;
+use ::error::{PluginError, PluginResult};
+use ::library::{PluginAsset, PluginLibrary};
+use ::manifest::{PluginManifest, MANIFEST_NAME};
+use ::registrar::Registrar;
+use ::{PLUGIN_API_VERSION, PLUGIN_BUILD, API_VERSION_SYMBOL, BUILD_SYMBOL, REGISTER_SYMBOL, UNLOAD_SYMBOL};
+
+/// Returns the plugin API version the plugin was built with
+pub type ApiVersionFn = extern "C" fn() -> u32;
+
+/// Returns the `PLUGIN_BUILD` the plugin was built with, as a nul-terminated string
+pub type BuildFn = extern "C" fn() -> *const c_char;
+
+/// Registers everything the plugin provides
+pub type RegisterFn = fn(&mut Registrar) -> Result<(), String>;
+
+/// Called before the plugin is unloaded
+pub type UnloadFn = fn();
+
+/// Entrypoints of a plugin, as exported by `declare_plugin!`
+#[derive(Clone, Copy)]
+pub struct Entrypoints {
+    pub api_version: ApiVersionFn,
+    pub build: BuildFn,
+    pub register: RegisterFn,
+    pub unload: Option<UnloadFn>,
+}
+
+/// Opens the library of a plugin and finds its entrypoints, given its manifest and where to copy the library to
+pub type LoaderFn = fn(&PluginManifest, PathBuf) -> PluginResult<(PluginLibrary, Entrypoints)>;
+
+/// Load the shared library of a plugin from a copy at `shadow`
+pub fn load_library(manifest: &PluginManifest, shadow: PathBuf) -> PluginResult<(PluginLibrary, Entrypoints)> {
+    let library = try_rethrow!(PluginLibrary::open(&manifest.name, &manifest.library, shadow));
+
+    let entrypoints = unsafe {
+        Entrypoints {
+            api_version: match library.get::<ApiVersionFn>(API_VERSION_SYMBOL) {
+                Some(symbol) => *symbol,
+                None => throw!(PluginError::MissingEntrypoint("combustion_plugin_api_version")),
+            },
+            build: match library.get::<BuildFn>(BUILD_SYMBOL) {
+                Some(symbol) => *symbol,
+                None => throw!(PluginError::MissingEntrypoint("combustion_plugin_build")),
+            },
+            register: match library.get::<RegisterFn>(REGISTER_SYMBOL) {
+                Some(symbol) => *symbol,
+                None => throw!(PluginError::MissingEntrypoint("combustion_plugin_register")),
+            },
+            unload: library.get::<UnloadFn>(UNLOAD_SYMBOL).map(|symbol| *symbol),
+        }
+    };
+
+    Ok((library, entrypoints))
+}
+
+struct LoadedPlugin {
+    manifest: PluginManifest,
+    /// Where the manifest was read from, for reloading
+    manifest_path: Option<PathBuf>,
+    registrar: Registrar,
+    unload: Option<UnloadFn>,
+    library: Arc<PluginLibrary>,
+}
+
+/// Loads and keeps track of plugins
+pub struct PluginHost {
+    directory: PathBuf,
+    shadow_directory: PathBuf,
+    loader: LoaderFn,
+    plugins: Vec<LoadedPlugin>,
+    /// Unloaded libraries which may still be in use
+    retired: Vec<Arc<PluginLibrary>>,
+    generation: u64,
+}
+
+impl PluginHost {
+    /// Create a host for plugins in the given directory.
+    ///
+    /// Loaded libraries are copied into a `.loaded` subdirectory first.
+    pub fn new<P: Into<PathBuf>>(directory: P) -> PluginHost {
+        PluginHost::with_loader(directory, load_library)
+    }
+
+    /// Create a host which opens plugins with `loader` instead of loading their shared libraries,
+    /// such as for plugins linked into the engine itself.
+    pub fn with_loader<P: Into<PathBuf>>(directory: P, loader: LoaderFn) -> PluginHost {
+        let directory = directory.into();
+
+        PluginHost {
+            shadow_directory: directory.join(".loaded"),
+            directory: directory,
+            loader: loader,
+            plugins: Vec::new(),
+            retired: Vec::new(),
+            generation: 0,
+        }
+    }
+
+    #[inline]
+    pub fn directory(&self) -> &Path { &self.directory }
+
+    /// Find the manifests of all plugins in the plugin directory, without loading them
+    pub fn discover(&self) -> PluginResult<Vec<(PathBuf, PluginResult<PluginManifest>)>> {
+        let mut manifests = Vec::new();
+
+        for entry in try_throw!(fs::read_dir(&self.directory)) {
+            let path = try_throw!(entry).path().join(MANIFEST_NAME);
+
+            if path.is_file() {
+                let manifest = PluginManifest::open(&path);
+
+                manifests.push((path, manifest));
+            }
+        }
+
+        manifests.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(manifests)
+    }
+
+    /// Load every plugin in the plugin directory which isn't already loaded,
+    /// returning the result for each manifest found
+    pub fn load_all(&mut self) -> PluginResult<Vec<(PathBuf, PluginResult<()>)>> {
+        let manifests = try_rethrow!(self.discover());
+
+        Ok(manifests.into_iter().filter_map(|(path, manifest)| {
+            match manifest {
+                Ok(ref manifest) if self.is_loaded(&manifest.name) => None,
+                Ok(manifest) => {
+                    let result = self.load_manifest(manifest, Some(path.clone()));
+
+                    Some((path, result))
+                }
+                Err(err) => Some((path, Err(err))),
+            }
+        }).collect())
+    }
+
+    /// Load a plugin from its manifest
+    pub fn load(&mut self, manifest: PluginManifest) -> PluginResult<()> {
+        self.load_manifest(manifest, None)
+    }
+
+    fn load_manifest(&mut self, manifest: PluginManifest, manifest_path: Option<PathBuf>) -> PluginResult<()> {
+        if self.is_loaded(&manifest.name) {
+            throw!(PluginError::AlreadyLoaded(manifest.name));
+        }
+
+        let plugin = try_rethrow!(self.open(manifest, manifest_path));
+
+        self.plugins.push(plugin);
+
+        Ok(())
+    }
+
+    /// Load the library of a plugin and register it
+    fn open(&mut self, manifest: PluginManifest, manifest_path: Option<PathBuf>) -> PluginResult<LoadedPlugin> {
+        if !manifest.is_supported() {
+            throw!(PluginError::UnsupportedEngine { name: manifest.name, required: manifest.engine.to_string() });
+        }
+
+        self.generation += 1;
+
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0);
+
+        let shadow = self.shadow_directory.join(format!("{}-{}-{}{}", manifest.name, timestamp, self.generation, DLL_SUFFIX));
+
+        let (library, entrypoints) = try_rethrow!((self.loader)(&manifest, shadow));
+
+        let library = Arc::new(library);
+
+        // Don't call anything else in a library built for another API
+        let api_version = (entrypoints.api_version)();
+
+        if api_version != PLUGIN_API_VERSION {
+            throw!(PluginError::UnsupportedApi { name: manifest.name, version: api_version });
+        }
+
+        // The other entrypoints use the Rust ABI and types, so they need the same compiler
+        let build = unsafe {
+            let build = (entrypoints.build)();
+
+            if build.is_null() { String::new() } else { CStr::from_ptr(build).to_string_lossy().into_owned() }
+        };
+
+        if build != PLUGIN_BUILD {
+            throw!(PluginError::IncompatibleBuild { name: manifest.name, build: build });
+        }
+
+        let mut registrar = Registrar::new(library.clone());
+
+        if let Err(message) = (entrypoints.register)(&mut registrar) {
+            // The registrar may hold closures from the library, so it has to go before the library does
+            drop(registrar);
+
+            self.retired.push(library);
+            self.release_unused();
+
+            throw!(PluginError::Registration(message));
+        }
+
+        Ok(LoadedPlugin {
+            manifest: manifest,
+            manifest_path: manifest_path,
+            registrar: registrar,
+            unload: entrypoints.unload,
+            library: library,
+        })
+    }
+
+    /// Unload a plugin. Its library stays open until nothing created by it is in use.
+    pub fn unload(&mut self, name: &str) -> PluginResult<()> {
+        match self.plugins.iter().position(|plugin| plugin.manifest.name == name) {
+            Some(index) => {
+                let plugin = self.plugins.remove(index);
+
+                self.retire(plugin);
+                self.release_unused();
+
+                Ok(())
+            }
+            None => throw!(PluginError::NotLoaded(name.to_string())),
+        }
+    }
+
+    /// Reload a plugin from its library, rereading its manifest if it was found by `load_all`.
+    ///
+    /// If the new version fails to load, the old one stays loaded.
+    pub fn reload(&mut self, name: &str) -> PluginResult<()> {
+        let index = match self.plugins.iter().position(|plugin| plugin.manifest.name == name) {
+            Some(index) => index,
+            None => throw!(PluginError::NotLoaded(name.to_string())),
+        };
+
+        let (manifest, manifest_path) = {
+            let plugin = &self.plugins[index];
+
+            match plugin.manifest_path {
+                Some(ref path) => (try_rethrow!(PluginManifest::open(path)), Some(path.clone())),
+                None => (plugin.manifest.clone(), None),
+            }
+        };
+
+        if manifest.name != name {
+            throw!(PluginError::InvalidManifest(format!("plugin was renamed from \"{}\" to \"{}\"", name, manifest.name)));
+        }
+
+        let plugin = try_rethrow!(self.open(manifest, manifest_path));
+
+        let old = mem::replace(&mut self.plugins[index], plugin);
+
+        self.retire(old);
+        self.release_unused();
+
+        Ok(())
+    }
+
+    fn retire(&mut self, plugin: LoadedPlugin) {
+        let LoadedPlugin { registrar, unload, library, .. } = plugin;
+
+        drop(registrar);
+
+        if let Some(unload) = unload {
+            unload();
+        }
+
+        self.retired.push(library);
+    }
+
+    /// Close the libraries of unloaded plugins which are no longer in use, returning how many were closed
+    pub fn release_unused(&mut self) -> usize {
+        let before = self.retired.len();
+
+        // Only the host holds a reference, so the library is closed from here rather than from its own code
+        self.retired.retain(|library| Arc::strong_count(library) > 1);
+
+        before - self.retired.len()
+    }
+
+    /// Check if a plugin with the given name is loaded
+    pub fn is_loaded(&self, name: &str) -> bool {
+        self.plugins.iter().any(|plugin| plugin.manifest.name == name)
+    }
+
+    /// Manifest of a loaded plugin
+    pub fn manifest(&self, name: &str) -> Option<&PluginManifest> {
+        self.plugins.iter().find(|plugin| plugin.manifest.name == name).map(|plugin| &plugin.manifest)
+    }
+
+    /// Manifests of all loaded plugins, in the order they were loaded
+    pub fn plugins(&self) -> Vec<&PluginManifest> {
+        self.plugins.iter().map(|plugin| &plugin.manifest).collect()
+    }
+
+    /// Registrations of a loaded plugin
+    pub fn registrar(&self, name: &str) -> Option<&Registrar> {
+        self.plugins.iter().find(|plugin| plugin.manifest.name == name).map(|plugin| &plugin.registrar)
+    }
+
+    /// Add the components and systems of all loaded plugins to a world and system builder.
+    ///
+    /// This should be done again with a new schedule whenever plugins are loaded, unloaded or reloaded.
+    pub fn install(&self, world: &mut World, registry: &mut ComponentRegistry, builder: &mut SystemBuilder) -> SystemResult<()> {
+        for plugin in &self.plugins {
+            plugin.registrar.install_components(world, registry);
+        }
+
+        for plugin in &self.plugins {
+            try_rethrow!(plugin.registrar.install_systems(builder));
+        }
+
+        Ok(())
+    }
+
+    /// Stop the actors of plugins which have been unloaded or reloaded since the last time,
+    /// and start the actors of plugins which have been loaded.
+    pub fn install_actors(&self, actors: &mut ActorSystem) -> PluginResult<()> {
+        actors.retain_plugin_actors(|library| {
+            self.plugins.iter().any(|plugin| &*plugin.library as *const PluginLibrary == &**library as *const PluginLibrary)
+        });
+
+        for plugin in &self.plugins {
+            if !actors.has_plugin_actors(&plugin.library) {
+                try_rethrow!(plugin.registrar.install_actors(actors));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Load an asset with a plugin format for the given extension, if any plugin has one.
+    ///
+    /// If several plugins have a format for the extension, the one loaded last is used.
+    pub fn load_asset(&self, extension: &str, medium: AssetMedium) -> Option<AssetResult<PluginAsset>> {
+        for plugin in self.plugins.iter().rev() {
+            if plugin.registrar.has_asset_format(extension) {
+                return plugin.registrar.load_asset(extension, medium);
+            }
+        }
+
+        None
+    }
+}
+
+impl Drop for PluginHost {
+    fn drop(&mut self) {
+        while let Some(plugin) = self.plugins.pop() {
+            self.retire(plugin);
+        }
+
+        self.release_unused();
+
+        // Anything still in use would crash if its library was closed, so leave those open
+        for library in self.retired.drain(..) {
+            mem::forget(library);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::fs::File;
+    use std::io::Write;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering, ATOMIC_BOOL_INIT, ATOMIC_USIZE_INIT};
+
+    use ::PLUGIN_BUILD_CSTR;
+
+    use super::*;
+
+    static REGISTERED: AtomicUsize = ATOMIC_USIZE_INIT;
+    static UNLOADED: AtomicUsize = ATOMIC_USIZE_INIT;
+    static FAIL_REGISTRATION: AtomicBool = ATOMIC_BOOL_INIT;
+
+    extern "C" fn api_version() -> u32 { PLUGIN_API_VERSION }
+
+    extern "C" fn other_api_version() -> u32 { PLUGIN_API_VERSION + 1 }
+
+    extern "C" fn build() -> *const c_char { PLUGIN_BUILD_CSTR.as_ptr() as *const c_char }
+
+    extern "C" fn other_build() -> *const c_char { b"rustc 1.0.0 (a59de37e9 2015-05-13)\0".as_ptr() as *const c_char }
+
+    fn register(_: &mut Registrar) -> Result<(), String> { Ok(()) }
+
+    fn register_incompatible(_: &mut Registrar) -> Result<(), String> {
+        panic!("Registered an incompatible plugin")
+    }
+
+    fn register_reloadable(_: &mut Registrar) -> Result<(), String> {
+        if FAIL_REGISTRATION.load(Ordering::SeqCst) {
+            return Err("broken".to_string());
+        }
+
+        REGISTERED.fetch_add(1, Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    fn unload_reloadable() {
+        UNLOADED.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Stands in for `load_library`, with entrypoints picked by plugin name
+    fn load_builtin(manifest: &PluginManifest, _: PathBuf) -> PluginResult<(PluginLibrary, Entrypoints)> {
+        let mut entrypoints = Entrypoints { api_version: api_version, build: build, register: register, unload: None };
+
+        match manifest.name.as_str() {
+            "other-api" => {
+                entrypoints.api_version = other_api_version;
+                entrypoints.register = register_incompatible;
+            }
+            "other-build" => {
+                entrypoints.build = other_build;
+                entrypoints.register = register_incompatible;
+            }
+            "reloadable" => {
+                entrypoints.register = register_reloadable;
+                entrypoints.unload = Some(unload_reloadable);
+            }
+            _ => {}
+        }
+
+        Ok((PluginLibrary::builtin(&manifest.name), entrypoints))
+    }
+
+    fn manifest(name: &str, engine: &str) -> String {
+        format!("[plugin]\nname = \"{}\"\nversion = \"0.1.0\"\nengine = \"{}\"\n", name, engine)
+    }
+
+    /// Create a fresh plugin directory with a subdirectory and manifest for each plugin
+    fn plugin_directory(test: &str, plugins: &[(&str, String)]) -> PathBuf {
+        let directory = env::temp_dir().join(format!("combustion_plugin_{}", test));
+
+        let _ = fs::remove_dir_all(&directory);
+
+        for &(name, ref manifest) in plugins {
+            fs::create_dir_all(directory.join(name)).unwrap();
+
+            File::create(directory.join(name).join(MANIFEST_NAME)).unwrap().write_all(manifest.as_bytes()).unwrap();
+        }
+
+        directory
+    }
+
+    fn error_of(result: &PluginResult<()>) -> &PluginError {
+        match *result {
+            Ok(_) => panic!("Expected an error"),
+            Err(ref err) => err.error(),
+        }
+    }
+
+    #[test]
+    fn discover_manifests() {
+        let directory = plugin_directory("discover", &[
+            ("b", manifest("b", "^0.1")),
+            ("a", manifest("a", "^0.1")),
+            ("broken", "[plugin]\nname = \"broken\"".to_string()),
+        ]);
+
+        fs::create_dir_all(directory.join("empty")).unwrap();
+
+        let host = PluginHost::with_loader(&directory, load_builtin);
+
+        let manifests = host.discover().unwrap();
+
+        // Directories without a manifest are skipped, and the rest are sorted by path
+        assert_eq!(manifests.len(), 3);
+        assert_eq!(manifests[0].1.as_ref().unwrap().name, "a");
+        assert_eq!(manifests[1].1.as_ref().unwrap().name, "b");
+        assert!(manifests[2].1.is_err());
+
+        assert!(host.plugins().is_empty());
+    }
+
+    #[test]
+    fn version_negotiation() {
+        let directory = plugin_directory("negotiation", &[
+            ("future", manifest("future", "^9")),
+            ("good", manifest("good", "^0.1")),
+            ("other-api", manifest("other-api", "^0.1")),
+            ("other-build", manifest("other-build", "^0.1")),
+        ]);
+
+        let mut host = PluginHost::with_loader(&directory, load_builtin);
+
+        let results = host.load_all().unwrap();
+
+        assert_eq!(results.len(), 4);
+
+        match *error_of(&results[0].1) {
+            PluginError::UnsupportedEngine { ref name, .. } => assert_eq!(name, "future"),
+            ref err => panic!("Unexpected error: {}", err),
+        }
+
+        assert!(results[1].1.is_ok());
+
+        match *error_of(&results[2].1) {
+            PluginError::UnsupportedApi { ref name, version } => {
+                assert_eq!(name, "other-api");
+                assert_eq!(version, PLUGIN_API_VERSION + 1);
+            }
+            ref err => panic!("Unexpected error: {}", err),
+        }
+
+        match *error_of(&results[3].1) {
+            PluginError::IncompatibleBuild { ref name, ref build } => {
+                assert_eq!(name, "other-build");
+                assert!(build.starts_with("rustc 1.0.0"));
+            }
+            ref err => panic!("Unexpected error: {}", err),
+        }
+
+        assert_eq!(host.plugins().len(), 1);
+        assert!(host.is_loaded("good"));
+
+        // Loading again skips the loaded plugin but retries the others
+        assert_eq!(host.load_all().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn reload() {
+        let directory = plugin_directory("reload", &[("reloadable", manifest("reloadable", "^0.1"))]);
+
+        let mut host = PluginHost::with_loader(&directory, load_builtin);
+
+        host.load_all().unwrap();
+
+        assert_eq!(REGISTERED.load(Ordering::SeqCst), 1);
+
+        host.reload("reloadable").unwrap();
+
+        // The new version is registered before the old one is unloaded
+        assert_eq!(REGISTERED.load(Ordering::SeqCst), 2);
+        assert_eq!(UNLOADED.load(Ordering::SeqCst), 1);
+        assert!(host.is_loaded("reloadable"));
+
+        // A reload which fails keeps the old version
+        FAIL_REGISTRATION.store(true, Ordering::SeqCst);
+
+        match *error_of(&host.reload("reloadable")) {
+            PluginError::Registration(ref message) => assert_eq!(message, "broken"),
+            ref err => panic!("Unexpected error: {}", err),
+        }
+
+        assert!(host.is_loaded("reloadable"));
+        assert_eq!(UNLOADED.load(Ordering::SeqCst), 1);
+
+        FAIL_REGISTRATION.store(false, Ordering::SeqCst);
+
+        host.unload("reloadable").unwrap();
+
+        assert!(!host.is_loaded("reloadable"));
+        assert_eq!(UNLOADED.load(Ordering::SeqCst), 2);
+        assert!(host.reload("reloadable").is_err());
+    }
+}
@@ -4,8 +4,30 @@
 //!
 //! The idea so far is to use an Actor-Model pattern for plugins, where the engine provides the "model" and the plugins are the actors,
 //! modifying or creating data. Though that may be subject to change in the future, it does allow some plugins to be run concurrently.
+//!
+//! Plugins are shared libraries with a `plugin.toml` manifest, loaded by a `PluginHost`.
+//! A plugin declares its registration entrypoint with `declare_plugin!`:
+//!
+//! ```ignore
+//! #[macro_use]
+//! extern crate combustion_plugin;
+//!
+//! use combustion_plugin::Registrar;
+//!
+//! fn register(registrar: &mut Registrar) -> Result<(), String> {
+//!     registrar.register_component::<Spin>();
+//!     registrar.add_system("spin", || SpinSystem, &[], SystemAccess::new().write::<Spin>());
+//!
+//!     Ok(())
+//! }
+//!
+//! declare_plugin!(register);
+//! ```
+//!
+//! Plugins can also add actors, which run concurrently on their own threads and change the world
+//! only through commands applied at sync points. See the `actor` module.
 
-#![feature(proc_macro)]
+#![feature(proc_macro, box_syntax)]
 
 extern crate libloading;
 #[macro_use]
@@ -15,7 +37,85 @@ extern crate serde;
 extern crate serde_derive;
 extern crate toml;
 extern crate semver;
+extern crate fnv;
+
+#[macro_use]
+extern crate trace_error;
+
+extern crate combustion_asset as asset;
+extern crate combustion_ecs as ecs;
 
 pub const COMBUSTION_PLUGIN_CARGO_TOML: &'static str = include_str!("../Cargo.toml");
 
-pub mod version;
\ No newline at end of file
+/// Version of the interface between the host and plugins.
+///
+/// Plugins built with a different version are refused before any of their code besides the version check is run.
+pub const PLUGIN_API_VERSION: u32 = 1;
+
+/// Compiler and plugin crate version the host and plugins have to be built with.
+///
+/// Registration passes Rust types like `Registrar` between the host and plugins, and those only have the same layout
+/// when built by the same compiler from the same source, so plugins from any other build are refused as well.
+pub const PLUGIN_BUILD: &'static str = concat!(env!("COMBUSTION_PLUGIN_RUSTC"), ", combustion_plugin ", env!("CARGO_PKG_VERSION"));
+
+#[doc(hidden)]
+pub const PLUGIN_BUILD_CSTR: &'static str = concat!(env!("COMBUSTION_PLUGIN_RUSTC"), ", combustion_plugin ", env!("CARGO_PKG_VERSION"), "\0");
+
+#[doc(hidden)]
+pub const API_VERSION_SYMBOL: &'static [u8] = b"combustion_plugin_api_version\0";
+#[doc(hidden)]
+pub const BUILD_SYMBOL: &'static [u8] = b"combustion_plugin_build\0";
+#[doc(hidden)]
+pub const REGISTER_SYMBOL: &'static [u8] = b"combustion_plugin_register\0";
+#[doc(hidden)]
+pub const UNLOAD_SYMBOL: &'static [u8] = b"combustion_plugin_unload\0";
+
+pub mod version;
+pub mod error;
+pub mod manifest;
+pub mod library;
+pub mod registrar;
+pub mod host;
+pub mod actor;
+
+pub use error::{PluginError, PluginResult};
+pub use actor::{Actor, ActorContext, ActorFrame, ActorSystem};
+pub use host::{PluginHost, Entrypoints};
+pub use manifest::PluginManifest;
+pub use registrar::Registrar;
+
+/// Export the entrypoints of a plugin.
+///
+/// The first function registers everything the plugin provides, and the optional second one
+/// is called right before the plugin is unloaded.
+///
+/// Only the version entrypoints use the C ABI. The others are called once the host has checked
+/// that the plugin was built with the same compiler, see `PLUGIN_BUILD`.
+#[macro_export]
+macro_rules! declare_plugin {
+    ($register:path) => {
+        #[no_mangle]
+        pub extern "C" fn combustion_plugin_api_version() -> u32 {
+            $crate::PLUGIN_API_VERSION
+        }
+
+        #[no_mangle]
+        pub extern "C" fn combustion_plugin_build() -> *const ::std::os::raw::c_char {
+            $crate::PLUGIN_BUILD_CSTR.as_ptr() as *const ::std::os::raw::c_char
+        }
+
+        #[no_mangle]
+        pub fn combustion_plugin_register(registrar: &mut $crate::Registrar) -> Result<(), String> {
+            $register(registrar)
+        }
+    };
+
+    ($register:path, $unload:path) => {
+        declare_plugin!($register);
+
+        #[no_mangle]
+        pub fn combustion_plugin_unload() {
+            $unload()
+        }
+    };
+}
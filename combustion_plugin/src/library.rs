@@ -0,0 +1,120 @@
+//! Loaded plugin libraries
+//!
+//! Anything created by a plugin, like its systems, holds a reference to the library it came from,
+//! so the library is only closed by the `PluginHost` once nothing from it is left.
+//!
+//! Libraries are loaded from a copy of the original file, which lets a plugin be rebuilt and reloaded
+//! while the old version is still in use. Plugins linked into the engine itself use a library without any file.
+
+use std::any::Any;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use libloading::{Library, Symbol};
+
+use ecs::{Delta, RunArg, System};
+
+use ::error::{PluginError, PluginResult};
+
+/// Shared library of a loaded plugin
+pub struct PluginLibrary {
+    name: String,
+    library: Option<Library>,
+    /// Copy of the library which was actually loaded
+    shadow: Option<PathBuf>,
+}
+
+impl PluginLibrary {
+    /// Copy the library to `shadow` and load it from there
+    pub fn open<P: AsRef<Path>>(name: &str, path: P, shadow: PathBuf) -> PluginResult<PluginLibrary> {
+        if let Some(parent) = shadow.parent() {
+            try_throw!(fs::create_dir_all(parent));
+        }
+
+        try_throw!(fs::copy(path, &shadow));
+
+        let library = match Library::new(&shadow) {
+            Ok(library) => library,
+            Err(err) => {
+                let _ = fs::remove_file(&shadow);
+
+                throw!(PluginError::Io(err))
+            }
+        };
+
+        Ok(PluginLibrary { name: name.to_string(), library: Some(library), shadow: Some(shadow) })
+    }
+
+    /// Library for a plugin linked into the engine, which has no symbols of its own
+    pub fn builtin(name: &str) -> PluginLibrary {
+        PluginLibrary { name: name.to_string(), library: None, shadow: None }
+    }
+
+    /// Name of the plugin the library belongs to
+    #[inline]
+    pub fn name(&self) -> &str { &self.name }
+
+    /// Look up a symbol in the library
+    pub unsafe fn get<T>(&self, symbol: &[u8]) -> Option<Symbol<T>> {
+        self.library.as_ref().and_then(|library| library.get(symbol).ok())
+    }
+}
+
+impl Drop for PluginLibrary {
+    fn drop(&mut self) {
+        // Close the library before deleting the file it was loaded from
+        drop(self.library.take());
+
+        if let Some(ref shadow) = self.shadow {
+            let _ = fs::remove_file(shadow);
+        }
+    }
+}
+
+/// System created by a plugin, which keeps its library loaded
+pub struct PluginSystem<S> {
+    system: S,
+    _library: Arc<PluginLibrary>,
+}
+
+impl<S> PluginSystem<S> {
+    pub fn new(system: S, library: Arc<PluginLibrary>) -> PluginSystem<S> {
+        PluginSystem { system: system, _library: library }
+    }
+}
+
+impl<S> System<Delta> for PluginSystem<S> where S: System<Delta> {
+    #[inline]
+    fn run(&mut self, arg: RunArg, delta: Delta) {
+        self.system.run(arg, delta)
+    }
+}
+
+/// Asset loaded by a plugin, which keeps its library loaded
+pub struct PluginAsset {
+    value: Box<Any + Send>,
+    _library: Arc<PluginLibrary>,
+}
+
+impl PluginAsset {
+    pub fn new(value: Box<Any + Send>, library: Arc<PluginLibrary>) -> PluginAsset {
+        PluginAsset { value: value, _library: library }
+    }
+
+    /// Check if the asset is of the given type
+    #[inline]
+    pub fn is<T: Any>(&self) -> bool {
+        self.value.is::<T>()
+    }
+
+    #[inline]
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.value.downcast_ref()
+    }
+
+    #[inline]
+    pub fn downcast_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.value.downcast_mut()
+    }
+}
@@ -0,0 +1,158 @@
+//! Plugin manifests
+//!
+//! Every plugin directory contains a `plugin.toml` describing the plugin:
+//!
+//! ```toml
+//! [plugin]
+//! name = "example"
+//! version = "0.1.0"
+//! engine = "^0.1"
+//! # Optional, defaults to the plugin name
+//! library = "example"
+//! description = "An example plugin"
+//! ```
+//!
+//! `library` is the name of the shared library without the platform prefix and extension,
+//! so `example` is found as `libexample.so`, `libexample.dylib` or `example.dll` next to the manifest.
+
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use semver::{Version, VersionReq};
+use toml::{Parser, Table, Value};
+
+use ::error::{PluginError, PluginResult};
+use ::version::COMBUSTION_PLUGIN_VERSION;
+
+/// File name of plugin manifests
+pub const MANIFEST_NAME: &'static str = "plugin.toml";
+
+/// Description of a plugin, as read from its `plugin.toml`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginManifest {
+    pub name: String,
+    pub version: Version,
+    /// Engine versions the plugin works with
+    pub engine: VersionReq,
+    pub description: Option<String>,
+    /// Full path to the shared library
+    pub library: PathBuf,
+}
+
+fn get_str<'a>(table: &'a Table, key: &str) -> PluginResult<Option<&'a str>> {
+    match table.get(key) {
+        Some(&Value::String(ref s)) => Ok(Some(s.as_str())),
+        Some(_) => throw!(PluginError::InvalidManifest(format!("\"{}\" must be a string", key))),
+        None => Ok(None),
+    }
+}
+
+fn require_str<'a>(table: &'a Table, key: &str) -> PluginResult<&'a str> {
+    match try_rethrow!(get_str(table, key)) {
+        Some(s) => Ok(s),
+        None => throw!(PluginError::InvalidManifest(format!("missing \"{}\"", key))),
+    }
+}
+
+impl PluginManifest {
+    /// Parse a manifest, with the library path relative to `directory`
+    pub fn parse<P: AsRef<Path>>(source: &str, directory: P) -> PluginResult<PluginManifest> {
+        let mut parser = Parser::new(source);
+
+        let root = match parser.parse() {
+            Some(root) => root,
+            None => {
+                let message = parser.errors.iter().map(|err| err.desc.clone()).collect::<Vec<_>>().join(", ");
+
+                throw!(PluginError::InvalidManifest(message))
+            }
+        };
+
+        let plugin = match root.get("plugin").and_then(|plugin| plugin.as_table()) {
+            Some(plugin) => plugin,
+            None => throw!(PluginError::InvalidManifest("missing [plugin] table".to_string())),
+        };
+
+        let name = try_rethrow!(require_str(plugin, "name"));
+
+        let version = match Version::parse(try_rethrow!(require_str(plugin, "version"))) {
+            Ok(version) => version,
+            Err(err) => throw!(PluginError::InvalidManifest(format!("invalid version: {}", err))),
+        };
+
+        let engine = match VersionReq::parse(try_rethrow!(require_str(plugin, "engine"))) {
+            Ok(engine) => engine,
+            Err(err) => throw!(PluginError::InvalidManifest(format!("invalid engine requirement: {:?}", err))),
+        };
+
+        let library = try_rethrow!(get_str(plugin, "library")).unwrap_or(name);
+
+        Ok(PluginManifest {
+            name: name.to_string(),
+            version: version,
+            engine: engine,
+            description: try_rethrow!(get_str(plugin, "description")).map(|s| s.to_string()),
+            library: directory.as_ref().join(format!("{}{}{}", DLL_PREFIX, library, DLL_SUFFIX)),
+        })
+    }
+
+    /// Read the manifest at the given path
+    pub fn open<P: AsRef<Path>>(path: P) -> PluginResult<PluginManifest> {
+        let path = path.as_ref();
+
+        let mut source = String::new();
+
+        try_throw!(try_throw!(File::open(path)).read_to_string(&mut source));
+
+        PluginManifest::parse(&source, path.parent().unwrap_or(Path::new(".")))
+    }
+
+    /// Check if the plugin works with this version of the engine
+    pub fn is_supported(&self) -> bool {
+        match *COMBUSTION_PLUGIN_VERSION {
+            Some(ref version) => self.engine.matches(version),
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_manifest() {
+        let manifest = PluginManifest::parse(r#"
+            [plugin]
+            name = "example"
+            version = "1.2.3"
+            engine = "^0.1"
+        "#, "plugins/example").unwrap();
+
+        assert_eq!(manifest.name, "example");
+        assert_eq!(manifest.version, Version::parse("1.2.3").unwrap());
+        assert_eq!(manifest.description, None);
+        assert_eq!(manifest.library, Path::new("plugins/example").join(format!("{}example{}", DLL_PREFIX, DLL_SUFFIX)));
+        assert!(manifest.is_supported());
+    }
+
+    #[test]
+    fn unsupported_engine() {
+        let manifest = PluginManifest::parse(r#"
+            [plugin]
+            name = "example"
+            version = "1.2.3"
+            engine = "^0.2"
+        "#, ".").unwrap();
+
+        assert!(!manifest.is_supported());
+    }
+
+    #[test]
+    fn missing_fields() {
+        assert!(PluginManifest::parse("[plugin]\nname = \"example\"", ".").is_err());
+        assert!(PluginManifest::parse("name = \"example\"", ".").is_err());
+    }
+}
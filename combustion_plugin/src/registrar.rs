@@ -0,0 +1,184 @@
+//! Registration of everything a plugin adds to the engine
+//!
+//! A plugin's registration entrypoint is given a `Registrar`, which records the components, systems
+//! and asset formats it provides. Nothing is added to the engine right away. Instead, the `PluginHost` installs
+//! everything from all loaded plugins whenever the world and schedule are (re)built, so the same registrations
+//! can be installed again after another plugin is loaded or unloaded.
+//!
+//! Actors are the exception, since they keep their own state. They are only started once per loaded library.
+
+use std::any::Any;
+use std::sync::Arc;
+use std::time::Duration;
+
+use fnv::FnvHashMap;
+
+use asset::asset::AssetMedium;
+use asset::error::AssetResult;
+
+use ecs::{Component, Delta, System, World};
+use ecs::builder::{SystemAccess, SystemBuilder, SystemConstructor};
+use ecs::error::SystemResult;
+use ecs::serialize::{ComponentRegistry, SerializeComponent};
+
+use ::actor::{Actor, ActorSystem};
+use ::error::PluginResult;
+use ::library::{PluginAsset, PluginLibrary, PluginSystem};
+
+/// Loads an asset from the given medium
+pub type AssetLoader = Box<Fn(AssetMedium) -> AssetResult<Box<Any + Send>> + Send + Sync>;
+
+type ComponentInstaller = Box<Fn(&mut World, &mut ComponentRegistry) + Send + Sync>;
+
+type SystemSpawner = Box<Fn(&str) -> SystemConstructor + Send + Sync>;
+
+type ActorSpawner = Box<Fn() -> Box<Actor> + Send + Sync>;
+
+struct ActorRegistration {
+    name: String,
+    spawn: ActorSpawner,
+    budget: Duration,
+}
+
+struct SystemRegistration {
+    name: String,
+    spawn: SystemSpawner,
+    deps: Vec<String>,
+    access: SystemAccess,
+}
+
+/// Collects everything a plugin registers
+pub struct Registrar {
+    components: Vec<ComponentInstaller>,
+    systems: Vec<SystemRegistration>,
+    actors: Vec<ActorRegistration>,
+    formats: FnvHashMap<String, AssetLoader>,
+    // Dropped last, since everything above was created by code in the library
+    library: Arc<PluginLibrary>,
+}
+
+impl Registrar {
+    pub fn new(library: Arc<PluginLibrary>) -> Registrar {
+        Registrar {
+            components: Vec::new(),
+            systems: Vec::new(),
+            actors: Vec::new(),
+            formats: FnvHashMap::default(),
+            library: library,
+        }
+    }
+
+    /// Name of the plugin being registered
+    #[inline]
+    pub fn plugin_name(&self) -> &str { self.library.name() }
+
+    /// Register a component type
+    pub fn register_component<C: Component>(&mut self) {
+        self.components.push(Box::new(|world: &mut World, _: &mut ComponentRegistry| world.register::<C>()));
+    }
+
+    /// Register a component type which can be saved in world snapshots
+    pub fn register_serialized_component<C: SerializeComponent>(&mut self) {
+        self.components.push(Box::new(|world: &mut World, registry: &mut ComponentRegistry| {
+            world.register::<C>();
+            registry.register::<C>();
+        }));
+    }
+
+    /// Add a system, which runs after all of its dependencies and only accesses the given data.
+    ///
+    /// A new system is created with `factory` every time the schedule is built.
+    pub fn add_system<N, F, S>(&mut self, name: N, factory: F, deps: &[&str], access: SystemAccess)
+        where N: Into<String>,
+              F: Fn() -> S + Send + Sync + 'static,
+              S: System<Delta> + 'static {
+        let library = self.library.clone();
+
+        let spawn: SystemSpawner = Box::new(move |name: &str| -> SystemConstructor {
+            let name = name.to_string();
+
+            let mut system = Some(PluginSystem::new(factory(), library.clone()));
+
+            box move |planner, priority| {
+                if let Some(system) = system.take() {
+                    planner.add_system(system, &name, priority);
+                }
+
+                Ok(())
+            }
+        });
+
+        self.systems.push(SystemRegistration {
+            name: name.into(),
+            spawn: spawn,
+            deps: deps.iter().map(|dep| dep.to_string()).collect(),
+            access: access,
+        });
+    }
+
+    /// Add an actor, which is created with `factory` and given the time budget for each frame
+    pub fn add_actor<N, F, A>(&mut self, name: N, factory: F, budget: Duration)
+        where N: Into<String>,
+              F: Fn() -> A + Send + Sync + 'static,
+              A: Actor {
+        self.actors.push(ActorRegistration {
+            name: name.into(),
+            spawn: box move || -> Box<Actor> { box factory() },
+            budget: budget,
+        });
+    }
+
+    /// Add a loader for assets with the given file extension, replacing any previous loader for it
+    pub fn add_asset_format<F>(&mut self, extension: &str, loader: F)
+        where F: Fn(AssetMedium) -> AssetResult<Box<Any + Send>> + Send + Sync + 'static {
+        self.formats.insert(extension.to_ascii_lowercase(), Box::new(loader));
+    }
+
+    /// Register all components with the world and snapshot registry
+    pub fn install_components(&self, world: &mut World, registry: &mut ComponentRegistry) {
+        for install in &self.components {
+            install(world, registry);
+        }
+    }
+
+    /// Add all systems to the builder
+    pub fn install_systems(&self, builder: &mut SystemBuilder) -> SystemResult<()> {
+        for system in &self.systems {
+            try_rethrow!(builder.add_system_with_access(system.name.clone(), (system.spawn)(&system.name),
+                                                        system.deps.clone(), system.access.clone()));
+        }
+
+        Ok(())
+    }
+
+    /// Start all actors, which keep the library open until they are stopped
+    pub fn install_actors(&self, actors: &mut ActorSystem) -> PluginResult<()> {
+        for actor in &self.actors {
+            try_rethrow!(actors.spawn_boxed(&actor.name, (actor.spawn)(), actor.budget, Some(self.library.clone())));
+        }
+
+        Ok(())
+    }
+
+    /// Names of the systems added by the plugin
+    pub fn systems(&self) -> Vec<&str> {
+        self.systems.iter().map(|system| system.name.as_str()).collect()
+    }
+
+    /// File extensions of the asset formats added by the plugin
+    pub fn asset_formats(&self) -> Vec<&str> {
+        self.formats.keys().map(|extension| extension.as_str()).collect()
+    }
+
+    /// Check if the plugin has a loader for the given extension
+    pub fn has_asset_format(&self, extension: &str) -> bool {
+        self.formats.contains_key(&extension.to_ascii_lowercase())
+    }
+
+    /// Load an asset with the plugin's loader for the given extension, if it has one
+    pub fn load_asset(&self, extension: &str, medium: AssetMedium) -> Option<AssetResult<PluginAsset>> {
+        self.formats.get(&extension.to_ascii_lowercase()).map(|loader| {
+            loader(medium).map(|value| PluginAsset::new(value, self.library.clone()))
+        })
+    }
+}
@@ -37,11 +37,19 @@ pub enum ProtocolError {
     Base64Error(Base64Error),
     /// Invalid type conversion
     MismatchedTypes(DataType, DataType),
+    /// A material or material preset with the given name does not exist
+    MissingMaterial(String),
+    /// Material presets inherit from each other in a cycle, given as the chain of names
+    PresetCycle(String),
 }
 
 impl Display for ProtocolError {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        f.write_str(self.description())
+        match *self {
+            ProtocolError::MissingMaterial(ref name) => write!(f, "{}: \"{}\"", self.description(), name),
+            ProtocolError::PresetCycle(ref chain) => write!(f, "{}: {}", self.description(), chain),
+            _ => f.write_str(self.description())
+        }
     }
 }
 
@@ -58,6 +66,8 @@ impl Error for ProtocolError {
             ProtocolError::NotInSchema(ref err) => err.description(),
             ProtocolError::Base64Error(ref err) => err.description(),
             ProtocolError::MismatchedTypes(..) => "Mismatched data types",
+            ProtocolError::MissingMaterial(_) => "Material not found",
+            ProtocolError::PresetCycle(_) => "Material presets form a cycle",
         }
     }
 }
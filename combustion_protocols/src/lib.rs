@@ -44,6 +44,24 @@ pub mod utils {
     pub mod protocol {
         #![allow(missing_docs)]
 
+        use common::color::Color;
+
         include!(concat!(env!("OUT_DIR"), "/protocols/utils_capnp.rs"));
+
+        impl<'a> color::Builder<'a> {
+            pub fn set_color(&mut self, color: &Color) {
+                self.set_r(color.r);
+                self.set_g(color.g);
+                self.set_b(color.b);
+                self.set_a(color.a);
+            }
+        }
+
+        impl<'a> color::Reader<'a> {
+            #[inline]
+            pub fn get_color(&self) -> Color {
+                Color::new(self.get_r(), self.get_g(), self.get_b(), self.get_a())
+            }
+        }
     }
 }
\ No newline at end of file
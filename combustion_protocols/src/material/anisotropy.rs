@@ -3,7 +3,7 @@
 use nalgebra::Vector3;
 
 /// Represents material anisotropy as a scaling amount and rotation
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Inspect)]
 pub struct MaterialAnisotropy {
     /// Amount of anisotropy
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -9,19 +9,26 @@ use std::path::PathBuf;
 use common::color::Color;
 use common::color::de as color_de;
 
+pub mod protocol;
+pub mod storage;
 pub mod defaults;
 pub mod anisotropy;
+pub mod resolve;
 
 #[cfg(feature = "sample")]
 pub mod sample;
 
 pub use self::defaults::*;
 pub use self::anisotropy::MaterialAnisotropy;
+pub use self::resolve::ResolvedMaterial;
 
 use self::anisotropy::de as anisotropy_de;
 
+/// File extension to Combustion material files
+pub const EXTENSION: &'static str = "cmat";
+
 /// Map of materials used for a certain model or scene
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MaterialMap {
     /// Map of named materials
     pub materials: HashMap<String, Material>
@@ -44,7 +51,7 @@ impl DerefMut for MaterialMap {
 }
 
 /// Represents a certain material for an object in a scene.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Inspect)]
 pub struct Material {
     /// Presets allow for materials to inherit properties from another material
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -138,7 +145,7 @@ pub struct Material {
 }
 
 /// Preferred rendering pipeline to use for the material
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Inspect)]
 pub enum RenderMethod {
     /// Use traditional forward rendering for this material.
     ///
@@ -163,7 +170,7 @@ pub enum RenderMethod {
 /// Which shader should be used for the material.
 ///
 /// Certain shaders are more optimized or use more accurate algorithms for special cases
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Inspect)]
 pub enum MaterialShader {
     /// All-in-one lighting shader used in deferred or forward rendering contexts
     #[serde(rename = "uber")]
@@ -0,0 +1,3 @@
+#![allow(missing_docs)]
+
+include!(concat!(env!("OUT_DIR"), "/protocols/material_capnp.rs"));
\ No newline at end of file
@@ -0,0 +1,189 @@
+//! Material preset resolution
+//!
+//! Flattens preset inheritance chains in a `MaterialMap` and fills in defaults,
+//! producing fully-specified materials for use at runtime.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use nalgebra::Vector3;
+
+use common::color::Color;
+
+use ::error::{ProtocolResult, ProtocolError};
+
+use super::{Material, MaterialMap, MaterialAnisotropy, MaterialShader, RenderMethod};
+
+/// Roughness used when neither roughness nor smoothness is specified
+pub const DEFAULT_ROUGHNESS: f32 = 0.5;
+/// Index-of-Refraction used when none is specified, which is about right for most dielectrics
+pub const DEFAULT_IOR: f32 = 1.5;
+
+/// Material with every property specified
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedMaterial {
+    /// Path to texture to apply to the material
+    pub texture: Option<PathBuf>,
+    /// Path to normal map for material
+    pub normal_map: Option<PathBuf>,
+    /// Path to tangent map for material
+    pub tangent_map: Option<PathBuf>,
+    /// Path to height map for material
+    pub height_map: Option<PathBuf>,
+    /// Path to texture to be used as roughness values
+    pub roughness_map: Option<PathBuf>,
+    /// Path to texture to be used as metallic values
+    pub metallic_map: Option<PathBuf>,
+    /// Roughness, with any smoothness already converted and averaged in
+    pub roughness: f32,
+    /// Metallic-ness, where `0.0` is purely dialectic
+    pub metallic: f32,
+    /// Color of the material
+    pub color: Color,
+    /// Emission strength
+    pub emission: f32,
+    /// Translucency, where `1.0` is fully opaque
+    pub translucency: f32,
+    /// Index-of-Refraction
+    pub ior: f32,
+    /// Amount of anisotropy
+    pub anisotropy: f32,
+    /// Rotation of anisotropy
+    pub anisotropy_rotation: Vector3<f32>,
+    /// Shader to use for the material
+    pub shader: MaterialShader,
+    /// Rendering pipeline to use for the material
+    pub render: RenderMethod,
+}
+
+/// Convert smoothness into roughness via `roughness = pow(1.0 - smoothness, 2.0)`
+#[inline]
+pub fn smoothness_to_roughness(smoothness: f32) -> f32 {
+    (1.0 - smoothness).powi(2)
+}
+
+impl MaterialAnisotropy {
+    /// Fill in any unspecified values from `parent`
+    pub fn inherit(&mut self, parent: &MaterialAnisotropy) {
+        self.amount = self.amount.or(parent.amount);
+        self.rotation = self.rotation.or(parent.rotation);
+    }
+}
+
+impl Material {
+    /// Fill in any unspecified values from `parent`.
+    ///
+    /// Roughness and smoothness are inherited together, so a material that specifies either one
+    /// completely overrides both from the parent. The preset of `self` is left untouched.
+    pub fn inherit(&mut self, parent: &Material) {
+        macro_rules! inherit {
+            ($($field:ident),*) => {$(
+                if self.$field.is_none() {
+                    self.$field = parent.$field.clone();
+                }
+            )*}
+        }
+
+        inherit!(texture, normal_map, tangent_map, height_map, roughness_map, metallic_map,
+                 metallic, emission, translucency, ior, shader, render);
+
+        if self.roughness.is_none() && self.smoothness.is_none() {
+            self.roughness = parent.roughness;
+            self.smoothness = parent.smoothness;
+        }
+
+        if self.color.is_none() {
+            self.color = parent.color;
+        }
+
+        self.anisotropy.inherit(&parent.anisotropy);
+    }
+
+    /// Effective roughness of this material alone, if specified.
+    ///
+    /// If both smoothness and roughness are specified, they are averaged together.
+    pub fn effective_roughness(&self) -> Option<f32> {
+        match (self.roughness, self.smoothness) {
+            (Some(roughness), Some(smoothness)) => Some((roughness + smoothness_to_roughness(smoothness)) * 0.5),
+            (Some(roughness), None) => Some(roughness),
+            (None, Some(smoothness)) => Some(smoothness_to_roughness(smoothness)),
+            (None, None) => None,
+        }
+    }
+
+    /// Fill in defaults for any unspecified values of this material, ignoring its preset
+    pub fn to_resolved(&self) -> ResolvedMaterial {
+        let translucency = self.translucency.unwrap_or(1.0);
+
+        ResolvedMaterial {
+            texture: self.texture.clone(),
+            normal_map: self.normal_map.clone(),
+            tangent_map: self.tangent_map.clone(),
+            height_map: self.height_map.clone(),
+            roughness_map: self.roughness_map.clone(),
+            metallic_map: self.metallic_map.clone(),
+            roughness: self.effective_roughness().unwrap_or(DEFAULT_ROUGHNESS),
+            metallic: self.metallic.unwrap_or(0.0),
+            color: if self.color.is_none() { Color::white() } else { self.color },
+            emission: self.emission.unwrap_or(0.0),
+            translucency: translucency,
+            ior: self.ior.unwrap_or(DEFAULT_IOR),
+            anisotropy: self.anisotropy.amount.unwrap_or(0.0),
+            anisotropy_rotation: self.anisotropy.rotation.unwrap_or_else(|| Vector3::new(0.0, 0.0, 0.0)),
+            shader: self.shader.unwrap_or(MaterialShader::Uber),
+            // Deferred rendering can't handle anything that isn't opaque
+            render: self.render.unwrap_or(if translucency < 1.0 { RenderMethod::Forward } else { RenderMethod::Deferred }),
+        }
+    }
+}
+
+impl MaterialMap {
+    /// Flatten the preset inheritance chain of a material into a single material with no preset.
+    ///
+    /// Returns `ProtocolError::MissingMaterial` if the material or any preset in its chain doesn't exist,
+    /// and `ProtocolError::PresetCycle` if the chain loops back on itself.
+    pub fn flatten(&self, name: &str) -> ProtocolResult<Material> {
+        let mut chain = vec![name.to_string()];
+
+        let mut flattened = match self.materials.get(name) {
+            Some(material) => material.clone(),
+            None => throw!(ProtocolError::MissingMaterial(name.to_string())),
+        };
+
+        while let Some(preset) = flattened.preset.take() {
+            if chain.contains(&preset) {
+                chain.push(preset);
+
+                throw!(ProtocolError::PresetCycle(chain.join(" -> ")));
+            }
+
+            let parent = match self.materials.get(&preset) {
+                Some(parent) => parent,
+                None => throw!(ProtocolError::MissingMaterial(preset.clone())),
+            };
+
+            chain.push(preset);
+
+            flattened.inherit(parent);
+            flattened.preset = parent.preset.clone();
+        }
+
+        Ok(flattened)
+    }
+
+    /// Flatten and fill in defaults for a single material
+    pub fn resolve(&self, name: &str) -> ProtocolResult<ResolvedMaterial> {
+        Ok(try_rethrow!(self.flatten(name)).to_resolved())
+    }
+
+    /// Resolve every material in the map
+    pub fn resolve_all(&self) -> ProtocolResult<HashMap<String, ResolvedMaterial>> {
+        let mut resolved = HashMap::with_capacity(self.materials.len());
+
+        for name in self.materials.keys() {
+            resolved.insert(name.clone(), try_rethrow!(self.resolve(name)));
+        }
+
+        Ok(resolved)
+    }
+}
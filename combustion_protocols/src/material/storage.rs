@@ -0,0 +1,269 @@
+//! Storage routines for materials
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use ::error::{ProtocolResult, ProtocolError};
+
+use ::traits::Storage;
+
+use ::math::protocol::vector3;
+use ::utils::protocol::option;
+
+use super::protocol;
+use super::{Material, MaterialMap, MaterialAnisotropy, MaterialShader, RenderMethod};
+
+/// Load an optional value stored as an unnamed `unset`/`value` union
+macro_rules! load_optional_value {
+    ($reader:expr, $group:ident) => {
+        match try_throw!($reader.which()) {
+            protocol::material::$group::Value(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// Save an optional value as an unnamed `unset`/`value` union
+macro_rules! save_optional_value {
+    ($builder:expr, $value:expr) => {
+        match $value {
+            Some(value) => $builder.set_value(value),
+            None => $builder.set_unset(()),
+        }
+    }
+}
+
+fn load_optional_path(reader: option::Reader<::capnp::text::Owned>) -> ProtocolResult<Option<PathBuf>> {
+    Ok(match try_throw!(reader.which()) {
+        option::Some(path) => Some(PathBuf::from(try_throw!(path))),
+        _ => None,
+    })
+}
+
+fn save_optional_path(mut builder: option::Builder<::capnp::text::Owned>, path: Option<&PathBuf>) -> ProtocolResult<()> {
+    if let Some(path) = path {
+        let path: &Path = path.as_ref();
+
+        try_throw!(builder.set_some(try_throw!(path.to_str().ok_or(ProtocolError::InvalidFormat))));
+    } else {
+        builder.set_none(());
+    }
+
+    Ok(())
+}
+
+fn shader_from_protocol(shader: protocol::MaterialShader) -> MaterialShader {
+    match shader {
+        protocol::MaterialShader::Uber => MaterialShader::Uber,
+        protocol::MaterialShader::Mirror => MaterialShader::Mirror,
+        protocol::MaterialShader::Metal => MaterialShader::Metal,
+        protocol::MaterialShader::Matte => MaterialShader::Matte,
+        protocol::MaterialShader::Substrate => MaterialShader::Substrate,
+        protocol::MaterialShader::Glass => MaterialShader::Glass,
+        protocol::MaterialShader::Hair => MaterialShader::Hair,
+    }
+}
+
+fn shader_to_protocol(shader: MaterialShader) -> protocol::MaterialShader {
+    match shader {
+        MaterialShader::Uber => protocol::MaterialShader::Uber,
+        MaterialShader::Mirror => protocol::MaterialShader::Mirror,
+        MaterialShader::Metal => protocol::MaterialShader::Metal,
+        MaterialShader::Matte => protocol::MaterialShader::Matte,
+        MaterialShader::Substrate => protocol::MaterialShader::Substrate,
+        MaterialShader::Glass => protocol::MaterialShader::Glass,
+        MaterialShader::Hair => protocol::MaterialShader::Hair,
+    }
+}
+
+fn render_from_protocol(render: protocol::RenderMethod) -> RenderMethod {
+    match render {
+        protocol::RenderMethod::Forward => RenderMethod::Forward,
+        protocol::RenderMethod::Deferred => RenderMethod::Deferred,
+        protocol::RenderMethod::ForwardPlus => RenderMethod::ForwardPlus,
+        protocol::RenderMethod::Clustered => RenderMethod::Clustered,
+    }
+}
+
+fn render_to_protocol(render: RenderMethod) -> protocol::RenderMethod {
+    match render {
+        RenderMethod::Forward => protocol::RenderMethod::Forward,
+        RenderMethod::Deferred => protocol::RenderMethod::Deferred,
+        RenderMethod::ForwardPlus => protocol::RenderMethod::ForwardPlus,
+        RenderMethod::Clustered => protocol::RenderMethod::Clustered,
+    }
+}
+
+impl<'a> Storage<'a> for MaterialAnisotropy {
+    type Builder = protocol::anisotropy::Builder<'a>;
+    type Reader = protocol::anisotropy::Reader<'a>;
+
+    type LoadArgs = ();
+    type SaveArgs = ();
+    type Query = ();
+
+    fn load_from_reader_args(reader: Self::Reader, _: ()) -> ProtocolResult<MaterialAnisotropy> {
+        let amount = match try_throw!(reader.get_amount().which()) {
+            protocol::anisotropy::amount::Value(amount) => Some(amount),
+            _ => None,
+        };
+
+        let rotation = match try_throw!(try_throw!(reader.get_rotation()).which()) {
+            option::Some(rotation) => Some(try_throw!(rotation).get_vector()),
+            _ => None,
+        };
+
+        Ok(MaterialAnisotropy { amount: amount, rotation: rotation })
+    }
+
+    fn save_to_builder_args(&self, mut builder: Self::Builder, _: ()) -> ProtocolResult<()> {
+        {
+            let mut amount_builder = builder.borrow().init_amount();
+
+            save_optional_value!(amount_builder, self.amount);
+        }
+
+        {
+            let mut rotation_builder: option::Builder<vector3::Owned> = builder.borrow().init_rotation();
+
+            if let Some(ref rotation) = self.rotation {
+                rotation_builder.init_some().set_vector(rotation);
+            } else {
+                rotation_builder.set_none(());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn query_reader_args(_: Self::Reader, _: ()) -> ProtocolResult<()> {
+        unimplemented!()
+    }
+}
+
+impl<'a> Storage<'a> for Material {
+    type Builder = protocol::material::Builder<'a>;
+    type Reader = protocol::material::Reader<'a>;
+
+    type LoadArgs = ();
+    type SaveArgs = ();
+    type Query = ();
+
+    fn load_from_reader_args(reader: Self::Reader, _: ()) -> ProtocolResult<Material> {
+        let preset = match try_throw!(try_throw!(reader.get_preset()).which()) {
+            option::Some(preset) => Some(try_throw!(preset).to_string()),
+            _ => None,
+        };
+
+        let shader = match try_throw!(reader.get_shader().which()) {
+            protocol::material::shader::Value(shader) => Some(shader_from_protocol(try_throw!(shader))),
+            _ => None,
+        };
+
+        let render = match try_throw!(reader.get_render().which()) {
+            protocol::material::render::Value(render) => Some(render_from_protocol(try_throw!(render))),
+            _ => None,
+        };
+
+        Ok(Material {
+            preset: preset,
+            texture: try_rethrow!(load_optional_path(try_throw!(reader.get_texture()))),
+            normal_map: try_rethrow!(load_optional_path(try_throw!(reader.get_normal_map()))),
+            tangent_map: try_rethrow!(load_optional_path(try_throw!(reader.get_tangent_map()))),
+            height_map: try_rethrow!(load_optional_path(try_throw!(reader.get_height_map()))),
+            roughness_map: try_rethrow!(load_optional_path(try_throw!(reader.get_roughness_map()))),
+            metallic_map: try_rethrow!(load_optional_path(try_throw!(reader.get_metallic_map()))),
+            roughness: load_optional_value!(reader.get_roughness(), roughness),
+            smoothness: load_optional_value!(reader.get_smoothness(), smoothness),
+            metallic: load_optional_value!(reader.get_metallic(), metallic),
+            color: try_throw!(reader.get_color()).get_color(),
+            emission: load_optional_value!(reader.get_emission(), emission),
+            translucency: load_optional_value!(reader.get_translucency(), translucency),
+            ior: load_optional_value!(reader.get_ior(), ior),
+            anisotropy: try_rethrow!(MaterialAnisotropy::load_from_reader(try_throw!(reader.get_anisotropy()))),
+            shader: shader,
+            render: render,
+        })
+    }
+
+    fn save_to_builder_args(&self, mut builder: Self::Builder, _: ()) -> ProtocolResult<()> {
+        {
+            let mut preset_builder = builder.borrow().init_preset();
+
+            if let Some(ref preset) = self.preset {
+                try_throw!(preset_builder.set_some(preset.as_str()));
+            } else {
+                preset_builder.set_none(());
+            }
+        }
+
+        try_rethrow!(save_optional_path(builder.borrow().init_texture(), self.texture.as_ref()));
+        try_rethrow!(save_optional_path(builder.borrow().init_normal_map(), self.normal_map.as_ref()));
+        try_rethrow!(save_optional_path(builder.borrow().init_tangent_map(), self.tangent_map.as_ref()));
+        try_rethrow!(save_optional_path(builder.borrow().init_height_map(), self.height_map.as_ref()));
+        try_rethrow!(save_optional_path(builder.borrow().init_roughness_map(), self.roughness_map.as_ref()));
+        try_rethrow!(save_optional_path(builder.borrow().init_metallic_map(), self.metallic_map.as_ref()));
+
+        { builder.borrow().init_color().set_color(&self.color); }
+
+        { save_optional_value!(builder.borrow().init_roughness(), self.roughness); }
+        { save_optional_value!(builder.borrow().init_smoothness(), self.smoothness); }
+        { save_optional_value!(builder.borrow().init_metallic(), self.metallic); }
+        { save_optional_value!(builder.borrow().init_emission(), self.emission); }
+        { save_optional_value!(builder.borrow().init_translucency(), self.translucency); }
+        { save_optional_value!(builder.borrow().init_ior(), self.ior); }
+
+        try_rethrow!(self.anisotropy.save_to_builder(builder.borrow().init_anisotropy()));
+
+        { save_optional_value!(builder.borrow().init_shader(), self.shader.map(shader_to_protocol)); }
+        { save_optional_value!(builder.borrow().init_render(), self.render.map(render_to_protocol)); }
+
+        Ok(())
+    }
+
+    fn query_reader_args(_: Self::Reader, _: ()) -> ProtocolResult<()> {
+        unimplemented!()
+    }
+}
+
+impl<'a> Storage<'a> for MaterialMap {
+    type Builder = protocol::material_map::Builder<'a>;
+    type Reader = protocol::material_map::Reader<'a>;
+
+    type LoadArgs = ();
+    type SaveArgs = ();
+    type Query = ();
+
+    fn load_from_reader_args(reader: Self::Reader, _: ()) -> ProtocolResult<MaterialMap> {
+        let raw_materials = try_throw!(reader.get_materials());
+
+        let mut materials = HashMap::with_capacity(raw_materials.len() as usize);
+
+        for entry_reader in raw_materials.iter() {
+            let name = try_throw!(entry_reader.get_first()).to_string();
+            let material = try_rethrow!(Material::load_from_reader(try_throw!(entry_reader.get_second())));
+
+            materials.insert(name, material);
+        }
+
+        Ok(MaterialMap { materials: materials })
+    }
+
+    fn save_to_builder_args(&self, builder: Self::Builder, _: ()) -> ProtocolResult<()> {
+        let mut entry_list_builder = builder.init_materials(self.materials.len() as u32);
+
+        for (i, (name, material)) in self.materials.iter().enumerate() {
+            let mut entry_builder = entry_list_builder.borrow().get(i as u32);
+
+            try_throw!(entry_builder.set_first(name.as_str()));
+
+            try_rethrow!(material.save_to_builder(entry_builder.init_second()));
+        }
+
+        Ok(())
+    }
+
+    fn query_reader_args(_: Self::Reader, _: ()) -> ProtocolResult<()> {
+        unimplemented!()
+    }
+}
@@ -1,6 +1,6 @@
 //! Data structures for manipulating math data
 
-use nalgebra::{Vector3, Matrix4};
+use nalgebra::{Vector3, Point3, Matrix4, Quaternion, Norm, Eye};
 
 /// 3D Transformations
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -17,4 +17,328 @@ pub enum Transform {
     /// Arbitrary matrix transform
     #[serde(rename = "matrix")]
     Matrix(Matrix4<f32>),
-}
\ No newline at end of file
+}
+
+impl Transform {
+    /// Convert the transform into a homogeneous matrix.
+    ///
+    /// Euler angle rotations are applied around the X axis first, then Y, then Z.
+    pub fn to_matrix(&self) -> Matrix4<f32> {
+        match *self {
+            Transform::Translation(ref translation) => {
+                compose_trs(translation, &Quaternion::new(1.0, 0.0, 0.0, 0.0), &Vector3::new(1.0, 1.0, 1.0))
+            },
+            Transform::Rotation(ref rotation) => {
+                let axis = |angle: f32, x: f32, y: f32, z: f32| {
+                    let (sin, cos) = (angle * 0.5).sin_cos();
+
+                    Quaternion::new(cos, x * sin, y * sin, z * sin)
+                };
+
+                let q = multiply_quaternions(&axis(rotation.z, 0.0, 0.0, 1.0),
+                                             &multiply_quaternions(&axis(rotation.y, 0.0, 1.0, 0.0), &axis(rotation.x, 1.0, 0.0, 0.0)));
+
+                compose_trs(&Vector3::new(0.0, 0.0, 0.0), &q, &Vector3::new(1.0, 1.0, 1.0))
+            },
+            Transform::Scale(ref scale) => {
+                compose_trs(&Vector3::new(0.0, 0.0, 0.0), &Quaternion::new(1.0, 0.0, 0.0, 0.0), scale)
+            },
+            Transform::Matrix(ref matrix) => *matrix,
+        }
+    }
+
+    /// Combine a list of transforms, applied in order, into a single homogeneous matrix
+    pub fn combine(transforms: &[Transform]) -> Matrix4<f32> {
+        transforms.iter().fold(Matrix4::new_identity(4), |matrix, transform| transform.to_matrix() * matrix)
+    }
+}
+
+/// Axis-aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Aabb {
+    /// Minimum corner
+    pub min: Point3<f32>,
+    /// Maximum corner
+    pub max: Point3<f32>,
+}
+
+impl Aabb {
+    /// Create a new bounding box from two corners
+    pub fn new(min: Point3<f32>, max: Point3<f32>) -> Aabb {
+        Aabb { min: min, max: max }
+    }
+
+    /// Compute the smallest box containing all of the given points
+    pub fn from_points<'a, I>(points: I) -> Option<Aabb> where I: IntoIterator<Item = &'a Point3<f32>> {
+        let mut points = points.into_iter();
+
+        points.next().map(|first| {
+            points.fold(Aabb::new(*first, *first), |aabb, point| aabb.extend(point))
+        })
+    }
+
+    /// Grow the box to contain a point
+    pub fn extend(&self, point: &Point3<f32>) -> Aabb {
+        Aabb {
+            min: Point3::new(self.min.x.min(point.x), self.min.y.min(point.y), self.min.z.min(point.z)),
+            max: Point3::new(self.max.x.max(point.x), self.max.y.max(point.y), self.max.z.max(point.z)),
+        }
+    }
+
+    /// Smallest box containing both boxes
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        self.extend(&other.min).extend(&other.max)
+    }
+
+    /// Center of the box
+    pub fn center(&self) -> Point3<f32> {
+        Point3::new((self.min.x + self.max.x) * 0.5,
+                    (self.min.y + self.max.y) * 0.5,
+                    (self.min.z + self.max.z) * 0.5)
+    }
+
+    /// Half of the size of the box along each axis
+    pub fn half_extents(&self) -> Vector3<f32> {
+        (self.max - self.min) * 0.5
+    }
+
+    /// Returns `true` if the two boxes overlap or touch
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x && self.max.x >= other.min.x &&
+            self.min.y <= other.max.y && self.max.y >= other.min.y &&
+            self.min.z <= other.max.z && self.max.z >= other.min.z
+    }
+
+    /// Returns `true` if the point is inside or on the box
+    pub fn contains(&self, point: &Point3<f32>) -> bool {
+        point.x >= self.min.x && point.x <= self.max.x &&
+            point.y >= self.min.y && point.y <= self.max.y &&
+            point.z >= self.min.z && point.z <= self.max.z
+    }
+
+    /// Returns `true` if the other box is entirely inside this one
+    pub fn contains_aabb(&self, other: &Aabb) -> bool {
+        self.contains(&other.min) && self.contains(&other.max)
+    }
+
+    /// Surface area of the box
+    pub fn surface_area(&self) -> f32 {
+        let d = self.max - self.min;
+
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    /// Grow the box by `margin` in every direction
+    pub fn grow(&self, margin: f32) -> Aabb {
+        let margin = Vector3::new(margin, margin, margin);
+
+        Aabb::new(self.min - margin, self.max + margin)
+    }
+
+    /// Compute the axis-aligned box containing this box after an affine transform
+    pub fn transform(&self, m: &Matrix4<f32>) -> Aabb {
+        let center = self.center();
+        let extents = self.half_extents();
+
+        let new_center = Point3::new(m.m11 * center.x + m.m12 * center.y + m.m13 * center.z + m.m14,
+                                     m.m21 * center.x + m.m22 * center.y + m.m23 * center.z + m.m24,
+                                     m.m31 * center.x + m.m32 * center.y + m.m33 * center.z + m.m34);
+
+        // Project the extents onto each axis with the absolute rotation-scale matrix
+        let new_extents = Vector3::new(m.m11.abs() * extents.x + m.m12.abs() * extents.y + m.m13.abs() * extents.z,
+                                       m.m21.abs() * extents.x + m.m22.abs() * extents.y + m.m23.abs() * extents.z,
+                                       m.m31.abs() * extents.x + m.m32.abs() * extents.y + m.m33.abs() * extents.z);
+
+        Aabb::new(new_center - new_extents, new_center + new_extents)
+    }
+}
+
+/// Bounding sphere
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Sphere {
+    /// Center of the sphere
+    pub center: Point3<f32>,
+    /// Radius of the sphere
+    pub radius: f32,
+}
+
+impl Sphere {
+    /// Create a new bounding sphere
+    pub fn new(center: Point3<f32>, radius: f32) -> Sphere {
+        Sphere { center: center, radius: radius }
+    }
+
+    /// Compute a bounding sphere for the given points, centered on their bounding box
+    pub fn from_points<'a, I>(points: I) -> Option<Sphere> where I: IntoIterator<Item = &'a Point3<f32>> + Clone {
+        Aabb::from_points(points.clone()).map(|aabb| {
+            let center = aabb.center();
+
+            let radius = points.into_iter().fold(0.0f32, |radius, point| radius.max((*point - center).norm()));
+
+            Sphere::new(center, radius)
+        })
+    }
+
+    /// Smallest sphere containing both spheres
+    pub fn union(&self, other: &Sphere) -> Sphere {
+        let offset = other.center - self.center;
+        let distance = offset.norm();
+
+        if distance + other.radius <= self.radius {
+            *self
+        } else if distance + self.radius <= other.radius {
+            *other
+        } else {
+            let radius = (distance + self.radius + other.radius) * 0.5;
+
+            Sphere::new(self.center + offset * ((radius - self.radius) / distance), radius)
+        }
+    }
+
+    /// Compute a sphere containing this sphere after an affine transform
+    pub fn transform(&self, m: &Matrix4<f32>) -> Sphere {
+        let c = &self.center;
+
+        let center = Point3::new(m.m11 * c.x + m.m12 * c.y + m.m13 * c.z + m.m14,
+                                 m.m21 * c.x + m.m22 * c.y + m.m23 * c.z + m.m24,
+                                 m.m31 * c.x + m.m32 * c.y + m.m33 * c.z + m.m34);
+
+        // Non-uniform scale stretches the sphere, so use the largest axis scale
+        let scale = (m.m11 * m.m11 + m.m21 * m.m21 + m.m31 * m.m31)
+            .max(m.m12 * m.m12 + m.m22 * m.m22 + m.m32 * m.m32)
+            .max(m.m13 * m.m13 + m.m23 * m.m23 + m.m33 * m.m33)
+            .sqrt();
+
+        Sphere::new(center, self.radius * scale)
+    }
+}
+
+/// Combined bounding box and sphere
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Bounds {
+    /// Axis-aligned bounding box
+    pub aabb: Aabb,
+    /// Bounding sphere
+    pub sphere: Sphere,
+}
+
+impl Bounds {
+    /// Compute bounds for the given points, or `None` if there are no points
+    pub fn from_points<'a, I>(points: I) -> Option<Bounds> where I: IntoIterator<Item = &'a Point3<f32>> + Clone {
+        Aabb::from_points(points.clone()).and_then(|aabb| {
+            Sphere::from_points(points).map(|sphere| Bounds { aabb: aabb, sphere: sphere })
+        })
+    }
+
+    /// Combine two bounds
+    pub fn union(&self, other: &Bounds) -> Bounds {
+        Bounds {
+            aabb: self.aabb.union(&other.aabb),
+            sphere: self.sphere.union(&other.sphere),
+        }
+    }
+
+    /// Transform both volumes
+    pub fn transform(&self, m: &Matrix4<f32>) -> Bounds {
+        let aabb = self.aabb.transform(m);
+        let sphere = self.sphere.transform(m);
+
+        // The transformed box may fit tighter than the transformed sphere
+        let box_radius = aabb.half_extents().norm();
+
+        Bounds {
+            aabb: aabb,
+            sphere: if box_radius < sphere.radius { Sphere::new(aabb.center(), box_radius) } else { sphere },
+        }
+    }
+}
+
+/// Normalize a quaternion, returning the identity rotation for zero-length quaternions
+pub fn normalize_quaternion(q: &Quaternion<f32>) -> Quaternion<f32> {
+    let length = (q.w * q.w + q.i * q.i + q.j * q.j + q.k * q.k).sqrt();
+
+    if length > 0.0 {
+        Quaternion::new(q.w / length, q.i / length, q.j / length, q.k / length)
+    } else {
+        Quaternion::new(1.0, 0.0, 0.0, 0.0)
+    }
+}
+
+/// Hamilton product of two quaternions, which applies `b` first and then `a`
+pub fn multiply_quaternions(a: &Quaternion<f32>, b: &Quaternion<f32>) -> Quaternion<f32> {
+    Quaternion::new(a.w * b.w - a.i * b.i - a.j * b.j - a.k * b.k,
+                    a.w * b.i + a.i * b.w + a.j * b.k - a.k * b.j,
+                    a.w * b.j - a.i * b.k + a.j * b.w + a.k * b.i,
+                    a.w * b.k + a.i * b.j - a.j * b.i + a.k * b.w)
+}
+
+/// Compose a homogeneous matrix from translation, rotation and scale, applied in scale-rotate-translate order
+pub fn compose_trs(translation: &Vector3<f32>, rotation: &Quaternion<f32>, scale: &Vector3<f32>) -> Matrix4<f32> {
+    let q = normalize_quaternion(rotation);
+
+    let (w, x, y, z) = (q.w, q.i, q.j, q.k);
+
+    let (xx, yy, zz) = (x * x, y * y, z * z);
+    let (xy, xz, yz) = (x * y, x * z, y * z);
+    let (wx, wy, wz) = (w * x, w * y, w * z);
+
+    Matrix4::new(
+        (1.0 - 2.0 * (yy + zz)) * scale.x, 2.0 * (xy - wz) * scale.y, 2.0 * (xz + wy) * scale.z, translation.x,
+        2.0 * (xy + wz) * scale.x, (1.0 - 2.0 * (xx + zz)) * scale.y, 2.0 * (yz - wx) * scale.z, translation.y,
+        2.0 * (xz - wy) * scale.x, 2.0 * (yz + wx) * scale.y, (1.0 - 2.0 * (xx + yy)) * scale.z, translation.z,
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+
+/// Decompose an affine homogeneous matrix into translation, rotation and scale.
+///
+/// Shear is discarded, and negative scale is folded into the X axis.
+pub fn decompose_trs(matrix: &Matrix4<f32>) -> (Vector3<f32>, Quaternion<f32>, Vector3<f32>) {
+    let m = matrix;
+
+    let translation = Vector3::new(m.m14, m.m24, m.m34);
+
+    let mut sx = (m.m11 * m.m11 + m.m21 * m.m21 + m.m31 * m.m31).sqrt();
+    let sy = (m.m12 * m.m12 + m.m22 * m.m22 + m.m32 * m.m32).sqrt();
+    let sz = (m.m13 * m.m13 + m.m23 * m.m23 + m.m33 * m.m33).sqrt();
+
+    let determinant = m.m11 * (m.m22 * m.m33 - m.m23 * m.m32) -
+        m.m12 * (m.m21 * m.m33 - m.m23 * m.m31) +
+        m.m13 * (m.m21 * m.m32 - m.m22 * m.m31);
+
+    if determinant < 0.0 {
+        sx = -sx;
+    }
+
+    let scale = Vector3::new(sx, sy, sz);
+
+    let inv = |s: f32| if s != 0.0 { 1.0 / s } else { 0.0 };
+
+    let (ix, iy, iz) = (inv(sx), inv(sy), inv(sz));
+
+    let (r11, r12, r13) = (m.m11 * ix, m.m12 * iy, m.m13 * iz);
+    let (r21, r22, r23) = (m.m21 * ix, m.m22 * iy, m.m23 * iz);
+    let (r31, r32, r33) = (m.m31 * ix, m.m32 * iy, m.m33 * iz);
+
+    let trace = r11 + r22 + r33;
+
+    let rotation = if trace > 0.0 {
+        let s = (trace + 1.0).sqrt() * 2.0;
+
+        Quaternion::new(0.25 * s, (r32 - r23) / s, (r13 - r31) / s, (r21 - r12) / s)
+    } else if r11 > r22 && r11 > r33 {
+        let s = (1.0 + r11 - r22 - r33).sqrt() * 2.0;
+
+        Quaternion::new((r32 - r23) / s, 0.25 * s, (r12 + r21) / s, (r13 + r31) / s)
+    } else if r22 > r33 {
+        let s = (1.0 + r22 - r11 - r33).sqrt() * 2.0;
+
+        Quaternion::new((r13 - r31) / s, (r12 + r21) / s, 0.25 * s, (r23 + r32) / s)
+    } else {
+        let s = (1.0 + r33 - r11 - r22).sqrt() * 2.0;
+
+        Quaternion::new((r21 - r12) / s, (r13 + r31) / s, (r23 + r32) / s, 0.25 * s)
+    };
+
+    (translation, normalize_quaternion(&rotation), scale)
+}
@@ -5,7 +5,7 @@ use ::error::ProtocolResult;
 use ::traits::{Storage, StorageQuery};
 
 use super::protocol;
-use super::data::Transform;
+use super::data::{Transform, Aabb, Sphere, Bounds};
 
 /// Query for determining what kind of transform is present without actually loading it
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -79,4 +79,64 @@ impl<'a> Storage<'a> for Transform {
             protocol::transform::transform::Matrix(_) => TransformQuery::Matrix,
         })
     }
-}
\ No newline at end of file
+}
+impl<'a> Storage<'a> for Bounds {
+    type Builder = protocol::bounds::Builder<'a>;
+    type Reader = protocol::bounds::Reader<'a>;
+
+    type LoadArgs = ();
+    type SaveArgs = ();
+    type Query = ();
+
+    fn load_from_reader_args(reader: Self::Reader, _: ()) -> ProtocolResult<Bounds> {
+        let aabb = try_throw!(reader.get_aabb());
+        let sphere = try_throw!(reader.get_sphere());
+
+        Ok(Bounds {
+            aabb: Aabb::new(try_throw!(aabb.get_min()).get_point(), try_throw!(aabb.get_max()).get_point()),
+            sphere: Sphere::new(try_throw!(sphere.get_center()).get_point(), sphere.get_radius()),
+        })
+    }
+
+    fn save_to_builder_args(&self, mut builder: Self::Builder, _: ()) -> ProtocolResult<()> {
+        {
+            let mut aabb_builder = builder.borrow().init_aabb();
+
+            { aabb_builder.borrow().init_min().set_point(&self.aabb.min); }
+            { aabb_builder.borrow().init_max().set_point(&self.aabb.max); }
+        }
+
+        {
+            let mut sphere_builder = builder.borrow().init_sphere();
+
+            { sphere_builder.borrow().init_center().set_point(&self.sphere.center); }
+
+            sphere_builder.set_radius(self.sphere.radius);
+        }
+
+        Ok(())
+    }
+
+    fn query_reader_args(_: Self::Reader, _: ()) -> ProtocolResult<()> {
+        unimplemented!()
+    }
+}
+
+/// Load optional bounds
+pub fn load_optional_bounds(reader: ::utils::protocol::option::Reader<protocol::bounds::Owned>) -> ProtocolResult<Option<Bounds>> {
+    Ok(match try_throw!(reader.which()) {
+        ::utils::protocol::option::Some(bounds) => Some(try_rethrow!(Bounds::load_from_reader(try_throw!(bounds)))),
+        _ => None,
+    })
+}
+
+/// Save optional bounds
+pub fn save_optional_bounds(mut builder: ::utils::protocol::option::Builder<protocol::bounds::Owned>, bounds: Option<&Bounds>) -> ProtocolResult<()> {
+    if let Some(bounds) = bounds {
+        try_rethrow!(bounds.save_to_builder(builder.init_some()));
+    } else {
+        builder.set_none(());
+    }
+
+    Ok(())
+}
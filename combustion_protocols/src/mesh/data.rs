@@ -4,6 +4,8 @@ use std::fmt::{Debug, Formatter, Result as FmtResult};
 
 use nalgebra::*;
 
+use ::math::data::Bounds;
+
 use super::protocol::MeshPrimitive;
 
 fn skip_serializing_if_none_or_empty<T>(value: &Option<Vec<T>>) -> bool {
@@ -28,15 +30,83 @@ pub struct Mesh {
     pub materials: Vec<u32>,
     /// Rendering primitive for the mesh
     pub primitive: MeshPrimitive,
+    /// Morph targets (blend shapes)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub morph_targets: Vec<MorphTarget>,
+    /// Default morph target weights, one per target
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub morph_weights: Vec<f32>,
+    /// Precomputed bounds of the base vertices
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub bounds: Option<Bounds>,
 }
 
 impl Debug for Mesh {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        write!(f, "Mesh {{{:?} primitive, vertices: {:?}, indices: {:?}, materials: {}}}",
+        write!(f, "Mesh {{{:?} primitive, vertices: {:?}, indices: {:?}, materials: {}, morph_targets: {}}}",
                self.primitive,
                self.vertices,
                self.indices.as_ref().map(|indices| indices.len()),
-               self.materials.len())
+               self.materials.len(),
+               self.morph_targets.len())
+    }
+}
+
+impl Mesh {
+    /// Compute the bounds of the base vertices, or `None` if the mesh has no vertices.
+    ///
+    /// Morph targets and skinning are not taken into account.
+    pub fn compute_bounds(&self) -> Option<Bounds> {
+        match self.vertices {
+            MeshVertices::Discrete(ref vertices) => Bounds::from_points(&vertices.positions),
+            MeshVertices::Interleaved(ref vertices) => {
+                let positions: Vec<Point3<f32>> = vertices.iter().map(|vertex| vertex.position).collect();
+
+                Bounds::from_points(&positions)
+            }
+        }
+    }
+
+    /// Recompute and store the bounds of the mesh
+    pub fn update_bounds(&mut self) {
+        self.bounds = self.compute_bounds();
+    }
+
+    /// Get the stored bounds, or compute them if there are none
+    pub fn bounds(&self) -> Option<Bounds> {
+        self.bounds.or_else(|| self.compute_bounds())
+    }
+}
+
+/// Per-vertex offsets applied on top of the base mesh vertices
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct MorphTarget {
+    /// Name of the morph target
+    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default)]
+    pub name: String,
+    /// Position offsets, one per base vertex
+    pub positions: Vec<Vector3<f32>>,
+    /// Optional normal offsets, one per base vertex
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub normals: Option<Vec<Vector3<f32>>>,
+    /// Optional tangent offsets, one per base vertex
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub tangents: Option<Vec<Vector3<f32>>>,
+}
+
+impl Debug for MorphTarget {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, r#"MorphTarget {{name: "{}", positions: {}, normals: {:?}, tangents: {:?}}}"#,
+               self.name,
+               self.positions.len(),
+               self.normals.as_ref().map(|normals| normals.len()),
+               self.tangents.as_ref().map(|tangents| tangents.len()))
     }
 }
 
@@ -63,6 +133,38 @@ pub enum MeshVertices {
     Interleaved(Vec<Vertex>),
 }
 
+impl MeshVertices {
+    /// Number of vertices, regardless of layout
+    pub fn len(&self) -> usize {
+        match *self {
+            MeshVertices::Discrete(ref vertices) => vertices.positions.len(),
+            MeshVertices::Interleaved(ref vertices) => vertices.len(),
+        }
+    }
+
+    /// Returns `true` if there are no vertices
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Copy the vertex data into discrete streams.
+    ///
+    /// Interleaved vertices always produce `Some` normals and uvs.
+    pub fn to_discrete(&self) -> Vertices {
+        match *self {
+            MeshVertices::Discrete(ref vertices) => vertices.clone(),
+            MeshVertices::Interleaved(ref vertices) => {
+                Vertices {
+                    positions: vertices.iter().map(|vertex| vertex.position).collect(),
+                    normals: Some(vertices.iter().map(|vertex| vertex.normal).collect()),
+                    uvs: Some(vertices.iter().map(|vertex| vertex.uv).collect()),
+                }
+            }
+        }
+    }
+}
+
 impl Debug for MeshVertices {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         write!(f, "MeshVertices {{ {} }}", match *self {
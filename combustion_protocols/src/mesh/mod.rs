@@ -2,4 +2,5 @@
 
 pub mod protocol;
 pub mod data;
-pub mod storage;
\ No newline at end of file
+pub mod storage;
+pub mod simplify;pub mod morph;
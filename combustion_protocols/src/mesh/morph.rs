@@ -0,0 +1,99 @@
+//! CPU evaluation of morph targets
+//!
+//! Applies weighted morph target offsets to the base vertices of a mesh, mostly as a reference
+//! for testing and for platforms without GPU morphing.
+
+use nalgebra::*;
+
+use ::error::{ProtocolResult, ProtocolError};
+
+use super::data::{Mesh, MeshVertices, MorphTarget};
+
+impl Mesh {
+    /// Produce a deformed copy of the mesh with its own default morph weights applied
+    pub fn morph_default(&self) -> ProtocolResult<Mesh> {
+        self.morph(&self.morph_weights)
+    }
+
+    /// Produce a deformed copy of the mesh with the given morph target weights applied.
+    ///
+    /// Missing weights are treated as zero and extra weights are ignored.
+    /// Morphed normals are renormalized. The result uses discrete vertices and has no morph targets or bounds of its own.
+    pub fn morph(&self, weights: &[f32]) -> ProtocolResult<Mesh> {
+        let mut vertices = self.vertices.to_discrete();
+
+        let num_vertices = vertices.positions.len();
+
+        for target in &self.morph_targets {
+            if target.positions.len() != num_vertices ||
+                target.normals.as_ref().map_or(false, |normals| normals.len() != num_vertices) {
+                throw!(ProtocolError::InvalidLength);
+            }
+        }
+
+        for (target, weight) in self.morph_targets.iter().zip(weights.iter()) {
+            if *weight == 0.0 {
+                continue;
+            }
+
+            for (position, offset) in vertices.positions.iter_mut().zip(target.positions.iter()) {
+                *position = *position + *offset * *weight;
+            }
+
+            if let (Some(ref mut normals), Some(ref offsets)) = (vertices.normals.as_mut(), target.normals.as_ref()) {
+                for (normal, offset) in normals.iter_mut().zip(offsets.iter()) {
+                    *normal = *normal + *offset * *weight;
+                }
+            }
+        }
+
+        if let Some(ref mut normals) = vertices.normals {
+            for normal in normals.iter_mut() {
+                let length = normal.norm();
+
+                if length > 0.0 {
+                    *normal = *normal / length;
+                }
+            }
+        }
+
+        Ok(Mesh {
+            vertices: MeshVertices::Discrete(vertices),
+            indices: self.indices.clone(),
+            materials: self.materials.clone(),
+            primitive: self.primitive,
+            morph_targets: Vec::new(),
+            morph_weights: Vec::new(),
+            bounds: None,
+        })
+    }
+}
+
+/// Apply weighted morph target tangent offsets to a set of base tangents.
+///
+/// Targets without tangent offsets are skipped. The results are renormalized.
+pub fn morph_tangents(tangents: &[Vector3<f32>], targets: &[MorphTarget], weights: &[f32]) -> ProtocolResult<Vec<Vector3<f32>>> {
+    let mut morphed = tangents.to_vec();
+
+    for (target, weight) in targets.iter().zip(weights.iter()) {
+        if let Some(ref offsets) = target.tangents {
+            if offsets.len() != tangents.len() {
+                throw!(ProtocolError::InvalidLength);
+            }
+
+            for (tangent, offset) in morphed.iter_mut().zip(offsets.iter()) {
+                *tangent = *tangent + *offset * *weight;
+            }
+        }
+    }
+
+    for tangent in &mut morphed {
+        let length = tangent.norm();
+
+        if length > 0.0 {
+            *tangent = *tangent / length;
+        }
+    }
+
+    Ok(morphed)
+}
@@ -0,0 +1,467 @@
+//! Mesh simplification using quadric error metrics
+//!
+//! This is the edge collapse algorithm from Garland and Heckbert's
+//! "Surface Simplification Using Quadric Error Metrics", restricted to half-edge collapses
+//! so the attributes of surviving vertices never have to be interpolated.
+//!
+//! UV seams and hard normal edges are preserved by locking any vertex that shares its position
+//! with another vertex that has different attributes. Open boundaries can be locked as well.
+
+use std::collections::HashMap;
+use std::ops::{Add, AddAssign, Mul};
+
+use nalgebra::*;
+
+use ::error::{ProtocolResult, ProtocolError};
+
+use super::protocol::MeshPrimitive;
+use super::data::{Mesh, MeshVertices, Vertices, MorphTarget};
+
+/// Options for controlling mesh simplification
+#[derive(Debug, Clone, Copy)]
+pub struct SimplifyOptions {
+    /// Fraction of triangles to keep, from `0.0` to `1.0`
+    pub ratio: f32,
+    /// Maximum quadric error a single collapse is allowed to introduce
+    pub max_error: f32,
+    /// Minimum cosine between the normals of a triangle before and after a collapse,
+    /// and between the vertex normals of the collapsed edge.
+    ///
+    /// Prevents triangles from flipping over and hard edges from being smoothed away.
+    pub min_normal_cos: f32,
+    /// Never move vertices on open boundaries of the mesh
+    pub lock_boundaries: bool,
+}
+
+impl Default for SimplifyOptions {
+    fn default() -> SimplifyOptions {
+        SimplifyOptions {
+            ratio: 0.5,
+            max_error: ::std::f32::INFINITY,
+            min_normal_cos: 0.5,
+            lock_boundaries: true,
+        }
+    }
+}
+
+/// Symmetric 4x4 error quadric, stored as the upper triangle
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn from_plane(a: f64, b: f64, c: f64, d: f64) -> Quadric {
+        Quadric([a * a, a * b, a * c, a * d,
+                        b * b, b * c, b * d,
+                               c * c, c * d,
+                                      d * d])
+    }
+
+    /// Evaluate `v^T Q v` for the homogeneous point `v`
+    fn error(&self, point: &Point3<f32>) -> f64 {
+        let q = &self.0;
+        let (x, y, z) = (point.x as f64, point.y as f64, point.z as f64);
+
+        q[0] * x * x + 2.0 * q[1] * x * y + 2.0 * q[2] * x * z + 2.0 * q[3] * x +
+            q[4] * y * y + 2.0 * q[5] * y * z + 2.0 * q[6] * y +
+            q[7] * z * z + 2.0 * q[8] * z +
+            q[9]
+    }
+}
+
+impl Add for Quadric {
+    type Output = Quadric;
+
+    fn add(mut self, other: Quadric) -> Quadric {
+        self += other;
+        self
+    }
+}
+
+impl AddAssign for Quadric {
+    fn add_assign(&mut self, other: Quadric) {
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            *a += *b;
+        }
+    }
+}
+
+impl Mul<f64> for Quadric {
+    type Output = Quadric;
+
+    fn mul(mut self, scale: f64) -> Quadric {
+        for a in self.0.iter_mut() {
+            *a *= scale;
+        }
+
+        self
+    }
+}
+
+/// Potential collapse of `from` onto `to`
+#[derive(Debug, Clone, Copy)]
+struct Collapse {
+    cost: f64,
+    from: u32,
+    to: u32,
+}
+
+/// Unnormalized face normal, which has a length of twice the triangle area
+#[inline]
+fn face_normal(positions: &[Point3<f32>], tri: &[u32; 3]) -> Vector3<f32> {
+    let p0 = positions[tri[0] as usize];
+    let p1 = positions[tri[1] as usize];
+    let p2 = positions[tri[2] as usize];
+
+    (p1 - p0).cross(&(p2 - p0))
+}
+
+/// Bitwise key for exact comparisons of vertex attributes
+fn attribute_key(vertices: &Vertices, i: usize) -> [u32; 8] {
+    let position = vertices.positions[i];
+    let normal = vertices.normals.as_ref().map_or(Vector3::new(0.0, 0.0, 0.0), |normals| normals[i]);
+    let (u, v) = vertices.uvs.as_ref().map_or((0.0, 0.0), |uvs| (uvs[i].u, uvs[i].v));
+
+    [position.x.to_bits(), position.y.to_bits(), position.z.to_bits(),
+        normal.x.to_bits(), normal.y.to_bits(), normal.z.to_bits(),
+        u.to_bits(), v.to_bits()]
+}
+
+fn position_key(position: &Point3<f32>) -> [u32; 3] {
+    [position.x.to_bits(), position.y.to_bits(), position.z.to_bits()]
+}
+
+/// Simplify a triangle mesh down to approximately `options.ratio` of its triangles.
+///
+/// The result always uses discrete vertices and explicit indices.
+/// Simplification stops early if no more collapses are possible within the given constraints.
+pub fn simplify(mesh: &Mesh, options: &SimplifyOptions) -> ProtocolResult<Mesh> {
+    if mesh.primitive != MeshPrimitive::Triangles {
+        throw!(ProtocolError::Unsupported);
+    }
+
+    let vertices = mesh.vertices.to_discrete();
+    let num_vertices = vertices.positions.len();
+
+    if vertices.normals.as_ref().map_or(false, |normals| normals.len() != num_vertices) ||
+        vertices.uvs.as_ref().map_or(false, |uvs| uvs.len() != num_vertices) {
+        throw!(ProtocolError::InvalidLength);
+    }
+
+    // Weld vertices with identical attributes so non-indexed meshes can be simplified at all
+    let mut canonical = Vec::with_capacity(num_vertices);
+
+    {
+        let mut table = HashMap::with_capacity(num_vertices);
+
+        for i in 0..num_vertices {
+            canonical.push(*table.entry(attribute_key(&vertices, i)).or_insert(i as u32));
+        }
+    }
+
+    let mut triangles: Vec<[u32; 3]> = match mesh.indices {
+        Some(ref indices) => {
+            if indices.len() % 3 != 0 {
+                throw!(ProtocolError::InvalidLength);
+            }
+
+            let mut triangles = Vec::with_capacity(indices.len() / 3);
+
+            for tri in indices.chunks(3) {
+                if tri.iter().any(|index| *index as usize >= num_vertices) {
+                    throw!(ProtocolError::InvalidLength);
+                }
+
+                triangles.push([canonical[tri[0] as usize], canonical[tri[1] as usize], canonical[tri[2] as usize]]);
+            }
+
+            triangles
+        },
+        None => {
+            if num_vertices % 3 != 0 {
+                throw!(ProtocolError::InvalidLength);
+            }
+
+            (0..num_vertices / 3).map(|t| {
+                [canonical[t * 3], canonical[t * 3 + 1], canonical[t * 3 + 2]]
+            }).collect()
+        }
+    };
+
+    let positions = &vertices.positions;
+
+    let mut dead: Vec<bool> = triangles.iter().map(|tri| {
+        tri[0] == tri[1] || tri[1] == tri[2] || tri[0] == tri[2]
+    }).collect();
+
+    let mut alive = dead.iter().filter(|dead| !**dead).count();
+
+    let ratio = options.ratio.max(0.0).min(1.0);
+    let target = (alive as f32 * ratio).ceil() as usize;
+
+    // Accumulate area-weighted plane quadrics for every vertex
+    let mut quadrics = vec![Quadric::default(); num_vertices];
+
+    for (tri, _) in triangles.iter().zip(dead.iter()).filter(|&(_, dead)| !*dead) {
+        let normal = face_normal(positions, tri);
+        let double_area = normal.norm();
+
+        if double_area <= 0.0 {
+            continue;
+        }
+
+        let normal = normal / double_area;
+        let d = -normal.dot(&positions[tri[0] as usize].to_vector());
+
+        let quadric = Quadric::from_plane(normal.x as f64, normal.y as f64, normal.z as f64, d as f64) * (double_area as f64 * 0.5);
+
+        for index in tri.iter() {
+            quadrics[*index as usize] += quadric;
+        }
+    }
+
+    let mut locked = vec![false; num_vertices];
+
+    // Any remaining vertices that share a position are split by a UV seam or hard normal, so keep them in place.
+    {
+        let mut groups: HashMap<[u32; 3], Vec<u32>> = HashMap::new();
+
+        for (i, index) in canonical.iter().enumerate() {
+            if i as u32 == *index {
+                groups.entry(position_key(&positions[i])).or_insert_with(Vec::new).push(*index);
+            }
+        }
+
+        for group in groups.values().filter(|group| group.len() > 1) {
+            for index in group {
+                locked[*index as usize] = true;
+            }
+        }
+    }
+
+    if options.lock_boundaries {
+        let mut edge_counts: HashMap<(u32, u32), u32> = HashMap::new();
+
+        for (tri, _) in triangles.iter().zip(dead.iter()).filter(|&(_, dead)| !*dead) {
+            for k in 0..3 {
+                let (a, b) = (tri[k], tri[(k + 1) % 3]);
+
+                *edge_counts.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+            }
+        }
+
+        for (&(a, b), count) in edge_counts.iter() {
+            if *count == 1 {
+                locked[a as usize] = true;
+                locked[b as usize] = true;
+            }
+        }
+    }
+
+    // Collapse edges in passes of independent collapses, cheapest first
+    while alive > target {
+        let mut vertex_faces: Vec<Vec<usize>> = vec![Vec::new(); num_vertices];
+
+        for (f, tri) in triangles.iter().enumerate() {
+            if !dead[f] {
+                for index in tri.iter() {
+                    vertex_faces[*index as usize].push(f);
+                }
+            }
+        }
+
+        let mut edges = Vec::new();
+
+        for (f, tri) in triangles.iter().enumerate() {
+            if !dead[f] {
+                for k in 0..3 {
+                    let (a, b) = (tri[k], tri[(k + 1) % 3]);
+
+                    edges.push((a.min(b), a.max(b)));
+                }
+            }
+        }
+
+        edges.sort();
+        edges.dedup();
+
+        let mut candidates = Vec::with_capacity(edges.len() * 2);
+
+        for (a, b) in edges {
+            let combined = quadrics[a as usize] + quadrics[b as usize];
+
+            if !locked[a as usize] {
+                candidates.push(Collapse { cost: combined.error(&positions[b as usize]), from: a, to: b });
+            }
+
+            if !locked[b as usize] {
+                candidates.push(Collapse { cost: combined.error(&positions[a as usize]), from: b, to: a });
+            }
+        }
+
+        candidates.sort_by(|a, b| a.cost.partial_cmp(&b.cost).unwrap_or(::std::cmp::Ordering::Equal));
+
+        let mut touched = vec![false; num_vertices];
+        let mut collapsed = 0;
+
+        for collapse in candidates {
+            if alive <= target || collapse.cost > options.max_error as f64 {
+                break;
+            }
+
+            let (from, to) = (collapse.from as usize, collapse.to as usize);
+
+            if touched[from] || touched[to] {
+                continue;
+            }
+
+            if !collapse_is_valid(&vertices, &triangles, &vertex_faces, collapse.from, collapse.to, options.min_normal_cos) {
+                continue;
+            }
+
+            for &f in vertex_faces[from].iter() {
+                let tri = &mut triangles[f];
+
+                for index in tri.iter() {
+                    touched[*index as usize] = true;
+                }
+
+                if tri.contains(&collapse.to) {
+                    dead[f] = true;
+                    alive -= 1;
+                } else {
+                    for index in tri.iter_mut() {
+                        if *index == collapse.from {
+                            *index = collapse.to;
+                        }
+                    }
+                }
+            }
+
+            let from_quadric = quadrics[from];
+
+            quadrics[to] += from_quadric;
+
+            collapsed += 1;
+        }
+
+        if collapsed == 0 {
+            break;
+        }
+    }
+
+    // Compact the remaining vertices in order of first use
+    let mut new_index = vec![u32::max_value(); num_vertices];
+    let mut order = Vec::new();
+    let mut indices = Vec::with_capacity(alive * 3);
+
+    for (tri, _) in triangles.iter().zip(dead.iter()).filter(|&(_, dead)| !*dead) {
+        for index in tri.iter() {
+            let index = *index as usize;
+
+            if new_index[index] == u32::max_value() {
+                new_index[index] = order.len() as u32;
+                order.push(index);
+            }
+
+            indices.push(new_index[index]);
+        }
+    }
+
+    let simplified = Vertices {
+        positions: order.iter().map(|i| vertices.positions[*i]).collect(),
+        normals: vertices.normals.as_ref().map(|normals| order.iter().map(|i| normals[*i]).collect()),
+        uvs: vertices.uvs.as_ref().map(|uvs| order.iter().map(|i| uvs[*i]).collect()),
+    };
+
+    // Surviving vertices are never moved, so their morph target offsets still apply as-is
+    let morph_targets = mesh.morph_targets.iter().map(|target| {
+        let remap = |offsets: &Vec<Vector3<f32>>| -> Vec<Vector3<f32>> {
+            order.iter().map(|i| offsets.get(*i).cloned().unwrap_or_else(|| Vector3::new(0.0, 0.0, 0.0))).collect()
+        };
+
+        MorphTarget {
+            name: target.name.clone(),
+            positions: remap(&target.positions),
+            normals: target.normals.as_ref().map(&remap),
+            tangents: target.tangents.as_ref().map(&remap),
+        }
+    }).collect();
+
+    Ok(Mesh {
+        vertices: MeshVertices::Discrete(simplified),
+        indices: Some(indices),
+        materials: mesh.materials.clone(),
+        primitive: MeshPrimitive::Triangles,
+        morph_targets: morph_targets,
+        morph_weights: mesh.morph_weights.clone(),
+        // Surviving vertices are a subset of the original ones, so the original bounds still contain them
+        bounds: mesh.bounds,
+    })
+}
+
+/// Checks that moving `from` onto `to` keeps the surface manifold, doesn't flip any faces
+/// and doesn't merge vertices with very different normals.
+fn collapse_is_valid(vertices: &Vertices,
+                     triangles: &[[u32; 3]],
+                     vertex_faces: &[Vec<usize>],
+                     from: u32, to: u32,
+                     min_normal_cos: f32) -> bool {
+    if let Some(ref normals) = vertices.normals {
+        let (a, b) = (normals[from as usize], normals[to as usize]);
+        let lengths = a.norm() * b.norm();
+
+        if lengths > 0.0 && a.dot(&b) / lengths < min_normal_cos {
+            return false;
+        }
+    }
+
+    // Link condition: an interior edge may only share its two opposite vertices
+    {
+        let neighbors = |v: u32| -> Vec<u32> {
+            let mut neighbors: Vec<u32> = vertex_faces[v as usize].iter()
+                                                                  .flat_map(|f| triangles[*f].iter().cloned())
+                                                                  .filter(|n| *n != v)
+                                                                  .collect();
+            neighbors.sort();
+            neighbors.dedup();
+            neighbors
+        };
+
+        let to_neighbors = neighbors(to);
+        let shared = neighbors(from).iter().filter(|n| to_neighbors.binary_search(n).is_ok()).count();
+
+        if shared > 2 {
+            return false;
+        }
+    }
+
+    let positions = &vertices.positions;
+
+    for &f in vertex_faces[from as usize].iter() {
+        let tri = triangles[f];
+
+        if tri.contains(&to) {
+            continue;
+        }
+
+        let before = face_normal(positions, &tri);
+
+        let mut after_tri = tri;
+
+        for index in after_tri.iter_mut() {
+            if *index == from {
+                *index = to;
+            }
+        }
+
+        let after = face_normal(positions, &after_tri);
+
+        let lengths = before.norm() * after.norm();
+
+        if lengths <= 0.0 || before.dot(&after) / lengths < min_normal_cos {
+            return false;
+        }
+    }
+
+    true
+}
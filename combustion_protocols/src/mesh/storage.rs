@@ -10,8 +10,10 @@ use ::utils;
 
 use ::traits::Storage;
 
+use ::math::storage::{load_optional_bounds, save_optional_bounds};
+
 use super::protocol;
-use super::data::{Mesh, MeshVertices, TexCoord, Vertex, Vertices};
+use super::data::{Mesh, MeshVertices, TexCoord, Vertex, Vertices, MorphTarget};
 
 /// Arguments to pass to the mesh storage routines
 #[derive(Debug, Clone, Copy)]
@@ -63,6 +65,18 @@ impl<'a> Storage<'a> for Mesh {
 
         let primitive = try_throw!(reader.get_primitive());
 
+        let raw_morph_targets = try_throw!(reader.get_morph_targets());
+
+        let mut morph_targets = Vec::with_capacity(raw_morph_targets.len() as usize);
+
+        for target_reader in raw_morph_targets.iter() {
+            morph_targets.push(try_rethrow!(MorphTarget::load_from_reader(target_reader)));
+        }
+
+        let morph_weights = try_throw!(reader.get_morph_weights()).iter().collect();
+
+        let bounds = try_rethrow!(load_optional_bounds(try_throw!(reader.get_bounds())));
+
         let vertices = match try_throw!(vertices_reader.which()) {
             protocol::mesh::vertices::Interleaved(vertices) => {
                 let vertices = try_throw!(vertices);
@@ -223,6 +237,9 @@ impl<'a> Storage<'a> for Mesh {
             indices: indices,
             materials: materials,
             primitive: primitive,
+            morph_targets: morph_targets,
+            morph_weights: morph_weights,
+            bounds: bounds,
         })
     }
 
@@ -251,6 +268,24 @@ impl<'a> Storage<'a> for Mesh {
 
         builder.set_primitive(self.primitive);
 
+        try_rethrow!(save_optional_bounds(builder.borrow().init_bounds(), self.bounds.as_ref()));
+
+        {
+            let mut morph_targets_builder = builder.borrow().init_morph_targets(self.morph_targets.len() as u32);
+
+            for (i, target) in self.morph_targets.iter().enumerate() {
+                try_rethrow!(target.save_to_builder(morph_targets_builder.borrow().get(i as u32)));
+            }
+        }
+
+        {
+            let mut morph_weights_builder = builder.borrow().init_morph_weights(self.morph_weights.len() as u32);
+
+            for (i, weight) in self.morph_weights.iter().enumerate() {
+                morph_weights_builder.set(i as u32, *weight);
+            }
+        }
+
         {
             let mut vertices_builder = builder.borrow().init_vertices();
 
@@ -361,4 +396,91 @@ impl<'a> Storage<'a> for Mesh {
     fn query_reader_args(_: Self::Reader, _: ()) -> ProtocolResult<()> {
         unimplemented!()
     }
-}
\ No newline at end of file
+}
+
+impl<'a> Storage<'a> for MorphTarget {
+    type Builder = protocol::morph_target::Builder<'a>;
+    type Reader = protocol::morph_target::Reader<'a>;
+
+    type LoadArgs = ();
+    type SaveArgs = ();
+    type Query = ();
+
+    fn load_from_reader_args(reader: Self::Reader, _: ()) -> ProtocolResult<Self> {
+        let raw_name = try_throw!(reader.get_name());
+        let raw_positions = try_throw!(reader.get_positions());
+
+        let mut positions = Vec::with_capacity(raw_positions.len() as usize);
+
+        for position in raw_positions.iter() {
+            positions.push(position.get_vector());
+        }
+
+        let normals = try_rethrow!(load_optional_offsets(try_throw!(reader.get_normals())));
+        let tangents = try_rethrow!(load_optional_offsets(try_throw!(reader.get_tangents())));
+
+        if normals.as_ref().map_or(false, |normals| normals.len() != positions.len()) ||
+            tangents.as_ref().map_or(false, |tangents| tangents.len() != positions.len()) {
+            throw!(ProtocolError::InvalidLength);
+        }
+
+        Ok(MorphTarget {
+            name: raw_name.to_string(),
+            positions: positions,
+            normals: normals,
+            tangents: tangents,
+        })
+    }
+
+    fn save_to_builder_args(&self, mut builder: Self::Builder, _: ()) -> ProtocolResult<()> {
+        builder.set_name(self.name.as_str());
+
+        {
+            let mut positions_list_builder = builder.borrow().init_positions(self.positions.len() as u32);
+
+            for (i, position) in self.positions.iter().enumerate() {
+                positions_list_builder.borrow().get(i as u32).set_vector(position);
+            }
+        }
+
+        save_optional_offsets(builder.borrow().init_normals(), self.normals.as_ref());
+        save_optional_offsets(builder.borrow().init_tangents(), self.tangents.as_ref());
+
+        Ok(())
+    }
+
+    fn query_reader_args(_: Self::Reader, _: ()) -> ProtocolResult<()> {
+        unimplemented!()
+    }
+}
+
+type OffsetList = ::capnp::struct_list::Owned<::math::protocol::vector3::Owned>;
+
+fn load_optional_offsets(reader: utils::protocol::option::Reader<OffsetList>) -> ProtocolResult<Option<Vec<Vector3<f32>>>> {
+    Ok(match try_throw!(reader.which()) {
+        utils::protocol::option::Some(raw_offsets) => {
+            let raw_offsets = try_throw!(raw_offsets);
+
+            let mut offsets = Vec::with_capacity(raw_offsets.len() as usize);
+
+            for offset in raw_offsets.iter() {
+                offsets.push(offset.get_vector());
+            }
+
+            Some(offsets)
+        },
+        _ => None,
+    })
+}
+
+fn save_optional_offsets(mut builder: utils::protocol::option::Builder<OffsetList>, offsets: Option<&Vec<Vector3<f32>>>) {
+    if let Some(offsets) = offsets {
+        let mut offsets_builder = builder.initn_some(offsets.len() as u32);
+
+        for (i, offset) in offsets.iter().enumerate() {
+            offsets_builder.borrow().get(i as u32).set_vector(offset);
+        }
+    } else {
+        builder.set_none(());
+    }
+}
@@ -0,0 +1,462 @@
+//! Animation clip sampling, pose blending and skinning
+//!
+//! Everything here is pure math over the model data structures, so it can be used by the runtime animation systems
+//! as well as offline tools and headless tests.
+
+use nalgebra::{Vector3, Point3, Quaternion, Matrix4, Norm};
+
+use ::math::data::{compose_trs, normalize_quaternion, multiply_quaternions as multiply};
+use ::mesh::data::Vertices;
+
+use super::data::{Joint, Skeleton, Skin, Keyframe, ChannelKeyframes, Channel, AnimationClip, Interpolation};
+
+/// Local transform of a single joint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JointPose {
+    /// Translation relative to the parent joint
+    pub translation: Vector3<f32>,
+    /// Rotation relative to the parent joint
+    pub rotation: Quaternion<f32>,
+    /// Scale relative to the parent joint
+    pub scale: Vector3<f32>,
+}
+
+impl Default for JointPose {
+    fn default() -> JointPose {
+        JointPose {
+            translation: Vector3::new(0.0, 0.0, 0.0),
+            rotation: Quaternion::new(1.0, 0.0, 0.0, 0.0),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl<'a> From<&'a Joint> for JointPose {
+    fn from(joint: &'a Joint) -> JointPose {
+        JointPose {
+            translation: joint.translation,
+            rotation: joint.rotation,
+            scale: joint.scale,
+        }
+    }
+}
+
+impl JointPose {
+    /// Local transform matrix of the joint
+    #[inline]
+    pub fn to_matrix(&self) -> Matrix4<f32> {
+        compose_trs(&self.translation, &self.rotation, &self.scale)
+    }
+
+    /// Interpolate towards `other` by `t`
+    pub fn lerp(&self, other: &JointPose, t: f32) -> JointPose {
+        JointPose {
+            translation: lerp_vector(&self.translation, &other.translation, t),
+            rotation: slerp(&self.rotation, &other.rotation, t),
+            scale: lerp_vector(&self.scale, &other.scale, t),
+        }
+    }
+}
+
+/// Local transforms of every joint in a skeleton
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Pose {
+    /// Joint transforms, in the same order as the skeleton joints
+    pub joints: Vec<JointPose>,
+}
+
+impl Pose {
+    /// Create the rest pose of a skeleton
+    pub fn rest(skeleton: &Skeleton) -> Pose {
+        Pose { joints: skeleton.joints.iter().map(JointPose::from).collect() }
+    }
+
+    /// Cross-fade towards `other` by `weight`, where `0.0` leaves this pose untouched
+    /// and `1.0` replaces it with `other`.
+    pub fn blend(&mut self, other: &Pose, weight: f32) {
+        for (joint, other_joint) in self.joints.iter_mut().zip(other.joints.iter()) {
+            *joint = joint.lerp(other_joint, weight);
+        }
+    }
+
+    /// Apply the difference between `additive` and `reference` on top of this pose, scaled by `weight`.
+    ///
+    /// `reference` is usually the first frame or the rest pose of the additive clip.
+    pub fn add(&mut self, additive: &Pose, reference: &Pose, weight: f32) {
+        let identity = Quaternion::new(1.0, 0.0, 0.0, 0.0);
+
+        for ((joint, additive_joint), reference_joint) in self.joints.iter_mut().zip(additive.joints.iter()).zip(reference.joints.iter()) {
+            joint.translation += (additive_joint.translation - reference_joint.translation) * weight;
+
+            let delta = multiply(&additive_joint.rotation, &conjugate(&reference_joint.rotation));
+
+            joint.rotation = normalize_quaternion(&multiply(&slerp(&identity, &delta, weight), &joint.rotation));
+
+            let scale_delta = Vector3::new(ratio(additive_joint.scale.x, reference_joint.scale.x),
+                                           ratio(additive_joint.scale.y, reference_joint.scale.y),
+                                           ratio(additive_joint.scale.z, reference_joint.scale.z));
+
+            let scale_delta = lerp_vector(&Vector3::new(1.0, 1.0, 1.0), &scale_delta, weight);
+
+            joint.scale = Vector3::new(joint.scale.x * scale_delta.x,
+                                       joint.scale.y * scale_delta.y,
+                                       joint.scale.z * scale_delta.z);
+        }
+    }
+
+    /// Compute the model space transform of every joint
+    pub fn global_matrices(&self, skeleton: &Skeleton) -> Vec<Matrix4<f32>> {
+        let mut globals: Vec<Matrix4<f32>> = Vec::with_capacity(self.joints.len());
+
+        for (joint, pose) in skeleton.joints.iter().zip(self.joints.iter()) {
+            let local = pose.to_matrix();
+
+            // Parents always come before their children, so their global transform is already known
+            let global = match joint.parent {
+                Some(parent) if (parent as usize) < globals.len() => globals[parent as usize] * local,
+                _ => local,
+            };
+
+            globals.push(global);
+        }
+
+        globals
+    }
+
+    /// Compute the skinning matrix of every joint, which takes bind pose vertices into the posed model space.
+    pub fn joint_palette(&self, skeleton: &Skeleton) -> Vec<Matrix4<f32>> {
+        self.global_matrices(skeleton).into_iter().zip(skeleton.joints.iter()).map(|(global, joint)| {
+            global * joint.inverse_bind
+        }).collect()
+    }
+}
+
+impl AnimationClip {
+    /// Sample every channel of the clip targeting `skeleton` at `time` seconds, overwriting the animated joint properties of `pose`.
+    ///
+    /// Time is clamped to the clip duration. Joints without channels keep their current value.
+    pub fn sample(&self, skeleton: u32, time: f32, pose: &mut Pose) {
+        let time = self.clamp_time(time);
+
+        for channel in &self.channels {
+            if channel.skeleton == skeleton {
+                if let Some(joint) = pose.joints.get_mut(channel.joint as usize) {
+                    channel.sample(time, joint);
+                }
+            }
+        }
+    }
+
+    /// Sample every morph weight channel of the clip targeting `mesh` at `time` seconds, overwriting `weights`.
+    ///
+    /// Time is clamped to the clip duration. `weights` is resized to fit the sampled values if needed.
+    pub fn sample_weights(&self, mesh: u32, time: f32, weights: &mut Vec<f32>) {
+        let time = self.clamp_time(time);
+
+        for channel in &self.channels {
+            if let ChannelKeyframes::Weights { mesh: target, ref keyframes } = channel.keyframes {
+                if target == mesh {
+                    if let Some(values) = sample_weights(keyframes, channel.interpolation, time) {
+                        if weights.len() < values.len() {
+                            weights.resize(values.len(), 0.0);
+                        }
+
+                        weights[..values.len()].copy_from_slice(&values);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl AnimationClip {
+    #[inline]
+    fn clamp_time(&self, time: f32) -> f32 {
+        time.max(0.0).min(self.duration.max(0.0))
+    }
+}
+
+impl Channel {
+    /// Sample the channel at `time` seconds and write the result into `joint`
+    pub fn sample(&self, time: f32, joint: &mut JointPose) {
+        match self.keyframes {
+            ChannelKeyframes::Translation(ref keys) => {
+                if let Some(value) = sample_vector(keys, self.interpolation, time) {
+                    joint.translation = value;
+                }
+            }
+            ChannelKeyframes::Scale(ref keys) => {
+                if let Some(value) = sample_vector(keys, self.interpolation, time) {
+                    joint.scale = value;
+                }
+            }
+            ChannelKeyframes::Rotation(ref keys) => {
+                if let Some(value) = sample_rotation(keys, self.interpolation, time) {
+                    joint.rotation = value;
+                }
+            }
+            ChannelKeyframes::Weights { .. } => {}
+        }
+    }
+}
+
+/// Skin bind pose vertices on the CPU using the given joint palette.
+///
+/// Normals are transformed by the inverse-transpose of each joint matrix, so they stay perpendicular to
+/// non-uniformly scaled surfaces, and renormalized. Vertices without any weights are left in place. UVs are copied unchanged.
+pub fn skin_vertices(vertices: &Vertices, skin: &Skin, palette: &[Matrix4<f32>]) -> Vertices {
+    let normal_palette: Vec<[[f32; 3]; 3]> = if vertices.normals.is_some() {
+        palette.iter().map(normal_matrix).collect()
+    } else {
+        Vec::new()
+    };
+
+    let mut positions = Vec::with_capacity(vertices.positions.len());
+    let mut normals = vertices.normals.as_ref().map(|normals| Vec::with_capacity(normals.len()));
+
+    for (i, position) in vertices.positions.iter().enumerate() {
+        let mut skinned_position = Vector3::new(0.0, 0.0, 0.0);
+        let mut skinned_normal = Vector3::new(0.0, 0.0, 0.0);
+        let mut total = 0.0;
+
+        let normal = vertices.normals.as_ref().and_then(|normals| normals.get(i));
+
+        if let Some(weights) = skin.weights.get(i) {
+            for (joint, weight) in weights.joints.iter().zip(weights.weights.iter()) {
+                if let Some(matrix) = palette.get(*joint as usize) {
+                    skinned_position += transform_point(matrix, position).to_vector() * *weight;
+
+                    if let (Some(normal), Some(normal_matrix)) = (normal, normal_palette.get(*joint as usize)) {
+                        skinned_normal += transform_normal(normal_matrix, normal) * *weight;
+                    }
+
+                    total += *weight;
+                }
+            }
+        }
+
+        if total > 0.0 {
+            positions.push((skinned_position / total).to_point());
+
+            if let Some(ref mut normals) = normals {
+                let length = skinned_normal.norm();
+
+                normals.push(if length > 0.0 { skinned_normal / length } else { normal.cloned().unwrap_or(skinned_normal) });
+            }
+        } else {
+            positions.push(*position);
+
+            if let Some(ref mut normals) = normals {
+                normals.push(normal.cloned().unwrap_or_else(|| Vector3::new(0.0, 0.0, 0.0)));
+            }
+        }
+    }
+
+    Vertices {
+        positions: positions,
+        normals: normals,
+        uvs: vertices.uvs.clone(),
+    }
+}
+
+/// Find the keyframes surrounding `time`, and how far between them it is
+fn find_segment<T>(keys: &[Keyframe<T>], time: f32) -> (usize, usize, f32) {
+    let last = keys.len() - 1;
+
+    if time <= keys[0].time {
+        return (0, 0, 0.0);
+    }
+
+    if time >= keys[last].time {
+        return (last, last, 0.0);
+    }
+
+    // First keyframe strictly after `time`
+    let next = match keys.binary_search_by(|key| key.time.partial_cmp(&time).unwrap_or(::std::cmp::Ordering::Less)) {
+        Ok(index) => return (index, index, 0.0),
+        Err(index) => index,
+    };
+
+    let previous = next - 1;
+
+    let span = keys[next].time - keys[previous].time;
+
+    (previous, next, if span > 0.0 { (time - keys[previous].time) / span } else { 0.0 })
+}
+
+/// Cubic Hermite spline basis weights for `p0`, `m0`, `p1` and `m1`
+fn hermite(t: f32) -> (f32, f32, f32, f32) {
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    (2.0 * t3 - 3.0 * t2 + 1.0, t3 - 2.0 * t2 + t, -2.0 * t3 + 3.0 * t2, t3 - t2)
+}
+
+fn sample_vector(keys: &[Keyframe<Vector3<f32>>], interpolation: Interpolation, time: f32) -> Option<Vector3<f32>> {
+    if keys.is_empty() {
+        return None;
+    }
+
+    let (previous, next, t) = find_segment(keys, time);
+
+    let (a, b) = (&keys[previous], &keys[next]);
+
+    Some(match interpolation {
+        _ if previous == next => a.value,
+        Interpolation::Step => a.value,
+        Interpolation::Linear => lerp_vector(&a.value, &b.value, t),
+        Interpolation::CubicSpline => {
+            let dt = b.time - a.time;
+            let (h00, h10, h01, h11) = hermite(t);
+
+            let out_tangent = a.tangents.map_or(Vector3::new(0.0, 0.0, 0.0), |(_, out_tangent)| out_tangent);
+            let in_tangent = b.tangents.map_or(Vector3::new(0.0, 0.0, 0.0), |(in_tangent, _)| in_tangent);
+
+            a.value * h00 + out_tangent * (h10 * dt) + b.value * h01 + in_tangent * (h11 * dt)
+        }
+    })
+}
+
+fn sample_rotation(keys: &[Keyframe<Quaternion<f32>>], interpolation: Interpolation, time: f32) -> Option<Quaternion<f32>> {
+    if keys.is_empty() {
+        return None;
+    }
+
+    let (previous, next, t) = find_segment(keys, time);
+
+    let (a, b) = (&keys[previous], &keys[next]);
+
+    Some(match interpolation {
+        _ if previous == next => a.value,
+        Interpolation::Step => a.value,
+        Interpolation::Linear => slerp(&a.value, &b.value, t),
+        Interpolation::CubicSpline => {
+            let dt = b.time - a.time;
+            let (h00, h10, h01, h11) = hermite(t);
+
+            let zero = Quaternion::new(0.0, 0.0, 0.0, 0.0);
+
+            let out_tangent = a.tangents.map_or(zero, |(_, out_tangent)| out_tangent);
+            let in_tangent = b.tangents.map_or(zero, |(in_tangent, _)| in_tangent);
+
+            let component = |p0: f32, m0: f32, p1: f32, m1: f32| p0 * h00 + m0 * h10 * dt + p1 * h01 + m1 * h11 * dt;
+
+            normalize_quaternion(&Quaternion::new(component(a.value.w, out_tangent.w, b.value.w, in_tangent.w),
+                                                  component(a.value.i, out_tangent.i, b.value.i, in_tangent.i),
+                                                  component(a.value.j, out_tangent.j, b.value.j, in_tangent.j),
+                                                  component(a.value.k, out_tangent.k, b.value.k, in_tangent.k)))
+        }
+    })
+}
+
+fn sample_weights(keys: &[Keyframe<Vec<f32>>], interpolation: Interpolation, time: f32) -> Option<Vec<f32>> {
+    if keys.is_empty() {
+        return None;
+    }
+
+    let (previous, next, t) = find_segment(keys, time);
+
+    let (a, b) = (&keys[previous], &keys[next]);
+
+    Some(match interpolation {
+        _ if previous == next => a.value.clone(),
+        Interpolation::Step => a.value.clone(),
+        Interpolation::Linear => a.value.iter().zip(b.value.iter()).map(|(a, b)| a + (b - a) * t).collect(),
+        Interpolation::CubicSpline => {
+            let dt = b.time - a.time;
+            let (h00, h10, h01, h11) = hermite(t);
+
+            a.value.iter().zip(b.value.iter()).enumerate().map(|(i, (p0, p1))| {
+                let m0 = a.tangents.as_ref().and_then(|&(_, ref out_tangents)| out_tangents.get(i).cloned()).unwrap_or(0.0);
+                let m1 = b.tangents.as_ref().and_then(|&(ref in_tangents, _)| in_tangents.get(i).cloned()).unwrap_or(0.0);
+
+                p0 * h00 + m0 * h10 * dt + p1 * h01 + m1 * h11 * dt
+            }).collect()
+        }
+    })
+}
+
+#[inline]
+fn lerp_vector(a: &Vector3<f32>, b: &Vector3<f32>, t: f32) -> Vector3<f32> {
+    *a + (*b - *a) * t
+}
+
+#[inline]
+fn ratio(a: f32, b: f32) -> f32 {
+    if b != 0.0 { a / b } else { 1.0 }
+}
+
+#[inline]
+fn dot(a: &Quaternion<f32>, b: &Quaternion<f32>) -> f32 {
+    a.w * b.w + a.i * b.i + a.j * b.j + a.k * b.k
+}
+
+#[inline]
+fn conjugate(q: &Quaternion<f32>) -> Quaternion<f32> {
+    Quaternion::new(q.w, -q.i, -q.j, -q.k)
+}
+
+/// Spherical linear interpolation along the shortest path
+fn slerp(a: &Quaternion<f32>, b: &Quaternion<f32>, t: f32) -> Quaternion<f32> {
+    let mut cos_theta = dot(a, b);
+
+    // Take the shortest path by flipping one of the quaternions
+    let b = if cos_theta < 0.0 {
+        cos_theta = -cos_theta;
+        Quaternion::new(-b.w, -b.i, -b.j, -b.k)
+    } else { *b };
+
+    // Fall back to normalized linear interpolation for nearly identical rotations
+    let (wa, wb) = if cos_theta > 0.9995 {
+        (1.0 - t, t)
+    } else {
+        let theta = cos_theta.acos();
+        let sin_theta = theta.sin();
+
+        (((1.0 - t) * theta).sin() / sin_theta, (t * theta).sin() / sin_theta)
+    };
+
+    normalize_quaternion(&Quaternion::new(a.w * wa + b.w * wb,
+                                          a.i * wa + b.i * wb,
+                                          a.j * wa + b.j * wb,
+                                          a.k * wa + b.k * wb))
+}
+
+#[inline]
+fn transform_point(m: &Matrix4<f32>, p: &Point3<f32>) -> Point3<f32> {
+    Point3::new(m.m11 * p.x + m.m12 * p.y + m.m13 * p.z + m.m14,
+                m.m21 * p.x + m.m22 * p.y + m.m23 * p.z + m.m24,
+                m.m31 * p.x + m.m32 * p.y + m.m33 * p.z + m.m34)
+}
+
+/// Inverse-transpose of the upper 3x3 of `m`.
+///
+/// Singular matrices give their cofactor matrix instead, which still transforms normals in the right direction.
+fn normal_matrix(m: &Matrix4<f32>) -> [[f32; 3]; 3] {
+    let cofactors = [
+        [m.m22 * m.m33 - m.m23 * m.m32, m.m23 * m.m31 - m.m21 * m.m33, m.m21 * m.m32 - m.m22 * m.m31],
+        [m.m13 * m.m32 - m.m12 * m.m33, m.m11 * m.m33 - m.m13 * m.m31, m.m12 * m.m31 - m.m11 * m.m32],
+        [m.m12 * m.m23 - m.m13 * m.m22, m.m13 * m.m21 - m.m11 * m.m23, m.m11 * m.m22 - m.m12 * m.m21],
+    ];
+
+    let determinant = m.m11 * cofactors[0][0] + m.m12 * cofactors[0][1] + m.m13 * cofactors[0][2];
+
+    let scale = if determinant != 0.0 { 1.0 / determinant } else { 1.0 };
+
+    let mut result = [[0.0; 3]; 3];
+
+    for (row, cofactor_row) in result.iter_mut().zip(cofactors.iter()) {
+        for (value, cofactor) in row.iter_mut().zip(cofactor_row.iter()) {
+            *value = cofactor * scale;
+        }
+    }
+
+    result
+}
+
+#[inline]
+fn transform_normal(m: &[[f32; 3]; 3], v: &Vector3<f32>) -> Vector3<f32> {
+    Vector3::new(m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+                 m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+                 m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z)
+}
@@ -0,0 +1,66 @@
+//! Hierarchical bounds computation for models
+
+use ::math::data::{Transform, Bounds};
+use ::mesh::data::Mesh;
+
+use super::data::{Node, Model};
+
+impl Node {
+    /// Compute the bounds of this node and all of its children, with the node transforms applied.
+    ///
+    /// Stored mesh bounds are used where present. Returns `None` if no meshes are reachable from the node.
+    pub fn compute_bounds(&self, meshes: &[Mesh]) -> Option<Bounds> {
+        let mut bounds: Option<Bounds> = None;
+
+        for mesh in &self.meshes {
+            if let Some(mesh_bounds) = meshes.get(*mesh as usize).and_then(Mesh::bounds) {
+                bounds = Some(bounds.map_or(mesh_bounds, |bounds| bounds.union(&mesh_bounds)));
+            }
+        }
+
+        for child in &self.children {
+            if let Some(child_bounds) = child.compute_bounds(meshes) {
+                bounds = Some(bounds.map_or(child_bounds, |bounds| bounds.union(&child_bounds)));
+            }
+        }
+
+        bounds.map(|bounds| bounds.transform(&Transform::combine(&self.transforms)))
+    }
+
+    /// Recompute and store the bounds of this node and all of its children
+    pub fn update_bounds(&mut self, meshes: &[Mesh]) -> Option<Bounds> {
+        let mut bounds: Option<Bounds> = None;
+
+        for mesh in &self.meshes {
+            if let Some(mesh_bounds) = meshes.get(*mesh as usize).and_then(Mesh::bounds) {
+                bounds = Some(bounds.map_or(mesh_bounds, |bounds| bounds.union(&mesh_bounds)));
+            }
+        }
+
+        for child in &mut self.children {
+            if let Some(child_bounds) = child.update_bounds(meshes) {
+                bounds = Some(bounds.map_or(child_bounds, |bounds| bounds.union(&child_bounds)));
+            }
+        }
+
+        self.bounds = bounds.map(|bounds| bounds.transform(&Transform::combine(&self.transforms)));
+
+        self.bounds
+    }
+}
+
+impl Model {
+    /// Recompute and store the bounds of every mesh and node in the model
+    pub fn update_bounds(&mut self) {
+        for mesh in &mut self.meshes {
+            mesh.update_bounds();
+        }
+
+        self.root.update_bounds(&self.meshes);
+    }
+
+    /// Get the bounds of the whole model, using stored bounds where present
+    pub fn bounds(&self) -> Option<Bounds> {
+        self.root.bounds.or_else(|| self.root.compute_bounds(&self.meshes))
+    }
+}
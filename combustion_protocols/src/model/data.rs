@@ -2,10 +2,14 @@
 
 use std::fmt::{Debug, Formatter, Result as FmtResult};
 
+use nalgebra::{Vector3, Quaternion, Matrix4};
+
 use common::traits::DefaultName;
 
 use ::mesh::data::Mesh;
-use ::math::data::Transform;
+use ::math::data::{Transform, Bounds};
+
+pub use super::protocol::Interpolation;
 
 /// Node within a `Model`
 #[derive(Named, Clone, Default, Serialize, Deserialize)]
@@ -25,6 +29,10 @@ pub struct Node {
     #[serde(skip_serializing_if = "Vec::is_empty")]
     #[serde(default)]
     pub transforms: Vec<Transform>,
+    /// Precomputed bounds of this node and its children, with the node transforms applied
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub bounds: Option<Bounds>,
 }
 
 impl Debug for Node {
@@ -51,10 +59,221 @@ pub struct Model {
     #[serde(skip_serializing_if = "Vec::is_empty")]
     #[serde(default)]
     pub materials: Vec<String>,
+    /// Level of detail chains for meshes in the model
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub lods: Vec<MeshLods>,
+    /// Skeletons used by skinned meshes
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub skeletons: Vec<Skeleton>,
+    /// Bindings of meshes to skeletons
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub skins: Vec<Skin>,
+    /// Keyframe animations
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub animations: Vec<AnimationClip>,
 }
 
 impl Debug for Model {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        write!(f, "Model {{root: {:?}, meshes: {:?}}}", self.root, self.meshes)
+        write!(f, "Model {{root: {:?}, meshes: {:?}, lods: {:?}, skeletons: {:?}, skins: {}, animations: {:?}}}",
+               self.root, self.meshes, self.lods, self.skeletons, self.skins.len(), self.animations)
+    }
+}
+
+/// Single reduced level of detail for a mesh
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct LodLevel {
+    /// Index of the mesh used for this level, in the `Model` mesh list
+    pub mesh: u32,
+    /// Projected screen size, as a fraction of the screen height, below which this level is used
+    pub screen_size: f32,
+}
+
+/// Chain of reduced detail levels for a single mesh
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeshLods {
+    /// Index of the full detail mesh, in the `Model` mesh list
+    pub mesh: u32,
+    /// Reduced detail levels, ordered from highest to lowest detail
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub levels: Vec<LodLevel>,
+}
+
+/// Single joint of a `Skeleton`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Joint {
+    /// Name of the joint, usually matching a `Node` name
+    pub name: String,
+    /// Index of the parent joint within the same skeleton
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub parent: Option<u32>,
+    /// Transforms model space into joint space at bind time
+    pub inverse_bind: Matrix4<f32>,
+    /// Local rest pose translation, relative to the parent joint
+    pub translation: Vector3<f32>,
+    /// Local rest pose rotation, relative to the parent joint
+    pub rotation: Quaternion<f32>,
+    /// Local rest pose scale, relative to the parent joint
+    pub scale: Vector3<f32>,
+}
+
+/// Joint hierarchy for skinned meshes
+#[derive(Named, Clone, Default, Serialize, Deserialize)]
+pub struct Skeleton {
+    /// Name of the skeleton
+    #[serde(default = "Skeleton::default_name")]
+    pub name: String,
+    /// Joints of the skeleton. Parent joints must come before their children.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub joints: Vec<Joint>,
+}
+
+impl Debug for Skeleton {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, r#"Skeleton {{name: "{}", joints: {}}}"#, self.name, self.joints.len())
     }
-}
\ No newline at end of file
+}
+
+impl Skeleton {
+    /// Find a joint index by name
+    pub fn find_joint(&self, name: &str) -> Option<u32> {
+        self.joints.iter().position(|joint| joint.name == name).map(|index| index as u32)
+    }
+}
+
+/// Joint influences for a single vertex
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VertexWeights {
+    /// Joint indices within the skeleton
+    pub joints: Vec<u32>,
+    /// Weights for each joint, which should add up to `1.0`
+    pub weights: Vec<f32>,
+}
+
+impl VertexWeights {
+    /// Keep only the `max` most influential joints and renormalize their weights
+    pub fn limit(&mut self, max: usize) {
+        let mut pairs: Vec<(u32, f32)> = self.joints.iter().cloned().zip(self.weights.iter().cloned()).collect();
+
+        pairs.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(::std::cmp::Ordering::Equal));
+        pairs.truncate(max);
+
+        let total: f32 = pairs.iter().map(|&(_, weight)| weight).sum();
+
+        self.joints = pairs.iter().map(|&(joint, _)| joint).collect();
+        self.weights = pairs.iter().map(|&(_, weight)| if total > 0.0 { weight / total } else { 0.0 }).collect();
+    }
+}
+
+/// Binds a mesh to a skeleton
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Skin {
+    /// Index of the skinned mesh
+    pub mesh: u32,
+    /// Index of the skeleton
+    pub skeleton: u32,
+    /// Joint influences, one per mesh vertex
+    pub weights: Vec<VertexWeights>,
+}
+
+/// Single keyframe of an animation channel
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Keyframe<T> {
+    /// Time of the keyframe, in seconds
+    pub time: f32,
+    /// Value at that time
+    pub value: T,
+    /// In and out tangents, only used for cubic spline interpolation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub tangents: Option<(T, T)>,
+}
+
+impl<T> Keyframe<T> {
+    /// Create a new keyframe without tangents
+    pub fn new(time: f32, value: T) -> Keyframe<T> {
+        Keyframe { time: time, value: value, tangents: None }
+    }
+}
+
+/// Keyframes for whichever joint property a channel animates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ChannelKeyframes {
+    /// Translation keyframes
+    #[serde(rename = "translation")]
+    Translation(Vec<Keyframe<Vector3<f32>>>),
+    /// Rotation keyframes
+    #[serde(rename = "rotation")]
+    Rotation(Vec<Keyframe<Quaternion<f32>>>),
+    /// Scale keyframes
+    #[serde(rename = "scale")]
+    Scale(Vec<Keyframe<Vector3<f32>>>),
+    /// Morph target weight keyframes, one weight per target of the mesh.
+    ///
+    /// These ignore the skeleton and joint of the channel.
+    #[serde(rename = "weights")]
+    Weights {
+        /// Index of the morphed mesh
+        mesh: u32,
+        /// Weight keyframes
+        keyframes: Vec<Keyframe<Vec<f32>>>,
+    },
+}
+
+impl ChannelKeyframes {
+    /// Number of keyframes
+    pub fn len(&self) -> usize {
+        match *self {
+            ChannelKeyframes::Translation(ref keys) => keys.len(),
+            ChannelKeyframes::Rotation(ref keys) => keys.len(),
+            ChannelKeyframes::Scale(ref keys) => keys.len(),
+            ChannelKeyframes::Weights { ref keyframes, .. } => keyframes.len(),
+        }
+    }
+
+    /// Returns `true` if there are no keyframes
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Animates a single property of a single joint, or the morph target weights of a mesh
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Channel {
+    /// Index of the skeleton
+    pub skeleton: u32,
+    /// Index of the joint within the skeleton
+    pub joint: u32,
+    /// How to interpolate between keyframes
+    pub interpolation: Interpolation,
+    /// Keyframes, ordered by time
+    pub keyframes: ChannelKeyframes,
+}
+
+/// Named keyframe animation
+#[derive(Named, Clone, Default, Serialize, Deserialize)]
+pub struct AnimationClip {
+    /// Name of the animation
+    #[serde(default = "AnimationClip::default_name")]
+    pub name: String,
+    /// Duration in seconds
+    pub duration: f32,
+    /// Animation channels
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub channels: Vec<Channel>,
+}
+
+impl Debug for AnimationClip {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, r#"AnimationClip {{name: "{}", duration: {}, channels: {}}}"#, self.name, self.duration, self.channels.len())
+    }
+}
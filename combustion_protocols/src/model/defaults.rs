@@ -2,10 +2,22 @@
 
 use common::traits::DefaultName;
 
-use super::data::Node;
+use super::data::{Node, Skeleton, AnimationClip};
 
 impl DefaultName for Node {
     fn default_name() -> String {
         "Untitled Node".to_string()
     }
-}
\ No newline at end of file
+}
+
+impl DefaultName for Skeleton {
+    fn default_name() -> String {
+        "Untitled Skeleton".to_string()
+    }
+}
+
+impl DefaultName for AnimationClip {
+    fn default_name() -> String {
+        "Untitled Animation".to_string()
+    }
+}
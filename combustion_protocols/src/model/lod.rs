@@ -0,0 +1,218 @@
+//! Level of detail generation and selection for models
+
+use ::error::{ProtocolResult, ProtocolError};
+
+use ::mesh::simplify::{simplify, SimplifyOptions};
+
+use super::data::{Model, Node, MeshLods, LodLevel, ChannelKeyframes};
+
+/// Settings for generating a single level of detail
+#[derive(Debug, Clone, Copy)]
+pub struct LodSettings {
+    /// Fraction of the full detail triangles to keep
+    pub ratio: f32,
+    /// Projected screen size below which the generated level is used
+    pub screen_size: f32,
+}
+
+impl LodSettings {
+    /// Create new `LodSettings`
+    pub fn new(ratio: f32, screen_size: f32) -> LodSettings {
+        LodSettings { ratio: ratio, screen_size: screen_size }
+    }
+
+    /// Reasonable default chain of three levels
+    pub fn default_chain() -> Vec<LodSettings> {
+        vec![
+            LodSettings::new(0.5, 0.5),
+            LodSettings::new(0.25, 0.25),
+            LodSettings::new(0.1, 0.1),
+        ]
+    }
+}
+
+impl Model {
+    /// Find the level of detail chain for a mesh, if it has one
+    pub fn lods_for(&self, mesh: u32) -> Option<&MeshLods> {
+        self.lods.iter().find(|lods| lods.mesh == mesh)
+    }
+
+    /// Select which mesh should be rendered in place of `mesh` at the given projected screen size.
+    ///
+    /// If the mesh has no level of detail chain, `mesh` itself is returned.
+    pub fn select_lod(&self, mesh: u32, screen_size: f32) -> u32 {
+        let mut selected = mesh;
+
+        if let Some(lods) = self.lods_for(mesh) {
+            for level in &lods.levels {
+                if screen_size < level.screen_size {
+                    selected = level.mesh;
+                } else {
+                    break;
+                }
+            }
+        }
+
+        selected
+    }
+
+    /// Generate a level of detail chain for a single mesh, replacing any existing chain for it.
+    ///
+    /// Each level is simplified from the full detail mesh. Meshes of a previous chain are overwritten in place,
+    /// extra levels are appended to the model mesh list and leftover meshes of a longer previous chain are removed.
+    /// `settings` should be ordered from highest to lowest detail.
+    pub fn generate_lods(&mut self, mesh: u32, settings: &[LodSettings], options: &SimplifyOptions) -> ProtocolResult<()> {
+        let leftover = try_rethrow!(self.replace_lods(mesh, settings, options));
+
+        self.remove_meshes(&leftover);
+
+        Ok(())
+    }
+
+    /// Generate the chain for `generate_lods`, returning the leftover meshes of the previous chain instead of removing them,
+    /// so callers can defer the removal until no other mesh indices are needed.
+    fn replace_lods(&mut self, mesh: u32, settings: &[LodSettings], options: &SimplifyOptions) -> ProtocolResult<Vec<u32>> {
+        if mesh as usize >= self.meshes.len() {
+            throw!(ProtocolError::NotPresent);
+        }
+
+        let mut simplified = Vec::with_capacity(settings.len());
+
+        // Simplify everything first, so a failure leaves the previous chain intact
+        for setting in settings {
+            simplified.push(try_rethrow!(simplify(&self.meshes[mesh as usize], &SimplifyOptions {
+                ratio: setting.ratio,
+                ..*options
+            })));
+        }
+
+        let previous: Vec<u32> = match self.lods.iter().position(|lods| lods.mesh == mesh) {
+            Some(index) => self.lods.remove(index).levels.iter().map(|level| level.mesh).collect(),
+            None => Vec::new(),
+        };
+
+        let mut levels = Vec::with_capacity(settings.len());
+
+        for (i, (setting, simplified)) in settings.iter().zip(simplified.into_iter()).enumerate() {
+            let index = match previous.get(i) {
+                Some(&index) => {
+                    self.meshes[index as usize] = simplified;
+                    index
+                }
+                None => {
+                    self.meshes.push(simplified);
+                    self.meshes.len() as u32 - 1
+                }
+            };
+
+            levels.push(LodLevel {
+                mesh: index,
+                screen_size: setting.screen_size,
+            });
+        }
+
+        self.lods.push(MeshLods {
+            mesh: mesh,
+            levels: levels,
+        });
+
+        Ok(if previous.len() > settings.len() { previous[settings.len()..].to_vec() } else { Vec::new() })
+    }
+
+    /// Remove meshes from the model mesh list, updating every mesh index which comes after them.
+    ///
+    /// Anything still referring to a removed mesh is dropped along with it.
+    fn remove_meshes(&mut self, removed: &[u32]) {
+        let mut removed = removed.to_vec();
+
+        removed.sort();
+        removed.dedup();
+
+        for &index in removed.iter().rev() {
+            self.meshes.remove(index as usize);
+        }
+
+        let remap = |index: u32| -> Option<u32> {
+            match removed.binary_search(&index) {
+                Ok(_) => None,
+                Err(shift) => Some(index - shift as u32),
+            }
+        };
+
+        fn remap_node<F: Fn(u32) -> Option<u32>>(node: &mut Node, remap: &F) {
+            node.meshes = node.meshes.iter().filter_map(|&mesh| remap(mesh)).collect();
+
+            for child in &mut node.children {
+                remap_node(child, remap);
+            }
+        }
+
+        remap_node(&mut self.root, &remap);
+
+        self.lods = self.lods.drain(..).filter_map(|lods| {
+            remap(lods.mesh).map(|mesh| MeshLods {
+                mesh: mesh,
+                levels: lods.levels.iter().filter_map(|level| remap(level.mesh).map(|index| LodLevel {
+                    mesh: index,
+                    screen_size: level.screen_size,
+                })).collect(),
+            })
+        }).collect();
+
+        self.skins = self.skins.drain(..).filter_map(|mut skin| {
+            remap(skin.mesh).map(|mesh| {
+                skin.mesh = mesh;
+                skin
+            })
+        }).collect();
+
+        for animation in &mut self.animations {
+            animation.channels.retain(|channel| match channel.keyframes {
+                ChannelKeyframes::Weights { mesh, .. } => remap(mesh).is_some(),
+                _ => true,
+            });
+
+            for channel in &mut animation.channels {
+                if let ChannelKeyframes::Weights { ref mut mesh, .. } = channel.keyframes {
+                    *mesh = remap(*mesh).unwrap();
+                }
+            }
+        }
+    }
+
+    /// Generate level of detail chains for every mesh referenced by the model nodes
+    pub fn generate_all_lods(&mut self, settings: &[LodSettings], options: &SimplifyOptions) -> ProtocolResult<()> {
+        let mut meshes = Vec::new();
+
+        fn collect_meshes(node: &Node, meshes: &mut Vec<u32>) {
+            meshes.extend(node.meshes.iter().cloned());
+
+            for child in &node.children {
+                collect_meshes(child, meshes);
+            }
+        }
+
+        collect_meshes(&self.root, &mut meshes);
+
+        meshes.sort();
+        meshes.dedup();
+
+        // Removing the leftovers of one chain would shift the indices of meshes after it, so do it once at the end
+        let mut leftover = Vec::new();
+
+        for mesh in meshes {
+            match self.replace_lods(mesh, settings, options) {
+                Ok(removed) => leftover.extend(removed),
+                Err(err) => {
+                    self.remove_meshes(&leftover);
+
+                    return Err(err);
+                }
+            }
+        }
+
+        self.remove_meshes(&leftover);
+
+        Ok(())
+    }
+}
@@ -4,6 +4,9 @@ pub mod protocol;
 pub mod data;
 pub mod defaults;
 pub mod storage;
+pub mod lod;
+pub mod animation;
+pub mod bounds;
 
 /// File extension to Combustion model files
 pub const EXTENSION: &'static str = "cmodel";
\ No newline at end of file
@@ -1,16 +1,20 @@
 //! Storage routines for models
 
+use nalgebra::Vector3;
+
 use ::error::ProtocolResult;
 
 use ::traits::Storage;
 
 use ::math::data::Transform;
+use ::math::storage::{load_optional_bounds, save_optional_bounds};
 
 use ::mesh::data::Mesh;
 use ::mesh::storage::MeshSaveArgs;
 
 use super::protocol;
-use super::data::{Node, Model};
+use super::data::{Node, Model, MeshLods, LodLevel, Joint, Skeleton, VertexWeights, Skin,
+                  Interpolation, Keyframe, ChannelKeyframes, Channel, AnimationClip};
 
 /// Arguments to pass to the model storage routines
 #[derive(Debug, Clone, Copy)]
@@ -56,6 +60,7 @@ impl<'a> Storage<'a> for Node {
             meshes: raw_meshes.iter().collect(),
             children: children,
             transforms: transforms,
+            bounds: try_rethrow!(load_optional_bounds(try_throw!(reader.get_bounds()))),
         };
 
         Ok(node)
@@ -90,6 +95,8 @@ impl<'a> Storage<'a> for Node {
             }
         }
 
+        try_rethrow!(save_optional_bounds(builder.borrow().init_bounds(), self.bounds.as_ref()));
+
         builder.set_name(self.name.as_str());
 
         Ok(())
@@ -112,6 +119,10 @@ impl<'a> Storage<'a> for Model {
         let raw_root = try_throw!(reader.get_root());
         let raw_meshes = try_throw!(reader.get_meshes());
         let raw_materials = try_throw!(reader.get_materials());
+        let raw_lods = try_throw!(reader.get_lods());
+        let raw_skeletons = try_throw!(reader.get_skeletons());
+        let raw_skins = try_throw!(reader.get_skins());
+        let raw_animations = try_throw!(reader.get_animations());
 
         let mut meshes = Vec::with_capacity(raw_meshes.len() as usize);
 
@@ -127,10 +138,46 @@ impl<'a> Storage<'a> for Model {
             materials.push(try_throw!(material).into());
         }
 
+        let mut lods = Vec::with_capacity(raw_lods.len() as usize);
+
+        for lods_reader in raw_lods.iter() {
+            let raw_levels = try_throw!(lods_reader.get_levels());
+
+            lods.push(MeshLods {
+                mesh: lods_reader.get_mesh(),
+                levels: raw_levels.iter().map(|level_reader| LodLevel {
+                    mesh: level_reader.get_mesh(),
+                    screen_size: level_reader.get_screen_size(),
+                }).collect(),
+            });
+        }
+
+        let mut skeletons = Vec::with_capacity(raw_skeletons.len() as usize);
+
+        for skeleton_reader in raw_skeletons.iter() {
+            skeletons.push(try_rethrow!(Skeleton::load_from_reader(skeleton_reader)));
+        }
+
+        let mut skins = Vec::with_capacity(raw_skins.len() as usize);
+
+        for skin_reader in raw_skins.iter() {
+            skins.push(try_rethrow!(Skin::load_from_reader(skin_reader)));
+        }
+
+        let mut animations = Vec::with_capacity(raw_animations.len() as usize);
+
+        for animation_reader in raw_animations.iter() {
+            animations.push(try_rethrow!(AnimationClip::load_from_reader(animation_reader)));
+        }
+
         let model = Model {
             meshes: meshes,
             root: root,
             materials: materials,
+            lods: lods,
+            skeletons: skeletons,
+            skins: skins,
+            animations: animations,
         };
 
         Ok(model)
@@ -157,10 +204,364 @@ impl<'a> Storage<'a> for Model {
             }
         }
 
+        {
+            let mut lods_list_builder = builder.borrow().init_lods(self.lods.len() as u32);
+
+            for (i, lods) in self.lods.iter().enumerate() {
+                let mut lods_builder = lods_list_builder.borrow().get(i as u32);
+
+                lods_builder.set_mesh(lods.mesh);
+
+                let mut levels_list_builder = lods_builder.init_levels(lods.levels.len() as u32);
+
+                for (j, level) in lods.levels.iter().enumerate() {
+                    let mut level_builder = levels_list_builder.borrow().get(j as u32);
+
+                    level_builder.set_mesh(level.mesh);
+                    level_builder.set_screen_size(level.screen_size);
+                }
+            }
+        }
+
+        {
+            let mut skeleton_list_builder = builder.borrow().init_skeletons(self.skeletons.len() as u32);
+
+            for (i, skeleton) in self.skeletons.iter().enumerate() {
+                try_rethrow!(skeleton.save_to_builder(skeleton_list_builder.borrow().get(i as u32)));
+            }
+        }
+
+        {
+            let mut skin_list_builder = builder.borrow().init_skins(self.skins.len() as u32);
+
+            for (i, skin) in self.skins.iter().enumerate() {
+                try_rethrow!(skin.save_to_builder(skin_list_builder.borrow().get(i as u32)));
+            }
+        }
+
+        {
+            let mut animation_list_builder = builder.borrow().init_animations(self.animations.len() as u32);
+
+            for (i, animation) in self.animations.iter().enumerate() {
+                try_rethrow!(animation.save_to_builder(animation_list_builder.borrow().get(i as u32)));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn query_reader_args(_: Self::Reader, _: ()) -> ProtocolResult<()> {
+        unimplemented!()
+    }
+}
+
+impl<'a> Storage<'a> for Skeleton {
+    type Builder = protocol::skeleton::Builder<'a>;
+    type Reader = protocol::skeleton::Reader<'a>;
+
+    type LoadArgs = ();
+    type SaveArgs = ();
+    type Query = ();
+
+    fn load_from_reader_args(reader: Self::Reader, _: ()) -> ProtocolResult<Self> {
+        let raw_name = try_throw!(reader.get_name());
+        let raw_joints = try_throw!(reader.get_joints());
+
+        let mut joints = Vec::with_capacity(raw_joints.len() as usize);
+
+        for joint_reader in raw_joints.iter() {
+            let parent = joint_reader.get_parent();
+
+            joints.push(Joint {
+                name: try_throw!(joint_reader.get_name()).to_string(),
+                parent: if parent < 0 { None } else { Some(parent as u32) },
+                inverse_bind: try_throw!(joint_reader.get_inverse_bind()).get_matrix(),
+                translation: try_throw!(joint_reader.get_translation()).get_vector(),
+                rotation: try_throw!(joint_reader.get_rotation()).get_quaternion(),
+                scale: try_throw!(joint_reader.get_scale()).get_vector(),
+            });
+        }
+
+        Ok(Skeleton {
+            name: raw_name.to_string(),
+            joints: joints,
+        })
+    }
+
+    fn save_to_builder_args(&self, mut builder: Self::Builder, _: ()) -> ProtocolResult<()> {
+        builder.set_name(self.name.as_str());
+
+        let mut joint_list_builder = builder.init_joints(self.joints.len() as u32);
+
+        for (i, joint) in self.joints.iter().enumerate() {
+            let mut joint_builder = joint_list_builder.borrow().get(i as u32);
+
+            joint_builder.set_name(joint.name.as_str());
+            joint_builder.set_parent(joint.parent.map_or(-1, |parent| parent as i32));
+
+            { joint_builder.borrow().init_inverse_bind().set_matrix(&joint.inverse_bind); }
+            { joint_builder.borrow().init_translation().set_vector(&joint.translation); }
+            { joint_builder.borrow().init_rotation().set_quaternion(&joint.rotation); }
+            { joint_builder.borrow().init_scale().set_vector(&joint.scale); }
+        }
+
+        Ok(())
+    }
+
+    fn query_reader_args(_: Self::Reader, _: ()) -> ProtocolResult<()> {
+        unimplemented!()
+    }
+}
+
+impl<'a> Storage<'a> for Skin {
+    type Builder = protocol::skin::Builder<'a>;
+    type Reader = protocol::skin::Reader<'a>;
+
+    type LoadArgs = ();
+    type SaveArgs = ();
+    type Query = ();
+
+    fn load_from_reader_args(reader: Self::Reader, _: ()) -> ProtocolResult<Self> {
+        let raw_weights = try_throw!(reader.get_weights());
+
+        let mut weights = Vec::with_capacity(raw_weights.len() as usize);
+
+        for weights_reader in raw_weights.iter() {
+            weights.push(VertexWeights {
+                joints: try_throw!(weights_reader.get_joints()).iter().collect(),
+                weights: try_throw!(weights_reader.get_weights()).iter().collect(),
+            });
+        }
+
+        Ok(Skin {
+            mesh: reader.get_mesh(),
+            skeleton: reader.get_skeleton(),
+            weights: weights,
+        })
+    }
+
+    fn save_to_builder_args(&self, mut builder: Self::Builder, _: ()) -> ProtocolResult<()> {
+        builder.set_mesh(self.mesh);
+        builder.set_skeleton(self.skeleton);
+
+        let mut weights_list_builder = builder.init_weights(self.weights.len() as u32);
+
+        for (i, vertex_weights) in self.weights.iter().enumerate() {
+            let mut weights_builder = weights_list_builder.borrow().get(i as u32);
+
+            {
+                let mut joints_builder = weights_builder.borrow().init_joints(vertex_weights.joints.len() as u32);
+
+                for (j, joint) in vertex_weights.joints.iter().enumerate() {
+                    joints_builder.set(j as u32, *joint);
+                }
+            }
+
+            {
+                let mut values_builder = weights_builder.borrow().init_weights(vertex_weights.weights.len() as u32);
+
+                for (j, weight) in vertex_weights.weights.iter().enumerate() {
+                    values_builder.set(j as u32, *weight);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn query_reader_args(_: Self::Reader, _: ()) -> ProtocolResult<()> {
+        unimplemented!()
+    }
+}
+
+impl<'a> Storage<'a> for AnimationClip {
+    type Builder = protocol::animation_clip::Builder<'a>;
+    type Reader = protocol::animation_clip::Reader<'a>;
+
+    type LoadArgs = ();
+    type SaveArgs = ();
+    type Query = ();
+
+    fn load_from_reader_args(reader: Self::Reader, _: ()) -> ProtocolResult<Self> {
+        let raw_name = try_throw!(reader.get_name());
+        let raw_channels = try_throw!(reader.get_channels());
+
+        let mut channels = Vec::with_capacity(raw_channels.len() as usize);
+
+        for channel_reader in raw_channels.iter() {
+            let interpolation = try_throw!(channel_reader.get_interpolation());
+
+            // Tangents are stored for every keyframe, but are only meaningful for cubic splines
+            let cubic = interpolation == Interpolation::CubicSpline;
+
+            let keyframes = match try_throw!(channel_reader.get_keyframes().which()) {
+                protocol::channel::keyframes::Translation(keys) => {
+                    ChannelKeyframes::Translation(try_rethrow!(load_vector_keys(try_throw!(keys), cubic)))
+                },
+                protocol::channel::keyframes::Scale(keys) => {
+                    ChannelKeyframes::Scale(try_rethrow!(load_vector_keys(try_throw!(keys), cubic)))
+                },
+                protocol::channel::keyframes::Rotation(keys) => {
+                    let keys = try_throw!(keys);
+
+                    let mut keyframes = Vec::with_capacity(keys.len() as usize);
+
+                    for key in keys.iter() {
+                        let value = try_throw!(key.get_value()).get_quaternion();
+
+                        keyframes.push(Keyframe {
+                            time: key.get_time(),
+                            value: value,
+                            tangents: if cubic {
+                                Some((try_throw!(key.get_in_tangent()).get_quaternion(),
+                                      try_throw!(key.get_out_tangent()).get_quaternion()))
+                            } else { None },
+                        });
+                    }
+
+                    ChannelKeyframes::Rotation(keyframes)
+                },
+                protocol::channel::keyframes::Weights(morph_weights) => {
+                    let morph_weights = try_throw!(morph_weights);
+                    let keys = try_throw!(morph_weights.get_keyframes());
+
+                    let mut keyframes = Vec::with_capacity(keys.len() as usize);
+
+                    for key in keys.iter() {
+                        let values: Vec<f32> = try_throw!(key.get_values()).iter().collect();
+
+                        keyframes.push(Keyframe {
+                            time: key.get_time(),
+                            tangents: if cubic {
+                                Some((try_throw!(key.get_in_tangents()).iter().collect(),
+                                      try_throw!(key.get_out_tangents()).iter().collect()))
+                            } else { None },
+                            value: values,
+                        });
+                    }
+
+                    ChannelKeyframes::Weights { mesh: morph_weights.get_mesh(), keyframes: keyframes }
+                },
+            };
+
+            channels.push(Channel {
+                skeleton: channel_reader.get_skeleton(),
+                joint: channel_reader.get_joint(),
+                interpolation: interpolation,
+                keyframes: keyframes,
+            });
+        }
+
+        Ok(AnimationClip {
+            name: raw_name.to_string(),
+            duration: reader.get_duration(),
+            channels: channels,
+        })
+    }
+
+    fn save_to_builder_args(&self, mut builder: Self::Builder, _: ()) -> ProtocolResult<()> {
+        builder.set_name(self.name.as_str());
+        builder.set_duration(self.duration);
+
+        let mut channel_list_builder = builder.init_channels(self.channels.len() as u32);
+
+        for (i, channel) in self.channels.iter().enumerate() {
+            let mut channel_builder = channel_list_builder.borrow().get(i as u32);
+
+            channel_builder.set_skeleton(channel.skeleton);
+            channel_builder.set_joint(channel.joint);
+            channel_builder.set_interpolation(channel.interpolation);
+
+            let keyframes_builder = channel_builder.init_keyframes();
+
+            match channel.keyframes {
+                ChannelKeyframes::Translation(ref keys) => {
+                    save_vector_keys(keyframes_builder.init_translation(keys.len() as u32), keys);
+                },
+                ChannelKeyframes::Scale(ref keys) => {
+                    save_vector_keys(keyframes_builder.init_scale(keys.len() as u32), keys);
+                },
+                ChannelKeyframes::Rotation(ref keys) => {
+                    let mut key_list_builder = keyframes_builder.init_rotation(keys.len() as u32);
+
+                    for (j, key) in keys.iter().enumerate() {
+                        let mut key_builder = key_list_builder.borrow().get(j as u32);
+
+                        key_builder.set_time(key.time);
+
+                        { key_builder.borrow().init_value().set_quaternion(&key.value); }
+
+                        if let Some((ref in_tangent, ref out_tangent)) = key.tangents {
+                            { key_builder.borrow().init_in_tangent().set_quaternion(in_tangent); }
+                            { key_builder.borrow().init_out_tangent().set_quaternion(out_tangent); }
+                        }
+                    }
+                },
+                ChannelKeyframes::Weights { mesh, ref keyframes } => {
+                    let mut morph_weights_builder = keyframes_builder.init_weights();
+
+                    morph_weights_builder.set_mesh(mesh);
+
+                    let mut key_list_builder = morph_weights_builder.init_keyframes(keyframes.len() as u32);
+
+                    for (j, key) in keyframes.iter().enumerate() {
+                        let mut key_builder = key_list_builder.borrow().get(j as u32);
+
+                        key_builder.set_time(key.time);
+
+                        save_floats(key_builder.borrow().init_values(key.value.len() as u32), &key.value);
+
+                        if let Some((ref in_tangents, ref out_tangents)) = key.tangents {
+                            save_floats(key_builder.borrow().init_in_tangents(in_tangents.len() as u32), in_tangents);
+                            save_floats(key_builder.borrow().init_out_tangents(out_tangents.len() as u32), out_tangents);
+                        }
+                    }
+                },
+            }
+        }
+
         Ok(())
     }
 
     fn query_reader_args(_: Self::Reader, _: ()) -> ProtocolResult<()> {
         unimplemented!()
     }
-}
\ No newline at end of file
+}
+
+fn load_vector_keys(keys: ::capnp::struct_list::Reader<protocol::vector3_key::Owned>, cubic: bool) -> ProtocolResult<Vec<Keyframe<Vector3<f32>>>> {
+    let mut keyframes = Vec::with_capacity(keys.len() as usize);
+
+    for key in keys.iter() {
+        keyframes.push(Keyframe {
+            time: key.get_time(),
+            value: try_throw!(key.get_value()).get_vector(),
+            tangents: if cubic {
+                Some((try_throw!(key.get_in_tangent()).get_vector(),
+                      try_throw!(key.get_out_tangent()).get_vector()))
+            } else { None },
+        });
+    }
+
+    Ok(keyframes)
+}
+
+fn save_vector_keys(mut key_list_builder: ::capnp::struct_list::Builder<protocol::vector3_key::Owned>, keys: &[Keyframe<Vector3<f32>>]) {
+    for (i, key) in keys.iter().enumerate() {
+        let mut key_builder = key_list_builder.borrow().get(i as u32);
+
+        key_builder.set_time(key.time);
+
+        { key_builder.borrow().init_value().set_vector(&key.value); }
+
+        if let Some((ref in_tangent, ref out_tangent)) = key.tangents {
+            { key_builder.borrow().init_in_tangent().set_vector(in_tangent); }
+            { key_builder.borrow().init_out_tangent().set_vector(out_tangent); }
+        }
+    }
+}
+
+fn save_floats(mut list_builder: ::capnp::primitive_list::Builder<f32>, values: &[f32]) {
+    for (i, value) in values.iter().enumerate() {
+        list_builder.set(i as u32, *value);
+    }
+}
@@ -22,6 +22,12 @@ impl DefaultName for Scene {
     }
 }
 
+impl DefaultName for Prefab {
+    fn default_name() -> String {
+        "Untitled Prefab".to_string()
+    }
+}
+
 impl DefaultName for Light {
     fn default_name() -> String {
         "Untitled Light".to_string()
@@ -111,6 +117,20 @@ impl Default for Light {
     }
 }
 
+impl Default for Node {
+    fn default() -> Node {
+        Node {
+            name: Node::default_name(),
+            children: Vec::new(),
+            transform: Vec::new(),
+            model: None,
+            material_overrides: Vec::new(),
+            instanced: false,
+            components: Vec::new(),
+        }
+    }
+}
+
 impl Default for Material {
     fn default() -> Material {
         Material {
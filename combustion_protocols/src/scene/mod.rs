@@ -1,6 +1,7 @@
 //! Scene description structures
 
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 use nalgebra::*;
 
@@ -10,12 +11,22 @@ use common::traits::named::DefaultName;
 
 use ::math::data::Transform;
 
+pub mod protocol;
+pub mod storage;
 pub mod defaults;
+pub mod value;
 
 #[cfg(feature = "sample")]
 pub mod sample;
 
 pub use self::defaults::*;
+pub use self::value::{Value, NodeComponent, PropertyValue};
+
+/// File extension to Combustion scene files
+pub const EXTENSION: &'static str = "cscene";
+
+/// File extension to Combustion prefab files
+pub const PREFAB_EXTENSION: &'static str = "cprefab";
 
 /// Entire scene description
 #[derive(Debug, Named, Serialize, Deserialize)]
@@ -33,6 +44,16 @@ pub struct Scene {
     pub root: Node,
 }
 
+/// Reusable hierarchy of nodes and their components, which can be instantiated many times
+#[derive(Debug, Named, Serialize, Deserialize)]
+pub struct Prefab {
+    /// Name of the prefab. Will default to `"Untitled Prefab"` if one is not specified.
+    #[serde(default = "Prefab::default_name")]
+    pub name: String,
+    /// Root node of the prefab
+    pub root: Node,
+}
+
 /// A single scene node
 #[derive(Debug, Named, Serialize, Deserialize)]
 pub struct Node {
@@ -46,7 +67,46 @@ pub struct Node {
     /// Transforms to apply to node children, in applied order
     #[serde(skip_serializing_if = "Vec::is_empty")]
     #[serde(default)]
-    pub transform: Vec<Transform>
+    pub transform: Vec<Transform>,
+    /// Model to place at this node
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default = "Option::default")]
+    pub model: Option<ModelReference>,
+    /// Materials to use instead of the model's own materials for specific meshes
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub material_overrides: Vec<MaterialOverride>,
+    /// Render the model using GPU instancing, shared with every other instanced node using the same model
+    #[serde(skip_serializing_if = "is_false")]
+    #[serde(default)]
+    pub instanced: bool,
+    /// Arbitrary typed components for the engine to attach to the node
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub components: Vec<NodeComponent>,
+}
+
+#[inline(always)]
+fn is_false(value: &bool) -> bool { !*value }
+
+/// Reference to a model asset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ModelReference {
+    /// Model file path
+    #[serde(rename = "path")]
+    Path(PathBuf),
+    /// Numeric asset ID
+    #[serde(rename = "id")]
+    Id(u64),
+}
+
+/// Overrides the material of a single mesh of a model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaterialOverride {
+    /// Index of the mesh within the model
+    pub mesh: u32,
+    /// Name of the material to use instead
+    pub material: String,
 }
 
 /// Varieties of lights
@@ -25,11 +25,30 @@ pub fn sample() -> Scene {
         materials: generate_named("Untitled Material", 2).collect(),
         root: Node {
             name: "Test node".into(),
-            children: vec![],
+            children: vec![
+                Node {
+                    name: "Test model node".into(),
+                    model: Some(ModelReference::Path("models/cube.cmodel".into())),
+                    material_overrides: vec![
+                        MaterialOverride { mesh: 0, material: "Untitled Material 2".into() }
+                    ],
+                    instanced: true,
+                    components: vec![{
+                        let mut component = NodeComponent::new("turntable");
+
+                        component.properties.insert("speed".into(), Value::Float(0.5));
+                        component.properties.insert("axis".into(), Value::Vector(Vector3::new(0.0, 1.0, 0.0)));
+
+                        component
+                    }],
+                    ..Node::default()
+                }
+            ],
             transform: vec![
                 Transform::Matrix(Matrix4::new_identity(4)),
                 Transform::Translation(Vector3::new(1.2, -0.251, 0.1456))
-            ]
+            ],
+            ..Node::default()
         }
     }
 }
\ No newline at end of file
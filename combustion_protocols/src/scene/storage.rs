@@ -0,0 +1,440 @@
+//! Storage routines for scenes
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use ::error::{ProtocolResult, ProtocolError};
+
+use ::traits::Storage;
+
+use ::math::data::Transform;
+
+use super::protocol;
+use super::{Scene, Prefab, Node, Light, LightKind, Material, ModelReference, MaterialOverride, NodeComponent, Value};
+
+impl<'a> Storage<'a> for Value {
+    type Builder = protocol::value::Builder<'a>;
+    type Reader = protocol::value::Reader<'a>;
+
+    type LoadArgs = ();
+    type SaveArgs = ();
+    type Query = ();
+
+    fn load_from_reader_args(reader: Self::Reader, _: ()) -> ProtocolResult<Value> {
+        Ok(match try_throw!(reader.which()) {
+            protocol::value::Bool(value) => Value::Bool(value),
+            protocol::value::Integer(value) => Value::Integer(value),
+            protocol::value::Float(value) => Value::Float(value),
+            protocol::value::String(value) => Value::String(try_throw!(value).to_string()),
+            protocol::value::Vector(value) => Value::Vector(try_throw!(value).get_vector()),
+            protocol::value::Color(value) => Value::Color(try_throw!(value).get_color()),
+            protocol::value::List(values) => {
+                let values = try_throw!(values);
+
+                let mut list = Vec::with_capacity(values.len() as usize);
+
+                for value_reader in values.iter() {
+                    list.push(try_rethrow!(Value::load_from_reader(value_reader)));
+                }
+
+                Value::List(list)
+            },
+        })
+    }
+
+    fn save_to_builder_args(&self, mut builder: Self::Builder, _: ()) -> ProtocolResult<()> {
+        match *self {
+            Value::Bool(value) => builder.set_bool(value),
+            Value::Integer(value) => builder.set_integer(value),
+            Value::Float(value) => builder.set_float(value),
+            Value::String(ref value) => builder.set_string(value.as_str()),
+            Value::Vector(ref value) => builder.init_vector().set_vector(value),
+            Value::Color(ref value) => builder.init_color().set_color(value),
+            Value::List(ref values) => {
+                let mut list_builder = builder.init_list(values.len() as u32);
+
+                for (i, value) in values.iter().enumerate() {
+                    try_rethrow!(value.save_to_builder(list_builder.borrow().get(i as u32)));
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    fn query_reader_args(_: Self::Reader, _: ()) -> ProtocolResult<()> {
+        unimplemented!()
+    }
+}
+
+impl<'a> Storage<'a> for NodeComponent {
+    type Builder = protocol::component::Builder<'a>;
+    type Reader = protocol::component::Reader<'a>;
+
+    type LoadArgs = ();
+    type SaveArgs = ();
+    type Query = ();
+
+    fn load_from_reader_args(reader: Self::Reader, _: ()) -> ProtocolResult<NodeComponent> {
+        let raw_properties = try_throw!(reader.get_properties());
+
+        let mut properties = HashMap::with_capacity(raw_properties.len() as usize);
+
+        for property_reader in raw_properties.iter() {
+            properties.insert(try_throw!(property_reader.get_first()).to_string(),
+                              try_rethrow!(Value::load_from_reader(try_throw!(property_reader.get_second()))));
+        }
+
+        Ok(NodeComponent {
+            name: try_throw!(reader.get_name()).to_string(),
+            properties: properties,
+        })
+    }
+
+    fn save_to_builder_args(&self, mut builder: Self::Builder, _: ()) -> ProtocolResult<()> {
+        {
+            let mut property_list_builder = builder.borrow().init_properties(self.properties.len() as u32);
+
+            for (i, (key, value)) in self.properties.iter().enumerate() {
+                let mut property_builder = property_list_builder.borrow().get(i as u32);
+
+                try_throw!(property_builder.set_first(key.as_str()));
+                try_rethrow!(value.save_to_builder(property_builder.init_second()));
+            }
+        }
+
+        builder.set_name(self.name.as_str());
+
+        Ok(())
+    }
+
+    fn query_reader_args(_: Self::Reader, _: ()) -> ProtocolResult<()> {
+        unimplemented!()
+    }
+}
+
+impl<'a> Storage<'a> for ModelReference {
+    type Builder = protocol::model_reference::Builder<'a>;
+    type Reader = protocol::model_reference::Reader<'a>;
+
+    type LoadArgs = ();
+    type SaveArgs = ();
+    type Query = ();
+
+    fn load_from_reader_args(reader: Self::Reader, _: ()) -> ProtocolResult<ModelReference> {
+        Ok(match try_throw!(reader.which()) {
+            protocol::model_reference::Path(path) => ModelReference::Path(PathBuf::from(try_throw!(path))),
+            protocol::model_reference::Id(id) => ModelReference::Id(id),
+        })
+    }
+
+    fn save_to_builder_args(&self, mut builder: Self::Builder, _: ()) -> ProtocolResult<()> {
+        match *self {
+            ModelReference::Path(ref path) => {
+                let path: &Path = path.as_ref();
+
+                builder.set_path(try_throw!(path.to_str().ok_or(ProtocolError::InvalidFormat)));
+            },
+            ModelReference::Id(id) => builder.set_id(id),
+        }
+
+        Ok(())
+    }
+
+    fn query_reader_args(_: Self::Reader, _: ()) -> ProtocolResult<()> {
+        unimplemented!()
+    }
+}
+
+impl<'a> Storage<'a> for Node {
+    type Builder = protocol::node::Builder<'a>;
+    type Reader = protocol::node::Reader<'a>;
+
+    type LoadArgs = ();
+    type SaveArgs = ();
+    type Query = ();
+
+    fn load_from_reader_args(reader: Self::Reader, _: ()) -> ProtocolResult<Node> {
+        let raw_name = try_throw!(reader.get_name());
+        let raw_children = try_throw!(reader.get_children());
+        let raw_transforms = try_throw!(reader.get_transforms());
+
+        let mut children = Vec::with_capacity(raw_children.len() as usize);
+
+        for child_reader in raw_children.iter() {
+            children.push(try_rethrow!(Node::load_from_reader(child_reader)));
+        }
+
+        let mut transforms = Vec::with_capacity(raw_transforms.len() as usize);
+
+        for transform_reader in raw_transforms.iter() {
+            transforms.push(try_rethrow!(Transform::load_from_reader(transform_reader)));
+        }
+
+        let raw_overrides = try_throw!(reader.get_material_overrides());
+        let raw_components = try_throw!(reader.get_components());
+
+        let model = match try_throw!(try_throw!(reader.get_model()).which()) {
+            ::utils::protocol::option::Some(model) => Some(try_rethrow!(ModelReference::load_from_reader(try_throw!(model)))),
+            _ => None,
+        };
+
+        let mut material_overrides = Vec::with_capacity(raw_overrides.len() as usize);
+
+        for override_reader in raw_overrides.iter() {
+            material_overrides.push(MaterialOverride {
+                mesh: override_reader.get_mesh(),
+                material: try_throw!(override_reader.get_material()).to_string(),
+            });
+        }
+
+        let mut components = Vec::with_capacity(raw_components.len() as usize);
+
+        for component_reader in raw_components.iter() {
+            components.push(try_rethrow!(NodeComponent::load_from_reader(component_reader)));
+        }
+
+        Ok(Node {
+            name: raw_name.to_string(),
+            children: children,
+            transform: transforms,
+            model: model,
+            material_overrides: material_overrides,
+            instanced: reader.get_instanced(),
+            components: components,
+        })
+    }
+
+    fn save_to_builder_args(&self, mut builder: Self::Builder, _: ()) -> ProtocolResult<()> {
+        {
+            let mut children_list_builder = builder.borrow().init_children(self.children.len() as u32);
+
+            for (i, child_node) in self.children.iter().enumerate() {
+                let child_builder = children_list_builder.borrow().get(i as u32);
+
+                try_rethrow!(child_node.save_to_builder(child_builder));
+            }
+        }
+
+        {
+            let mut transform_list_builder = builder.borrow().init_transforms(self.transform.len() as u32);
+
+            for (i, transform) in self.transform.iter().enumerate() {
+                let transform_builder = transform_list_builder.borrow().get(i as u32);
+
+                try_rethrow!(transform.save_to_builder(transform_builder));
+            }
+        }
+
+        {
+            let mut model_builder = builder.borrow().init_model();
+
+            if let Some(ref model) = self.model {
+                try_rethrow!(model.save_to_builder(model_builder.init_some()));
+            } else {
+                model_builder.set_none(());
+            }
+        }
+
+        {
+            let mut override_list_builder = builder.borrow().init_material_overrides(self.material_overrides.len() as u32);
+
+            for (i, material_override) in self.material_overrides.iter().enumerate() {
+                let mut override_builder = override_list_builder.borrow().get(i as u32);
+
+                override_builder.set_mesh(material_override.mesh);
+                override_builder.set_material(material_override.material.as_str());
+            }
+        }
+
+        {
+            let mut component_list_builder = builder.borrow().init_components(self.components.len() as u32);
+
+            for (i, component) in self.components.iter().enumerate() {
+                let component_builder = component_list_builder.borrow().get(i as u32);
+
+                try_rethrow!(component.save_to_builder(component_builder));
+            }
+        }
+
+        builder.set_instanced(self.instanced);
+        builder.set_name(self.name.as_str());
+
+        Ok(())
+    }
+
+    fn query_reader_args(_: Self::Reader, _: ()) -> ProtocolResult<()> {
+        unimplemented!()
+    }
+}
+
+impl<'a> Storage<'a> for Light {
+    type Builder = protocol::light::Builder<'a>;
+    type Reader = protocol::light::Reader<'a>;
+
+    type LoadArgs = ();
+    type SaveArgs = ();
+    type Query = ();
+
+    fn load_from_reader_args(reader: Self::Reader, _: ()) -> ProtocolResult<Light> {
+        let raw_properties = try_throw!(reader.get_properties());
+
+        let mut properties = HashMap::with_capacity(raw_properties.len() as usize);
+
+        for property_reader in raw_properties.iter() {
+            properties.insert(try_throw!(property_reader.get_first()).to_string(),
+                              try_throw!(property_reader.get_second()).to_string());
+        }
+
+        let kind = match try_throw!(reader.get_kind()) {
+            protocol::LightKind::Directional => LightKind::Directional,
+            protocol::LightKind::Point => LightKind::Point,
+            protocol::LightKind::Spotlight => LightKind::Spotlight,
+        };
+
+        Ok(Light {
+            name: try_throw!(reader.get_name()).to_string(),
+            zdistance: (reader.get_z_near(), reader.get_z_far()),
+            position: try_throw!(reader.get_position()).get_point(),
+            direction: try_throw!(reader.get_direction()).get_vector(),
+            color: try_throw!(reader.get_color()).get_color(),
+            ambient: try_throw!(reader.get_ambient()).get_color(),
+            kind: kind,
+            effect_radius: reader.get_effect_radius(),
+            inner_cone: reader.get_inner_cone(),
+            outer_cone: reader.get_outer_cone(),
+            intensity: reader.get_intensity(),
+            properties: properties,
+        })
+    }
+
+    fn save_to_builder_args(&self, mut builder: Self::Builder, _: ()) -> ProtocolResult<()> {
+        {
+            let mut property_list_builder = builder.borrow().init_properties(self.properties.len() as u32);
+
+            for (i, (key, value)) in self.properties.iter().enumerate() {
+                let mut property_builder = property_list_builder.borrow().get(i as u32);
+
+                try_throw!(property_builder.set_first(key.as_str()));
+                try_throw!(property_builder.set_second(value.as_str()));
+            }
+        }
+
+        { builder.borrow().init_position().set_point(&self.position); }
+        { builder.borrow().init_direction().set_vector(&self.direction); }
+        { builder.borrow().init_color().set_color(&self.color); }
+        { builder.borrow().init_ambient().set_color(&self.ambient); }
+
+        builder.set_name(self.name.as_str());
+
+        builder.set_kind(match self.kind {
+            LightKind::Directional => protocol::LightKind::Directional,
+            LightKind::Point => protocol::LightKind::Point,
+            LightKind::Spotlight => protocol::LightKind::Spotlight,
+        });
+
+        builder.set_z_near(self.zdistance.0);
+        builder.set_z_far(self.zdistance.1);
+        builder.set_effect_radius(self.effect_radius);
+        builder.set_inner_cone(self.inner_cone);
+        builder.set_outer_cone(self.outer_cone);
+        builder.set_intensity(self.intensity);
+
+        Ok(())
+    }
+
+    fn query_reader_args(_: Self::Reader, _: ()) -> ProtocolResult<()> {
+        unimplemented!()
+    }
+}
+
+impl<'a> Storage<'a> for Scene {
+    type Builder = protocol::scene::Builder<'a>;
+    type Reader = protocol::scene::Reader<'a>;
+
+    type LoadArgs = ();
+    type SaveArgs = ();
+    type Query = ();
+
+    fn load_from_reader_args(reader: Self::Reader, _: ()) -> ProtocolResult<Scene> {
+        let raw_lights = try_throw!(reader.get_lights());
+        let raw_materials = try_throw!(reader.get_materials());
+
+        let mut lights = Vec::with_capacity(raw_lights.len() as usize);
+
+        for light_reader in raw_lights.iter() {
+            lights.push(try_rethrow!(Light::load_from_reader(light_reader)));
+        }
+
+        let mut materials = Vec::with_capacity(raw_materials.len() as usize);
+
+        for material in raw_materials.iter() {
+            materials.push(Material { name: try_throw!(material).to_string() });
+        }
+
+        Ok(Scene {
+            name: try_throw!(reader.get_name()).to_string(),
+            lights: lights,
+            materials: materials,
+            root: try_rethrow!(Node::load_from_reader(try_throw!(reader.get_root()))),
+        })
+    }
+
+    fn save_to_builder_args(&self, mut builder: Self::Builder, _: ()) -> ProtocolResult<()> {
+        {
+            let mut light_list_builder = builder.borrow().init_lights(self.lights.len() as u32);
+
+            for (i, light) in self.lights.iter().enumerate() {
+                let light_builder = light_list_builder.borrow().get(i as u32);
+
+                try_rethrow!(light.save_to_builder(light_builder));
+            }
+        }
+
+        {
+            let mut material_list_builder = builder.borrow().init_materials(self.materials.len() as u32);
+
+            for (i, material) in self.materials.iter().enumerate() {
+                material_list_builder.set(i as u32, material.name.as_str());
+            }
+        }
+
+        try_rethrow!(self.root.save_to_builder(builder.borrow().init_root()));
+
+        builder.set_name(self.name.as_str());
+
+        Ok(())
+    }
+
+    fn query_reader_args(_: Self::Reader, _: ()) -> ProtocolResult<()> {
+        unimplemented!()
+    }
+}
+
+impl<'a> Storage<'a> for Prefab {
+    type Builder = protocol::prefab::Builder<'a>;
+    type Reader = protocol::prefab::Reader<'a>;
+
+    type LoadArgs = ();
+    type SaveArgs = ();
+    type Query = ();
+
+    fn load_from_reader_args(reader: Self::Reader, _: ()) -> ProtocolResult<Prefab> {
+        Ok(Prefab {
+            name: try_throw!(reader.get_name()).to_string(),
+            root: try_rethrow!(Node::load_from_reader(try_throw!(reader.get_root()))),
+        })
+    }
+
+    fn save_to_builder_args(&self, mut builder: Self::Builder, _: ()) -> ProtocolResult<()> {
+        try_rethrow!(self.root.save_to_builder(builder.borrow().init_root()));
+
+        builder.set_name(self.name.as_str());
+
+        Ok(())
+    }
+
+    fn query_reader_args(_: Self::Reader, _: ()) -> ProtocolResult<()> {
+        unimplemented!()
+    }
+}
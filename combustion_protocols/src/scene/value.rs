@@ -0,0 +1,193 @@
+//! Typed values for arbitrary scene node components
+
+use std::collections::HashMap;
+
+use nalgebra::{Vector3, Point3};
+
+use common::color::Color;
+
+/// A single typed component property value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Value {
+    /// Boolean value
+    #[serde(rename = "bool")]
+    Bool(bool),
+    /// Integer value
+    #[serde(rename = "integer")]
+    Integer(i64),
+    /// Floating point value
+    #[serde(rename = "float")]
+    Float(f64),
+    /// String value
+    #[serde(rename = "string")]
+    String(String),
+    /// 3D Vector value
+    #[serde(rename = "vector")]
+    Vector(Vector3<f32>),
+    /// Color value
+    #[serde(rename = "color")]
+    Color(Color),
+    /// List of values, which need not all be the same type
+    #[serde(rename = "list")]
+    List(Vec<Value>),
+}
+
+/// Arbitrary named component attached to a scene node, to be interpreted by the engine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeComponent {
+    /// Name of the component kind
+    pub name: String,
+    /// Properties of the component
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    #[serde(default)]
+    pub properties: HashMap<String, Value>,
+}
+
+impl NodeComponent {
+    /// Create a new component with no properties
+    pub fn new<S: Into<String>>(name: S) -> NodeComponent {
+        NodeComponent { name: name.into(), properties: HashMap::new() }
+    }
+
+    /// Get a property value by name
+    #[inline]
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.properties.get(key)
+    }
+}
+
+/// Conversion between Rust values and component property values
+pub trait PropertyValue: Sized {
+    /// Convert into a property value
+    fn to_value(&self) -> Value;
+
+    /// Convert a property value back, if it is the right type
+    fn from_value(value: &Value) -> Option<Self>;
+}
+
+impl PropertyValue for Value {
+    #[inline]
+    fn to_value(&self) -> Value { self.clone() }
+
+    #[inline]
+    fn from_value(value: &Value) -> Option<Value> { Some(value.clone()) }
+}
+
+impl PropertyValue for bool {
+    fn to_value(&self) -> Value { Value::Bool(*self) }
+
+    fn from_value(value: &Value) -> Option<bool> {
+        match *value {
+            Value::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_integer_property_value {
+    ($($ty:ty),*) => {$(
+        impl PropertyValue for $ty {
+            fn to_value(&self) -> Value { Value::Integer(*self as i64) }
+
+            fn from_value(value: &Value) -> Option<$ty> {
+                match *value {
+                    Value::Integer(value) if value >= <$ty>::min_value() as i64 && value <= <$ty>::max_value() as i64 => Some(value as $ty),
+                    _ => None,
+                }
+            }
+        }
+    )*}
+}
+
+impl_integer_property_value!(i8, i16, i32, i64, u8, u16, u32);
+
+macro_rules! impl_unsigned_property_value {
+    ($($ty:ty),*) => {$(
+        impl PropertyValue for $ty {
+            fn to_value(&self) -> Value { Value::Integer(*self as i64) }
+
+            fn from_value(value: &Value) -> Option<$ty> {
+                match *value {
+                    Value::Integer(value) if value >= 0 => Some(value as $ty),
+                    _ => None,
+                }
+            }
+        }
+    )*}
+}
+
+impl_unsigned_property_value!(u64, usize);
+
+macro_rules! impl_float_property_value {
+    ($($ty:ty),*) => {$(
+        impl PropertyValue for $ty {
+            fn to_value(&self) -> Value { Value::Float(*self as f64) }
+
+            fn from_value(value: &Value) -> Option<$ty> {
+                match *value {
+                    Value::Float(value) => Some(value as $ty),
+                    // Integers are accepted so hand-written files don't need a decimal point
+                    Value::Integer(value) => Some(value as $ty),
+                    _ => None,
+                }
+            }
+        }
+    )*}
+}
+
+impl_float_property_value!(f32, f64);
+
+impl PropertyValue for String {
+    fn to_value(&self) -> Value { Value::String(self.clone()) }
+
+    fn from_value(value: &Value) -> Option<String> {
+        match *value {
+            Value::String(ref value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl PropertyValue for Vector3<f32> {
+    fn to_value(&self) -> Value { Value::Vector(*self) }
+
+    fn from_value(value: &Value) -> Option<Vector3<f32>> {
+        match *value {
+            Value::Vector(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl PropertyValue for Point3<f32> {
+    fn to_value(&self) -> Value { Value::Vector(self.to_vector()) }
+
+    fn from_value(value: &Value) -> Option<Point3<f32>> {
+        match *value {
+            Value::Vector(value) => Some(Point3::new(value.x, value.y, value.z)),
+            _ => None,
+        }
+    }
+}
+
+impl PropertyValue for Color {
+    fn to_value(&self) -> Value { Value::Color(*self) }
+
+    fn from_value(value: &Value) -> Option<Color> {
+        match *value {
+            Value::Color(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl<T: PropertyValue> PropertyValue for Vec<T> {
+    fn to_value(&self) -> Value { Value::List(self.iter().map(PropertyValue::to_value).collect()) }
+
+    fn from_value(value: &Value) -> Option<Vec<T>> {
+        match *value {
+            Value::List(ref values) => values.iter().map(T::from_value).collect(),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,71 @@
+extern crate combustion_common as common;
+extern crate combustion_protocols as protocols;
+extern crate nalgebra;
+
+use nalgebra::Vector3;
+
+use common::color::Color;
+use common::traits::inspect::{Inspect, InspectValue, InspectError};
+
+use protocols::material::{Material, MaterialShader, RenderMethod};
+
+#[test]
+fn inspect_material_fields() {
+    let material = Material::default();
+
+    let fields = material.fields();
+
+    assert_eq!(material.type_name(), "Material");
+    assert!(fields.iter().any(|field| field.name == "roughness" && field.type_name == "Option<f32>"));
+    assert!(fields.iter().any(|field| field.name == "color" && field.type_name == "Color"));
+    assert!(fields.iter().any(|field| field.name == "anisotropy"));
+}
+
+#[test]
+fn inspect_material_get_set() {
+    let mut material = Material::default();
+
+    assert_eq!(material.get("roughness"), Some(InspectValue::None));
+
+    material.set("roughness", InspectValue::Float(0.5)).unwrap();
+    material.set("color", InspectValue::Color(Color::new(1.0, 0.5, 0.25, 1.0))).unwrap();
+    material.set("color.g", InspectValue::Integer(0)).unwrap();
+
+    assert_eq!(material.roughness, Some(0.5));
+    assert_eq!(material.get("color.r"), Some(InspectValue::Float(1.0)));
+    assert_eq!(material.color.g, 0.0);
+
+    // Optional values can be cleared again
+    material.set("roughness", InspectValue::None).unwrap();
+
+    assert_eq!(material.roughness, None);
+
+    material.set("anisotropy.rotation", InspectValue::Vector3(Vector3::new(0.0, 1.0, 0.0))).unwrap();
+    material.set("anisotropy.rotation.x", InspectValue::Float(2.0)).unwrap();
+
+    assert_eq!(material.anisotropy.rotation, Some(Vector3::new(2.0, 1.0, 0.0)));
+}
+
+#[test]
+fn inspect_material_enums() {
+    let mut material = Material::default();
+
+    material.set("shader", InspectValue::Enum("Glass".to_string())).unwrap();
+    material.set("render", InspectValue::String("Deferred".to_string())).unwrap();
+
+    assert_eq!(material.shader, Some(MaterialShader::Glass));
+    assert_eq!(material.render, Some(RenderMethod::Deferred));
+    assert_eq!(material.get("shader"), Some(InspectValue::Enum("Glass".to_string())));
+
+    assert_eq!(material.set("shader", InspectValue::Enum("Unknown".to_string())), Err(InspectError::InvalidValue("Option")));
+}
+
+#[test]
+fn inspect_material_errors() {
+    let mut material = Material::default();
+
+    assert_eq!(material.set("missing", InspectValue::Bool(true)), Err(InspectError::UnknownField("missing".to_string())));
+    assert_eq!(material.set("color.q", InspectValue::Float(1.0)), Err(InspectError::UnknownField("q".to_string())));
+    assert!(material.set("ior", InspectValue::Bool(true)).is_err());
+    assert_eq!(material.get("color.q"), None);
+}
@@ -0,0 +1,135 @@
+extern crate combustion_common as common;
+extern crate combustion_protocols as protocols;
+extern crate serde_yaml;
+
+use std::fs::File;
+
+use common::color::Color;
+
+use protocols::error::ProtocolError;
+use protocols::material::{Material, MaterialMap, MaterialAnisotropy, MaterialShader, RenderMethod};
+use protocols::material::resolve::{smoothness_to_roughness, DEFAULT_IOR};
+
+fn material_map(materials: Vec<(&str, Material)>) -> MaterialMap {
+    let mut map = MaterialMap::default();
+
+    for (name, material) in materials {
+        map.insert(name.to_string(), material);
+    }
+
+    map
+}
+
+fn preset(name: &str) -> Option<String> {
+    Some(name.to_string())
+}
+
+#[test]
+fn resolve_inheritance_chain() {
+    let map = material_map(vec![
+        ("base", Material {
+            roughness: Some(0.8),
+            metallic: Some(1.0),
+            ior: Some(2.0),
+            anisotropy: MaterialAnisotropy::from_amount(0.5),
+            shader: Some(MaterialShader::Metal),
+            ..Material::default()
+        }),
+        ("painted", Material {
+            preset: preset("base"),
+            color: Color::new(1.0, 0.0, 0.0, 1.0),
+            metallic: Some(0.0),
+            ..Material::default()
+        }),
+        ("glossy", Material {
+            preset: preset("painted"),
+            smoothness: Some(0.9),
+            ..Material::default()
+        }),
+    ]);
+
+    let resolved = map.resolve("glossy").unwrap();
+
+    // Nearest definition wins
+    assert_eq!(resolved.metallic, 0.0);
+    assert_eq!(resolved.color, Color::new(1.0, 0.0, 0.0, 1.0));
+    assert_eq!(resolved.ior, 2.0);
+    assert_eq!(resolved.anisotropy, 0.5);
+    assert_eq!(resolved.shader, MaterialShader::Metal);
+
+    // Smoothness overrides the inherited roughness instead of being averaged with it
+    assert_eq!(resolved.roughness, smoothness_to_roughness(0.9));
+
+    assert!(map.flatten("glossy").unwrap().preset.is_none());
+}
+
+#[test]
+fn resolve_defaults() {
+    let map = material_map(vec![
+        ("empty", Material::default()),
+        ("clear", Material { translucency: Some(0.5), ..Material::default() }),
+    ]);
+
+    let resolved = map.resolve("empty").unwrap();
+
+    assert_eq!(resolved.color, Color::white());
+    assert_eq!(resolved.metallic, 0.0);
+    assert_eq!(resolved.ior, DEFAULT_IOR);
+    assert_eq!(resolved.render, RenderMethod::Deferred);
+
+    assert_eq!(map.resolve("clear").unwrap().render, RenderMethod::Forward);
+}
+
+#[test]
+fn roughness_smoothness_average() {
+    let map = material_map(vec![
+        ("both", Material { roughness: Some(0.5), smoothness: Some(0.5), ..Material::default() }),
+    ]);
+
+    assert_eq!(map.resolve("both").unwrap().roughness, (0.5 + 0.25) / 2.0);
+}
+
+#[test]
+fn resolve_errors() {
+    let map = material_map(vec![
+        ("a", Material { preset: preset("b"), ..Material::default() }),
+        ("b", Material { preset: preset("c"), ..Material::default() }),
+        ("c", Material { preset: preset("a"), ..Material::default() }),
+        ("orphan", Material { preset: preset("nowhere"), ..Material::default() }),
+    ]);
+
+    match *map.resolve("a").unwrap_err().error() {
+        ProtocolError::PresetCycle(ref chain) => assert_eq!(chain, "a -> b -> c -> a"),
+        ref err => panic!("unexpected error {:?}", err),
+    }
+
+    match *map.resolve("orphan").unwrap_err().error() {
+        ProtocolError::MissingMaterial(ref name) => assert_eq!(name, "nowhere"),
+        ref err => panic!("unexpected error {:?}", err),
+    }
+
+    match *map.resolve("missing").unwrap_err().error() {
+        ProtocolError::MissingMaterial(ref name) => assert_eq!(name, "missing"),
+        ref err => panic!("unexpected error {:?}", err),
+    }
+}
+
+#[test]
+fn resolve_yaml() {
+    let mut map: MaterialMap = serde_yaml::from_reader(File::open("tests/material.yaml").unwrap()).unwrap();
+
+    // The sample refers to a glass preset that isn't defined in the file itself
+    assert!(map.resolve("sapphire").is_err());
+
+    map.insert("glass".to_string(), Material {
+        shader: Some(MaterialShader::Glass),
+        translucency: Some(0.1),
+        ..Material::default()
+    });
+
+    let sapphire = map.resolve("sapphire").unwrap();
+
+    assert_eq!(sapphire.shader, MaterialShader::Glass);
+    assert_eq!(sapphire.ior, 1.763);
+    assert_eq!(sapphire.render, RenderMethod::Forward);
+}
@@ -0,0 +1,108 @@
+extern crate combustion_protocols as protocols;
+extern crate nalgebra;
+
+use nalgebra::{Point3, Vector3, Norm};
+
+use protocols::mesh::data::{Mesh, MeshVertices, Vertices, MorphTarget};
+use protocols::mesh::protocol::MeshPrimitive;
+use protocols::model::data::{AnimationClip, Channel, ChannelKeyframes, Keyframe, Interpolation};
+
+fn triangle() -> Mesh {
+    Mesh {
+        vertices: MeshVertices::Discrete(Vertices {
+            positions: vec![Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 0.0, 0.0), Point3::new(0.0, 1.0, 0.0)],
+            normals: Some(vec![Vector3::new(0.0, 0.0, 1.0); 3]),
+            uvs: None,
+        }),
+        indices: None,
+        materials: Vec::new(),
+        primitive: MeshPrimitive::Triangles,
+        morph_targets: vec![
+            MorphTarget {
+                name: "raise".to_string(),
+                positions: vec![Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 2.0), Vector3::new(0.0, 0.0, 0.0)],
+                normals: None,
+                tangents: None,
+            },
+            MorphTarget {
+                name: "shift".to_string(),
+                positions: vec![Vector3::new(1.0, 0.0, 0.0); 3],
+                normals: Some(vec![Vector3::new(1.0, 0.0, -1.0); 3]),
+                tangents: None,
+            },
+        ],
+        morph_weights: vec![0.5, 0.0],
+        bounds: None,
+    }
+}
+
+fn positions(mesh: &Mesh) -> Vec<Point3<f32>> {
+    match mesh.vertices {
+        MeshVertices::Discrete(ref vertices) => vertices.positions.clone(),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn morph_default_weights() {
+    let morphed = triangle().morph_default().unwrap();
+
+    assert_eq!(positions(&morphed)[1], Point3::new(1.0, 0.0, 1.0));
+    assert_eq!(positions(&morphed)[0], Point3::new(0.0, 0.0, 0.0));
+    assert!(morphed.morph_targets.is_empty());
+}
+
+#[test]
+fn morph_combined_weights_and_normals() {
+    let morphed = triangle().morph(&[1.0, 1.0]).unwrap();
+
+    assert_eq!(positions(&morphed)[1], Point3::new(2.0, 0.0, 2.0));
+    assert_eq!(positions(&morphed)[2], Point3::new(1.0, 1.0, 0.0));
+
+    if let MeshVertices::Discrete(ref vertices) = morphed.vertices {
+        let normal = vertices.normals.as_ref().unwrap()[0];
+
+        // (0, 0, 1) + (1, 0, -1) = (1, 0, 0), already unit length
+        assert!((normal - Vector3::new(1.0, 0.0, 0.0)).norm() < 1e-5);
+    }
+}
+
+#[test]
+fn morph_mismatched_target() {
+    let mut mesh = triangle();
+
+    mesh.morph_targets[0].positions.pop();
+
+    assert!(mesh.morph(&[1.0]).is_err());
+}
+
+#[test]
+fn sample_weight_channel() {
+    let clip = AnimationClip {
+        name: "Blink".to_string(),
+        duration: 1.0,
+        channels: vec![Channel {
+            skeleton: 0,
+            joint: 0,
+            interpolation: Interpolation::Linear,
+            keyframes: ChannelKeyframes::Weights {
+                mesh: 3,
+                keyframes: vec![
+                    Keyframe::new(0.0, vec![0.0, 1.0]),
+                    Keyframe::new(1.0, vec![1.0, 0.0]),
+                ],
+            },
+        }],
+    };
+
+    let mut weights = Vec::new();
+
+    // Channels for other meshes are ignored
+    clip.sample_weights(0, 0.5, &mut weights);
+
+    assert!(weights.is_empty());
+
+    clip.sample_weights(3, 0.25, &mut weights);
+
+    assert_eq!(weights, vec![0.25, 0.75]);
+}
@@ -0,0 +1,210 @@
+extern crate combustion_protocols as protocols;
+extern crate nalgebra;
+
+use nalgebra::{Point3, Vector3};
+
+use protocols::mesh::data::{Mesh, MeshVertices, Vertices, TexCoord};
+use protocols::mesh::protocol::MeshPrimitive;
+use protocols::mesh::simplify::{simplify, SimplifyOptions};
+use protocols::model::data::{Model, Node};
+use protocols::model::lod::LodSettings;
+
+/// Flat grid of `n` by `n` quads on the XZ plane
+fn grid(n: u32) -> Mesh {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+
+    for z in 0..(n + 1) {
+        for x in 0..(n + 1) {
+            positions.push(Point3::new(x as f32, 0.0, z as f32));
+            normals.push(Vector3::new(0.0, 1.0, 0.0));
+            uvs.push(TexCoord::new(x as f32 / n as f32, z as f32 / n as f32));
+        }
+    }
+
+    let mut indices = Vec::new();
+
+    for z in 0..n {
+        for x in 0..n {
+            let i = z * (n + 1) + x;
+
+            indices.extend_from_slice(&[i, i + n + 1, i + 1]);
+            indices.extend_from_slice(&[i + 1, i + n + 1, i + n + 2]);
+        }
+    }
+
+    Mesh {
+        vertices: MeshVertices::Discrete(Vertices {
+            positions: positions,
+            normals: Some(normals),
+            uvs: Some(uvs),
+        }),
+        indices: Some(indices),
+        materials: Vec::new(),
+        primitive: MeshPrimitive::Triangles,
+        morph_targets: Vec::new(),
+        morph_weights: Vec::new(),
+        bounds: None,
+    }
+}
+
+fn triangle_count(mesh: &Mesh) -> usize {
+    mesh.indices.as_ref().unwrap().len() / 3
+}
+
+#[test]
+fn simplify_grid() {
+    let mesh = grid(10);
+
+    let simplified = simplify(&mesh, &SimplifyOptions { ratio: 0.25, ..SimplifyOptions::default() }).unwrap();
+
+    // Interior collapses on a flat grid cost nothing, so at least half should go
+    assert!(triangle_count(&simplified) < triangle_count(&mesh) / 2);
+    assert!(triangle_count(&simplified) > 0);
+
+    let num_vertices = simplified.vertices.len() as u32;
+
+    assert!(simplified.indices.as_ref().unwrap().iter().all(|index| *index < num_vertices));
+
+    // Boundary vertices are locked, so the corners must survive
+    if let MeshVertices::Discrete(ref vertices) = simplified.vertices {
+        for corner in &[Point3::new(0.0, 0.0, 0.0), Point3::new(10.0, 0.0, 10.0)] {
+            assert!(vertices.positions.iter().any(|position| position == corner));
+        }
+    } else {
+        unreachable!();
+    }
+}
+
+#[test]
+fn simplify_preserves_seams() {
+    let mut mesh = grid(4);
+
+    // Split the center vertex into two with different UVs, like a UV seam would
+    if let MeshVertices::Discrete(ref mut vertices) = mesh.vertices {
+        vertices.positions.push(Point3::new(2.0, 0.0, 2.0));
+        vertices.normals.as_mut().unwrap().push(Vector3::new(0.0, 1.0, 0.0));
+        vertices.uvs.as_mut().unwrap().push(TexCoord::new(0.9, 0.9));
+    }
+
+    let seam_index = mesh.vertices.len() as u32 - 1;
+
+    // Point the last triangle touching the center vertex at the seam copy
+    for index in mesh.indices.as_mut().unwrap().iter_mut().rev() {
+        if *index == 12 {
+            *index = seam_index;
+            break;
+        }
+    }
+
+    let simplified = simplify(&mesh, &SimplifyOptions { ratio: 0.0, ..SimplifyOptions::default() }).unwrap();
+
+    if let MeshVertices::Discrete(ref vertices) = simplified.vertices {
+        let center = Point3::new(2.0, 0.0, 2.0);
+
+        assert_eq!(vertices.positions.iter().filter(|position| **position == center).count(), 2);
+    } else {
+        unreachable!();
+    }
+}
+
+#[test]
+fn generate_and_select_lods() {
+    let mut model = Model {
+        root: Node { meshes: vec![0], ..Node::default() },
+        meshes: vec![grid(8)],
+        ..Model::default()
+    };
+
+    model.generate_all_lods(&LodSettings::default_chain(), &SimplifyOptions::default()).unwrap();
+
+    assert_eq!(model.meshes.len(), 4);
+    assert_eq!(model.lods_for(0).unwrap().levels.len(), 3);
+
+    assert_eq!(model.select_lod(0, 1.0), 0);
+    assert_eq!(model.select_lod(0, 0.4), 1);
+    assert_eq!(model.select_lod(0, 0.2), 2);
+    assert_eq!(model.select_lod(0, 0.01), 3);
+
+    let mut previous = triangle_count(&model.meshes[0]);
+
+    for mesh in &model.meshes[1..] {
+        let count = triangle_count(mesh);
+
+        assert!(count <= previous);
+
+        previous = count;
+    }
+}
+
+#[test]
+fn regenerate_lods_reuses_meshes() {
+    let mut model = Model {
+        root: Node { meshes: vec![0, 1], ..Node::default() },
+        meshes: vec![grid(8), grid(4)],
+        ..Model::default()
+    };
+
+    model.generate_all_lods(&LodSettings::default_chain(), &SimplifyOptions::default()).unwrap();
+
+    assert_eq!(model.meshes.len(), 8);
+
+    model.generate_all_lods(&LodSettings::default_chain(), &SimplifyOptions::default()).unwrap();
+
+    assert_eq!(model.meshes.len(), 8);
+
+    // A shorter chain for the first mesh removes its leftover level, shifting the second chain down
+    model.generate_lods(0, &[LodSettings::new(0.5, 0.5)], &SimplifyOptions::default()).unwrap();
+
+    assert_eq!(model.meshes.len(), 6);
+    assert_eq!(model.lods_for(0).unwrap().levels.len(), 1);
+
+    let second = model.lods_for(1).unwrap();
+
+    assert_eq!(second.levels.len(), 3);
+
+    for level in &second.levels {
+        assert!((level.mesh as usize) < model.meshes.len());
+        assert!(triangle_count(&model.meshes[level.mesh as usize]) <= triangle_count(&model.meshes[1]));
+    }
+
+    // A longer chain appends the extra levels
+    model.generate_lods(0, &LodSettings::default_chain(), &SimplifyOptions::default()).unwrap();
+
+    assert_eq!(model.meshes.len(), 8);
+    assert_eq!(model.lods_for(0).unwrap().levels.len(), 3);
+}
+
+#[test]
+fn shrinking_chain_before_other_meshes() {
+    let mut model = Model {
+        root: Node { meshes: vec![0], ..Node::default() },
+        meshes: vec![grid(8)],
+        ..Model::default()
+    };
+
+    model.generate_lods(0, &LodSettings::default_chain(), &SimplifyOptions::default()).unwrap();
+
+    // The second mesh comes after the first chain, so removing its leftover levels shifts it down
+    model.meshes.push(grid(4));
+    model.root.meshes.push(4);
+
+    model.generate_all_lods(&[LodSettings::new(0.5, 0.5)], &SimplifyOptions::default()).unwrap();
+
+    assert_eq!(model.meshes.len(), 4);
+    assert_eq!(model.root.meshes, vec![0, 2]);
+
+    assert_eq!(model.lods_for(0).unwrap().levels.len(), 1);
+    assert_eq!(model.lods_for(2).unwrap().levels.len(), 1);
+    assert!(model.lods_for(4).is_none());
+
+    assert_eq!(triangle_count(&model.meshes[2]), triangle_count(&grid(4)));
+
+    for lods in &model.lods {
+        for level in &lods.levels {
+            assert!((level.mesh as usize) < model.meshes.len());
+            assert!(triangle_count(&model.meshes[level.mesh as usize]) <= triangle_count(&model.meshes[lods.mesh as usize]));
+        }
+    }
+}
@@ -0,0 +1,284 @@
+extern crate capnp;
+extern crate combustion_protocols as protocols;
+extern crate nalgebra;
+
+use capnp::message;
+
+use nalgebra::{Vector3, Point3, Quaternion, Norm};
+
+use protocols::traits::Storage;
+use protocols::math::data::{compose_trs, decompose_trs};
+use protocols::model::protocol;
+use protocols::mesh::data::Vertices;
+use protocols::model::data::{Skeleton, Joint, Skin, VertexWeights, AnimationClip, Channel, ChannelKeyframes, Keyframe, Interpolation};
+use protocols::model::animation::{Pose, skin_vertices};
+
+fn joint(name: &str, parent: Option<u32>, translation: Vector3<f32>) -> Joint {
+    Joint {
+        name: name.to_string(),
+        parent: parent,
+        inverse_bind: compose_trs(&-translation, &Quaternion::new(1.0, 0.0, 0.0, 0.0), &Vector3::new(1.0, 1.0, 1.0)),
+        translation: translation,
+        rotation: Quaternion::new(1.0, 0.0, 0.0, 0.0),
+        scale: Vector3::new(1.0, 1.0, 1.0),
+    }
+}
+
+#[test]
+fn trs_round_trip() {
+    let translation = Vector3::new(1.0, 2.0, 3.0);
+    let rotation = Quaternion::new(0.70710677, 0.0, 0.70710677, 0.0);
+    let scale = Vector3::new(2.0, 2.0, 2.0);
+
+    let (t, r, s) = decompose_trs(&compose_trs(&translation, &rotation, &scale));
+
+    assert!((t - translation).norm() < 1e-5);
+    assert!((s - scale).norm() < 1e-5);
+    assert!((r.w - rotation.w).abs() < 1e-5 && (r.j - rotation.j).abs() < 1e-5);
+}
+
+#[test]
+fn skeleton_storage_round_trip() {
+    let skeleton = Skeleton {
+        name: "Rig".to_string(),
+        joints: vec![
+            joint("hips", None, Vector3::new(0.0, 1.0, 0.0)),
+            joint("spine", Some(0), Vector3::new(0.0, 0.5, 0.0)),
+        ],
+    };
+
+    let mut message = message::Builder::new_default();
+
+    skeleton.save_to_builder(message.init_root::<protocol::skeleton::Builder>()).unwrap();
+
+    let loaded = Skeleton::load_from_reader(message.get_root_as_reader::<protocol::skeleton::Reader>().unwrap()).unwrap();
+
+    assert_eq!(loaded.name, "Rig");
+    assert_eq!(loaded.joints.len(), 2);
+    assert_eq!(loaded.joints[0].parent, None);
+    assert_eq!(loaded.joints[1].parent, Some(0));
+    assert_eq!(loaded.find_joint("spine"), Some(1));
+    assert_eq!(loaded.joints[1].inverse_bind, skeleton.joints[1].inverse_bind);
+}
+
+#[test]
+fn animation_storage_round_trip() {
+    let clip = AnimationClip {
+        name: "Walk".to_string(),
+        duration: 1.0,
+        channels: vec![
+            Channel {
+                skeleton: 0,
+                joint: 1,
+                interpolation: Interpolation::Linear,
+                keyframes: ChannelKeyframes::Translation(vec![
+                    Keyframe::new(0.0, Vector3::new(0.0, 0.0, 0.0)),
+                    Keyframe::new(1.0, Vector3::new(0.0, 1.0, 0.0)),
+                ]),
+            },
+            Channel {
+                skeleton: 0,
+                joint: 0,
+                interpolation: Interpolation::CubicSpline,
+                keyframes: ChannelKeyframes::Rotation(vec![
+                    Keyframe {
+                        time: 0.0,
+                        value: Quaternion::new(1.0, 0.0, 0.0, 0.0),
+                        tangents: Some((Quaternion::new(0.0, 0.0, 0.0, 0.0), Quaternion::new(0.0, 0.0, 0.5, 0.0))),
+                    },
+                ]),
+            },
+        ],
+    };
+
+    let mut message = message::Builder::new_default();
+
+    clip.save_to_builder(message.init_root::<protocol::animation_clip::Builder>()).unwrap();
+
+    let loaded = AnimationClip::load_from_reader(message.get_root_as_reader::<protocol::animation_clip::Reader>().unwrap()).unwrap();
+
+    assert_eq!(loaded.name, "Walk");
+    assert_eq!(loaded.channels.len(), 2);
+
+    match loaded.channels[0].keyframes {
+        ChannelKeyframes::Translation(ref keys) => {
+            assert_eq!(keys.len(), 2);
+            assert_eq!(keys[1].value, Vector3::new(0.0, 1.0, 0.0));
+            // Linear channels never carry tangents
+            assert!(keys[0].tangents.is_none());
+        }
+        _ => panic!("expected translation keyframes"),
+    }
+
+    match loaded.channels[1].keyframes {
+        ChannelKeyframes::Rotation(ref keys) => {
+            assert_eq!(keys[0].tangents.unwrap().1, Quaternion::new(0.0, 0.0, 0.5, 0.0));
+        }
+        _ => panic!("expected rotation keyframes"),
+    }
+}
+
+fn two_joint_skeleton() -> Skeleton {
+    Skeleton {
+        name: "Arm".to_string(),
+        joints: vec![
+            joint("shoulder", None, Vector3::new(0.0, 0.0, 0.0)),
+            joint("elbow", Some(0), Vector3::new(1.0, 0.0, 0.0)),
+        ],
+    }
+}
+
+fn translation_clip(joint: u32, interpolation: Interpolation, to: Vector3<f32>) -> AnimationClip {
+    AnimationClip {
+        name: "Move".to_string(),
+        duration: 1.0,
+        channels: vec![Channel {
+            skeleton: 0,
+            joint: joint,
+            interpolation: interpolation,
+            keyframes: ChannelKeyframes::Translation(vec![
+                Keyframe::new(0.0, Vector3::new(0.0, 0.0, 0.0)),
+                Keyframe::new(1.0, to),
+            ]),
+        }],
+    }
+}
+
+#[test]
+fn sample_linear_and_step() {
+    let skeleton = two_joint_skeleton();
+
+    let mut pose = Pose::rest(&skeleton);
+
+    translation_clip(0, Interpolation::Linear, Vector3::new(2.0, 0.0, 0.0)).sample(0, 0.25, &mut pose);
+
+    assert!((pose.joints[0].translation - Vector3::new(0.5, 0.0, 0.0)).norm() < 1e-5);
+    // Joints without channels keep their rest pose
+    assert_eq!(pose.joints[1].translation, Vector3::new(1.0, 0.0, 0.0));
+
+    translation_clip(0, Interpolation::Step, Vector3::new(2.0, 0.0, 0.0)).sample(0, 0.75, &mut pose);
+
+    assert_eq!(pose.joints[0].translation, Vector3::new(0.0, 0.0, 0.0));
+
+    // Past the end, the last keyframe holds
+    translation_clip(0, Interpolation::Linear, Vector3::new(2.0, 0.0, 0.0)).sample(0, 5.0, &mut pose);
+
+    assert_eq!(pose.joints[0].translation, Vector3::new(2.0, 0.0, 0.0));
+}
+
+#[test]
+fn blend_and_additive() {
+    let skeleton = two_joint_skeleton();
+
+    let mut a = Pose::rest(&skeleton);
+    let mut b = Pose::rest(&skeleton);
+
+    b.joints[0].translation = Vector3::new(4.0, 0.0, 0.0);
+
+    a.blend(&b, 0.25);
+
+    assert!((a.joints[0].translation - Vector3::new(1.0, 0.0, 0.0)).norm() < 1e-5);
+
+    let reference = Pose::rest(&skeleton);
+
+    let mut additive = Pose::rest(&skeleton);
+
+    additive.joints[1].translation = Vector3::new(1.0, 2.0, 0.0);
+
+    a.add(&additive, &reference, 0.5);
+
+    assert!((a.joints[1].translation - Vector3::new(1.0, 1.0, 0.0)).norm() < 1e-5);
+}
+
+#[test]
+fn palette_and_cpu_skinning() {
+    let skeleton = two_joint_skeleton();
+
+    let mut pose = Pose::rest(&skeleton);
+
+    // The rest pose palette is the identity, since the inverse bind matrices undo the rest pose
+    for matrix in pose.joint_palette(&skeleton) {
+        let (translation, _, scale) = decompose_trs(&matrix);
+
+        assert!(translation.norm() < 1e-5);
+        assert!((scale - Vector3::new(1.0, 1.0, 1.0)).norm() < 1e-5);
+    }
+
+    pose.joints[0].translation = Vector3::new(0.0, 3.0, 0.0);
+
+    let palette = pose.joint_palette(&skeleton);
+
+    let vertices = Vertices {
+        positions: vec![Point3::new(1.0, 0.0, 0.0), Point3::new(5.0, 0.0, 0.0)],
+        normals: None,
+        uvs: None,
+    };
+
+    let skin = Skin {
+        mesh: 0,
+        skeleton: 0,
+        weights: vec![
+            VertexWeights { joints: vec![1], weights: vec![1.0] },
+            VertexWeights::default(),
+        ],
+    };
+
+    let skinned = skin_vertices(&vertices, &skin, &palette);
+
+    // Moving the shoulder carries the elbow, and with it the weighted vertex
+    assert_eq!(skinned.positions[0], Point3::new(1.0, 3.0, 0.0));
+    // Unweighted vertices stay where they are
+    assert_eq!(skinned.positions[1], Point3::new(5.0, 0.0, 0.0));
+}
+
+#[test]
+fn sample_clamps_to_duration() {
+    let skeleton = two_joint_skeleton();
+
+    let mut pose = Pose::rest(&skeleton);
+
+    let mut clip = translation_clip(0, Interpolation::Linear, Vector3::new(2.0, 0.0, 0.0));
+
+    // A keyframe past the end of the clip is never reached
+    if let ChannelKeyframes::Translation(ref mut keys) = clip.channels[0].keyframes {
+        keys.push(Keyframe::new(2.0, Vector3::new(4.0, 0.0, 0.0)));
+    }
+
+    clip.sample(0, 5.0, &mut pose);
+
+    assert!((pose.joints[0].translation - Vector3::new(2.0, 0.0, 0.0)).norm() < 1e-5);
+
+    clip.sample(0, -1.0, &mut pose);
+
+    assert!(pose.joints[0].translation.norm() < 1e-5);
+}
+
+#[test]
+fn skinned_normals_use_inverse_transpose() {
+    let palette = vec![compose_trs(&Vector3::new(0.0, 0.0, 0.0), &Quaternion::new(1.0, 0.0, 0.0, 0.0), &Vector3::new(2.0, 1.0, 1.0))];
+
+    // Normal of the plane x + y = 0
+    let vertices = Vertices {
+        positions: vec![Point3::new(1.0, -1.0, 0.0)],
+        normals: Some(vec![Vector3::new(1.0, 1.0, 0.0) / 2.0f32.sqrt()]),
+        uvs: None,
+    };
+
+    let skin = Skin {
+        mesh: 0,
+        skeleton: 0,
+        weights: vec![VertexWeights { joints: vec![0], weights: vec![1.0] }],
+    };
+
+    let skinned = skin_vertices(&vertices, &skin, &palette);
+
+    assert_eq!(skinned.positions[0], Point3::new(2.0, -1.0, 0.0));
+
+    // Stretching along X turns the plane into x / 2 + y = 0, so the normal leans towards Y
+    let normal = skinned.normals.unwrap()[0];
+
+    assert!((normal - Vector3::new(1.0, 2.0, 0.0) / 5.0f32.sqrt()).norm() < 1e-5);
+
+    // The normal stays perpendicular to the skinned surface
+    assert!((normal.x * 2.0 - normal.y).abs() < 1e-5);
+}
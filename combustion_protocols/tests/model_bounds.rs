@@ -0,0 +1,128 @@
+extern crate combustion_protocols as protocols;
+extern crate nalgebra;
+
+use nalgebra::{Point3, Vector3, Norm};
+
+use protocols::math::data::{Transform, Aabb, Sphere};
+use protocols::mesh::data::{Mesh, MeshVertices, Vertices};
+use protocols::mesh::protocol::MeshPrimitive;
+use protocols::model::data::{Model, Node};
+
+fn unit_cube() -> Mesh {
+    let mut positions = Vec::new();
+
+    for &x in &[-1.0, 1.0] {
+        for &y in &[-1.0, 1.0] {
+            for &z in &[-1.0, 1.0] {
+                positions.push(Point3::new(x, y, z));
+            }
+        }
+    }
+
+    Mesh {
+        vertices: MeshVertices::Discrete(Vertices { positions: positions, normals: None, uvs: None }),
+        indices: None,
+        materials: Vec::new(),
+        primitive: MeshPrimitive::Points,
+        morph_targets: Vec::new(),
+        morph_weights: Vec::new(),
+        bounds: None,
+    }
+}
+
+#[test]
+fn mesh_bounds() {
+    let bounds = unit_cube().compute_bounds().unwrap();
+
+    assert_eq!(bounds.aabb, Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0)));
+    assert_eq!(bounds.sphere.center, Point3::new(0.0, 0.0, 0.0));
+    assert!((bounds.sphere.radius - 3.0f32.sqrt()).abs() < 1e-5);
+}
+
+#[test]
+fn empty_mesh_has_no_bounds() {
+    let mut mesh = unit_cube();
+
+    mesh.vertices = MeshVertices::Discrete(Vertices { positions: Vec::new(), normals: None, uvs: None });
+
+    assert!(mesh.compute_bounds().is_none());
+}
+
+#[test]
+fn sphere_union() {
+    let a = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0);
+    let b = Sphere::new(Point3::new(4.0, 0.0, 0.0), 1.0);
+
+    let union = a.union(&b);
+
+    assert_eq!(union.center, Point3::new(2.0, 0.0, 0.0));
+    assert_eq!(union.radius, 3.0);
+
+    // Contained spheres don't grow the union
+    assert_eq!(union.union(&a), union);
+}
+
+#[test]
+fn hierarchical_node_bounds() {
+    let mut model = Model {
+        meshes: vec![unit_cube()],
+        root: Node {
+            meshes: vec![0],
+            transforms: vec![Transform::Scale(Vector3::new(2.0, 2.0, 2.0))],
+            children: vec![Node {
+                meshes: vec![0],
+                // Scale first, then move along X
+                transforms: vec![
+                    Transform::Scale(Vector3::new(0.5, 0.5, 0.5)),
+                    Transform::Translation(Vector3::new(10.0, 0.0, 0.0)),
+                ],
+                ..Node::default()
+            }],
+            ..Node::default()
+        },
+        ..Model::default()
+    };
+
+    let computed = model.bounds().unwrap();
+
+    model.update_bounds();
+
+    let child = model.root.children[0].bounds.unwrap();
+
+    assert_eq!(child.aabb, Aabb::new(Point3::new(9.5, -0.5, -0.5), Point3::new(10.5, 0.5, 0.5)));
+
+    // The child bounds are in the root's local space, so the root scale applies on top
+    let root = model.root.bounds.unwrap();
+
+    assert_eq!(root.aabb, Aabb::new(Point3::new(-2.0, -2.0, -2.0), Point3::new(21.0, 2.0, 2.0)));
+    assert_eq!(model.meshes[0].bounds, unit_cube().compute_bounds());
+    assert_eq!(computed, root);
+}
+
+#[test]
+fn rotated_aabb() {
+    use std::f32::consts::FRAC_PI_2;
+
+    let aabb = Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(2.0, 1.0, 1.0));
+
+    // A quarter turn around Z swaps the X and Y extents
+    let rotated = aabb.transform(&Transform::Rotation(Vector3::new(0.0, 0.0, FRAC_PI_2)).to_matrix());
+
+    assert!((rotated.half_extents() - Vector3::new(0.5, 1.0, 0.5)).norm() < 1e-5);
+}
+
+#[test]
+fn aabb_containment_and_area() {
+    let outer = Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(2.0, 2.0, 2.0));
+    let inner = Aabb::new(Point3::new(0.5, 0.5, 0.5), Point3::new(1.0, 1.0, 1.0));
+
+    assert!(outer.contains_aabb(&inner));
+    assert!(!inner.contains_aabb(&outer));
+
+    assert_eq!(outer.surface_area(), 24.0);
+
+    let grown = inner.grow(0.5);
+
+    assert_eq!(grown, Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.5, 1.5, 1.5)));
+    assert!(outer.contains_aabb(&grown));
+}
@@ -0,0 +1,71 @@
+extern crate capnp;
+extern crate combustion_protocols as protocols;
+extern crate serde_json;
+extern crate serde_yaml;
+
+use std::fs::File;
+
+use capnp::message;
+
+use protocols::traits::Storage;
+use protocols::scene::{self, Scene, Prefab, Value, PropertyValue};
+use protocols::scene::sample::sample as sample_scene;
+use protocols::material::{self, MaterialMap};
+
+#[test]
+fn scene_round_trip() {
+    let scene = sample_scene();
+
+    let mut message = message::Builder::new_default();
+
+    scene.save_to_builder(message.init_root::<scene::protocol::scene::Builder>()).unwrap();
+
+    let loaded = Scene::load_from_reader(message.get_root_as_reader::<scene::protocol::scene::Reader>().unwrap()).unwrap();
+
+    assert_eq!(serde_json::to_value(&loaded).unwrap(), serde_json::to_value(&scene).unwrap());
+}
+
+#[test]
+fn prefab_round_trip() {
+    let prefab = Prefab { name: "Sample Prefab".to_string(), root: sample_scene().root };
+
+    let mut message = message::Builder::new_default();
+
+    prefab.save_to_builder(message.init_root::<scene::protocol::prefab::Builder>()).unwrap();
+
+    let loaded = Prefab::load_from_reader(message.get_root_as_reader::<scene::protocol::prefab::Reader>().unwrap()).unwrap();
+
+    assert_eq!(serde_json::to_value(&loaded).unwrap(), serde_json::to_value(&prefab).unwrap());
+}
+
+#[test]
+fn property_value_conversions() {
+    assert_eq!(42u32.to_value(), Value::Integer(42));
+    assert_eq!(u32::from_value(&Value::Integer(42)), Some(42));
+    assert_eq!(u32::from_value(&Value::Integer(-1)), None);
+    assert_eq!(i8::from_value(&Value::Integer(1000)), None);
+
+    assert_eq!(f32::from_value(&Value::Integer(2)), Some(2.0));
+    assert_eq!(bool::from_value(&Value::Float(1.0)), None);
+
+    let list = vec![1.5f64, 2.5];
+
+    assert_eq!(Vec::<f64>::from_value(&list.to_value()), Some(list));
+}
+
+#[test]
+fn material_map_round_trip() {
+    let map: MaterialMap = serde_yaml::from_reader(File::open("tests/material.yaml").unwrap()).unwrap();
+
+    let mut message = message::Builder::new_default();
+
+    map.save_to_builder(message.init_root::<material::protocol::material_map::Builder>()).unwrap();
+
+    let loaded = MaterialMap::load_from_reader(message.get_root_as_reader::<material::protocol::material_map::Reader>().unwrap()).unwrap();
+
+    assert_eq!(loaded.len(), map.len());
+
+    for (name, material) in map.iter() {
+        assert_eq!(serde_json::to_value(&loaded[name]).unwrap(), serde_json::to_value(material).unwrap());
+    }
+}
@@ -0,0 +1,363 @@
+//! Animator component
+//!
+//! Holds the playback state of skeletal animations for a model, and the pose sampled from them each frame.
+
+use std::sync::Arc;
+
+use ecs;
+
+use protocols::model::data::Model;
+use protocols::model::animation::Pose;
+
+/// Playback state of a single clip
+#[derive(Clone, Copy, Debug)]
+pub struct Playback {
+    /// Index of the clip within the model
+    pub clip: usize,
+    /// Current time in seconds
+    pub time: f32,
+    /// Playback rate, where `1.0` is normal speed
+    pub speed: f32,
+    /// Wrap around at the end of the clip instead of holding the last frame
+    pub looping: bool,
+}
+
+impl Playback {
+    /// Start playing a clip from the beginning at normal speed
+    pub fn new(clip: usize, looping: bool) -> Playback {
+        Playback { clip: clip, time: 0.0, speed: 1.0, looping: looping }
+    }
+
+    /// Advance the playback time by `delta` seconds, given the clip duration
+    pub fn advance(&mut self, delta: f32, duration: f32) {
+        self.time += delta * self.speed;
+
+        if duration <= 0.0 {
+            self.time = 0.0;
+        } else if self.looping {
+            self.time %= duration;
+
+            if self.time < 0.0 {
+                self.time += duration;
+            }
+        } else {
+            self.time = self.time.max(0.0).min(duration);
+        }
+    }
+}
+
+/// Cross-fade out of a previous clip
+#[derive(Clone, Copy, Debug)]
+pub struct CrossFade {
+    /// Clip being faded out of
+    pub from: Playback,
+    /// Total fade time in seconds
+    pub duration: f32,
+    /// Time elapsed since the fade began
+    pub elapsed: f32,
+}
+
+impl CrossFade {
+    /// Weight of the new clip, from `0.0` to `1.0`
+    pub fn weight(&self) -> f32 {
+        if self.duration > 0.0 { (self.elapsed / self.duration).min(1.0) } else { 1.0 }
+    }
+
+    /// Returns `true` once the previous clip is fully faded out
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+/// Additive animation layered on top of the base clip
+#[derive(Clone, Copy, Debug)]
+pub struct AdditiveLayer {
+    /// Playback of the additive clip
+    pub playback: Playback,
+    /// Strength of the layer
+    pub weight: f32,
+}
+
+pub struct Component {
+    /// Model containing the skeleton and clips
+    pub model: Arc<Model>,
+    /// Index of the animated skeleton within the model
+    pub skeleton: u32,
+    /// Base clip, if any is playing
+    pub current: Option<Playback>,
+    /// Fade out of the previous base clip
+    pub fade: Option<CrossFade>,
+    /// Additive layers, applied in order
+    pub layers: Vec<AdditiveLayer>,
+    /// Pose sampled by the animation system
+    pub pose: Pose,
+    /// Morph target weights sampled by the animation system, as `(mesh index, weights)`
+    pub morph_weights: Vec<(u32, Vec<f32>)>,
+}
+
+impl ecs::Component for Component {
+    type Storage = ecs::VecStorage<Component>;
+}
+
+impl Component {
+    /// Create a new animator for the given skeleton, starting in the rest pose
+    pub fn new(model: Arc<Model>, skeleton: u32) -> Component {
+        let pose = model.skeletons.get(skeleton as usize).map(Pose::rest).unwrap_or_default();
+
+        let morph_weights = model.meshes.iter().enumerate().filter(|&(_, mesh)| !mesh.morph_targets.is_empty()).map(|(i, mesh)| {
+            (i as u32, mesh.morph_weights.clone())
+        }).collect();
+
+        Component {
+            model: model,
+            skeleton: skeleton,
+            current: None,
+            fade: None,
+            layers: Vec::new(),
+            pose: pose,
+            morph_weights: morph_weights,
+        }
+    }
+
+    /// Get the sampled morph target weights of a mesh, if it has any
+    pub fn weights(&self, mesh: u32) -> Option<&[f32]> {
+        self.morph_weights.iter().find(|&&(index, _)| index == mesh).map(|&(_, ref weights)| weights.as_slice())
+    }
+
+    /// Find a clip index by name
+    pub fn find_clip(&self, name: &str) -> Option<usize> {
+        self.model.animations.iter().position(|clip| clip.name == name)
+    }
+
+    /// Immediately switch to playing a clip
+    pub fn play(&mut self, clip: usize, looping: bool) {
+        self.current = Some(Playback::new(clip, looping));
+        self.fade = None;
+    }
+
+    /// Cross-fade from the current clip into another one over `duration` seconds
+    pub fn cross_fade(&mut self, clip: usize, looping: bool, duration: f32) {
+        self.fade = self.current.map(|from| CrossFade { from: from, duration: duration, elapsed: 0.0 });
+        self.current = Some(Playback::new(clip, looping));
+    }
+
+    /// Add an additive layer on top of the base clip
+    pub fn add_layer(&mut self, clip: usize, weight: f32) {
+        self.layers.push(AdditiveLayer { playback: Playback::new(clip, true), weight: weight });
+    }
+
+    /// Stop all playback, leaving the last sampled pose in place
+    pub fn stop(&mut self) {
+        self.current = None;
+        self.fade = None;
+        self.layers.clear();
+    }
+    /// Advance playback by `delta` seconds and sample the active clips into the pose and morph target weights
+    pub fn update(&mut self, delta: f32) {
+        // Stopped animators keep their last pose
+        if self.current.is_none() && self.fade.is_none() && self.layers.is_empty() {
+            return;
+        }
+
+        let model = self.model.clone();
+
+        let skeleton = match model.skeletons.get(self.skeleton as usize) {
+            Some(skeleton) => skeleton,
+            None => return,
+        };
+
+        // Start from the rest pose so joints without channels don't keep stale values
+        let mut pose = Pose::rest(skeleton);
+
+        if let Some(ref mut current) = self.current {
+            if let Some(clip) = model.animations.get(current.clip) {
+                current.advance(delta, clip.duration);
+
+                clip.sample(self.skeleton, current.time, &mut pose);
+
+                for &mut (mesh, ref mut weights) in &mut self.morph_weights {
+                    clip.sample_weights(mesh, current.time, weights);
+                }
+            }
+        }
+
+        if let Some(mut fade) = self.fade.take() {
+            fade.elapsed += delta;
+
+            if !fade.is_finished() {
+                if let Some(clip) = model.animations.get(fade.from.clip) {
+                    fade.from.advance(delta, clip.duration);
+
+                    let mut from_pose = Pose::rest(skeleton);
+
+                    clip.sample(self.skeleton, fade.from.time, &mut from_pose);
+
+                    // Blend from the old pose towards the new one
+                    from_pose.blend(&pose, fade.weight());
+
+                    pose = from_pose;
+                }
+
+                self.fade = Some(fade);
+            }
+        }
+
+        if !self.layers.is_empty() {
+            let reference = Pose::rest(skeleton);
+
+            for layer in &mut self.layers {
+                if let Some(clip) = model.animations.get(layer.playback.clip) {
+                    layer.playback.advance(delta, clip.duration);
+
+                    let mut additive = reference.clone();
+
+                    clip.sample(self.skeleton, layer.playback.time, &mut additive);
+
+                    pose.add(&additive, &reference, layer.weight);
+                }
+            }
+        }
+
+        self.pose = pose;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use nalgebra::{Vector3, Quaternion, Matrix4, Eye, Norm};
+
+    use protocols::model::data::{Model, Skeleton, Joint, AnimationClip, Channel, ChannelKeyframes, Keyframe, Interpolation};
+
+    use super::*;
+
+    fn clip(name: &str, to: Vector3<f32>) -> AnimationClip {
+        AnimationClip {
+            name: name.to_string(),
+            duration: 1.0,
+            channels: vec![Channel {
+                skeleton: 0,
+                joint: 0,
+                interpolation: Interpolation::Linear,
+                keyframes: ChannelKeyframes::Translation(vec![
+                    Keyframe::new(0.0, Vector3::new(0.0, 0.0, 0.0)),
+                    Keyframe::new(1.0, to),
+                ]),
+            }],
+        }
+    }
+
+    fn model() -> Arc<Model> {
+        Arc::new(Model {
+            skeletons: vec![Skeleton {
+                name: "Root".to_string(),
+                joints: vec![Joint {
+                    name: "root".to_string(),
+                    parent: None,
+                    inverse_bind: Matrix4::new_identity(4),
+                    translation: Vector3::new(0.0, 0.0, 0.0),
+                    rotation: Quaternion::new(1.0, 0.0, 0.0, 0.0),
+                    scale: Vector3::new(1.0, 1.0, 1.0),
+                }],
+            }],
+            animations: vec![
+                clip("right", Vector3::new(4.0, 0.0, 0.0)),
+                clip("up", Vector3::new(0.0, 4.0, 0.0)),
+            ],
+            ..Model::default()
+        })
+    }
+
+    fn translation(animator: &Component) -> Vector3<f32> {
+        animator.pose.joints[0].translation
+    }
+
+    #[test]
+    fn playback_wraps_and_holds() {
+        let mut looping = Playback::new(0, true);
+
+        looping.advance(2.5, 1.0);
+
+        assert!((looping.time - 0.5).abs() < 1e-5);
+
+        looping.speed = -1.0;
+        looping.advance(1.0, 1.0);
+
+        assert!((looping.time - 0.5).abs() < 1e-5);
+
+        let mut once = Playback::new(0, false);
+
+        once.advance(2.5, 1.0);
+
+        assert_eq!(once.time, 1.0);
+    }
+
+    #[test]
+    fn play_samples_the_clip() {
+        let mut animator = Component::new(model(), 0);
+
+        assert_eq!(animator.find_clip("up"), Some(1));
+
+        // Nothing is playing, so the rest pose stays
+        animator.update(0.5);
+
+        assert_eq!(translation(&animator), Vector3::new(0.0, 0.0, 0.0));
+
+        animator.play(0, true);
+        animator.update(0.25);
+
+        assert!((translation(&animator) - Vector3::new(1.0, 0.0, 0.0)).norm() < 1e-5);
+
+        animator.update(1.0);
+
+        assert!((translation(&animator) - Vector3::new(1.0, 0.0, 0.0)).norm() < 1e-5);
+
+        // Stopping keeps the last pose
+        animator.stop();
+        animator.update(0.5);
+
+        assert!((translation(&animator) - Vector3::new(1.0, 0.0, 0.0)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn cross_fade_blends_clips() {
+        let mut animator = Component::new(model(), 0);
+
+        animator.play(0, false);
+        animator.update(0.5);
+
+        animator.cross_fade(1, false, 1.0);
+        animator.update(0.5);
+
+        // Halfway between the end of "right" and the middle of "up"
+        assert!((translation(&animator) - Vector3::new(2.0, 1.0, 0.0)).norm() < 1e-5);
+
+        animator.update(0.5);
+
+        assert!(animator.fade.is_none());
+        assert!((translation(&animator) - Vector3::new(0.0, 4.0, 0.0)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn additive_layers_stack() {
+        let mut animator = Component::new(model(), 0);
+
+        animator.play(0, false);
+        animator.add_layer(1, 0.5);
+        animator.update(0.5);
+
+        assert!((translation(&animator) - Vector3::new(2.0, 1.0, 0.0)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn missing_skeleton_is_ignored() {
+        let mut animator = Component::new(model(), 3);
+
+        animator.play(0, true);
+        animator.update(0.5);
+
+        assert!(animator.pose.joints.is_empty());
+    }
+}
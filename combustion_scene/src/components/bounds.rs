@@ -0,0 +1,30 @@
+//! Bounds component
+//!
+//! Stores the local-space bounds of an entity, and the world-space box last computed from its transform.
+
+use ecs;
+
+use protocols::math::data::{Aabb, Bounds};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Component {
+    /// Bounds in the entity's local space
+    pub local: Bounds,
+    /// World-space bounding box, updated by the spatial system
+    pub world: Option<Aabb>,
+}
+
+impl ecs::Component for Component {
+    type Storage = ecs::VecStorage<Component>;
+}
+
+impl From<Bounds> for Component {
+    #[inline(always)]
+    fn from(bounds: Bounds) -> Component { Component::new(bounds) }
+}
+
+impl Component {
+    pub fn new(local: Bounds) -> Component {
+        Component { local: local, world: None }
+    }
+}
@@ -0,0 +1,12 @@
+//! Instanced rendering component
+//!
+//! Marks model entities which are rendered with GPU instancing, shared with every other instanced entity using the same model.
+
+use ecs;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Component;
+
+impl ecs::Component for Component {
+    type Storage = ecs::NullStorage<Component>;
+}
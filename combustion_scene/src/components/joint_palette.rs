@@ -0,0 +1,163 @@
+//! Joint palette component
+//!
+//! Stores the skinning matrices produced by the skinning system for the renderer to upload,
+//! and optionally the CPU skinned vertices when GPU skinning isn't available.
+
+use nalgebra::Matrix4;
+
+use ecs;
+
+use protocols::mesh::data::Vertices;
+use protocols::model::animation::skin_vertices;
+
+use super::animator::Component as Animator;
+
+pub struct Component {
+    /// Skinning matrix for each joint of the skeleton
+    pub matrices: Vec<Matrix4<f32>>,
+    /// CPU skinned vertices for each skinned mesh of the model, as `(mesh index, vertices)`
+    pub vertices: Vec<(u32, Vertices)>,
+    /// Skin meshes on the CPU as well
+    pub cpu_skinning: bool,
+}
+
+impl ecs::Component for Component {
+    type Storage = ecs::VecStorage<Component>;
+}
+
+impl Default for Component {
+    #[inline(always)]
+    fn default() -> Component { Component::new() }
+}
+
+impl Component {
+    /// Create an empty palette that is only used for GPU skinning
+    pub fn new() -> Component {
+        Component {
+            matrices: Vec::new(),
+            vertices: Vec::new(),
+            cpu_skinning: false,
+        }
+    }
+
+    /// Create an empty palette that also skins meshes on the CPU
+    pub fn with_cpu_skinning() -> Component {
+        Component { cpu_skinning: true, ..Component::new() }
+    }
+
+    /// Get the CPU skinned vertices of a mesh, if there are any
+    pub fn skinned(&self, mesh: u32) -> Option<&Vertices> {
+        self.vertices.iter().find(|&&(index, _)| index == mesh).map(|&(_, ref vertices)| vertices)
+    }
+    /// Rebuild the skinning matrices from the pose of an animator, and skin its meshes on the CPU if requested
+    pub fn update(&mut self, animator: &Animator) {
+        let model = &animator.model;
+
+        let skeleton = match model.skeletons.get(animator.skeleton as usize) {
+            Some(skeleton) => skeleton,
+            None => return,
+        };
+
+        self.matrices = animator.pose.joint_palette(skeleton);
+
+        self.vertices.clear();
+
+        if self.cpu_skinning {
+            for skin in model.skins.iter().filter(|skin| skin.skeleton == animator.skeleton) {
+                if let Some(mesh) = model.meshes.get(skin.mesh as usize) {
+                    let vertices = skin_vertices(&mesh.vertices.to_discrete(), skin, &self.matrices);
+
+                    self.vertices.push((skin.mesh, vertices));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use nalgebra::{Vector3, Point3, Quaternion, Matrix4, Eye};
+
+    use protocols::mesh::data::{Mesh, MeshVertices, Vertices};
+    use protocols::mesh::protocol::MeshPrimitive;
+    use protocols::model::data::{Model, Skeleton, Joint, Skin, VertexWeights};
+
+    use super::*;
+
+    fn model() -> Arc<Model> {
+        let mut inverse_bind = Matrix4::new_identity(4);
+
+        inverse_bind.m24 = -1.0;
+
+        Arc::new(Model {
+            meshes: vec![Mesh {
+                vertices: MeshVertices::Discrete(Vertices {
+                    positions: vec![Point3::new(0.0, 1.0, 0.0), Point3::new(0.0, 2.0, 0.0)],
+                    normals: None,
+                    uvs: None,
+                }),
+                indices: None,
+                materials: Vec::new(),
+                primitive: MeshPrimitive::Points,
+                morph_targets: Vec::new(),
+                morph_weights: Vec::new(),
+                bounds: None,
+            }],
+            skeletons: vec![Skeleton {
+                name: "Stick".to_string(),
+                joints: vec![Joint {
+                    name: "top".to_string(),
+                    parent: None,
+                    inverse_bind: inverse_bind,
+                    translation: Vector3::new(0.0, 1.0, 0.0),
+                    rotation: Quaternion::new(1.0, 0.0, 0.0, 0.0),
+                    scale: Vector3::new(1.0, 1.0, 1.0),
+                }],
+            }],
+            skins: vec![Skin {
+                mesh: 0,
+                skeleton: 0,
+                weights: vec![
+                    VertexWeights::default(),
+                    VertexWeights { joints: vec![0], weights: vec![1.0] },
+                ],
+            }],
+            ..Model::default()
+        })
+    }
+
+    #[test]
+    fn rest_pose_palette() {
+        let animator = Animator::new(model(), 0);
+
+        let mut palette = Component::new();
+
+        palette.update(&animator);
+
+        assert_eq!(palette.matrices, vec![Matrix4::new_identity(4)]);
+        // Without CPU skinning only the matrices are produced
+        assert!(palette.skinned(0).is_none());
+    }
+
+    #[test]
+    fn cpu_skinning() {
+        let mut animator = Animator::new(model(), 0);
+
+        animator.pose.joints[0].translation = Vector3::new(3.0, 1.0, 0.0);
+
+        let mut palette = Component::with_cpu_skinning();
+
+        palette.update(&animator);
+
+        let skinned = palette.skinned(0).unwrap();
+
+        assert_eq!(skinned.positions, vec![Point3::new(0.0, 1.0, 0.0), Point3::new(3.0, 2.0, 0.0)]);
+
+        // The previous frame's vertices are replaced
+        palette.update(&animator);
+
+        assert_eq!(palette.vertices.len(), 1);
+    }
+}
@@ -0,0 +1,30 @@
+//! Entity components used by scenes
+
+use ecs::World;
+
+pub mod node;
+pub mod name;
+pub mod transform;
+pub mod model;
+pub mod instanced;
+pub mod properties;
+pub mod animator;
+pub mod joint_palette;
+pub mod bounds;
+pub mod occluder;
+pub mod prefab_instance;
+
+/// Register every scene component with the world
+pub fn register_all(world: &mut World) {
+    ecs_register_mod!(world, node);
+    ecs_register_mod!(world, name);
+    ecs_register_mod!(world, transform);
+    ecs_register_mod!(world, model);
+    ecs_register_mod!(world, instanced);
+    ecs_register_mod!(world, properties);
+    ecs_register_mod!(world, animator);
+    ecs_register_mod!(world, joint_palette);
+    ecs_register_mod!(world, bounds);
+    ecs_register_mod!(world, occluder);
+    ecs_register_mod!(world, prefab_instance);
+}
@@ -0,0 +1,25 @@
+//! Model component
+//!
+//! Places a model asset at the entity, with per-mesh material overrides.
+
+use ecs;
+
+use protocols::scene::{ModelReference, MaterialOverride};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Component {
+    /// Model asset to place at the entity
+    pub reference: ModelReference,
+    /// Materials to use instead of the model's own materials for specific meshes
+    pub material_overrides: Vec<MaterialOverride>,
+}
+
+impl ecs::Component for Component {
+    type Storage = ecs::VecStorage<Component>;
+}
+
+impl Component {
+    pub fn new(reference: ModelReference) -> Component {
+        Component { reference: reference, material_overrides: Vec::new() }
+    }
+}
@@ -0,0 +1,17 @@
+//! Human-readable name component
+
+use ecs;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Component(pub String);
+
+impl ecs::Component for Component {
+    type Storage = ecs::VecStorage<Component>;
+}
+
+impl<'a> From<&'a str> for Component {
+    #[inline(always)]
+    fn from(name: &'a str) -> Component {
+        Component(name.to_string())
+    }
+}
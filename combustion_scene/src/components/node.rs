@@ -0,0 +1,29 @@
+//! Scene graph node component
+//!
+//! Links an entity to the scene graph node it belongs to.
+
+use petgraph::graph::NodeIndex;
+
+use ecs;
+
+use ::Ix;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct Component {
+    index: NodeIndex<Ix>
+}
+
+impl ecs::Component for Component {
+    type Storage = ecs::VecStorage<Component>;
+}
+
+impl Component {
+    #[inline(always)]
+    pub fn new(index: NodeIndex<Ix>) -> Component {
+        Component { index: index }
+    }
+
+    /// Index of the entity in the scene graph
+    #[inline(always)]
+    pub fn index(&self) -> NodeIndex<Ix> { self.index }
+}
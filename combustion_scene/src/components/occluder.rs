@@ -0,0 +1,23 @@
+//! Occluder component
+//!
+//! Marks an entity as able to hide other entities behind it during occlusion culling.
+
+use ecs;
+
+use protocols::math::data::Aabb;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Component {
+    /// Box in the entity's local space which must be entirely inside its geometry
+    pub volume: Aabb,
+}
+
+impl ecs::Component for Component {
+    type Storage = ecs::VecStorage<Component>;
+}
+
+impl Component {
+    pub fn new(volume: Aabb) -> Component {
+        Component { volume: volume }
+    }
+}
@@ -0,0 +1,27 @@
+//! Prefab instance component
+//!
+//! Links an entity back to the prefab node it was spawned from, along with its per-instance overrides
+
+use ecs;
+
+use protocols::scene::NodeComponent;
+
+#[derive(Clone, Debug)]
+pub struct Component {
+    /// Name of the prefab
+    pub prefab: String,
+    /// Path of child indices from the prefab root to the node this entity was spawned from
+    pub path: Vec<usize>,
+    /// Component properties which replace the prefab values for this entity only
+    pub overrides: Vec<NodeComponent>,
+}
+
+impl ecs::Component for Component {
+    type Storage = ecs::VecStorage<Component>;
+}
+
+impl Component {
+    /// Returns `true` if the entity is the root of its prefab instance
+    #[inline]
+    pub fn is_root(&self) -> bool { self.path.is_empty() }
+}
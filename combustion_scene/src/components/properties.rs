@@ -0,0 +1,33 @@
+//! Arbitrary typed components from scene descriptions, for game systems to interpret
+
+use std::ops::{Deref, DerefMut};
+
+use ecs;
+
+use protocols::scene::NodeComponent;
+
+#[derive(Clone, Debug, Default)]
+pub struct Component(pub Vec<NodeComponent>);
+
+impl ecs::Component for Component {
+    type Storage = ecs::VecStorage<Component>;
+}
+
+impl Component {
+    /// Find a scene component by name
+    pub fn find(&self, name: &str) -> Option<&NodeComponent> {
+        self.0.iter().find(|component| component.name == name)
+    }
+}
+
+impl Deref for Component {
+    type Target = Vec<NodeComponent>;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl DerefMut for Component {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target { &mut self.0 }
+}
@@ -0,0 +1,52 @@
+//! Transform matrix component
+//!
+//! The `matrix` is local to the entity's parent in the scene graph, and `world` is the result of combining it
+//! with the world matrices of its ancestors.
+
+use nalgebra::{Matrix4, Eye};
+
+use ecs;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Component {
+    /// Transformation matrix, relative to the parent
+    pub matrix: Matrix4<f32>,
+    /// World-space transformation matrix
+    pub world: Matrix4<f32>,
+    /// Set when `matrix` has changed and `world` needs to be recomputed
+    pub dirty: bool,
+}
+
+impl ecs::Component for Component {
+    type Storage = ecs::VecStorage<Component>;
+}
+
+impl Default for Component {
+    #[inline(always)]
+    fn default() -> Component { Component::new() }
+}
+
+impl Component {
+    /// Create a new identity transform
+    #[inline]
+    pub fn new() -> Component {
+        Component::from_matrix(Matrix4::new_identity(4))
+    }
+
+    /// Create a transform from a local transformation matrix
+    pub fn from_matrix(matrix: Matrix4<f32>) -> Component {
+        Component {
+            matrix: matrix,
+            world: Matrix4::new_identity(4),
+            dirty: true,
+        }
+    }
+
+    /// Set the local transformation matrix, marking the world matrix as dirty if it changed
+    pub fn set_matrix(&mut self, matrix: Matrix4<f32>) {
+        if self.matrix != matrix {
+            self.matrix = matrix;
+            self.dirty = true;
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! Visibility of indexed entities from a camera
+//!
+//! The renderer culls once per frame while filling its render queue, testing every entity in the `Bvh`
+//! against the camera frustum and, if enabled, the occlusion buffer. Entities which aren't in the index are never culled.
+
+use fnv::FnvHashSet;
+
+use nalgebra::Matrix4;
+
+use ecs::Entity;
+
+use protocols::math::data::Aabb;
+
+use spatial::{Bvh, Frustum, OcclusionBuffer};
+
+/// Counters from the last culling pass, for profiling
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CullingStats {
+    /// Indexed entities considered
+    pub total: usize,
+    /// Entities outside the camera frustum
+    pub frustum_culled: usize,
+    /// Entities hidden behind occluders
+    pub occlusion_culled: usize,
+    /// Entities left visible
+    pub visible: usize,
+    /// Occluders rasterized into the occlusion buffer
+    pub occluders: usize,
+}
+
+/// Culling settings and the results of the last culling pass
+#[derive(Debug, Clone, Default)]
+pub struct Culling {
+    /// Occlusion buffer, or `None` to only perform frustum culling
+    pub occlusion: Option<OcclusionBuffer>,
+    /// Results of the last culling pass
+    pub stats: CullingStats,
+    culled: FnvHashSet<Entity>,
+}
+
+impl Culling {
+    /// Create a new resource which only performs frustum culling
+    pub fn new() -> Culling {
+        Culling::default()
+    }
+
+    /// Create a new resource which also performs occlusion culling at the given buffer resolution
+    pub fn with_occlusion(width: usize, height: usize) -> Culling {
+        Culling { occlusion: Some(OcclusionBuffer::new(width, height)), ..Culling::default() }
+    }
+
+    /// Check if the entity was left visible by the last culling pass
+    #[inline]
+    pub fn is_visible(&self, entity: Entity) -> bool {
+        !self.culled.contains(&entity)
+    }
+
+    /// Cull the entities in `spatial` as seen through the combined projection and view matrix of a camera.
+    ///
+    /// `occluders` gives the entity, local occluder volume and world transform of every occluder.
+    /// They are only rasterized if occlusion culling is enabled, and skipped if they are indexed but outside the frustum.
+    pub fn cull<'a, I>(&mut self, spatial: &Bvh, view_projection: &Matrix4<f32>, occluders: I)
+        where I: IntoIterator<Item = (Entity, &'a Aabb, &'a Matrix4<f32>)>
+    {
+        let frustum = Frustum::from_matrix(view_projection);
+
+        let in_frustum: FnvHashSet<Entity> = spatial.query_frustum(&frustum).into_iter().collect();
+
+        let mut stats = CullingStats::default();
+
+        self.culled.clear();
+
+        if let Some(ref mut buffer) = self.occlusion {
+            buffer.clear();
+
+            for (entity, volume, world) in occluders {
+                // Occluders outside the frustum can't hide anything inside it
+                if spatial.contains(entity) && !in_frustum.contains(&entity) {
+                    continue;
+                }
+
+                if buffer.rasterize_aabb(volume, &(*view_projection * *world)) {
+                    stats.occluders += 1;
+                }
+            }
+        }
+
+        for entity in spatial.entities() {
+            stats.total += 1;
+
+            if !in_frustum.contains(&entity) {
+                stats.frustum_culled += 1;
+                self.culled.insert(entity);
+                continue;
+            }
+
+            if let (Some(buffer), Some(aabb)) = (self.occlusion.as_ref(), spatial.get(entity)) {
+                if !buffer.test_aabb(aabb, view_projection) {
+                    stats.occlusion_culled += 1;
+                    self.culled.insert(entity);
+                    continue;
+                }
+            }
+
+            stats.visible += 1;
+        }
+
+        self.stats = stats;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Point3, Matrix4, Eye};
+
+    use ecs::{Entity, World};
+
+    use protocols::math::data::Aabb;
+
+    use ::spatial::Bvh;
+
+    use super::*;
+
+    fn cube(x: f32, y: f32, z: f32, half: f32) -> Aabb {
+        Aabb::new(Point3::new(x - half, y - half, z - half), Point3::new(x + half, y + half, z + half))
+    }
+
+    /// Index a new entity with a box of the given half size around `position`
+    fn spawn(world: &World, spatial: &mut Bvh, position: [f32; 3], half: f32) -> Entity {
+        let entity = world.create_later();
+
+        spatial.insert(entity, cube(position[0], position[1], position[2], half));
+
+        entity
+    }
+
+    #[test]
+    fn frustum_culling() {
+        let world = World::new();
+        let mut spatial = Bvh::new();
+
+        let inside = spawn(&world, &mut spatial, [0.0, 0.0, 0.0], 0.25);
+        let crossing = spawn(&world, &mut spatial, [1.0, 0.0, 0.0], 0.25);
+        let outside = spawn(&world, &mut spatial, [0.0, 3.0, 0.0], 0.25);
+
+        // Not in the index at all
+        let unbounded = world.create_later();
+
+        let mut culling = Culling::new();
+
+        // The identity matrix gives the clip space cube from -1 to 1
+        culling.cull(&spatial, &Matrix4::new_identity(4), None);
+
+        assert!(culling.is_visible(inside));
+        assert!(culling.is_visible(crossing));
+        assert!(!culling.is_visible(outside));
+        assert!(culling.is_visible(unbounded));
+
+        assert_eq!(culling.stats, CullingStats { total: 3, frustum_culled: 1, occlusion_culled: 0, visible: 2, occluders: 0 });
+
+        // Results of the previous pass are replaced
+        spatial.update(outside, cube(0.0, 0.5, 0.0, 0.25));
+
+        culling.cull(&spatial, &Matrix4::new_identity(4), None);
+
+        assert!(culling.is_visible(outside));
+        assert_eq!(culling.stats.visible, 3);
+    }
+
+    #[test]
+    fn occlusion_culling() {
+        let world = World::new();
+        let mut spatial = Bvh::new();
+
+        // Wall close to the camera, which looks down +z in clip space
+        let wall = spawn(&world, &mut spatial, [0.0, 0.0, -0.5], 0.5);
+        let wall_volume = cube(0.0, 0.0, 0.0, 0.45);
+        let mut wall_world = Matrix4::new_identity(4);
+
+        wall_world.m34 = -0.5;
+
+        let hidden = spawn(&world, &mut spatial, [0.0, 0.0, 0.5], 0.2);
+        let beside = spawn(&world, &mut spatial, [0.75, 0.0, 0.5], 0.2);
+        let partly = spawn(&world, &mut spatial, [0.45, 0.0, 0.5], 0.2);
+
+        let mut culling = Culling::with_occlusion(64, 64);
+
+        culling.cull(&spatial, &Matrix4::new_identity(4), Some((wall, &wall_volume, &wall_world)));
+
+        assert!(!culling.is_visible(hidden));
+        assert!(culling.is_visible(beside));
+        assert!(culling.is_visible(partly));
+
+        // The occluder's own bounds surround its volume, so it doesn't hide itself
+        assert!(culling.is_visible(wall));
+
+        assert_eq!(culling.stats, CullingStats { total: 4, frustum_culled: 0, occlusion_culled: 1, visible: 3, occluders: 1 });
+
+        // Moving the wall out of the frustum uncovers the hidden box
+        wall_world.m24 = 3.0;
+
+        spatial.update(wall, cube(0.0, 3.0, -0.5, 0.5));
+
+        culling.cull(&spatial, &Matrix4::new_identity(4), Some((wall, &wall_volume, &wall_world)));
+
+        assert!(culling.is_visible(hidden));
+        assert!(!culling.is_visible(wall));
+        assert_eq!(culling.stats.occluders, 0);
+    }
+}
@@ -0,0 +1,284 @@
+//! Structural differences between scene graph snapshots
+//!
+//! Each change stores the full node on both sides, so any diff can be inverted for undo/redo and
+//! checked against the snapshot it is applied to.
+
+use error::*;
+use snapshot::{NodeId, NodeSnapshot, GraphSnapshot};
+
+/// Change to a single node
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeChange {
+    /// Node was added
+    Added(NodeId, NodeSnapshot),
+    /// Node was removed
+    Removed(NodeId, NodeSnapshot),
+    /// Node was changed from the first value to the second
+    Modified(NodeId, NodeSnapshot, NodeSnapshot),
+}
+
+impl NodeChange {
+    /// ID of the changed node
+    pub fn id(&self) -> NodeId {
+        match *self {
+            NodeChange::Added(id, _) | NodeChange::Removed(id, _) | NodeChange::Modified(id, _, _) => id,
+        }
+    }
+
+    /// Create the change which undoes this one
+    pub fn invert(&self) -> NodeChange {
+        match *self {
+            NodeChange::Added(id, ref node) => NodeChange::Removed(id, node.clone()),
+            NodeChange::Removed(id, ref node) => NodeChange::Added(id, node.clone()),
+            NodeChange::Modified(id, ref before, ref after) => NodeChange::Modified(id, after.clone(), before.clone()),
+        }
+    }
+}
+
+/// Set of changes between two snapshots
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SceneDiff {
+    /// Root before and after the change, if it changed
+    pub root: Option<(NodeId, NodeId)>,
+    /// Changes to individual nodes, ordered by node ID
+    pub changes: Vec<NodeChange>,
+}
+
+impl SceneDiff {
+    /// Compute the changes needed to turn `before` into `after`
+    pub fn between(before: &GraphSnapshot, after: &GraphSnapshot) -> SceneDiff {
+        let mut changes = Vec::new();
+
+        for (&id, before_node) in &before.nodes {
+            match after.nodes.get(&id) {
+                Some(after_node) if after_node != before_node => {
+                    changes.push(NodeChange::Modified(id, before_node.clone(), after_node.clone()));
+                }
+                Some(_) => {}
+                None => changes.push(NodeChange::Removed(id, before_node.clone())),
+            }
+        }
+
+        for (&id, after_node) in &after.nodes {
+            if !before.nodes.contains_key(&id) {
+                changes.push(NodeChange::Added(id, after_node.clone()));
+            }
+        }
+
+        changes.sort_by_key(NodeChange::id);
+
+        SceneDiff {
+            root: if before.root != after.root { Some((before.root, after.root)) } else { None },
+            changes: changes,
+        }
+    }
+
+    /// Check if there are no changes
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none() && self.changes.is_empty()
+    }
+
+    /// Create the diff which undoes this one
+    pub fn invert(&self) -> SceneDiff {
+        SceneDiff {
+            root: self.root.map(|(before, after)| (after, before)),
+            changes: self.changes.iter().map(NodeChange::invert).collect(),
+        }
+    }
+
+    /// Apply the changes to a snapshot.
+    ///
+    /// Every change is checked against the snapshot before anything is modified, and `SceneError::Conflict`
+    /// is returned with the node ID if the snapshot doesn't match what the diff expects.
+    pub fn apply(&self, snapshot: &mut GraphSnapshot) -> SceneResult<()> {
+        if let Some((before, _)) = self.root {
+            if snapshot.root != before {
+                return Err(SceneError::Conflict(snapshot.root));
+            }
+        }
+
+        for change in &self.changes {
+            let matches = match *change {
+                NodeChange::Added(id, _) => !snapshot.nodes.contains_key(&id),
+                NodeChange::Removed(id, ref node) | NodeChange::Modified(id, ref node, _) => snapshot.nodes.get(&id) == Some(node),
+            };
+
+            if !matches {
+                return Err(SceneError::Conflict(change.id()));
+            }
+        }
+
+        for change in &self.changes {
+            match *change {
+                NodeChange::Added(id, ref node) | NodeChange::Modified(id, _, ref node) => {
+                    snapshot.nodes.insert(id, node.clone());
+                }
+                NodeChange::Removed(id, _) => {
+                    snapshot.nodes.remove(&id);
+                }
+            }
+        }
+
+        if let Some((_, after)) = self.root {
+            snapshot.root = after;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use protocols::scene::Value;
+
+    use error::*;
+    use edge::SceneEdge;
+    use snapshot::{NodeId, NodeSnapshot, KindSnapshot, GraphSnapshot};
+
+    use super::*;
+
+    fn node(kind: KindSnapshot, children: Vec<NodeId>) -> NodeSnapshot {
+        NodeSnapshot { kind: kind, edge: SceneEdge::new(), children: children, properties: BTreeMap::new() }
+    }
+
+    /// Root `0` with an entity child `1` and a meta child `2`
+    fn before() -> GraphSnapshot {
+        let mut nodes = BTreeMap::new();
+
+        nodes.insert(0, node(KindSnapshot::Entity(0), vec![1, 2]));
+        nodes.insert(1, node(KindSnapshot::Entity(1), vec![]));
+        nodes.insert(2, node(KindSnapshot::Meta, vec![]));
+
+        GraphSnapshot { root: 0, nodes: nodes }
+    }
+
+    /// Removes `2`, adds `3` under `1` and gives `1` a property
+    fn after() -> GraphSnapshot {
+        let mut snapshot = before();
+
+        snapshot.nodes.remove(&2);
+        snapshot.nodes.get_mut(&0).unwrap().children = vec![1];
+
+        {
+            let first = snapshot.nodes.get_mut(&1).unwrap();
+
+            first.children.push(3);
+            first.properties.insert("label".to_string(), Value::String("first".to_string()));
+        }
+
+        snapshot.nodes.insert(3, node(KindSnapshot::MultiEntity(vec![2, 3]), vec![]));
+
+        snapshot
+    }
+
+    #[test]
+    fn between_finds_every_change() {
+        let (before, after) = (before(), after());
+
+        let diff = SceneDiff::between(&before, &after);
+
+        assert_eq!(diff.root, None);
+        assert_eq!(diff.changes.iter().map(NodeChange::id).collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+
+        assert_eq!(diff.changes[0], NodeChange::Modified(0, before.nodes[&0].clone(), after.nodes[&0].clone()));
+        assert_eq!(diff.changes[1], NodeChange::Modified(1, before.nodes[&1].clone(), after.nodes[&1].clone()));
+        assert_eq!(diff.changes[2], NodeChange::Removed(2, before.nodes[&2].clone()));
+        assert_eq!(diff.changes[3], NodeChange::Added(3, after.nodes[&3].clone()));
+
+        assert!(SceneDiff::between(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn apply_and_invert() {
+        let (before, after) = (before(), after());
+
+        let diff = SceneDiff::between(&before, &after);
+
+        let mut snapshot = before.clone();
+
+        diff.apply(&mut snapshot).unwrap();
+
+        assert_eq!(snapshot, after);
+
+        // Undo
+        diff.invert().apply(&mut snapshot).unwrap();
+
+        assert_eq!(snapshot, before);
+
+        assert_eq!(diff.invert().invert(), diff);
+        assert_eq!(diff.invert(), SceneDiff::between(&after, &before));
+    }
+
+    #[test]
+    fn root_changes() {
+        let before = before();
+
+        let mut after = before.clone();
+
+        after.root = 1;
+        after.nodes.remove(&0);
+        after.nodes.get_mut(&1).unwrap().children.push(2);
+
+        let diff = SceneDiff::between(&before, &after);
+
+        assert_eq!(diff.root, Some((0, 1)));
+        assert_eq!(diff.invert().root, Some((1, 0)));
+
+        let mut snapshot = before.clone();
+
+        diff.apply(&mut snapshot).unwrap();
+
+        assert_eq!(snapshot, after);
+
+        diff.invert().apply(&mut snapshot).unwrap();
+
+        assert_eq!(snapshot, before);
+    }
+
+    fn assert_conflict(result: SceneResult<()>, expected: NodeId) {
+        match result {
+            Err(SceneError::Conflict(id)) => assert_eq!(id, expected),
+            result => panic!("expected a conflict on {}, got {:?}", expected, result),
+        }
+    }
+
+    #[test]
+    fn conflicts_leave_the_snapshot_unchanged() {
+        let diff = SceneDiff::between(&before(), &after());
+
+        // Applying twice conflicts on the first modified node
+        let mut snapshot = after();
+
+        assert_conflict(diff.apply(&mut snapshot), 0);
+        assert_eq!(snapshot, after());
+
+        // Node changed by someone else after the diff was made
+        let mut snapshot = before();
+
+        snapshot.nodes.get_mut(&2).unwrap().edge = SceneEdge::without_scale();
+
+        let changed = snapshot.clone();
+
+        assert_conflict(diff.apply(&mut snapshot), 2);
+        assert_eq!(snapshot, changed);
+
+        // Node added where the diff adds one
+        let mut snapshot = before();
+
+        snapshot.nodes.insert(3, node(KindSnapshot::Meta, vec![]));
+
+        assert_conflict(diff.apply(&mut snapshot), 3);
+
+        // Root which isn't the one the diff expects
+        let mut root_change = SceneDiff::default();
+
+        root_change.root = Some((5, 0));
+
+        let mut snapshot = before();
+
+        assert_conflict(root_change.apply(&mut snapshot), 0);
+        assert_eq!(snapshot, before());
+    }
+}
@@ -1,2 +1,37 @@
-//TODO
-pub struct SceneEdge {}
+/// Relationship between a parent and child node in the scene graph.
+///
+/// Controls which parts of the parent's world transform are applied to the child.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SceneEdge {
+    /// Inherit the parent's translation
+    pub inherit_translation: bool,
+    /// Inherit the parent's rotation
+    pub inherit_rotation: bool,
+    /// Inherit the parent's scale
+    pub inherit_scale: bool,
+}
+
+impl Default for SceneEdge {
+    #[inline(always)]
+    fn default() -> SceneEdge { SceneEdge::new() }
+}
+
+impl SceneEdge {
+    /// Create a new edge which inherits the entire parent transform
+    #[inline(always)]
+    pub fn new() -> SceneEdge {
+        SceneEdge { inherit_translation: true, inherit_rotation: true, inherit_scale: true }
+    }
+
+    /// Create a new edge which only inherits the parent's translation and rotation
+    #[inline(always)]
+    pub fn without_scale() -> SceneEdge {
+        SceneEdge { inherit_scale: false, ..SceneEdge::new() }
+    }
+
+    /// Check if the entire parent transform is inherited
+    #[inline(always)]
+    pub fn inherits_all(&self) -> bool {
+        self.inherit_translation && self.inherit_rotation && self.inherit_scale
+    }
+}
@@ -12,8 +12,11 @@ pub enum SceneError {
     WouldCycle,
     MissingChild(Entity),
     InvalidNode,
+    InvalidNodeId(u64),
     InvalidEdge,
     AlreadyExists(Entity, NodeIndex<Ix>),
+    InvalidProperty(String),
+    Conflict(u64),
 }
 
 impl Display for SceneError {
@@ -28,8 +31,11 @@ impl Error for SceneError {
             SceneError::WouldCycle => "Relationship Would Cycle",
             SceneError::MissingChild(_) => "Missing Child",
             SceneError::InvalidNode => "Invalid Node",
+            SceneError::InvalidNodeId(_) => "Invalid Node ID",
             SceneError::InvalidEdge => "Invalid Edge",
             SceneError::AlreadyExists(..) => "Entity Already Exists",
+            SceneError::InvalidProperty(_) => "Invalid Property",
+            SceneError::Conflict(_) => "Conflicting Change",
         }
     }
 }
\ No newline at end of file
@@ -1,11 +1,12 @@
 use std::ops::{Deref, DerefMut};
+use std::mem;
+use std::collections::VecDeque;
 
 use petgraph::prelude::*;
 use petgraph::visit::*;
 use petgraph::algo::*;
 
 use ecs::{Entity, World};
-use std::collections::hash_map::Entry;
 use fnv::FnvHashMap;
 
 use error::*;
@@ -30,47 +31,109 @@ impl SceneGraph {
         SceneGraph { graph: graph, cycle_state: DfsSpace::default(), root: root, entity_table: FnvHashMap::default() }
     }
 
-    #[inline(always)]
-    pub fn root(&self) -> NodeIndex<Ix> { self.root }
+    /// Move the graph out, leaving behind an empty graph with a meta node as its root.
+    ///
+    /// Lets a graph stored as a world resource be modified alongside a mutable borrow of the world.
+    pub fn take(&mut self) -> SceneGraph {
+        let mut graph = StableDiGraph::default();
 
-    /// Adds a new scene node to the graph with the given parent, and returns the new node's index
-    pub fn add_child(&mut self, parent: NodeIndex<Ix>, node: SceneNode) -> SceneResult<NodeIndex<Ix>> {
-        let child_node = self.graph.add_node(node);
+        let root = graph.add_node(SceneNode::new_meta_node());
+
+        mem::replace(self, SceneGraph { graph: graph, cycle_state: DfsSpace::default(), root: root, entity_table: FnvHashMap::default() })
+    }
 
-        {
-            // Get a reference to the node we literally just added...
-            let node = self.graph.node_weight(child_node).unwrap();
+    /// Build a graph from nodes at specific indices and the parent-child edges between them.
+    ///
+    /// Node `i` of `nodes` will have index `i` in the new graph, so indices from a previous graph stay valid.
+    pub fn from_indexed(root: NodeIndex<Ix>, nodes: Vec<Option<SceneNode>>, edges: Vec<(NodeIndex<Ix>, NodeIndex<Ix>, SceneEdge)>) -> SceneResult<SceneGraph> {
+        let mut graph = StableDiGraph::default();
+        let mut entity_table = FnvHashMap::default();
+        let mut placeholders = Vec::new();
 
-            match node.kind() {
-                &SceneNodeKind::EntityNode(ref node) => {
-                    let entity = node.entity();
+        for node in nodes {
+            match node {
+                Some(node) => {
+                    let entities = node.entities();
+                    let index = graph.add_node(node);
 
-                    match self.entity_table.entry(entity) {
-                        Entry::Occupied(entry) => {
-                            return Err(SceneError::AlreadyExists(entity, entry.get().clone()));
-                        }
-                        Entry::Vacant(entry) => {
-                            entry.insert(child_node);
+                    for entity in entities {
+                        if let Some(existing) = entity_table.insert(entity, index) {
+                            return Err(SceneError::AlreadyExists(entity, existing));
                         }
                     }
                 }
-                &SceneNodeKind::MultiEntityNode(ref node) => {
-                    for entity in node.iter().cloned() {
-                        match self.entity_table.entry(entity) {
-                            Entry::Occupied(entry) => {
-                                return Err(SceneError::AlreadyExists(entity, entry.get().clone()));
-                            }
-                            Entry::Vacant(entry) => {
-                                entry.insert(child_node);
-                            }
-                        }
-                    }
+                None => {
+                    placeholders.push(graph.add_node(SceneNode::new_meta_node()));
                 }
-                _ => {}
             }
         }
 
-        self.graph.add_edge(parent, child_node, SceneEdge {});
+        // Removing placeholders only after adding everything keeps the other indices in place
+        for placeholder in placeholders {
+            graph.remove_node(placeholder);
+        }
+
+        if graph.node_weight(root).is_none() {
+            return Err(SceneError::InvalidNode);
+        }
+
+        // The root isn't part of the lookup table
+        for entity in graph[root].entities() {
+            entity_table.remove(&entity);
+        }
+
+        let mut scene_graph = SceneGraph { graph: graph, cycle_state: DfsSpace::default(), root: root, entity_table: entity_table };
+
+        for (parent, child, edge) in edges {
+            if scene_graph.graph.node_weight(parent).is_none() || scene_graph.graph.node_weight(child).is_none() {
+                return Err(SceneError::InvalidNode);
+            }
+
+            if child == root || scene_graph.parent(child).is_some() {
+                return Err(SceneError::InvalidEdge);
+            }
+
+            if has_path_connecting(&scene_graph.graph, child, parent, Some(&mut scene_graph.cycle_state)) {
+                return Err(SceneError::WouldCycle);
+            }
+
+            scene_graph.graph.add_edge(parent, child, edge);
+        }
+
+        Ok(scene_graph)
+    }
+
+    #[inline(always)]
+    pub fn root(&self) -> NodeIndex<Ix> { self.root }
+
+    /// Adds a new scene node to the graph with the given parent, and returns the new node's index
+    #[inline]
+    pub fn add_child(&mut self, parent: NodeIndex<Ix>, node: SceneNode) -> SceneResult<NodeIndex<Ix>> {
+        self.add_child_with_edge(parent, node, SceneEdge::default())
+    }
+
+    /// Adds a new scene node to the graph with the given parent and relationship, and returns the new node's index
+    pub fn add_child_with_edge(&mut self, parent: NodeIndex<Ix>, node: SceneNode, edge: SceneEdge) -> SceneResult<NodeIndex<Ix>> {
+        if self.graph.node_weight(parent).is_none() {
+            return Err(SceneError::InvalidNode);
+        }
+
+        let entities = node.entities();
+
+        // Check every entity before touching the graph, so a failure leaves it unchanged
+        for entity in &entities {
+            if let Some(existing) = self.entity_table.get(entity) {
+                return Err(SceneError::AlreadyExists(*entity, *existing));
+            }
+        }
+
+        let child_node = self.graph.add_node(node);
+
+        for entity in entities {
+            self.entity_table.insert(entity, child_node);
+        }
+
+        self.graph.add_edge(parent, child_node, edge);
 
         Ok(child_node)
     }
@@ -99,16 +162,157 @@ impl SceneGraph {
         self.graph.node_weight(index)
     }
 
+    /// Find the node value from the given index, for editing properties.
+    ///
+    /// To change which entities a node refers to, use `add_entity`, `remove_entity` or `replace_kind`
+    /// so the entity lookup table stays up to date.
+    ///
+    /// This operation is `O(1)`
+    #[inline]
+    pub fn lookup_node_mut(&mut self, index: NodeIndex<Ix>) -> Option<&mut SceneNode> {
+        self.graph.node_weight_mut(index)
+    }
+
+    /// Add an entity to a `MultiEntityNode`
+    pub fn add_entity(&mut self, node: NodeIndex<Ix>, entity: Entity) -> SceneResult<()> {
+        if let Some(existing) = self.entity_table.get(&entity) {
+            return Err(SceneError::AlreadyExists(entity, *existing));
+        }
+
+        match self.graph.node_weight_mut(node).map(SceneNode::kind_mut) {
+            Some(&mut SceneNodeKind::MultiEntityNode(ref mut multi)) => multi.add(entity),
+            _ => return Err(SceneError::InvalidNode),
+        }
+
+        self.entity_table.insert(entity, node);
+
+        Ok(())
+    }
+
+    /// Remove an entity from a `MultiEntityNode`.
+    ///
+    /// Returns `SceneError::MissingChild` if the entity is not part of the node.
+    pub fn remove_entity(&mut self, node: NodeIndex<Ix>, entity: Entity) -> SceneResult<()> {
+        match self.graph.node_weight_mut(node).map(SceneNode::kind_mut) {
+            Some(&mut SceneNodeKind::MultiEntityNode(ref mut multi)) => {
+                if !multi.remove(entity) {
+                    return Err(SceneError::MissingChild(entity));
+                }
+            }
+            _ => return Err(SceneError::InvalidNode),
+        }
+
+        self.entity_table.remove(&entity);
+
+        Ok(())
+    }
+
+    /// Replace the kind of a node, such as turning a `MetaNode` into an `EntityNode`, and return the previous kind.
+    ///
+    /// Properties on the node are kept.
+    pub fn replace_kind(&mut self, node: NodeIndex<Ix>, kind: SceneNodeKind) -> SceneResult<SceneNodeKind> {
+        let new_entities = kind.entities();
+
+        let old_entities = match self.graph.node_weight(node) {
+            Some(existing) => existing.entities(),
+            None => return Err(SceneError::InvalidNode),
+        };
+
+        for entity in &new_entities {
+            if let Some(existing) = self.entity_table.get(entity) {
+                if *existing != node {
+                    return Err(SceneError::AlreadyExists(*entity, *existing));
+                }
+            }
+        }
+
+        for entity in &old_entities {
+            self.entity_table.remove(entity);
+        }
+
+        for entity in new_entities {
+            self.entity_table.insert(entity, node);
+        }
+
+        let old_kind = ::std::mem::replace(self.graph.node_weight_mut(node).unwrap().kind_mut(), kind);
+
+        Ok(old_kind)
+    }
+
+    /// Get the relationship between a node and its parent
+    pub fn parent_edge(&self, node: NodeIndex<Ix>) -> Option<&SceneEdge> {
+        self.parent(node).and_then(|parent| self.graph.find_edge(parent, node)).and_then(|edge| self.graph.edge_weight(edge))
+    }
+
+    /// Get the relationship between a node and its parent for editing
+    pub fn parent_edge_mut(&mut self, node: NodeIndex<Ix>) -> Option<&mut SceneEdge> {
+        match self.parent(node).and_then(|parent| self.graph.find_edge(parent, node)) {
+            Some(edge) => self.graph.edge_weight_mut(edge),
+            None => None,
+        }
+    }
+
+    /// Find the parent of a node, which is `None` for the root or nodes not in the graph.
+    ///
+    /// This operation is `O(e')`
+    pub fn parent(&self, node: NodeIndex<Ix>) -> Option<NodeIndex<Ix>> {
+        self.graph.neighbors_directed(node, Incoming).next()
+    }
+
+    /// Get the direct children of a node, in the order they were added.
+    pub fn children(&self, node: NodeIndex<Ix>) -> Vec<NodeIndex<Ix>> {
+        let mut children: Vec<_> = self.graph.neighbors_directed(node, Outgoing).collect();
+
+        // Neighbors are iterated most recently added first
+        children.reverse();
+
+        children
+    }
+
+    /// Iterate over the ancestors of a node, starting at its parent and ending with the root.
+    #[inline]
+    pub fn ancestors(&self, node: NodeIndex<Ix>) -> Ancestors {
+        Ancestors { graph: self, current: self.parent(node) }
+    }
+
+    /// Check if `ancestor` is somewhere above `node` in the hierarchy
+    pub fn is_ancestor(&self, ancestor: NodeIndex<Ix>, node: NodeIndex<Ix>) -> bool {
+        self.ancestors(node).any(|index| index == ancestor)
+    }
+
+    /// Iterate over a subtree in depth-first pre-order, starting with `start` itself
+    #[inline]
+    pub fn depth_first(&self, start: NodeIndex<Ix>) -> DepthFirst {
+        DepthFirst { graph: self, stack: if self.graph.node_weight(start).is_some() { vec![start] } else { Vec::new() } }
+    }
+
+    /// Iterate over a subtree in breadth-first order, starting with `start` itself
+    #[inline]
+    pub fn breadth_first(&self, start: NodeIndex<Ix>) -> BreadthFirst {
+        let mut queue = VecDeque::new();
+
+        if self.graph.node_weight(start).is_some() {
+            queue.push_back(start);
+        }
+
+        BreadthFirst { graph: self, queue: queue }
+    }
+
     /// This operation is `O(e' + e')` for the two edge lookups.
     pub fn reparent(&mut self, child: NodeIndex<Ix>, old_parent: NodeIndex<Ix>, new_parent: NodeIndex<Ix>) -> SceneResult<()> {
-        if has_path_connecting(&self.graph, new_parent, child, Some(&mut self.cycle_state)) {
+        if self.graph.node_weight(new_parent).is_none() {
+            return Err(SceneError::InvalidNode);
+        }
+
+        if has_path_connecting(&self.graph, child, new_parent, Some(&mut self.cycle_state)) {
             return Err(SceneError::WouldCycle);
         }
 
         if let Some(edge) = self.graph.find_edge(old_parent, child) {
-            self.graph.remove_edge(edge);
+            // Keep the existing relationship when moving the child
+            let edge = self.graph.remove_edge(edge).unwrap_or_default();
 
-            self.graph.add_edge(new_parent, child, SceneEdge {});
+            self.graph.add_edge(new_parent, child, edge);
 
             Ok(())
         } else {
@@ -116,12 +320,142 @@ impl SceneGraph {
         }
     }
 
-    pub fn recursive_remove(&mut self, _node: NodeIndex<Ix>) -> SceneResult<()> {
-        //TODO
+    /// Move a node and its subtree under a new parent
+    pub fn move_subtree(&mut self, node: NodeIndex<Ix>, new_parent: NodeIndex<Ix>) -> SceneResult<()> {
+        match self.parent(node) {
+            Some(old_parent) => self.reparent(node, old_parent, new_parent),
+            None => Err(SceneError::InvalidNode),
+        }
+    }
+
+    /// Copy a node and its subtree under a new parent, returning the index of the copy.
+    ///
+    /// Entities cannot be shared between nodes, so `clone_entity` is used to create the entities of the copied nodes.
+    /// Node properties are not copied.
+    pub fn clone_subtree<F>(&mut self, node: NodeIndex<Ix>, new_parent: NodeIndex<Ix>, mut clone_entity: F) -> SceneResult<NodeIndex<Ix>>
+        where F: FnMut(Entity) -> Entity {
+        if self.is_ancestor(node, new_parent) || node == new_parent {
+            return Err(SceneError::WouldCycle);
+        }
+
+        let edge = self.parent_edge(node).cloned().unwrap_or_default();
+
+        self.clone_subtree_impl(node, new_parent, edge, &mut clone_entity)
+    }
+
+    fn clone_subtree_impl(&mut self, node: NodeIndex<Ix>, new_parent: NodeIndex<Ix>, edge: SceneEdge,
+                          clone_entity: &mut FnMut(Entity) -> Entity) -> SceneResult<NodeIndex<Ix>> {
+        let copy = match self.graph.node_weight(node) {
+            Some(existing) => existing.clone_with(|entity| clone_entity(entity)),
+            None => return Err(SceneError::InvalidNode),
+        };
+
+        let copy = try!(self.add_child_with_edge(new_parent, copy, edge));
+
+        for child in self.children(node) {
+            let child_edge = self.parent_edge(child).cloned().unwrap_or_default();
+
+            try!(self.clone_subtree_impl(child, copy, child_edge, clone_entity));
+        }
+
+        Ok(copy)
+    }
+
+    /// Remove a node and its entire subtree from the graph, returning every entity that was referred to.
+    ///
+    /// The root cannot be removed.
+    pub fn recursive_remove(&mut self, node: NodeIndex<Ix>) -> SceneResult<Vec<Entity>> {
+        if node == self.root || self.graph.node_weight(node).is_none() {
+            return Err(SceneError::InvalidNode);
+        }
+
+        let subtree: Vec<_> = self.depth_first(node).collect();
+
+        let mut entities = Vec::new();
+
+        for index in subtree {
+            if let Some(removed) = self.graph.remove_node(index) {
+                for entity in removed.entities() {
+                    self.entity_table.remove(&entity);
+
+                    entities.push(entity);
+                }
+            }
+        }
+
+        Ok(entities)
+    }
+
+    /// Remove a node and its entire subtree from the graph, and delete all of their entities from the world
+    pub fn recursive_remove_and_delete(&mut self, node: NodeIndex<Ix>, world: &World) -> SceneResult<()> {
+        for entity in try!(self.recursive_remove(node)) {
+            world.delete_later(entity);
+        }
+
         Ok(())
     }
 }
 
+/// Iterator over the ancestors of a node
+pub struct Ancestors<'a> {
+    graph: &'a SceneGraph,
+    current: Option<NodeIndex<Ix>>,
+}
+
+impl<'a> Iterator for Ancestors<'a> {
+    type Item = NodeIndex<Ix>;
+
+    fn next(&mut self) -> Option<NodeIndex<Ix>> {
+        let graph = self.graph;
+        let current = self.current;
+
+        self.current = current.and_then(|index| graph.parent(index));
+
+        current
+    }
+}
+
+/// Depth-first pre-order iterator over a subtree
+pub struct DepthFirst<'a> {
+    graph: &'a SceneGraph,
+    stack: Vec<NodeIndex<Ix>>,
+}
+
+impl<'a> Iterator for DepthFirst<'a> {
+    type Item = NodeIndex<Ix>;
+
+    fn next(&mut self) -> Option<NodeIndex<Ix>> {
+        let graph = self.graph;
+
+        self.stack.pop().map(|index| {
+            // Neighbors come most recently added first, so pushing them in that order visits the first child next
+            self.stack.extend(graph.graph.neighbors_directed(index, Outgoing));
+
+            index
+        })
+    }
+}
+
+/// Breadth-first iterator over a subtree
+pub struct BreadthFirst<'a> {
+    graph: &'a SceneGraph,
+    queue: VecDeque<NodeIndex<Ix>>,
+}
+
+impl<'a> Iterator for BreadthFirst<'a> {
+    type Item = NodeIndex<Ix>;
+
+    fn next(&mut self) -> Option<NodeIndex<Ix>> {
+        let graph = self.graph;
+
+        self.queue.pop_front().map(|index| {
+            self.queue.extend(graph.children(index));
+
+            index
+        })
+    }
+}
+
 impl Deref for SceneGraph {
     type Target = StableDiGraph<SceneNode, SceneEdge, Ix>;
 
@@ -132,4 +466,326 @@ impl Deref for SceneGraph {
 impl DerefMut for SceneGraph {
     #[inline(always)]
     fn deref_mut(&mut self) -> &mut Self::Target { &mut self.graph }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use ecs::World;
+
+    use error::*;
+    use node::*;
+    use edge::*;
+
+    use super::*;
+
+    fn entity_node(world: &World) -> SceneNode {
+        SceneNode::new_entity_node(world.create_later())
+    }
+
+    /// Builds:
+    ///
+    /// ```text
+    /// root
+    /// ├── a
+    /// │   ├── c
+    /// │   │   └── e
+    /// │   └── d
+    /// └── b
+    /// ```
+    fn tree(world: &World) -> (SceneGraph, Vec<NodeIndex<Ix>>) {
+        let mut graph = SceneGraph::new(world);
+
+        let root = graph.root();
+
+        let a = graph.add_child(root, entity_node(world)).unwrap();
+        let b = graph.add_child(root, entity_node(world)).unwrap();
+        let c = graph.add_child(a, entity_node(world)).unwrap();
+        let d = graph.add_child(a, entity_node(world)).unwrap();
+        let e = graph.add_child(c, entity_node(world)).unwrap();
+
+        (graph, vec![root, a, b, c, d, e])
+    }
+
+    fn entity(graph: &SceneGraph, node: NodeIndex<Ix>) -> Entity {
+        graph.lookup_node(node).unwrap().entities()[0]
+    }
+
+    #[test]
+    fn children_keep_insertion_order() {
+        let world = World::new();
+        let (graph, nodes) = tree(&world);
+
+        assert_eq!(graph.children(nodes[0]), vec![nodes[1], nodes[2]]);
+        assert_eq!(graph.children(nodes[1]), vec![nodes[3], nodes[4]]);
+        assert!(graph.children(nodes[5]).is_empty());
+    }
+
+    #[test]
+    fn ancestors() {
+        let world = World::new();
+        let (graph, nodes) = tree(&world);
+
+        assert_eq!(graph.ancestors(nodes[5]).collect::<Vec<_>>(), vec![nodes[3], nodes[1], nodes[0]]);
+        assert_eq!(graph.ancestors(nodes[0]).count(), 0);
+
+        assert!(graph.is_ancestor(nodes[1], nodes[5]));
+        assert!(graph.is_ancestor(nodes[0], nodes[2]));
+        assert!(!graph.is_ancestor(nodes[2], nodes[5]));
+        assert!(!graph.is_ancestor(nodes[5], nodes[5]));
+    }
+
+    #[test]
+    fn traversal_order() {
+        let world = World::new();
+        let (graph, nodes) = tree(&world);
+
+        let (root, a, b, c, d, e) = (nodes[0], nodes[1], nodes[2], nodes[3], nodes[4], nodes[5]);
+
+        assert_eq!(graph.depth_first(root).collect::<Vec<_>>(), vec![root, a, c, e, d, b]);
+        assert_eq!(graph.breadth_first(root).collect::<Vec<_>>(), vec![root, a, b, c, d, e]);
+
+        // Subtrees only visit their own nodes
+        assert_eq!(graph.depth_first(a).collect::<Vec<_>>(), vec![a, c, e, d]);
+        assert_eq!(graph.breadth_first(c).collect::<Vec<_>>(), vec![c, e]);
+    }
+
+    #[test]
+    fn traversal_of_missing_node_is_empty() {
+        let world = World::new();
+        let (mut graph, nodes) = tree(&world);
+
+        graph.recursive_remove(nodes[3]).unwrap();
+
+        assert_eq!(graph.depth_first(nodes[3]).count(), 0);
+        assert_eq!(graph.breadth_first(nodes[5]).count(), 0);
+    }
+
+    #[test]
+    fn recursive_remove() {
+        let world = World::new();
+        let (mut graph, nodes) = tree(&world);
+
+        let removed_entities = vec![entity(&graph, nodes[1]), entity(&graph, nodes[3]), entity(&graph, nodes[5]), entity(&graph, nodes[4])];
+
+        let b_entity = entity(&graph, nodes[2]);
+
+        assert_eq!(graph.recursive_remove(nodes[1]).unwrap(), removed_entities);
+
+        for removed in &[nodes[1], nodes[3], nodes[4], nodes[5]] {
+            assert!(graph.lookup_node(*removed).is_none());
+        }
+
+        for removed in &removed_entities {
+            assert!(graph.lookup_index(*removed).is_none());
+        }
+
+        assert_eq!(graph.lookup_index(b_entity), Some(&nodes[2]));
+        assert_eq!(graph.children(nodes[0]), vec![nodes[2]]);
+
+        // Removing it again fails, as does removing the root
+        assert!(graph.recursive_remove(nodes[1]).is_err());
+        assert!(graph.recursive_remove(nodes[0]).is_err());
+    }
+
+    #[test]
+    fn recursive_remove_and_delete() {
+        let mut world = World::new();
+
+        let mut graph = SceneGraph::new(&world);
+
+        let root = graph.root();
+
+        let parent = world.create_now().build();
+        let child = world.create_now().build();
+        let other = world.create_now().build();
+
+        let parent_index = graph.add_child(root, SceneNode::new_entity_node(parent)).unwrap();
+        graph.add_child(parent_index, SceneNode::new_multi_entity_node(Some(vec![child]))).unwrap();
+        graph.add_child(root, SceneNode::new_entity_node(other)).unwrap();
+
+        graph.recursive_remove_and_delete(parent_index, &world).unwrap();
+
+        world.maintain();
+
+        assert!(!world.is_alive(parent));
+        assert!(!world.is_alive(child));
+        assert!(world.is_alive(other));
+    }
+
+    #[test]
+    fn move_subtree() {
+        let world = World::new();
+        let (mut graph, nodes) = tree(&world);
+
+        let (root, a, b, c, e) = (nodes[0], nodes[1], nodes[2], nodes[3], nodes[5]);
+
+        *graph.parent_edge_mut(c).unwrap() = SceneEdge::without_scale();
+
+        graph.move_subtree(c, b).unwrap();
+
+        assert_eq!(graph.parent(c), Some(b));
+        assert_eq!(graph.parent(e), Some(c));
+        assert_eq!(graph.children(a), vec![nodes[4]]);
+        // The relationship moves along with the node
+        assert_eq!(graph.parent_edge(c), Some(&SceneEdge::without_scale()));
+
+        // A node can't be moved into its own subtree, or under itself
+        match graph.move_subtree(b, e) {
+            Err(SceneError::WouldCycle) => {}
+            result => panic!("expected a cycle error, got {:?}", result),
+        }
+
+        match graph.move_subtree(b, b) {
+            Err(SceneError::WouldCycle) => {}
+            result => panic!("expected a cycle error, got {:?}", result),
+        }
+
+        assert_eq!(graph.parent(b), Some(root));
+
+        // The root has no parent to move away from
+        assert!(graph.move_subtree(root, a).is_err());
+    }
+
+    #[test]
+    fn move_subtree_to_missing_parent() {
+        let world = World::new();
+        let (mut graph, nodes) = tree(&world);
+
+        graph.recursive_remove(nodes[2]).unwrap();
+
+        match graph.move_subtree(nodes[3], nodes[2]) {
+            Err(SceneError::InvalidNode) => {}
+            result => panic!("expected an invalid node error, got {:?}", result),
+        }
+
+        assert_eq!(graph.parent(nodes[3]), Some(nodes[1]));
+    }
+
+    #[test]
+    fn clone_subtree() {
+        let world = World::new();
+        let (mut graph, nodes) = tree(&world);
+
+        let (a, b, c, e) = (nodes[1], nodes[2], nodes[3], nodes[5]);
+
+        *graph.parent_edge_mut(c).unwrap() = SceneEdge::without_scale();
+
+        let mut cloned = Vec::new();
+
+        let copy = graph.clone_subtree(c, b, |_| {
+            let entity = world.create_later();
+
+            cloned.push(entity);
+
+            entity
+        }).unwrap();
+
+        assert_eq!(cloned.len(), 2);
+        assert_eq!(graph.parent(copy), Some(b));
+        assert_eq!(graph.parent_edge(copy), Some(&SceneEdge::without_scale()));
+
+        let copied_child = graph.children(copy)[0];
+
+        assert_eq!(graph.lookup_index(cloned[0]), Some(&copy));
+        assert_eq!(graph.lookup_index(cloned[1]), Some(&copied_child));
+
+        // The original is untouched
+        assert_eq!(graph.parent(c), Some(a));
+        assert_eq!(graph.children(c), vec![e]);
+
+        // A subtree can't be copied into itself
+        match graph.clone_subtree(a, e, |_| world.create_later()) {
+            Err(SceneError::WouldCycle) => {}
+            result => panic!("expected a cycle error, got {:?}", result.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn replace_kind_keeps_entity_table_in_sync() {
+        let world = World::new();
+        let (mut graph, nodes) = tree(&world);
+
+        let (a, b) = (nodes[1], nodes[2]);
+
+        let old = entity(&graph, a);
+        let first = world.create_later();
+        let second = world.create_later();
+
+        let previous = graph.replace_kind(a, SceneNodeKind::MultiEntityNode(MultiEntityNode::new(Some(vec![first, second])))).unwrap();
+
+        assert_eq!(previous.entities(), vec![old]);
+        assert!(graph.lookup_index(old).is_none());
+        assert_eq!(graph.lookup_index(first), Some(&a));
+        assert_eq!(graph.lookup_index(second), Some(&a));
+
+        // Entities belonging to another node are rejected, leaving everything unchanged
+        let b_entity = entity(&graph, b);
+
+        match graph.replace_kind(a, SceneNodeKind::EntityNode(EntityNode::new(b_entity))) {
+            Err(SceneError::AlreadyExists(existing, index)) => {
+                assert_eq!(existing, b_entity);
+                assert_eq!(index, b);
+            }
+            result => panic!("expected an already exists error, got {:?}", result.map(|_| ())),
+        }
+
+        assert_eq!(graph.lookup_index(first), Some(&a));
+
+        // Keeping some of the node's own entities is fine
+        graph.replace_kind(a, SceneNodeKind::EntityNode(EntityNode::new(first))).unwrap();
+
+        assert_eq!(graph.lookup_index(first), Some(&a));
+        assert!(graph.lookup_index(second).is_none());
+
+        graph.replace_kind(a, SceneNodeKind::MetaNode).unwrap();
+
+        assert!(graph.lookup_index(first).is_none());
+        assert!(graph.lookup_node(a).unwrap().is_meta());
+    }
+
+    #[test]
+    fn multi_entity_nodes() {
+        let world = World::new();
+        let mut graph = SceneGraph::new(&world);
+
+        let root = graph.root();
+
+        let node = graph.add_child(root, SceneNode::new_multi_entity_node(None)).unwrap();
+        let single = graph.add_child(root, entity_node(&world)).unwrap();
+
+        let entity = world.create_later();
+
+        graph.add_entity(node, entity).unwrap();
+
+        assert_eq!(graph.lookup_index(entity), Some(&node));
+
+        // An entity can only be in one node, and only multi-entity nodes can hold more
+        assert!(graph.add_entity(node, entity).is_err());
+        assert!(graph.add_entity(single, world.create_later()).is_err());
+
+        graph.remove_entity(node, entity).unwrap();
+
+        assert!(graph.lookup_index(entity).is_none());
+
+        match graph.remove_entity(node, entity) {
+            Err(SceneError::MissingChild(missing)) => assert_eq!(missing, entity),
+            result => panic!("expected a missing child error, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn take_leaves_empty_graph() {
+        let world = World::new();
+        let (mut graph, nodes) = tree(&world);
+
+        let taken = graph.take();
+
+        assert_eq!(taken.root(), nodes[0]);
+        assert_eq!(taken.children(nodes[1]), vec![nodes[3], nodes[4]]);
+        assert!(taken.lookup_index(entity(&taken, nodes[5])).is_some());
+
+        assert!(graph.lookup_node(graph.root()).unwrap().is_meta());
+        assert!(graph.children(graph.root()).is_empty());
+        assert!(graph.lookup_index(entity(&taken, nodes[5])).is_none());
+    }
+}
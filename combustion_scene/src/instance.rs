@@ -0,0 +1,372 @@
+//! Instantiation of scene descriptions into the world and scene graph, and snapshots of them back out again
+//!
+//! Lights are spawned with the light component of the game, through the `SceneLight` trait.
+
+use petgraph::graph::NodeIndex;
+use nalgebra::{Point3, Matrix4, Eye};
+
+use common::traits::{Named, DefaultName};
+
+use ecs::{self, Entity, World};
+
+use protocols::scene::{Scene, Node, Light, Material};
+use protocols::math::data::Transform;
+
+use ::Ix;
+use ::error::SceneResult;
+use ::graph::SceneGraph;
+use ::node::{SceneNode, SceneNodeExt, SceneNodeKind};
+
+use ::components::name::Component as NameComponent;
+use ::components::node::Component as NodeComponent;
+use ::components::transform::Component as TransformComponent;
+use ::components::model::Component as ModelComponent;
+use ::components::instanced::Component as InstancedComponent;
+use ::components::properties::Component as PropertiesComponent;
+
+/// Light component which can be created from the lights of a scene description and saved back into them
+pub trait SceneLight: ecs::Component {
+    /// Create a light component from a scene light. The position is applied to the transform of the light entity.
+    fn from_light(light: &Light) -> Self;
+
+    /// Save the light into `light`, which already has the name and position of the light entity.
+    ///
+    /// Returns `false` if the scene description can't represent the light, which leaves it out of the snapshot.
+    fn to_light(&self, light: &mut Light) -> bool;
+}
+
+/// Handle to a scene that has been instantiated into the world
+pub struct SceneInstance {
+    /// Name of the scene
+    pub name: String,
+    /// Materials used by the scene
+    pub materials: Vec<Material>,
+    /// Entity for the root node of the scene
+    pub root: Entity,
+    /// Scene graph index of the root node of the scene
+    pub root_index: NodeIndex<Ix>,
+    /// Light entities, which are attached to the scene graph root
+    pub lights: Vec<Entity>,
+}
+
+/// Spawn entities for every node and light in `scene`, and link the nodes together in `graph`.
+///
+/// The scene root is attached to the root of `graph`. Node transforms are combined into the local matrix
+/// of a transform component, and light positions become the translation of theirs.
+///
+/// Nodes referencing a model get a model component with any material overrides, and nodes marked for instancing
+/// get an instanced component. Any other scene components are attached as a properties component.
+/// Lights get an `L` light component.
+pub fn instantiate<L: SceneLight>(world: &mut World, graph: &mut SceneGraph, scene: &Scene) -> SceneResult<SceneInstance> {
+    let graph_root = graph.root();
+
+    let (root, root_index) = try!(spawn_node(world, graph, graph_root, &scene.root));
+
+    let mut lights = Vec::with_capacity(scene.lights.len());
+
+    for light in &scene.lights {
+        let entity = spawn_light::<L>(world, light);
+
+        let index = try!(graph.add_child(graph_root, SceneNode::new_entity_node(entity)));
+
+        world.write::<NodeComponent>().insert(entity, NodeComponent::new(index));
+
+        lights.push(entity);
+    }
+
+    Ok(SceneInstance {
+        name: scene.name.clone(),
+        materials: scene.materials.clone(),
+        root: root,
+        root_index: root_index,
+        lights: lights,
+    })
+}
+
+fn spawn_node(world: &mut World, graph: &mut SceneGraph, parent: NodeIndex<Ix>, node: &Node) -> SceneResult<(Entity, NodeIndex<Ix>)> {
+    let entity = world.create_now()
+                      .with(NameComponent(node.name().clone()))
+                      .with(TransformComponent::from_matrix(Transform::combine(&node.transform)))
+                      .build();
+
+    let index = try!(graph.add_child(parent, SceneNode::new_entity_node(entity)));
+
+    world.write::<NodeComponent>().insert(entity, NodeComponent::new(index));
+
+    if let Some(ref reference) = node.model {
+        world.write::<ModelComponent>().insert(entity, ModelComponent {
+            reference: reference.clone(),
+            material_overrides: node.material_overrides.clone(),
+        });
+
+        if node.instanced {
+            world.write::<InstancedComponent>().insert(entity, InstancedComponent);
+        }
+    }
+
+    if !node.components.is_empty() {
+        world.write::<PropertiesComponent>().insert(entity, PropertiesComponent(node.components.clone()));
+    }
+
+    for child in &node.children {
+        try!(spawn_node(world, graph, index, child));
+    }
+
+    Ok((entity, index))
+}
+
+fn spawn_light<L: SceneLight>(world: &mut World, light: &Light) -> Entity {
+    let translation = Transform::Translation(light.position.to_vector());
+
+    world.create_now()
+         .with(NameComponent(light.name.clone()))
+         .with(TransformComponent::from_matrix(translation.to_matrix()))
+         .with(L::from_light(light))
+         .build()
+}
+
+/// Snapshot an instantiated scene back into a `Scene` description for saving.
+///
+/// Node transforms are saved as a single matrix, and left out entirely if they are the identity.
+/// Only entity nodes are included, and lights the scene description can't represent are skipped.
+pub fn snapshot<L: SceneLight>(world: &World, graph: &SceneGraph, instance: &SceneInstance) -> Scene {
+    let names = world.read::<NameComponent>();
+    let transforms = world.read::<TransformComponent>();
+    let light_components = world.read::<L>();
+    let models = world.read::<ModelComponent>();
+    let instanced = world.read::<InstancedComponent>();
+    let properties = world.read::<PropertiesComponent>();
+
+    let local_node = |entity: Entity| -> Node {
+        let mut node = Node::default();
+
+        if let Some(name) = names.get(entity) {
+            node.set_name(name.0.clone());
+        }
+
+        if let Some(transform) = transforms.get(entity) {
+            if transform.matrix != Matrix4::new_identity(4) {
+                node.transform.push(Transform::Matrix(transform.matrix));
+            }
+        }
+
+        if let Some(model) = models.get(entity) {
+            node.model = Some(model.reference.clone());
+            node.material_overrides = model.material_overrides.clone();
+            node.instanced = instanced.get(entity).is_some();
+        }
+
+        if let Some(properties) = properties.get(entity) {
+            node.components = properties.0.clone();
+        }
+
+        node
+    };
+
+    let mut lights = Vec::with_capacity(instance.lights.len());
+
+    for &entity in &instance.lights {
+        if let Some(component) = light_components.get(entity) {
+            let position = transforms.get(entity).map_or_else(|| Point3::new(0.0, 0.0, 0.0), |transform| {
+                Point3::new(transform.matrix.m14, transform.matrix.m24, transform.matrix.m34)
+            });
+
+            let mut light = Light {
+                name: names.get(entity).map_or_else(Light::default_name, |name| name.0.clone()),
+                position: position,
+                ..Light::default()
+            };
+
+            if component.to_light(&mut light) {
+                lights.push(light);
+            }
+        }
+    }
+
+    Scene {
+        name: instance.name.clone(),
+        lights: lights,
+        materials: instance.materials.clone(),
+        root: snapshot_node(graph, instance.root_index, &local_node).unwrap_or_default(),
+    }
+}
+
+fn snapshot_node(graph: &SceneGraph, index: NodeIndex<Ix>, local_node: &Fn(Entity) -> Node) -> Option<Node> {
+    let mut node = match graph.lookup_node(index).map(SceneNode::kind) {
+        Some(&SceneNodeKind::EntityNode(ref entity_node)) => local_node(entity_node.entity()),
+        _ => return None,
+    };
+
+    node.children = graph.children(index).into_iter().filter_map(|child| snapshot_node(graph, child, local_node)).collect();
+
+    Some(node)
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Vector3, Point3, Matrix4};
+
+    use common::traits::Named;
+
+    use ecs::World;
+
+    use protocols::scene::{Scene, Node, Light, LightKind, ModelReference, MaterialOverride, NodeComponent as SceneComponent, Value};
+    use protocols::math::data::Transform;
+
+    use ::graph::SceneGraph;
+    use ::components;
+
+    use super::*;
+
+    /// Keeps the whole scene light, standing in for the light component of the game
+    #[derive(Clone, Debug)]
+    struct TestLight(Light);
+
+    impl ecs::Component for TestLight {
+        type Storage = ecs::VecStorage<TestLight>;
+    }
+
+    impl SceneLight for TestLight {
+        fn from_light(light: &Light) -> TestLight { TestLight(light.clone()) }
+
+        fn to_light(&self, light: &mut Light) -> bool {
+            *light = Light { name: light.name.clone(), position: light.position, ..self.0.clone() };
+
+            true
+        }
+    }
+
+    fn node(name: &str, transform: Vec<Transform>, children: Vec<Node>) -> Node {
+        let mut node = Node::default();
+
+        node.set_name(name.to_string());
+        node.transform = transform;
+        node.children = children;
+
+        node
+    }
+
+    fn scene() -> Scene {
+        let mut first = node("first", vec![Transform::Translation(Vector3::new(1.0, 0.0, 0.0)),
+                                           Transform::Rotation(Vector3::new(0.0, 1.0, 0.0))], vec![
+            node("leaf", Vec::new(), Vec::new()),
+        ]);
+
+        first.model = Some(ModelReference::Path("models/crate.cmodel".into()));
+        first.material_overrides = vec![MaterialOverride { mesh: 1, material: "Rusty".to_string() }];
+        first.instanced = true;
+
+        let mut health = SceneComponent::new("Health");
+
+        health.properties.insert("value".to_string(), Value::Integer(100));
+
+        let mut second = node("second", Vec::new(), Vec::new());
+
+        second.components = vec![health];
+
+        Scene {
+            name: "Test Scene".to_string(),
+            lights: vec![
+                Light {
+                    name: "Sun".to_string(),
+                    kind: LightKind::Directional,
+                    direction: Vector3::new(0.0, -1.0, 0.0),
+                    ..Light::default()
+                },
+                Light {
+                    name: "Lamp".to_string(),
+                    kind: LightKind::Point,
+                    position: Point3::new(1.0, 2.0, 3.0),
+                    effect_radius: 5.0,
+                    ..Light::default()
+                },
+            ],
+            materials: Vec::new(),
+            root: node("root", vec![Transform::Scale(Vector3::new(2.0, 2.0, 2.0))], vec![first, second]),
+        }
+    }
+
+    fn matrix(node: &Node) -> Matrix4<f32> {
+        Transform::combine(&node.transform)
+    }
+
+    fn assert_same_nodes(a: &Node, b: &Node) {
+        assert_eq!(a.name(), b.name());
+        assert_eq!(matrix(a), matrix(b));
+        assert_eq!(a.model, b.model);
+        assert_eq!(a.material_overrides, b.material_overrides);
+        assert_eq!(a.instanced, b.instanced);
+        assert_eq!(a.components, b.components);
+        assert_eq!(a.children.len(), b.children.len());
+
+        for (a, b) in a.children.iter().zip(b.children.iter()) {
+            assert_same_nodes(a, b);
+        }
+    }
+
+    fn instantiate_and_snapshot(scene: &Scene) -> Scene {
+        let mut world = World::new();
+
+        components::register_all(&mut world);
+
+        world.register::<TestLight>();
+
+        let mut graph = SceneGraph::new(&world);
+
+        let instance = instantiate::<TestLight>(&mut world, &mut graph, scene).unwrap();
+
+        assert_eq!(instance.lights.len(), scene.lights.len());
+
+        // Nodes without a model reference get no model component
+        assert_eq!(world.read::<ModelComponent>().get(instance.root), None);
+        assert_eq!(graph.parent(instance.root_index), Some(graph.root()));
+
+        // Every node is linked back to its graph node
+        let nodes = world.read::<NodeComponent>();
+
+        assert_eq!(nodes.get(instance.root).map(NodeComponent::index), Some(instance.root_index));
+
+        for &light in &instance.lights {
+            let index = nodes.get(light).unwrap().index();
+
+            assert_eq!(graph.parent(index), Some(graph.root()));
+        }
+
+        snapshot::<TestLight>(&world, &graph, &instance)
+    }
+
+    #[test]
+    fn round_trip() {
+        let original = scene();
+
+        let saved = instantiate_and_snapshot(&original);
+
+        assert_eq!(saved.name, original.name);
+        assert_same_nodes(&saved.root, &original.root);
+
+        // Identity transforms are left out
+        assert!(saved.root.children[1].transform.is_empty());
+
+        assert!(saved.root.children[0].instanced);
+        assert_eq!(saved.root.children[1].components[0].get("value"), Some(&Value::Integer(100)));
+
+        assert_eq!(saved.lights.len(), 2);
+
+        assert_eq!(saved.lights[0].name, "Sun");
+        assert_eq!(saved.lights[0].direction, Vector3::new(0.0, -1.0, 0.0));
+
+        match saved.lights[1].kind {
+            LightKind::Point => {}
+            kind => panic!("expected a point light, got {:?}", kind),
+        }
+
+        assert_eq!(saved.lights[1].position, Point3::new(1.0, 2.0, 3.0));
+        assert_eq!(saved.lights[1].effect_radius, 5.0);
+
+        // Saving the loaded snapshot again gives the same scene
+        let resaved = instantiate_and_snapshot(&saved);
+
+        assert_same_nodes(&resaved.root, &saved.root);
+        assert_eq!(resaved.lights.len(), saved.lights.len());
+    }
+}
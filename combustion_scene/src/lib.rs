@@ -1,8 +1,12 @@
 extern crate petgraph;
 extern crate fnv;
 extern crate typemap;
+extern crate nalgebra;
 
+extern crate combustion_common as common;
+#[macro_use]
 extern crate combustion_ecs as ecs;
+extern crate combustion_protocols as protocols;
 
 /// Numeric index type
 pub type Ix = usize;
@@ -11,8 +15,24 @@ pub mod error;
 pub mod node;
 pub mod edge;
 pub mod graph;
+pub mod property;
+pub mod prefab;
+pub mod snapshot;
+pub mod diff;
+pub mod spatial;
+pub mod culling;
+pub mod instance;
+pub mod components;
+pub mod systems;
 
 pub use error::{SceneError, SceneResult};
 pub use node::{SceneNode, SceneNodeExt, SceneNodeKind, EntityNode, MultiEntityNode};
 pub use edge::SceneEdge;
-pub use graph::SceneGraph;
\ No newline at end of file
+pub use graph::SceneGraph;
+pub use property::{PropertyRegistry, SerializableProperty};
+pub use prefab::{PrefabRegistry, PrefabComponent, PrefabLibrary, PrefabInstance, PrefabOverrides};
+pub use snapshot::{GraphSnapshot, NodeSnapshot, KindSnapshot};
+pub use diff::{SceneDiff, NodeChange};
+pub use spatial::{Bvh, OcclusionBuffer, Frustum, Ray, Plane, Containment};
+pub use culling::{Culling, CullingStats};
+pub use instance::SceneInstance;
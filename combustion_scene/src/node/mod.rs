@@ -15,6 +15,17 @@ pub enum SceneNodeKind {
     MetaNode,
 }
 
+impl SceneNodeKind {
+    /// Get all entities referred to by this kind of node
+    pub fn entities(&self) -> Vec<Entity> {
+        match *self {
+            SceneNodeKind::EntityNode(ref node) => vec![node.entity()],
+            SceneNodeKind::MultiEntityNode(ref node) => node.to_vec(),
+            SceneNodeKind::MetaNode => Vec::new(),
+        }
+    }
+}
+
 /// A single scene node which can contain any `SceneNodeKind` and arbitrary typed properties.
 pub struct SceneNode {
     kind: SceneNodeKind,
@@ -43,6 +54,21 @@ impl SceneNode {
     #[inline]
     pub fn kind_mut(&mut self) -> &mut SceneNodeKind { &mut self.kind }
 
+    /// Get all entities referred to by this node
+    #[inline]
+    pub fn entities(&self) -> Vec<Entity> { self.kind.entities() }
+
+    /// Create a copy of this node with no properties, using `clone_entity` to produce the entities of the new node
+    pub fn clone_with<F>(&self, mut clone_entity: F) -> SceneNode where F: FnMut(Entity) -> Entity {
+        SceneNode::new(match self.kind {
+            SceneNodeKind::EntityNode(ref node) => SceneNodeKind::EntityNode(EntityNode::new(clone_entity(node.entity()))),
+            SceneNodeKind::MultiEntityNode(ref node) => {
+                SceneNodeKind::MultiEntityNode(MultiEntityNode::new(Some(node.iter().cloned().map(clone_entity).collect())))
+            },
+            SceneNodeKind::MetaNode => SceneNodeKind::MetaNode,
+        })
+    }
+
     /// Set a type property on this node.
     ///
     /// Returns the previous value if one existed.
@@ -11,10 +11,30 @@ impl MultiEntityNode {
     pub fn new(entities: Option<Vec<Entity>>) -> MultiEntityNode {
         MultiEntityNode { entities: entities.unwrap_or_default() }
     }
+
+    /// Add an entity to this node.
+    ///
+    /// Prefer `SceneGraph::add_entity` for nodes already in a graph, so the entity lookup table is kept up to date.
+    pub fn add(&mut self, entity: Entity) {
+        self.entities.push(entity);
+    }
+
+    /// Remove an entity from this node, returning `false` if it wasn't present.
+    ///
+    /// Prefer `SceneGraph::remove_entity` for nodes already in a graph, so the entity lookup table is kept up to date.
+    pub fn remove(&mut self, entity: Entity) -> bool {
+        if let Some(position) = self.entities.iter().position(|e| *e == entity) {
+            self.entities.remove(position);
+
+            true
+        } else {
+            false
+        }
+    }
 }
 
 impl Deref for MultiEntityNode {
     type Target = Vec<Entity>;
 
     fn deref(&self) -> &Vec<Entity> { &self.entities }
-}
\ No newline at end of file
+}
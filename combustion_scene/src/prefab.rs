@@ -0,0 +1,736 @@
+//! Registry of components which can be stored in prefabs, and instantiation of prefabs into the world
+//!
+//! Prefab nodes carry their components as named `NodeComponent`s. Component types are registered here
+//! with the same name, so they can be created on entities and read back out again without knowing their types.
+//!
+//! `PrefabComponent` is usually implemented with `#[derive(Component)]` and `#[ecs(prefab = "name")]`.
+//!
+//! Every entity spawned from a prefab gets a prefab instance component recording which prefab node it came from
+//! and its per-instance overrides. When a prefab is edited, components and transforms which changed in the prefab
+//! are reapplied to every instance, with the overrides still taking precedence, and components removed from the prefab
+//! are removed from the instances.
+//!
+//! Nodes added to or removed from a prefab are not propagated. Instances must be recreated to pick those up.
+
+use std::collections::HashMap;
+
+use petgraph::graph::NodeIndex;
+
+use common::traits::Named;
+
+use ecs::{Component, Entity, World, Join};
+
+use protocols::scene::{Prefab, Node};
+use protocols::math::data::Transform;
+
+pub use protocols::scene::{NodeComponent, Value, PropertyValue};
+
+use ::Ix;
+use ::error::SceneResult;
+use ::graph::SceneGraph;
+use ::node::SceneNode;
+
+use ::components::name::Component as NameComponent;
+use ::components::node::Component as NodeIndexComponent;
+use ::components::transform::Component as TransformComponent;
+use ::components::properties::Component as PropertiesComponent;
+use ::components::prefab_instance::Component as PrefabInstanceComponent;
+
+/// Component which can be converted to and from a prefab node component
+pub trait PrefabComponent: Component + Sized {
+    /// Stable name of the component, matching `NodeComponent::name`
+    fn prefab_name() -> &'static str;
+
+    /// Convert the component into a node component holding all of its fields
+    fn to_node_component(&self) -> NodeComponent;
+
+    /// Create the component from a node component, if every field is present and the right type
+    fn from_node_component(component: &NodeComponent) -> Option<Self>;
+
+    /// Set the fields present in the node component, leaving the rest unchanged.
+    ///
+    /// Returns `false` if any present field is the wrong type.
+    fn apply_node_component(&mut self, component: &NodeComponent) -> bool;
+}
+
+struct PrefabEntry {
+    name: &'static str,
+    load: fn(&World, Entity, &NodeComponent) -> bool,
+    save: fn(&World, Entity) -> Option<NodeComponent>,
+    remove: fn(&World, Entity),
+}
+
+fn load_component<C: PrefabComponent>(world: &World, entity: Entity, component: &NodeComponent) -> bool {
+    let mut storage = world.write::<C>();
+
+    if let Some(existing) = storage.get_mut(entity) {
+        return existing.apply_node_component(component);
+    }
+
+    match C::from_node_component(component) {
+        Some(value) => {
+            storage.insert(entity, value);
+
+            true
+        }
+        None => false,
+    }
+}
+
+fn save_component<C: PrefabComponent>(world: &World, entity: Entity) -> Option<NodeComponent> {
+    world.read::<C>().get(entity).map(C::to_node_component)
+}
+
+fn remove_component<C: PrefabComponent>(world: &World, entity: Entity) {
+    world.write::<C>().remove(entity);
+}
+
+/// Set of component types which can be stored in prefabs
+#[derive(Default)]
+pub struct PrefabRegistry {
+    entries: Vec<PrefabEntry>,
+}
+
+impl PrefabRegistry {
+    pub fn new() -> PrefabRegistry {
+        PrefabRegistry::default()
+    }
+
+    /// Register a component type. Registering the same name twice replaces the previous type.
+    pub fn register<C: PrefabComponent>(&mut self) {
+        let entry = PrefabEntry {
+            name: C::prefab_name(),
+            load: load_component::<C>,
+            save: save_component::<C>,
+            remove: remove_component::<C>,
+        };
+
+        let name = entry.name;
+
+        if let Some(existing) = self.entries.iter_mut().find(|existing| existing.name == name) {
+            *existing = entry;
+
+            return;
+        }
+
+        self.entries.push(entry);
+    }
+
+    /// Returns `true` if a component type is registered with the given name
+    pub fn is_registered(&self, name: &str) -> bool {
+        self.entries.iter().any(|entry| entry.name == name)
+    }
+
+    /// Add or update a component on the entity from a node component.
+    ///
+    /// Returns `false` if no component with that name is registered, or the component could not be created.
+    pub fn load(&self, world: &World, entity: Entity, component: &NodeComponent) -> bool {
+        match self.entries.iter().find(|entry| entry.name == component.name) {
+            Some(entry) => (entry.load)(world, entity, component),
+            None => false,
+        }
+    }
+
+    /// Remove the component with the given name from the entity.
+    ///
+    /// Returns `false` if no component with that name is registered.
+    pub fn remove(&self, world: &World, entity: Entity, name: &str) -> bool {
+        match self.entries.iter().find(|entry| entry.name == name) {
+            Some(entry) => {
+                (entry.remove)(world, entity);
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Convert all registered components present on the entity into node components
+    pub fn save(&self, world: &World, entity: Entity) -> Vec<NodeComponent> {
+        self.entries.iter().filter_map(|entry| (entry.save)(world, entity)).collect()
+    }
+}
+
+/// Combine prefab components with overrides.
+///
+/// Override properties replace prefab properties of the component with the same name,
+/// and override components without a prefab counterpart are added.
+pub fn merge_components(base: &[NodeComponent], overrides: &[NodeComponent]) -> Vec<NodeComponent> {
+    let mut merged: Vec<NodeComponent> = base.to_vec();
+
+    for component in overrides {
+        if let Some(existing) = merged.iter_mut().find(|existing| existing.name == component.name) {
+            for (key, value) in &component.properties {
+                existing.properties.insert(key.clone(), value.clone());
+            }
+
+            continue;
+        }
+
+        merged.push(component.clone());
+    }
+
+    merged
+}
+
+/// Per-instance component overrides, keyed by the path of child indices from the prefab root to the node
+pub type PrefabOverrides = HashMap<Vec<usize>, Vec<NodeComponent>>;
+
+/// Handle to a prefab that has been instantiated into the world
+pub struct PrefabInstance {
+    /// Name of the prefab
+    pub prefab: String,
+    /// Entity for the root node of the prefab
+    pub root: Entity,
+    /// Scene graph index of the root node of the prefab
+    pub root_index: NodeIndex<Ix>,
+    /// All entities spawned for the prefab, in depth-first order
+    pub entities: Vec<Entity>,
+}
+
+/// Collection of prefabs and the component types they can contain
+#[derive(Default)]
+pub struct PrefabLibrary {
+    prefabs: HashMap<String, Prefab>,
+    /// Component types which are created from prefab node components
+    pub components: PrefabRegistry,
+}
+
+/// Find the node at the given path of child indices
+fn node_at<'a>(root: &'a Node, path: &[usize]) -> Option<&'a Node> {
+    path.iter().fold(Some(root), |node, &child| node.and_then(|node| node.children.get(child)))
+}
+
+/// Apply the node components which differ from `previous` to an entity and remove the ones no longer present,
+/// putting any unregistered components in a properties component.
+///
+/// Components which didn't change are left alone, so runtime changes to them are kept.
+fn update_components(world: &World, registry: &PrefabRegistry, entity: Entity, previous: &[NodeComponent], components: &[NodeComponent]) {
+    let mut unregistered = world.read::<PropertiesComponent>().get(entity).map_or_else(Vec::new, |properties| properties.0.clone());
+
+    for component in components {
+        if previous.contains(component) {
+            continue;
+        }
+
+        unregistered.retain(|existing| existing.name != component.name);
+
+        if !registry.is_registered(&component.name) || !registry.load(world, entity, component) {
+            unregistered.push(component.clone());
+        }
+    }
+
+    for component in previous {
+        if components.iter().any(|existing| existing.name == component.name) {
+            continue;
+        }
+
+        unregistered.retain(|existing| existing.name != component.name);
+
+        registry.remove(world, entity, &component.name);
+    }
+
+    let mut properties = world.write::<PropertiesComponent>();
+
+    if unregistered.is_empty() {
+        properties.remove(entity);
+    } else {
+        properties.insert(entity, PropertiesComponent(unregistered));
+    }
+}
+
+/// Replace the local transform of an entity with the combined node transform
+fn apply_transform(world: &World, entity: Entity, transform: &[Transform]) {
+    let matrix = Transform::combine(transform);
+
+    let mut transforms = world.write::<TransformComponent>();
+
+    if let Some(existing) = transforms.get_mut(entity) {
+        existing.set_matrix(matrix);
+
+        return;
+    }
+
+    transforms.insert(entity, TransformComponent::from_matrix(matrix));
+}
+
+impl PrefabLibrary {
+    pub fn new() -> PrefabLibrary {
+        PrefabLibrary::default()
+    }
+
+    /// Get a prefab by name
+    pub fn get(&self, name: &str) -> Option<&Prefab> {
+        self.prefabs.get(name)
+    }
+
+    /// Add a prefab, returning any previous prefab with the same name.
+    ///
+    /// Existing instances are not changed. Use `update` to propagate the changes to them.
+    pub fn insert(&mut self, prefab: Prefab) -> Option<Prefab> {
+        self.prefabs.insert(prefab.name.clone(), prefab)
+    }
+
+    /// Spawn entities for every node of the named prefab and link them together in `graph` under `parent`.
+    ///
+    /// `overrides` replace prefab component properties for this instance only.
+    /// Returns `None` if there is no prefab with that name.
+    pub fn instantiate(&self, world: &mut World, graph: &mut SceneGraph, name: &str,
+                       parent: NodeIndex<Ix>, overrides: &PrefabOverrides) -> SceneResult<Option<PrefabInstance>> {
+        let prefab = match self.prefabs.get(name) {
+            Some(prefab) => prefab,
+            None => return Ok(None),
+        };
+
+        let mut entities = Vec::new();
+        let mut path = Vec::new();
+
+        let (root, root_index) = try!(self.spawn_node(world, graph, parent, prefab, &prefab.root, &mut path, overrides, &mut entities));
+
+        Ok(Some(PrefabInstance {
+            prefab: prefab.name.clone(),
+            root: root,
+            root_index: root_index,
+            entities: entities,
+        }))
+    }
+
+    fn spawn_node(&self, world: &mut World, graph: &mut SceneGraph, parent: NodeIndex<Ix>, prefab: &Prefab, node: &Node,
+                  path: &mut Vec<usize>, overrides: &PrefabOverrides, entities: &mut Vec<Entity>) -> SceneResult<(Entity, NodeIndex<Ix>)> {
+        let node_overrides = overrides.get(&path[..]).cloned().unwrap_or_default();
+
+        let entity = world.create_now()
+                          .with(NameComponent(node.name().clone()))
+                          .with(TransformComponent::from_matrix(Transform::combine(&node.transform)))
+                          .with(PrefabInstanceComponent {
+                              prefab: prefab.name.clone(),
+                              path: path.clone(),
+                              overrides: node_overrides.clone(),
+                          })
+                          .build();
+
+        entities.push(entity);
+
+        let index = try!(graph.add_child(parent, SceneNode::new_entity_node(entity)));
+
+        world.write::<NodeIndexComponent>().insert(entity, NodeIndexComponent::new(index));
+
+        update_components(world, &self.components, entity, &[], &merge_components(&node.components, &node_overrides));
+
+        for (i, child) in node.children.iter().enumerate() {
+            path.push(i);
+
+            try!(self.spawn_node(world, graph, index, prefab, child, path, overrides, entities));
+
+            path.pop();
+        }
+
+        Ok((entity, index))
+    }
+
+    /// Replace a prefab and propagate the changes to every existing instance of it.
+    ///
+    /// Only components and transforms which differ from the previous version of the prefab are reapplied,
+    /// so changes made to instances at runtime are otherwise kept. Components no longer in the prefab are removed.
+    /// Instance roots keep their own transforms.
+    ///
+    /// Returns the number of updated entities.
+    pub fn update(&mut self, world: &World, prefab: Prefab) -> usize {
+        let name = prefab.name.clone();
+
+        let previous = self.insert(prefab);
+
+        let prefab = &self.prefabs[&name];
+
+        let mut changed = Vec::new();
+
+        {
+            let instances = world.read::<PrefabInstanceComponent>();
+            let entities = world.entities();
+
+            for (instance, entity) in (&instances, &entities).iter() {
+                if instance.prefab != prefab.name {
+                    continue;
+                }
+
+                if let Some(node) = node_at(&prefab.root, &instance.path) {
+                    let previous_node = previous.as_ref().and_then(|previous| node_at(&previous.root, &instance.path));
+
+                    changed.push((entity, instance.path.clone(), instance.overrides.clone(), node, previous_node));
+                }
+            }
+        }
+
+        for &(entity, ref path, ref overrides, node, previous_node) in &changed {
+            let transform_changed = previous_node.map_or(true, |previous_node| {
+                Transform::combine(&previous_node.transform) != Transform::combine(&node.transform)
+            });
+
+            if transform_changed && !path.is_empty() {
+                apply_transform(world, entity, &node.transform);
+            }
+
+            let merged = merge_components(&node.components, overrides);
+
+            let previous_merged = previous_node.map_or_else(Vec::new, |previous_node| merge_components(&previous_node.components, overrides));
+
+            update_components(world, &self.components, entity, &previous_merged, &merged);
+
+            if node.name() != previous_node.map_or(node.name(), |previous_node| previous_node.name()) {
+                world.write::<NameComponent>().insert(entity, NameComponent(node.name().clone()));
+            }
+        }
+
+        changed.len()
+    }
+
+    /// Set an override on an entity spawned from a prefab and apply it.
+    ///
+    /// Returns `false` if the entity is not part of a prefab instance.
+    pub fn set_override(&self, world: &World, entity: Entity, component: NodeComponent) -> bool {
+        let (prefab, path, previous, overrides) = {
+            let mut instances = world.write::<PrefabInstanceComponent>();
+
+            let instance = match instances.get_mut(entity) {
+                Some(instance) => instance,
+                None => return false,
+            };
+
+            let previous = instance.overrides.clone();
+
+            instance.overrides = merge_components(&instance.overrides, &[component]);
+
+            (instance.prefab.clone(), instance.path.clone(), previous, instance.overrides.clone())
+        };
+
+        self.reapply(world, entity, &prefab, &path, &previous, &overrides);
+
+        true
+    }
+
+    /// Remove all overrides of a component from an entity spawned from a prefab, restoring the prefab values.
+    ///
+    /// Returns `false` if the entity is not part of a prefab instance.
+    pub fn clear_override(&self, world: &World, entity: Entity, name: &str) -> bool {
+        let (prefab, path, previous, overrides) = {
+            let mut instances = world.write::<PrefabInstanceComponent>();
+
+            let instance = match instances.get_mut(entity) {
+                Some(instance) => instance,
+                None => return false,
+            };
+
+            let previous = instance.overrides.clone();
+
+            instance.overrides.retain(|component| component.name != name);
+
+            (instance.prefab.clone(), instance.path.clone(), previous, instance.overrides.clone())
+        };
+
+        self.reapply(world, entity, &prefab, &path, &previous, &overrides);
+
+        true
+    }
+
+    /// Apply a change of overrides to an entity, leaving components untouched by the change alone
+    fn reapply(&self, world: &World, entity: Entity, prefab: &str, path: &[usize], previous: &[NodeComponent], overrides: &[NodeComponent]) {
+        let base = self.prefabs.get(prefab)
+                       .and_then(|prefab| node_at(&prefab.root, path))
+                       .map_or_else(Vec::new, |node| node.components.clone());
+
+        update_components(world, &self.components, entity, &merge_components(&base, previous), &merge_components(&base, overrides));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::Vector3;
+
+    use common::traits::Named;
+
+    use ecs::{self, World};
+
+    use protocols::scene::{Prefab, Node};
+    use protocols::math::data::Transform;
+
+    use ::graph::SceneGraph;
+    use ::components;
+    use ::components::transform::Component as TransformComponent;
+    use ::components::properties::Component as PropertiesComponent;
+    use ::components::prefab_instance::Component as PrefabInstanceComponent;
+
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Health {
+        value: i64,
+        regen: f64,
+    }
+
+    impl ecs::Component for Health {
+        type Storage = ecs::VecStorage<Health>;
+    }
+
+    impl PrefabComponent for Health {
+        fn prefab_name() -> &'static str { "Health" }
+
+        fn to_node_component(&self) -> NodeComponent {
+            let mut component = NodeComponent::new("Health");
+
+            component.properties.insert("value".to_string(), self.value.to_value());
+            component.properties.insert("regen".to_string(), self.regen.to_value());
+
+            component
+        }
+
+        fn from_node_component(component: &NodeComponent) -> Option<Health> {
+            Some(Health {
+                value: match component.get("value").and_then(i64::from_value) { Some(value) => value, None => return None },
+                regen: match component.get("regen").and_then(f64::from_value) { Some(regen) => regen, None => return None },
+            })
+        }
+
+        fn apply_node_component(&mut self, component: &NodeComponent) -> bool {
+            if let Some(value) = component.get("value") {
+                match i64::from_value(value) {
+                    Some(value) => self.value = value,
+                    None => return false,
+                }
+            }
+
+            if let Some(regen) = component.get("regen") {
+                match f64::from_value(regen) {
+                    Some(regen) => self.regen = regen,
+                    None => return false,
+                }
+            }
+
+            true
+        }
+    }
+
+    fn health(value: i64, regen: f64) -> NodeComponent {
+        Health { value: value, regen: regen }.to_node_component()
+    }
+
+    fn health_value(value: i64) -> NodeComponent {
+        let mut component = NodeComponent::new("Health");
+
+        component.properties.insert("value".to_string(), Value::Integer(value));
+
+        component
+    }
+
+    fn node(name: &str, x: f32, components: Vec<NodeComponent>, children: Vec<Node>) -> Node {
+        let mut node = Node::default();
+
+        node.set_name(name.to_string());
+        node.transform = vec![Transform::Translation(Vector3::new(x, 0.0, 0.0))];
+        node.components = components;
+        node.children = children;
+
+        node
+    }
+
+    fn prefab(root_x: f32, child_x: f32, child_health: NodeComponent) -> Prefab {
+        let mut tag = NodeComponent::new("Tag");
+
+        tag.properties.insert("kind".to_string(), Value::String("enemy".to_string()));
+
+        Prefab {
+            name: "Enemy".to_string(),
+            root: node("root", root_x, vec![tag], vec![
+                node("body", child_x, vec![child_health], Vec::new()),
+            ]),
+        }
+    }
+
+    fn setup() -> (World, SceneGraph, PrefabLibrary) {
+        let mut world = World::new();
+
+        components::register_all(&mut world);
+
+        world.register::<Health>();
+
+        let graph = SceneGraph::new(&world);
+
+        let mut library = PrefabLibrary::new();
+
+        library.components.register::<Health>();
+        library.insert(prefab(1.0, 2.0, health(100, 0.5)));
+
+        (world, graph, library)
+    }
+
+    fn translation(world: &World, entity: Entity) -> f32 {
+        world.read::<TransformComponent>().get(entity).unwrap().matrix.m14
+    }
+
+    fn health_of(world: &World, entity: Entity) -> Option<Health> {
+        world.read::<Health>().get(entity).cloned()
+    }
+
+    #[test]
+    fn merge_overrides_properties() {
+        let merged = merge_components(&[health(100, 0.5)], &[health_value(50), NodeComponent::new("Extra")]);
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0], health(50, 0.5));
+        assert_eq!(merged[1].name, "Extra");
+    }
+
+    #[test]
+    fn instantiate_spawns_every_node() {
+        let (mut world, mut graph, library) = setup();
+
+        let root = graph.root();
+
+        assert!(library.instantiate(&mut world, &mut graph, "Missing", root, &PrefabOverrides::new()).unwrap().is_none());
+
+        let instance = library.instantiate(&mut world, &mut graph, "Enemy", root, &PrefabOverrides::new()).unwrap().unwrap();
+
+        assert_eq!(instance.prefab, "Enemy");
+        assert_eq!(instance.entities.len(), 2);
+        assert_eq!(instance.entities[0], instance.root);
+
+        let body = instance.entities[1];
+
+        assert_eq!(graph.parent(*graph.lookup_index(body).unwrap()), Some(instance.root_index));
+
+        assert_eq!(translation(&world, instance.root), 1.0);
+        assert_eq!(translation(&world, body), 2.0);
+
+        // Registered components are created, the rest become properties
+        assert_eq!(health_of(&world, body), Some(Health { value: 100, regen: 0.5 }));
+        assert!(health_of(&world, instance.root).is_none());
+        assert_eq!(world.read::<PropertiesComponent>().get(instance.root).unwrap().find("Tag").unwrap().name, "Tag");
+        assert!(world.read::<PropertiesComponent>().get(body).is_none());
+
+        let instances = world.read::<PrefabInstanceComponent>();
+
+        assert!(instances.get(instance.root).unwrap().is_root());
+        assert_eq!(instances.get(body).unwrap().path, vec![0]);
+    }
+
+    #[test]
+    fn update_propagates_changes_and_keeps_overrides() {
+        let (mut world, mut graph, mut library) = setup();
+
+        let root = graph.root();
+
+        let mut overrides = PrefabOverrides::new();
+
+        overrides.insert(vec![0], vec![health_value(10)]);
+
+        let plain = library.instantiate(&mut world, &mut graph, "Enemy", root, &PrefabOverrides::new()).unwrap().unwrap();
+        let overridden = library.instantiate(&mut world, &mut graph, "Enemy", root, &overrides).unwrap().unwrap();
+
+        assert_eq!(health_of(&world, overridden.entities[1]), Some(Health { value: 10, regen: 0.5 }));
+
+        assert_eq!(library.update(&world, prefab(5.0, 3.0, health(200, 1.5))), 4);
+
+        assert_eq!(health_of(&world, plain.entities[1]), Some(Health { value: 200, regen: 1.5 }));
+        assert_eq!(health_of(&world, overridden.entities[1]), Some(Health { value: 10, regen: 1.5 }));
+
+        // Children follow the prefab transform, but roots keep their own
+        assert_eq!(translation(&world, plain.entities[1]), 3.0);
+        assert_eq!(translation(&world, plain.root), 1.0);
+    }
+
+    #[test]
+    fn update_keeps_unchanged_runtime_state() {
+        let (mut world, mut graph, mut library) = setup();
+
+        let root = graph.root();
+
+        let instance = library.instantiate(&mut world, &mut graph, "Enemy", root, &PrefabOverrides::new()).unwrap().unwrap();
+
+        let body = instance.entities[1];
+
+        world.write::<Health>().get_mut(body).unwrap().value = 1;
+
+        library.update(&world, prefab(1.0, 4.0, health(100, 0.5)));
+
+        assert_eq!(health_of(&world, body), Some(Health { value: 1, regen: 0.5 }));
+        assert_eq!(translation(&world, body), 4.0);
+    }
+
+    #[test]
+    fn update_only_reapplies_changed_components() {
+        let (mut world, mut graph, mut library) = setup();
+
+        let root = graph.root();
+
+        let instance = library.instantiate(&mut world, &mut graph, "Enemy", root, &PrefabOverrides::new()).unwrap().unwrap();
+
+        let with_loot = |gold: i64| {
+            let mut loot = NodeComponent::new("Loot");
+
+            loot.properties.insert("gold".to_string(), Value::Integer(gold));
+
+            let mut updated = prefab(1.0, 2.0, health(100, 0.5));
+
+            updated.root.children[0].components.push(loot);
+
+            updated
+        };
+
+        library.update(&world, with_loot(5));
+
+        let body = instance.entities[1];
+
+        world.write::<Health>().get_mut(body).unwrap().value = 1;
+
+        // Only the loot changes, so the health changed at runtime is kept
+        library.update(&world, with_loot(10));
+
+        assert_eq!(health_of(&world, body), Some(Health { value: 1, regen: 0.5 }));
+        assert_eq!(world.read::<PropertiesComponent>().get(body).unwrap().find("Loot").unwrap().get("gold"), Some(&Value::Integer(10)));
+    }
+
+    #[test]
+    fn update_removes_deleted_components() {
+        let (mut world, mut graph, mut library) = setup();
+
+        let root = graph.root();
+
+        let instance = library.instantiate(&mut world, &mut graph, "Enemy", root, &PrefabOverrides::new()).unwrap().unwrap();
+
+        let body = instance.entities[1];
+
+        let mut updated = prefab(1.0, 2.0, health(100, 0.5));
+
+        updated.root.components.clear();
+        updated.root.children[0].components.clear();
+
+        library.update(&world, updated);
+
+        // Both registered and unregistered components are removed
+        assert!(health_of(&world, body).is_none());
+        assert!(world.read::<PropertiesComponent>().get(instance.root).is_none());
+    }
+
+    #[test]
+    fn set_and_clear_overrides() {
+        let (mut world, mut graph, library) = setup();
+
+        let root = graph.root();
+
+        let instance = library.instantiate(&mut world, &mut graph, "Enemy", root, &PrefabOverrides::new()).unwrap().unwrap();
+
+        let body = instance.entities[1];
+
+        assert!(library.set_override(&world, body, health_value(42)));
+        assert_eq!(health_of(&world, body), Some(Health { value: 42, regen: 0.5 }));
+        assert_eq!(world.read::<PrefabInstanceComponent>().get(body).unwrap().overrides, vec![health_value(42)]);
+
+        assert!(library.clear_override(&world, body, "Health"));
+        assert_eq!(health_of(&world, body), Some(Health { value: 100, regen: 0.5 }));
+        assert!(world.read::<PrefabInstanceComponent>().get(body).unwrap().overrides.is_empty());
+
+        let stranger = world.create_now().build();
+
+        assert!(!library.set_override(&world, stranger, health_value(1)));
+        assert!(!library.clear_override(&world, stranger, "Health"));
+    }
+}
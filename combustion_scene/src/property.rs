@@ -0,0 +1,90 @@
+//! Registry of node properties which can be serialized
+//!
+//! Node properties are stored in a typemap, so there is no way to find out which types are present
+//! or how to convert them. Property keys are registered here with a stable name and conversions to and from
+//! the scene protocol `Value` type.
+
+use typemap::Key;
+
+use protocols::scene::Value;
+
+use node::SceneNode;
+
+/// Typemap key for a node property that can be serialized
+pub trait SerializableProperty: Key {
+    /// Stable name of the property, used as the key when serialized
+    fn name() -> &'static str;
+
+    /// Convert the property value into a protocol value
+    fn to_value(value: &Self::Value) -> Value;
+
+    /// Convert a protocol value back into the property value, if it is the right type
+    fn from_value(value: &Value) -> Option<Self::Value>;
+}
+
+struct PropertyEntry {
+    name: &'static str,
+    save: fn(&SceneNode) -> Option<Value>,
+    load: fn(&mut SceneNode, &Value) -> bool,
+}
+
+fn save_property<K: SerializableProperty>(node: &SceneNode) -> Option<Value> {
+    node.get_property::<K>().map(K::to_value)
+}
+
+fn load_property<K: SerializableProperty>(node: &mut SceneNode, value: &Value) -> bool {
+    match K::from_value(value) {
+        Some(value) => {
+            node.set_property::<K>(value);
+
+            true
+        }
+        None => false,
+    }
+}
+
+/// Set of serializable node property types
+#[derive(Default)]
+pub struct PropertyRegistry {
+    entries: Vec<PropertyEntry>,
+}
+
+impl PropertyRegistry {
+    pub fn new() -> PropertyRegistry {
+        PropertyRegistry::default()
+    }
+
+    /// Register a property type. Registering the same name twice replaces the previous type.
+    pub fn register<K: SerializableProperty>(&mut self) {
+        let entry = PropertyEntry {
+            name: K::name(),
+            save: save_property::<K>,
+            load: load_property::<K>,
+        };
+
+        let name = entry.name;
+
+        if let Some(existing) = self.entries.iter_mut().find(|existing| existing.name == name) {
+            *existing = entry;
+
+            return;
+        }
+
+        self.entries.push(entry);
+    }
+
+    /// Convert all registered properties present on the node into named values
+    pub fn save(&self, node: &SceneNode) -> Vec<(&'static str, Value)> {
+        self.entries.iter().filter_map(|entry| (entry.save)(node).map(|value| (entry.name, value))).collect()
+    }
+
+    /// Set a property on the node from a named value.
+    ///
+    /// Returns `false` if no property with that name is registered, or the value is the wrong type.
+    pub fn load(&self, node: &mut SceneNode, name: &str, value: &Value) -> bool {
+        match self.entries.iter().find(|entry| entry.name == name) {
+            Some(entry) => (entry.load)(node, value),
+            None => false,
+        }
+    }
+}
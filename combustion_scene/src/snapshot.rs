@@ -0,0 +1,495 @@
+//! Snapshots of the scene graph structure and node properties
+//!
+//! A `GraphSnapshot` is a plain copy of the graph which can be compared, patched and converted to and from
+//! the scene protocol. Node IDs are the node indices in the graph, and are preserved when restoring a snapshot.
+
+use std::collections::BTreeMap;
+
+use petgraph::graph::NodeIndex;
+
+use ecs::Entity;
+
+use protocols::scene::{Node, NodeComponent, Value};
+
+use error::*;
+use node::*;
+use edge::SceneEdge;
+use graph::SceneGraph;
+use property::PropertyRegistry;
+
+use super::Ix;
+
+/// Identifier of a node within a snapshot
+pub type NodeId = u64;
+
+/// Serializable identifier of an entity, as given by the caller
+pub type EntityId = u64;
+
+/// Largest node ID accepted when restoring a snapshot.
+///
+/// Gaps between IDs are filled with placeholder nodes, so this bounds the memory used by a snapshot with sparse IDs.
+pub const MAX_NODE_ID: NodeId = 1 << 20;
+
+/// Name of the node component which holds graph structure information in the scene protocol
+pub const GRAPH_COMPONENT: &'static str = "scene_graph";
+
+/// Name of the node component which holds node properties in the scene protocol
+pub const PROPERTIES_COMPONENT: &'static str = "properties";
+
+/// Snapshot of a `SceneNodeKind`
+#[derive(Debug, Clone, PartialEq)]
+pub enum KindSnapshot {
+    /// Node for a single entity
+    Entity(EntityId),
+    /// Node for several entities
+    MultiEntity(Vec<EntityId>),
+    /// Node without entities
+    Meta,
+}
+
+/// Snapshot of a single node
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeSnapshot {
+    /// Kind of the node
+    pub kind: KindSnapshot,
+    /// Relationship to the parent node. Ignored for the root.
+    pub edge: SceneEdge,
+    /// Children of the node, in order
+    pub children: Vec<NodeId>,
+    /// Registered properties of the node
+    pub properties: BTreeMap<String, Value>,
+}
+
+/// Snapshot of a whole scene graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphSnapshot {
+    /// Root node
+    pub root: NodeId,
+    /// All nodes, including the root
+    pub nodes: BTreeMap<NodeId, NodeSnapshot>,
+}
+
+impl SceneGraph {
+    /// Take a snapshot of the graph, including all properties registered in `registry`.
+    ///
+    /// `entity_id` gives a serializable ID for each entity.
+    pub fn snapshot<F>(&self, registry: &PropertyRegistry, entity_id: F) -> GraphSnapshot where F: Fn(Entity) -> EntityId {
+        let mut nodes = BTreeMap::new();
+
+        for index in self.depth_first(self.root()) {
+            let node = match self.lookup_node(index) {
+                Some(node) => node,
+                None => continue,
+            };
+
+            let kind = match *node.kind() {
+                SceneNodeKind::EntityNode(ref entity_node) => KindSnapshot::Entity(entity_id(entity_node.entity())),
+                SceneNodeKind::MultiEntityNode(ref multi) => KindSnapshot::MultiEntity(multi.iter().cloned().map(&entity_id).collect()),
+                SceneNodeKind::MetaNode => KindSnapshot::Meta,
+            };
+
+            nodes.insert(index.index() as NodeId, NodeSnapshot {
+                kind: kind,
+                edge: self.parent_edge(index).cloned().unwrap_or_default(),
+                children: self.children(index).into_iter().map(|child| child.index() as NodeId).collect(),
+                properties: registry.save(node).into_iter().map(|(name, value)| (name.to_string(), value)).collect(),
+            });
+        }
+
+        GraphSnapshot { root: self.root().index() as NodeId, nodes: nodes }
+    }
+
+    /// Rebuild a graph from a snapshot, keeping the node IDs as node indices.
+    ///
+    /// `entity` gives the entity for each serialized entity ID, and properties are loaded using `registry`.
+    ///
+    /// Returns `SceneError::InvalidNodeId` if any node ID is larger than `MAX_NODE_ID`.
+    pub fn restore<F>(snapshot: &GraphSnapshot, registry: &PropertyRegistry, mut entity: F) -> SceneResult<SceneGraph> where F: FnMut(EntityId) -> Entity {
+        if let Some(&last) = snapshot.nodes.keys().next_back() {
+            if last > MAX_NODE_ID {
+                return Err(SceneError::InvalidNodeId(last));
+            }
+        }
+
+        if snapshot.root > MAX_NODE_ID {
+            return Err(SceneError::InvalidNodeId(snapshot.root));
+        }
+
+        let len = snapshot.nodes.keys().next_back().map_or(0, |last| *last as usize + 1);
+
+        let mut nodes: Vec<Option<SceneNode>> = (0..len).map(|_| None).collect();
+        let mut edges = Vec::new();
+
+        for (&id, node_snapshot) in &snapshot.nodes {
+            let mut node = match node_snapshot.kind {
+                KindSnapshot::Entity(entity_id) => SceneNode::new_entity_node(entity(entity_id)),
+                KindSnapshot::MultiEntity(ref entity_ids) => SceneNode::new_multi_entity_node(Some(entity_ids.iter().map(|entity_id| entity(*entity_id)).collect())),
+                KindSnapshot::Meta => SceneNode::new_meta_node(),
+            };
+
+            for (name, value) in &node_snapshot.properties {
+                if !registry.load(&mut node, name, value) {
+                    return Err(SceneError::InvalidProperty(name.clone()));
+                }
+            }
+
+            for child in &node_snapshot.children {
+                let edge = match snapshot.nodes.get(child) {
+                    Some(child_snapshot) => child_snapshot.edge,
+                    None => return Err(SceneError::InvalidNode),
+                };
+
+                edges.push((NodeIndex::new(id as usize), NodeIndex::new(*child as usize), edge));
+            }
+
+            nodes[id as usize] = Some(node);
+        }
+
+        SceneGraph::from_indexed(NodeIndex::<Ix>::new(snapshot.root as usize), nodes, edges)
+    }
+}
+
+impl GraphSnapshot {
+    /// Convert the snapshot into a scene protocol node tree.
+    ///
+    /// Graph structure and properties are stored as node components named by `GRAPH_COMPONENT` and `PROPERTIES_COMPONENT`.
+    pub fn to_scene_node(&self) -> SceneResult<Node> {
+        self.node_to_scene_node(self.root)
+    }
+
+    fn node_to_scene_node(&self, id: NodeId) -> SceneResult<Node> {
+        let node_snapshot = match self.nodes.get(&id) {
+            Some(node_snapshot) => node_snapshot,
+            None => return Err(SceneError::InvalidNode),
+        };
+
+        let mut graph_component = NodeComponent::new(GRAPH_COMPONENT);
+
+        {
+            let properties = &mut graph_component.properties;
+
+            properties.insert("id".to_string(), Value::Integer(id as i64));
+
+            match node_snapshot.kind {
+                KindSnapshot::Entity(entity) => {
+                    properties.insert("kind".to_string(), Value::String("entity".to_string()));
+                    properties.insert("entities".to_string(), Value::List(vec![Value::Integer(entity as i64)]));
+                }
+                KindSnapshot::MultiEntity(ref entities) => {
+                    properties.insert("kind".to_string(), Value::String("multi_entity".to_string()));
+                    properties.insert("entities".to_string(), Value::List(entities.iter().map(|entity| Value::Integer(*entity as i64)).collect()));
+                }
+                KindSnapshot::Meta => {
+                    properties.insert("kind".to_string(), Value::String("meta".to_string()));
+                }
+            }
+
+            properties.insert("inherit_translation".to_string(), Value::Bool(node_snapshot.edge.inherit_translation));
+            properties.insert("inherit_rotation".to_string(), Value::Bool(node_snapshot.edge.inherit_rotation));
+            properties.insert("inherit_scale".to_string(), Value::Bool(node_snapshot.edge.inherit_scale));
+        }
+
+        let mut node = Node::default();
+
+        node.components.push(graph_component);
+
+        if !node_snapshot.properties.is_empty() {
+            let mut properties_component = NodeComponent::new(PROPERTIES_COMPONENT);
+
+            properties_component.properties = node_snapshot.properties.iter().map(|(name, value)| (name.clone(), value.clone())).collect();
+
+            node.components.push(properties_component);
+        }
+
+        for child in &node_snapshot.children {
+            node.children.push(try!(self.node_to_scene_node(*child)));
+        }
+
+        Ok(node)
+    }
+
+    /// Convert a scene protocol node tree created by `to_scene_node` back into a snapshot
+    pub fn from_scene_node(root: &Node) -> SceneResult<GraphSnapshot> {
+        let mut nodes = BTreeMap::new();
+
+        let root_id = try!(GraphSnapshot::node_from_scene_node(root, &mut nodes));
+
+        Ok(GraphSnapshot { root: root_id, nodes: nodes })
+    }
+
+    fn node_from_scene_node(node: &Node, nodes: &mut BTreeMap<NodeId, NodeSnapshot>) -> SceneResult<NodeId> {
+        let graph_component = match node.components.iter().find(|component| component.name == GRAPH_COMPONENT) {
+            Some(graph_component) => &graph_component.properties,
+            None => return Err(SceneError::InvalidNode),
+        };
+
+        let id = match graph_component.get("id") {
+            Some(&Value::Integer(id)) if id >= 0 => id as NodeId,
+            _ => return Err(SceneError::InvalidProperty("id".to_string())),
+        };
+
+        let entities = match graph_component.get("entities") {
+            Some(&Value::List(ref values)) => {
+                let mut entities = Vec::with_capacity(values.len());
+
+                for value in values {
+                    match *value {
+                        Value::Integer(entity) if entity >= 0 => entities.push(entity as EntityId),
+                        _ => return Err(SceneError::InvalidProperty("entities".to_string())),
+                    }
+                }
+
+                entities
+            }
+            _ => Vec::new(),
+        };
+
+        let kind = match graph_component.get("kind") {
+            Some(&Value::String(ref kind)) if kind == "entity" && entities.len() == 1 => KindSnapshot::Entity(entities[0]),
+            Some(&Value::String(ref kind)) if kind == "multi_entity" => KindSnapshot::MultiEntity(entities),
+            Some(&Value::String(ref kind)) if kind == "meta" => KindSnapshot::Meta,
+            _ => return Err(SceneError::InvalidProperty("kind".to_string())),
+        };
+
+        let flag = |name: &str| -> SceneResult<bool> {
+            match graph_component.get(name) {
+                Some(&Value::Bool(flag)) => Ok(flag),
+                None => Ok(true),
+                _ => Err(SceneError::InvalidProperty(name.to_string())),
+            }
+        };
+
+        let edge = SceneEdge {
+            inherit_translation: try!(flag("inherit_translation")),
+            inherit_rotation: try!(flag("inherit_rotation")),
+            inherit_scale: try!(flag("inherit_scale")),
+        };
+
+        let properties = match node.components.iter().find(|component| component.name == PROPERTIES_COMPONENT) {
+            Some(component) => component.properties.iter().map(|(name, value)| (name.clone(), value.clone())).collect(),
+            None => BTreeMap::new(),
+        };
+
+        let mut children = Vec::with_capacity(node.children.len());
+
+        for child in &node.children {
+            children.push(try!(GraphSnapshot::node_from_scene_node(child, nodes)));
+        }
+
+        if nodes.contains_key(&id) {
+            return Err(SceneError::InvalidProperty("id".to_string()));
+        }
+
+        nodes.insert(id, NodeSnapshot { kind: kind, edge: edge, children: children, properties: properties });
+
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use typemap::Key;
+
+    use ecs::{Entity, World};
+
+    use protocols::scene::Value;
+
+    use error::*;
+    use node::*;
+    use edge::SceneEdge;
+    use graph::SceneGraph;
+    use property::{PropertyRegistry, SerializableProperty};
+
+    use super::*;
+
+    struct Label;
+
+    impl Key for Label {
+        type Value = String;
+    }
+
+    impl SerializableProperty for Label {
+        fn name() -> &'static str { "label" }
+
+        fn to_value(value: &String) -> Value { Value::String(value.clone()) }
+
+        fn from_value(value: &Value) -> Option<String> {
+            match *value {
+                Value::String(ref value) => Some(value.clone()),
+                _ => None,
+            }
+        }
+    }
+
+    fn registry() -> PropertyRegistry {
+        let mut registry = PropertyRegistry::new();
+
+        registry.register::<Label>();
+
+        registry
+    }
+
+    fn entity_id(entity: Entity) -> EntityId {
+        entity.get_id() as EntityId
+    }
+
+    /// Builds a root with a labeled entity node, a multi-entity node which doesn't inherit scale,
+    /// and a meta node under the first, with a removed node leaving a gap in the indices.
+    fn graph(world: &World) -> SceneGraph {
+        let mut graph = SceneGraph::new(world);
+
+        let root = graph.root();
+
+        let mut first = SceneNode::new_entity_node(world.create_later());
+
+        first.set_property::<Label>("first".to_string());
+
+        let first = graph.add_child(root, first).unwrap();
+
+        let removed = graph.add_child(root, SceneNode::new_meta_node()).unwrap();
+
+        let multi = SceneNode::new_multi_entity_node(Some(vec![world.create_later(), world.create_later()]));
+
+        graph.add_child_with_edge(root, multi, SceneEdge::without_scale()).unwrap();
+        graph.add_child(first, SceneNode::new_meta_node()).unwrap();
+
+        graph.recursive_remove(removed).unwrap();
+
+        graph
+    }
+
+    fn restore(snapshot: &GraphSnapshot, world: &World) -> SceneResult<SceneGraph> {
+        let entities: Vec<Entity> = (0..16).map(|_| world.create_later()).collect();
+
+        // Entity IDs from `entity_id` are allocation order, so any world with enough entities can resolve them
+        SceneGraph::restore(snapshot, &registry(), |id| entities[id as usize])
+    }
+
+    #[test]
+    fn snapshot_restore_round_trip() {
+        let world = World::new();
+        let graph = graph(&world);
+
+        let snapshot = graph.snapshot(&registry(), entity_id);
+
+        assert_eq!(snapshot.nodes.len(), 4);
+
+        let restored = restore(&snapshot, &World::new()).unwrap();
+
+        assert_eq!(restored.snapshot(&registry(), entity_id), snapshot);
+
+        // Node indices are kept, including the gap left by the removed node
+        assert_eq!(restored.root(), graph.root());
+        assert_eq!(restored.children(restored.root()), graph.children(graph.root()));
+
+        let first = restored.children(restored.root())[0];
+
+        assert_eq!(restored.lookup_node(first).unwrap().get_property::<Label>(), Some(&"first".to_string()));
+
+        let multi = restored.children(restored.root())[1];
+
+        assert_eq!(restored.parent_edge(multi), Some(&SceneEdge::without_scale()));
+        assert_eq!(restored.lookup_node(multi).unwrap().entities().len(), 2);
+    }
+
+    #[test]
+    fn scene_node_round_trip() {
+        let world = World::new();
+
+        let snapshot = graph(&world).snapshot(&registry(), entity_id);
+
+        let node = snapshot.to_scene_node().unwrap();
+
+        assert_eq!(GraphSnapshot::from_scene_node(&node).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn unknown_properties_are_rejected() {
+        let world = World::new();
+
+        let mut snapshot = graph(&world).snapshot(&registry(), entity_id);
+
+        snapshot.nodes.get_mut(&snapshot.root).unwrap().properties.insert("unknown".to_string(), Value::Bool(true));
+
+        match restore(&snapshot, &world) {
+            Err(SceneError::InvalidProperty(ref name)) if name == "unknown" => {}
+            result => panic!("expected an invalid property, got {:?}", result.err()),
+        }
+    }
+
+    #[test]
+    fn missing_children_are_rejected() {
+        let world = World::new();
+
+        let mut snapshot = graph(&world).snapshot(&registry(), entity_id);
+
+        snapshot.nodes.get_mut(&snapshot.root).unwrap().children.push(1000);
+
+        match restore(&snapshot, &world) {
+            Err(SceneError::InvalidNode) => {}
+            result => panic!("expected an invalid node, got {:?}", result.err()),
+        }
+    }
+
+    #[test]
+    fn large_node_ids_are_rejected() {
+        let world = World::new();
+
+        let mut snapshot = graph(&world).snapshot(&registry(), entity_id);
+
+        let node = NodeSnapshot { kind: KindSnapshot::Meta, edge: SceneEdge::new(), children: Vec::new(), properties: Default::default() };
+
+        snapshot.nodes.insert(u64::max_value(), node.clone());
+
+        match restore(&snapshot, &world) {
+            Err(SceneError::InvalidNodeId(id)) => assert_eq!(id, u64::max_value()),
+            result => panic!("expected an invalid node ID, got {:?}", result.err()),
+        }
+
+        snapshot.nodes.remove(&u64::max_value());
+        snapshot.nodes.insert(MAX_NODE_ID + 1, node);
+
+        match restore(&snapshot, &world) {
+            Err(SceneError::InvalidNodeId(id)) => assert_eq!(id, MAX_NODE_ID + 1),
+            result => panic!("expected an invalid node ID, got {:?}", result.err()),
+        }
+    }
+
+    #[test]
+    fn negative_ids_are_rejected() {
+        let world = World::new();
+
+        let mut node = graph(&world).snapshot(&registry(), entity_id).to_scene_node().unwrap();
+
+        node.children[0].components[0].properties.insert("id".to_string(), Value::Integer(-1));
+
+        match GraphSnapshot::from_scene_node(&node) {
+            Err(SceneError::InvalidProperty(ref name)) if name == "id" => {}
+            result => panic!("expected an invalid id, got {:?}", result.err()),
+        }
+
+        let mut node = graph(&world).snapshot(&registry(), entity_id).to_scene_node().unwrap();
+
+        node.components[0].properties.insert("entities".to_string(), Value::List(vec![Value::Integer(-1)]));
+
+        match GraphSnapshot::from_scene_node(&node) {
+            Err(SceneError::InvalidProperty(ref name)) if name == "entities" => {}
+            result => panic!("expected invalid entities, got {:?}", result.err()),
+        }
+    }
+
+    #[test]
+    fn duplicate_ids_are_rejected() {
+        let world = World::new();
+
+        let snapshot = graph(&world).snapshot(&registry(), entity_id);
+
+        let mut node = snapshot.to_scene_node().unwrap();
+
+        let duplicate = snapshot.to_scene_node().unwrap().children.remove(0);
+
+        node.children.push(duplicate);
+
+        assert!(GraphSnapshot::from_scene_node(&node).is_err());
+    }
+}
@@ -0,0 +1,818 @@
+//! Dynamic bounding volume hierarchy
+//!
+//! Leaves store a tight box for queries and a fattened box for the tree, so small movements
+//! don't require restructuring. Insertion picks the sibling with the lowest surface area cost,
+//! and `rebuild` recreates a balanced tree from scratch when the tree has degraded.
+
+use std::cmp::Ordering;
+use std::collections::hash_map::Keys;
+use std::iter::Cloned;
+
+use fnv::FnvHashMap;
+
+use ecs::Entity;
+
+use protocols::math::data::{Aabb, Sphere};
+
+use super::{Containment, Frustum, Ray, sphere_intersects_aabb};
+
+/// Default amount leaf boxes are grown by
+pub const DEFAULT_MARGIN: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy)]
+enum NodeKind {
+    Leaf(Entity, Aabb),
+    Branch(usize, usize),
+    Free,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    aabb: Aabb,
+    parent: Option<usize>,
+    kind: NodeKind,
+}
+
+/// Dynamic bounding volume hierarchy of entities
+#[derive(Debug, Clone)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    free: Vec<usize>,
+    root: Option<usize>,
+    leaves: FnvHashMap<Entity, usize>,
+    margin: f32,
+}
+
+impl Default for Bvh {
+    #[inline(always)]
+    fn default() -> Bvh { Bvh::new() }
+}
+
+impl Bvh {
+    /// Create an empty hierarchy using `DEFAULT_MARGIN`
+    pub fn new() -> Bvh {
+        Bvh::with_margin(DEFAULT_MARGIN)
+    }
+
+    /// Create an empty hierarchy where leaf boxes are grown by `margin`
+    pub fn with_margin(margin: f32) -> Bvh {
+        Bvh { nodes: Vec::new(), free: Vec::new(), root: None, leaves: FnvHashMap::default(), margin: margin }
+    }
+
+    /// Number of entities in the hierarchy
+    #[inline]
+    pub fn len(&self) -> usize { self.leaves.len() }
+
+    /// Returns `true` if there are no entities in the hierarchy
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.leaves.is_empty() }
+
+    /// Returns `true` if the entity is in the hierarchy
+    #[inline]
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.leaves.contains_key(&entity)
+    }
+
+    /// Get the bounding box the entity was last inserted or updated with
+    pub fn get(&self, entity: Entity) -> Option<&Aabb> {
+        self.leaves.get(&entity).and_then(|&leaf| match self.nodes[leaf].kind {
+            NodeKind::Leaf(_, ref aabb) => Some(aabb),
+            _ => None,
+        })
+    }
+
+    /// Iterate over all entities in the hierarchy
+    pub fn entities(&self) -> Cloned<Keys<Entity, usize>> {
+        self.leaves.keys().cloned()
+    }
+
+    /// Bounding box of the whole hierarchy
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.root.map(|root| self.nodes[root].aabb)
+    }
+
+    /// Height of the tree, with a single leaf being `1`
+    pub fn height(&self) -> usize {
+        let mut height = 0;
+        let mut stack: Vec<(usize, usize)> = self.root.into_iter().map(|root| (root, 1)).collect();
+
+        while let Some((index, depth)) = stack.pop() {
+            height = height.max(depth);
+
+            if let NodeKind::Branch(left, right) = self.nodes[index].kind {
+                stack.push((left, depth + 1));
+                stack.push((right, depth + 1));
+            }
+        }
+
+        height
+    }
+
+    /// Remove all entities
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.free.clear();
+        self.leaves.clear();
+        self.root = None;
+    }
+
+    /// Insert an entity with the given world-space bounding box, or update it if it already exists
+    pub fn insert(&mut self, entity: Entity, aabb: Aabb) {
+        if self.leaves.contains_key(&entity) {
+            self.update(entity, aabb);
+        } else {
+            let fat = aabb.grow(self.margin);
+
+            let leaf = self.allocate(Node { aabb: fat, parent: None, kind: NodeKind::Leaf(entity, aabb) });
+
+            self.leaves.insert(entity, leaf);
+            self.insert_leaf(leaf);
+        }
+    }
+
+    /// Remove an entity, returning `true` if it existed
+    pub fn remove(&mut self, entity: Entity) -> bool {
+        if let Some(leaf) = self.leaves.remove(&entity) {
+            self.remove_leaf(leaf);
+            self.release(leaf);
+
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Update the bounding box of an entity, inserting it if it doesn't exist.
+    ///
+    /// The tree is only restructured if the new box escapes the fattened leaf box.
+    /// Returns `true` if the tree was restructured.
+    pub fn update(&mut self, entity: Entity, aabb: Aabb) -> bool {
+        let leaf = match self.leaves.get(&entity).cloned() {
+            Some(leaf) => leaf,
+            None => {
+                self.insert(entity, aabb);
+                return true;
+            }
+        };
+
+        self.nodes[leaf].kind = NodeKind::Leaf(entity, aabb);
+
+        if self.nodes[leaf].aabb.contains_aabb(&aabb) {
+            return false;
+        }
+
+        let fat = aabb.grow(self.margin);
+
+        self.remove_leaf(leaf);
+        self.nodes[leaf].aabb = fat;
+        self.insert_leaf(leaf);
+
+        true
+    }
+
+    /// Remove all entities for which `f` returns `false`
+    pub fn retain<F>(&mut self, mut f: F) where F: FnMut(Entity) -> bool {
+        let removed: Vec<Entity> = self.leaves.keys().cloned().filter(|entity| !f(*entity)).collect();
+
+        for entity in removed {
+            self.remove(entity);
+        }
+    }
+
+    /// Recompute all branch boxes from their leaves without changing the tree structure.
+    ///
+    /// Leaf boxes are shrunk back to their entity boxes plus the margin.
+    pub fn refit(&mut self) {
+        let root = match self.root {
+            Some(root) => root,
+            None => return,
+        };
+
+        // Parents always come before their children in this order, so iterate it backwards
+        let mut order = Vec::with_capacity(self.nodes.len());
+        let mut stack = vec![root];
+
+        while let Some(index) = stack.pop() {
+            order.push(index);
+
+            if let NodeKind::Branch(left, right) = self.nodes[index].kind {
+                stack.push(left);
+                stack.push(right);
+            }
+        }
+
+        for &index in order.iter().rev() {
+            let aabb = match self.nodes[index].kind {
+                NodeKind::Leaf(_, aabb) => aabb.grow(self.margin),
+                NodeKind::Branch(left, right) => self.nodes[left].aabb.union(&self.nodes[right].aabb),
+                NodeKind::Free => continue,
+            };
+
+            self.nodes[index].aabb = aabb;
+        }
+    }
+
+    /// Rebuild a balanced tree from all entities, splitting at the median along the longest axis
+    pub fn rebuild(&mut self) {
+        let mut items: Vec<(Entity, Aabb)> = self.leaves.values().filter_map(|&leaf| match self.nodes[leaf].kind {
+            NodeKind::Leaf(entity, aabb) => Some((entity, aabb)),
+            _ => None,
+        }).collect();
+
+        self.clear();
+
+        if !items.is_empty() {
+            let root = self.build(&mut items[..], None);
+
+            self.root = Some(root);
+        }
+    }
+
+    fn build(&mut self, items: &mut [(Entity, Aabb)], parent: Option<usize>) -> usize {
+        if items.len() == 1 {
+            let (entity, aabb) = items[0];
+            let fat = aabb.grow(self.margin);
+
+            let leaf = self.allocate(Node { aabb: fat, parent: parent, kind: NodeKind::Leaf(entity, aabb) });
+
+            self.leaves.insert(entity, leaf);
+
+            return leaf;
+        }
+
+        let centers = items.iter().fold(None, |bounds: Option<Aabb>, &(_, ref aabb)| {
+            let center = aabb.center();
+
+            Some(match bounds {
+                Some(bounds) => bounds.extend(&center),
+                None => Aabb::new(center, center),
+            })
+        }).unwrap();
+
+        let extents = centers.half_extents();
+
+        let axis = if extents.x >= extents.y && extents.x >= extents.z { 0 } else if extents.y >= extents.z { 1 } else { 2 };
+
+        items.sort_by(|&(_, ref a), &(_, ref b)| {
+            let (a, b) = (a.center(), b.center());
+
+            let (a, b) = match axis {
+                0 => (a.x, b.x),
+                1 => (a.y, b.y),
+                _ => (a.z, b.z),
+            };
+
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        });
+
+        let middle = items.len() / 2;
+
+        let branch = self.allocate(Node { aabb: items[0].1, parent: parent, kind: NodeKind::Free });
+
+        let (left_items, right_items) = items.split_at_mut(middle);
+
+        let left = self.build(left_items, Some(branch));
+        let right = self.build(right_items, Some(branch));
+
+        let aabb = self.nodes[left].aabb.union(&self.nodes[right].aabb);
+
+        self.nodes[branch].aabb = aabb;
+        self.nodes[branch].kind = NodeKind::Branch(left, right);
+
+        branch
+    }
+
+    /// Find all entities whose bounding box overlaps `aabb`
+    pub fn query_aabb(&self, aabb: &Aabb) -> Vec<Entity> {
+        self.query(|node_aabb| node_aabb.intersects(aabb))
+    }
+
+    /// Find all entities whose bounding box overlaps `sphere`
+    pub fn query_sphere(&self, sphere: &Sphere) -> Vec<Entity> {
+        self.query(|node_aabb| sphere_intersects_aabb(sphere, node_aabb))
+    }
+
+    /// Find all entities whose bounding box is at least partially inside the frustum
+    pub fn query_frustum(&self, frustum: &Frustum) -> Vec<Entity> {
+        let mut results = Vec::new();
+        let mut stack: Vec<(usize, bool)> = self.root.into_iter().map(|root| (root, false)).collect();
+
+        // Once a branch is entirely inside, everything below it is too and needs no more tests
+        while let Some((index, inside)) = stack.pop() {
+            let node = &self.nodes[index];
+
+            match node.kind {
+                NodeKind::Leaf(entity, ref aabb) => {
+                    if inside || frustum.test_aabb(aabb) != Containment::Outside {
+                        results.push(entity);
+                    }
+                }
+                NodeKind::Branch(left, right) => {
+                    let inside = inside || match frustum.test_aabb(&node.aabb) {
+                        Containment::Outside => continue,
+                        Containment::Intersecting => false,
+                        Containment::Inside => true,
+                    };
+
+                    stack.push((left, inside));
+                    stack.push((right, inside));
+                }
+                NodeKind::Free => {}
+            }
+        }
+
+        results
+    }
+
+    /// Find all entities hit by the ray within `max_distance`, sorted by the distance to their bounding box
+    pub fn raycast(&self, ray: &Ray, max_distance: f32) -> Vec<(Entity, f32)> {
+        let mut results = Vec::new();
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+
+            match node.kind {
+                NodeKind::Leaf(entity, ref aabb) => {
+                    if let Some(distance) = ray.intersect_aabb(aabb) {
+                        if distance <= max_distance {
+                            results.push((entity, distance));
+                        }
+                    }
+                }
+                NodeKind::Branch(left, right) => {
+                    match ray.intersect_aabb(&node.aabb) {
+                        Some(distance) if distance <= max_distance => {
+                            stack.push(left);
+                            stack.push(right);
+                        }
+                        _ => {}
+                    }
+                }
+                NodeKind::Free => {}
+            }
+        }
+
+        results.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+
+        results
+    }
+
+    /// Find the closest entity hit by the ray within `max_distance`
+    pub fn raycast_first(&self, ray: &Ray, max_distance: f32) -> Option<(Entity, f32)> {
+        self.raycast(ray, max_distance).into_iter().next()
+    }
+
+    fn query<F>(&self, test: F) -> Vec<Entity> where F: Fn(&Aabb) -> bool {
+        let mut results = Vec::new();
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+
+            match node.kind {
+                NodeKind::Leaf(entity, ref aabb) => {
+                    if test(aabb) {
+                        results.push(entity);
+                    }
+                }
+                NodeKind::Branch(left, right) => {
+                    if test(&node.aabb) {
+                        stack.push(left);
+                        stack.push(right);
+                    }
+                }
+                NodeKind::Free => {}
+            }
+        }
+
+        results
+    }
+
+    fn allocate(&mut self, node: Node) -> usize {
+        if let Some(index) = self.free.pop() {
+            self.nodes[index] = node;
+            index
+        } else {
+            self.nodes.push(node);
+            self.nodes.len() - 1
+        }
+    }
+
+    fn release(&mut self, index: usize) {
+        self.nodes[index].kind = NodeKind::Free;
+        self.nodes[index].parent = None;
+        self.free.push(index);
+    }
+
+    fn insert_leaf(&mut self, leaf: usize) {
+        let root = match self.root {
+            Some(root) => root,
+            None => {
+                self.nodes[leaf].parent = None;
+                self.root = Some(leaf);
+                return;
+            }
+        };
+
+        let leaf_aabb = self.nodes[leaf].aabb;
+
+        // Descend towards the sibling with the lowest surface area cost
+        let mut sibling = root;
+
+        while let NodeKind::Branch(left, right) = self.nodes[sibling].kind {
+            let area = self.nodes[sibling].aabb.surface_area();
+            let combined_area = self.nodes[sibling].aabb.union(&leaf_aabb).surface_area();
+
+            // Cost of making a new parent for this node and the leaf
+            let cost = 2.0 * combined_area;
+
+            // Minimum cost pushed down to the children
+            let inheritance_cost = 2.0 * (combined_area - area);
+
+            let child_cost = |child: usize| -> f32 {
+                let child_aabb = &self.nodes[child].aabb;
+                let union_area = child_aabb.union(&leaf_aabb).surface_area();
+
+                match self.nodes[child].kind {
+                    NodeKind::Leaf(..) => union_area + inheritance_cost,
+                    _ => union_area - child_aabb.surface_area() + inheritance_cost,
+                }
+            };
+
+            let left_cost = child_cost(left);
+            let right_cost = child_cost(right);
+
+            if cost < left_cost && cost < right_cost {
+                break;
+            }
+
+            sibling = if left_cost < right_cost { left } else { right };
+        }
+
+        let old_parent = self.nodes[sibling].parent;
+        let combined = self.nodes[sibling].aabb.union(&leaf_aabb);
+
+        let new_parent = self.allocate(Node {
+            aabb: combined,
+            parent: old_parent,
+            kind: NodeKind::Branch(sibling, leaf),
+        });
+
+        self.nodes[sibling].parent = Some(new_parent);
+        self.nodes[leaf].parent = Some(new_parent);
+
+        match old_parent {
+            Some(old_parent) => {
+                self.replace_child(old_parent, sibling, new_parent);
+                self.refit_ancestors(old_parent);
+            }
+            None => {
+                self.root = Some(new_parent);
+            }
+        }
+    }
+
+    fn remove_leaf(&mut self, leaf: usize) {
+        if self.root == Some(leaf) {
+            self.root = None;
+            return;
+        }
+
+        let parent = self.nodes[leaf].parent.expect("Non-root leaf without a parent");
+
+        let sibling = match self.nodes[parent].kind {
+            NodeKind::Branch(left, right) => if left == leaf { right } else { left },
+            _ => unreachable!(),
+        };
+
+        match self.nodes[parent].parent {
+            Some(grandparent) => {
+                self.replace_child(grandparent, parent, sibling);
+                self.nodes[sibling].parent = Some(grandparent);
+                self.refit_ancestors(grandparent);
+            }
+            None => {
+                self.nodes[sibling].parent = None;
+                self.root = Some(sibling);
+            }
+        }
+
+        self.nodes[leaf].parent = None;
+        self.release(parent);
+    }
+
+    fn replace_child(&mut self, parent: usize, old: usize, new: usize) {
+        if let NodeKind::Branch(ref mut left, ref mut right) = self.nodes[parent].kind {
+            if *left == old {
+                *left = new;
+            } else if *right == old {
+                *right = new;
+            }
+        }
+    }
+
+    fn refit_ancestors(&mut self, start: usize) {
+        let mut current = Some(start);
+
+        while let Some(index) = current {
+            if let NodeKind::Branch(left, right) = self.nodes[index].kind {
+                let aabb = self.nodes[left].aabb.union(&self.nodes[right].aabb);
+
+                self.nodes[index].aabb = aabb;
+            }
+
+            current = self.nodes[index].parent;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use nalgebra::{Point3, Vector3, Matrix4, Eye, Norm};
+
+    use ecs::{Entity, World};
+
+    use protocols::math::data::{Aabb, Sphere};
+
+    use super::super::{Containment, Frustum, Ray};
+    use super::*;
+
+    fn cube(x: f32, y: f32, z: f32, half: f32) -> Aabb {
+        Aabb::new(Point3::new(x - half, y - half, z - half), Point3::new(x + half, y + half, z + half))
+    }
+
+    fn entities(world: &World, count: usize) -> Vec<Entity> {
+        (0..count).map(|_| world.create_later()).collect()
+    }
+
+    fn sorted(mut entities: Vec<Entity>) -> Vec<Entity> {
+        entities.sort_by_key(|entity| entity.get_id());
+        entities
+    }
+
+    /// Row of unit cubes along the x axis, two units apart
+    fn row(world: &World, count: usize) -> (Bvh, Vec<Entity>) {
+        let entities = entities(world, count);
+        let mut bvh = Bvh::new();
+
+        for (i, entity) in entities.iter().enumerate() {
+            bvh.insert(*entity, cube(i as f32 * 2.0, 0.0, 0.0, 0.5));
+        }
+
+        (bvh, entities)
+    }
+
+    /// Check the structure of the tree, and that it matches the leaf table
+    fn check(bvh: &Bvh) {
+        let mut reachable = HashSet::new();
+        let mut stack: Vec<usize> = bvh.root.into_iter().collect();
+
+        if let Some(root) = bvh.root {
+            assert_eq!(bvh.nodes[root].parent, None);
+        }
+
+        while let Some(index) = stack.pop() {
+            assert!(reachable.insert(index), "node {} is reachable twice", index);
+
+            let node = &bvh.nodes[index];
+
+            match node.kind {
+                NodeKind::Leaf(entity, ref aabb) => {
+                    assert_eq!(bvh.leaves.get(&entity), Some(&index));
+                    assert!(node.aabb.contains_aabb(aabb), "fat box doesn't contain the entity box");
+                }
+                NodeKind::Branch(left, right) => {
+                    for &child in &[left, right] {
+                        assert_eq!(bvh.nodes[child].parent, Some(index));
+                        assert!(node.aabb.contains_aabb(&bvh.nodes[child].aabb), "branch box doesn't contain its child");
+
+                        stack.push(child);
+                    }
+                }
+                NodeKind::Free => panic!("free node {} is reachable", index),
+            }
+        }
+
+        // Every node is either in the tree or free, and every leaf is in the table
+        assert_eq!(reachable.len() + bvh.free.len(), bvh.nodes.len());
+        assert_eq!(reachable.len(), if bvh.leaves.is_empty() { 0 } else { bvh.leaves.len() * 2 - 1 });
+
+        for &free in &bvh.free {
+            assert!(!reachable.contains(&free));
+        }
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let world = World::new();
+        let (mut bvh, entities) = row(&world, 16);
+
+        check(&bvh);
+
+        assert_eq!(bvh.len(), 16);
+        assert_eq!(bvh.get(entities[3]), Some(&cube(6.0, 0.0, 0.0, 0.5)));
+        assert!(bvh.bounds().unwrap().contains_aabb(&Aabb::new(Point3::new(-0.5, -0.5, -0.5), Point3::new(30.5, 0.5, 0.5))));
+
+        for entity in entities.iter().enumerate().filter(|&(i, _)| i % 2 == 0).map(|(_, entity)| entity) {
+            assert!(bvh.remove(*entity));
+        }
+
+        check(&bvh);
+
+        assert_eq!(bvh.len(), 8);
+        assert!(!bvh.contains(entities[0]));
+        assert!(bvh.contains(entities[1]));
+        assert!(!bvh.remove(entities[0]));
+
+        // Freed nodes are reused
+        let nodes = bvh.nodes.len();
+
+        bvh.insert(entities[0], cube(0.0, 0.0, 0.0, 0.5));
+
+        check(&bvh);
+
+        assert_eq!(bvh.nodes.len(), nodes);
+
+        bvh.retain(|entity| entity == entities[0]);
+
+        check(&bvh);
+
+        assert_eq!(bvh.entities().collect::<Vec<_>>(), vec![entities[0]]);
+
+        bvh.clear();
+
+        check(&bvh);
+
+        assert!(bvh.is_empty());
+        assert_eq!(bvh.bounds(), None);
+        assert_eq!(bvh.height(), 0);
+    }
+
+    #[test]
+    fn small_updates_stay_in_the_fat_box() {
+        let world = World::new();
+        let (mut bvh, entities) = row(&world, 8);
+
+        let before = bvh.nodes.iter().map(|node| node.aabb).collect::<Vec<_>>();
+
+        // Smaller than the margin, so no restructuring
+        assert!(!bvh.update(entities[2], cube(4.05, 0.0, 0.0, 0.5)));
+
+        check(&bvh);
+
+        assert_eq!(bvh.get(entities[2]), Some(&cube(4.05, 0.0, 0.0, 0.5)));
+        assert_eq!(bvh.nodes.iter().map(|node| node.aabb).collect::<Vec<_>>(), before);
+
+        // Queries use the tight box, not the fat one
+        assert!(bvh.query_aabb(&cube(3.5, 0.0, 0.0, 0.0)).is_empty());
+        assert_eq!(bvh.query_aabb(&cube(3.6, 0.0, 0.0, 0.0)), vec![entities[2]]);
+    }
+
+    #[test]
+    fn large_updates_restructure() {
+        let world = World::new();
+        let (mut bvh, entities) = row(&world, 8);
+
+        assert!(bvh.update(entities[0], cube(100.0, 0.0, 0.0, 0.5)));
+
+        check(&bvh);
+
+        assert_eq!(bvh.query_aabb(&cube(100.0, 0.0, 0.0, 1.0)), vec![entities[0]]);
+        assert!(bvh.query_aabb(&cube(0.0, 0.0, 0.0, 0.5)).is_empty());
+
+        // Updating an entity which isn't in the tree inserts it
+        let extra = world.create_later();
+
+        assert!(bvh.update(extra, cube(-10.0, 0.0, 0.0, 0.5)));
+
+        check(&bvh);
+
+        assert_eq!(bvh.len(), 9);
+        assert_eq!(bvh.query_aabb(&cube(-10.0, 0.0, 0.0, 0.0)), vec![extra]);
+    }
+
+    #[test]
+    fn rebuild_and_refit() {
+        let world = World::new();
+        let entities = entities(&world, 64);
+        let mut bvh = Bvh::new();
+
+        // Inserting in sorted order along a line is the worst case for incremental insertion
+        for (i, entity) in entities.iter().enumerate() {
+            bvh.insert(*entity, cube(i as f32, 0.0, 0.0, 0.25));
+        }
+
+        let query = cube(10.0, 0.0, 0.0, 3.0);
+        let expected = sorted(bvh.query_aabb(&query));
+
+        bvh.rebuild();
+
+        check(&bvh);
+
+        assert_eq!(bvh.len(), 64);
+        assert_eq!(bvh.height(), 7);
+        assert_eq!(sorted(bvh.query_aabb(&query)), expected);
+
+        // Small moves keep the fat boxes, which refitting then shrinks back around the new boxes
+        for (i, entity) in entities.iter().enumerate() {
+            assert!(!bvh.update(*entity, cube(i as f32 + 0.05, 0.0, 0.0, 0.25)));
+        }
+
+        bvh.refit();
+
+        check(&bvh);
+
+        assert_eq!(sorted(bvh.query_aabb(&query)), expected);
+    }
+
+    #[test]
+    fn sphere_queries() {
+        let world = World::new();
+        let (bvh, entities) = row(&world, 8);
+
+        let found = sorted(bvh.query_sphere(&Sphere::new(Point3::new(4.0, 0.0, 0.0), 1.6)));
+
+        assert_eq!(found, vec![entities[1], entities[2], entities[3]]);
+
+        // Close to the corner of the box, but not touching it
+        assert!(bvh.query_sphere(&Sphere::new(Point3::new(1.0, 1.0, 1.0), 0.8)).is_empty());
+    }
+
+    #[test]
+    fn frustum_queries() {
+        let world = World::new();
+        let (bvh, entities) = row(&world, 8);
+
+        // The identity matrix gives the clip space cube from -1 to 1
+        let frustum = Frustum::from_matrix(&Matrix4::new_identity(4));
+
+        assert_eq!(frustum.test_aabb(&cube(0.0, 0.0, 0.0, 0.5)), Containment::Inside);
+        assert_eq!(frustum.test_aabb(&cube(1.0, 0.0, 0.0, 0.5)), Containment::Intersecting);
+        assert_eq!(frustum.test_aabb(&cube(2.0, 0.0, 0.0, 0.5)), Containment::Outside);
+
+        assert_eq!(frustum.test_sphere(&Sphere::new(Point3::new(0.0, 0.0, 0.0), 0.5)), Containment::Inside);
+        assert_eq!(frustum.test_sphere(&Sphere::new(Point3::new(0.0, 1.2, 0.0), 0.5)), Containment::Intersecting);
+        assert_eq!(frustum.test_sphere(&Sphere::new(Point3::new(0.0, 0.0, -2.0), 0.5)), Containment::Outside);
+
+        assert_eq!(bvh.query_frustum(&frustum), vec![entities[0]]);
+
+        // Perspective looking down -z from the origin with a 90 degree field of view, near 1 and far 100
+        let (near, far) = (1.0, 100.0);
+
+        let perspective = Matrix4::new(1.0, 0.0, 0.0, 0.0,
+                                       0.0, 1.0, 0.0, 0.0,
+                                       0.0, 0.0, (far + near) / (near - far), 2.0 * far * near / (near - far),
+                                       0.0, 0.0, -1.0, 0.0);
+
+        let frustum = Frustum::from_matrix(&perspective);
+
+        assert_eq!(frustum.test_aabb(&cube(0.0, 0.0, -10.0, 1.0)), Containment::Inside);
+        assert_eq!(frustum.test_aabb(&cube(8.0, 0.0, -10.0, 0.5)), Containment::Inside);
+        assert_eq!(frustum.test_aabb(&cube(12.0, 0.0, -10.0, 0.5)), Containment::Outside);
+        assert_eq!(frustum.test_aabb(&cube(0.0, 0.0, 10.0, 1.0)), Containment::Outside);
+        assert_eq!(frustum.test_aabb(&cube(0.0, 0.0, -0.5, 0.1)), Containment::Outside);
+        assert_eq!(frustum.test_aabb(&cube(0.0, 0.0, -200.0, 1.0)), Containment::Outside);
+        assert_eq!(frustum.test_aabb(&cube(0.0, 0.0, -100.0, 1.0)), Containment::Intersecting);
+
+        // Planes point inwards and are normalized
+        for plane in &frustum.planes {
+            assert!((plane.normal.norm() - 1.0).abs() < 1e-5);
+            assert!(plane.distance(&Point3::new(0.0, 0.0, -10.0)) > 0.0);
+        }
+    }
+
+    #[test]
+    fn raycasts() {
+        let world = World::new();
+        let (bvh, entities) = row(&world, 8);
+
+        let ray = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+
+        let hits = bvh.raycast(&ray, 9.0);
+
+        assert_eq!(hits.iter().map(|hit| hit.0).collect::<Vec<_>>(), vec![entities[0], entities[1], entities[2]]);
+        assert!((hits[0].1 - 4.5).abs() < 1e-5);
+        assert!((hits[2].1 - 8.5).abs() < 1e-5);
+
+        assert_eq!(bvh.raycast_first(&ray, 100.0).map(|hit| hit.0), Some(entities[0]));
+        assert_eq!(bvh.raycast_first(&ray, 4.0), None);
+
+        // Starting inside a box hits it at zero
+        let inside = Ray::new(Point3::new(4.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+
+        assert_eq!(bvh.raycast(&inside, 100.0), vec![(entities[2], 0.0)]);
+
+        // Parallel to the row, but above it
+        let above = Ray::new(Point3::new(-5.0, 1.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+
+        assert!(bvh.raycast(&above, 100.0).is_empty());
+
+        // Pointing away
+        let away = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vector3::new(-1.0, 0.0, 0.0));
+
+        assert!(bvh.raycast(&away, 100.0).is_empty());
+    }
+}
@@ -0,0 +1,214 @@
+//! Spatial queries over scene entities
+//!
+//! Shapes used to query a `Bvh`, which indexes entities by their world-space bounding boxes.
+
+use nalgebra::{Vector3, Point3, Matrix4};
+
+use protocols::math::data::{Aabb, Sphere};
+
+pub mod bvh;
+pub mod occlusion;
+
+pub use self::bvh::Bvh;
+pub use self::occlusion::OcclusionBuffer;
+
+/// Result of testing a volume against a frustum
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Containment {
+    /// Entirely outside
+    Outside,
+    /// Partially inside
+    Intersecting,
+    /// Entirely inside
+    Inside,
+}
+
+/// Plane in the form `normal . p + d = 0`, with the normal pointing to the inside
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    /// Unit normal of the plane
+    pub normal: Vector3<f32>,
+    /// Signed distance of the plane from the origin along the normal
+    pub d: f32,
+}
+
+impl Plane {
+    /// Create a plane from unnormalized coefficients
+    pub fn new(a: f32, b: f32, c: f32, d: f32) -> Plane {
+        let length = (a * a + b * b + c * c).sqrt();
+
+        if length > 0.0 {
+            Plane { normal: Vector3::new(a / length, b / length, c / length), d: d / length }
+        } else {
+            Plane { normal: Vector3::new(a, b, c), d: d }
+        }
+    }
+
+    /// Signed distance from the plane to the point, positive on the inside
+    #[inline]
+    pub fn distance(&self, point: &Point3<f32>) -> f32 {
+        self.normal.x * point.x + self.normal.y * point.y + self.normal.z * point.z + self.d
+    }
+
+    /// Test which side of the plane the box is on
+    pub fn test_aabb(&self, aabb: &Aabb) -> Containment {
+        // Corners furthest along and against the normal
+        let positive = Point3::new(if self.normal.x >= 0.0 { aabb.max.x } else { aabb.min.x },
+                                   if self.normal.y >= 0.0 { aabb.max.y } else { aabb.min.y },
+                                   if self.normal.z >= 0.0 { aabb.max.z } else { aabb.min.z });
+
+        let negative = Point3::new(if self.normal.x >= 0.0 { aabb.min.x } else { aabb.max.x },
+                                   if self.normal.y >= 0.0 { aabb.min.y } else { aabb.max.y },
+                                   if self.normal.z >= 0.0 { aabb.min.z } else { aabb.max.z });
+
+        if self.distance(&positive) < 0.0 {
+            Containment::Outside
+        } else if self.distance(&negative) < 0.0 {
+            Containment::Intersecting
+        } else {
+            Containment::Inside
+        }
+    }
+
+    /// Test which side of the plane the sphere is on
+    pub fn test_sphere(&self, sphere: &Sphere) -> Containment {
+        let distance = self.distance(&sphere.center);
+
+        if distance < -sphere.radius {
+            Containment::Outside
+        } else if distance < sphere.radius {
+            Containment::Intersecting
+        } else {
+            Containment::Inside
+        }
+    }
+}
+
+/// View frustum made of six planes facing inwards
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    /// Left, right, bottom, top, near and far planes
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Extract the frustum planes from a combined projection and view matrix.
+    ///
+    /// Works for both perspective and orthographic projections.
+    pub fn from_matrix(m: &Matrix4<f32>) -> Frustum {
+        Frustum {
+            planes: [
+                Plane::new(m.m41 + m.m11, m.m42 + m.m12, m.m43 + m.m13, m.m44 + m.m14),
+                Plane::new(m.m41 - m.m11, m.m42 - m.m12, m.m43 - m.m13, m.m44 - m.m14),
+                Plane::new(m.m41 + m.m21, m.m42 + m.m22, m.m43 + m.m23, m.m44 + m.m24),
+                Plane::new(m.m41 - m.m21, m.m42 - m.m22, m.m43 - m.m23, m.m44 - m.m24),
+                Plane::new(m.m41 + m.m31, m.m42 + m.m32, m.m43 + m.m33, m.m44 + m.m34),
+                Plane::new(m.m41 - m.m31, m.m42 - m.m32, m.m43 - m.m33, m.m44 - m.m34),
+            ]
+        }
+    }
+
+    /// Test the box against all planes
+    pub fn test_aabb(&self, aabb: &Aabb) -> Containment {
+        let mut result = Containment::Inside;
+
+        for plane in &self.planes {
+            match plane.test_aabb(aabb) {
+                Containment::Outside => return Containment::Outside,
+                Containment::Intersecting => result = Containment::Intersecting,
+                Containment::Inside => {}
+            }
+        }
+
+        result
+    }
+
+    /// Test the sphere against all planes
+    pub fn test_sphere(&self, sphere: &Sphere) -> Containment {
+        let mut result = Containment::Inside;
+
+        for plane in &self.planes {
+            match plane.test_sphere(sphere) {
+                Containment::Outside => return Containment::Outside,
+                Containment::Intersecting => result = Containment::Intersecting,
+                Containment::Inside => {}
+            }
+        }
+
+        result
+    }
+}
+
+/// Ray with an origin and direction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    /// Starting point of the ray
+    pub origin: Point3<f32>,
+    /// Direction of the ray. Distances are measured in multiples of its length.
+    pub direction: Vector3<f32>,
+}
+
+impl Ray {
+    /// Create a new ray
+    pub fn new(origin: Point3<f32>, direction: Vector3<f32>) -> Ray {
+        Ray { origin: origin, direction: direction }
+    }
+
+    /// Point along the ray at the given distance
+    pub fn at(&self, distance: f32) -> Point3<f32> {
+        self.origin + self.direction * distance
+    }
+
+    /// Distance along the ray where it enters the box, or `None` if it misses.
+    ///
+    /// Returns `0.0` if the origin is inside the box.
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let mut near = 0.0f32;
+        let mut far = ::std::f32::INFINITY;
+
+        let axes = [
+            (self.origin.x, self.direction.x, aabb.min.x, aabb.max.x),
+            (self.origin.y, self.direction.y, aabb.min.y, aabb.max.y),
+            (self.origin.z, self.direction.z, aabb.min.z, aabb.max.z),
+        ];
+
+        for &(origin, direction, min, max) in &axes {
+            if direction == 0.0 {
+                // Parallel to the slab, so it either always or never overlaps
+                if origin < min || origin > max {
+                    return None;
+                }
+            } else {
+                let inv = 1.0 / direction;
+
+                let (t0, t1) = {
+                    let a = (min - origin) * inv;
+                    let b = (max - origin) * inv;
+
+                    if a < b { (a, b) } else { (b, a) }
+                };
+
+                near = near.max(t0);
+                far = far.min(t1);
+
+                if near > far {
+                    return None;
+                }
+            }
+        }
+
+        Some(near)
+    }
+}
+
+/// Returns `true` if the sphere overlaps the box
+pub fn sphere_intersects_aabb(sphere: &Sphere, aabb: &Aabb) -> bool {
+    let c = &sphere.center;
+
+    // Closest point on the box to the sphere center
+    let dx = c.x - c.x.max(aabb.min.x).min(aabb.max.x);
+    let dy = c.y - c.y.max(aabb.min.y).min(aabb.max.y);
+    let dz = c.z - c.z.max(aabb.min.z).min(aabb.max.z);
+
+    dx * dx + dy * dy + dz * dz <= sphere.radius * sphere.radius
+}
@@ -0,0 +1,309 @@
+//! Software-rasterized occlusion buffer
+//!
+//! Occluders are rasterized as boxes into a small depth buffer on the CPU, then the screen-space rectangles
+//! of other bounding boxes are tested against it. Occluder boxes must be entirely inside the geometry they
+//! stand in for, or visible objects may be culled.
+//!
+//! Anything crossing the near plane is treated as visible and never occludes, so the test is always conservative.
+
+use nalgebra::{Point3, Matrix4};
+
+use protocols::math::data::Aabb;
+
+/// Minimum clip-space `w` a vertex must have to be projected
+const NEAR_EPSILON: f32 = 1e-5;
+
+/// Triangles making up the faces of a box, indexing corners as `x | y << 1 | z << 2`
+const BOX_TRIANGLES: [[usize; 3]; 12] = [
+    [0, 1, 3], [0, 3, 2],
+    [4, 6, 7], [4, 7, 5],
+    [0, 4, 5], [0, 5, 1],
+    [2, 3, 7], [2, 7, 6],
+    [0, 2, 6], [0, 6, 4],
+    [1, 5, 7], [1, 7, 3],
+];
+
+/// Depth buffer used for occlusion culling
+#[derive(Debug, Clone)]
+pub struct OcclusionBuffer {
+    width: usize,
+    height: usize,
+    depth: Vec<f32>,
+}
+
+impl OcclusionBuffer {
+    /// Create a new buffer with the given resolution, cleared to the far plane
+    pub fn new(width: usize, height: usize) -> OcclusionBuffer {
+        OcclusionBuffer { width: width, height: height, depth: vec![1.0; width * height] }
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize { self.width }
+
+    #[inline]
+    pub fn height(&self) -> usize { self.height }
+
+    /// Change the resolution of the buffer, clearing it
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.depth = vec![1.0; width * height];
+    }
+
+    /// Reset every pixel to the far plane
+    pub fn clear(&mut self) {
+        for depth in &mut self.depth {
+            *depth = 1.0;
+        }
+    }
+
+    /// Depth at a pixel, from `0.0` at the near plane to `1.0` at the far plane
+    pub fn depth(&self, x: usize, y: usize) -> Option<f32> {
+        if x < self.width && y < self.height {
+            Some(self.depth[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    /// Project a point into screen space, with `z` as depth. `None` if it is at or behind the eye.
+    fn project(&self, m: &Matrix4<f32>, p: &Point3<f32>) -> Option<Point3<f32>> {
+        let w = m.m41 * p.x + m.m42 * p.y + m.m43 * p.z + m.m44;
+
+        if w < NEAR_EPSILON {
+            return None;
+        }
+
+        let x = (m.m11 * p.x + m.m12 * p.y + m.m13 * p.z + m.m14) / w;
+        let y = (m.m21 * p.x + m.m22 * p.y + m.m23 * p.z + m.m24) / w;
+        let z = (m.m31 * p.x + m.m32 * p.y + m.m33 * p.z + m.m34) / w;
+
+        Some(Point3::new((x * 0.5 + 0.5) * self.width as f32,
+                         (0.5 - y * 0.5) * self.height as f32,
+                         z * 0.5 + 0.5))
+    }
+
+    /// Project all corners of a box, or `None` if any of them is behind the eye
+    fn project_box(&self, m: &Matrix4<f32>, aabb: &Aabb) -> Option<[Point3<f32>; 8]> {
+        let mut corners = [Point3::new(0.0, 0.0, 0.0); 8];
+
+        for (i, corner) in corners.iter_mut().enumerate() {
+            let point = Point3::new(if i & 1 == 0 { aabb.min.x } else { aabb.max.x },
+                                    if i & 2 == 0 { aabb.min.y } else { aabb.max.y },
+                                    if i & 4 == 0 { aabb.min.z } else { aabb.max.z });
+
+            *corner = match self.project(m, &point) {
+                Some(projected) => projected,
+                None => return None,
+            };
+        }
+
+        Some(corners)
+    }
+
+    /// Rasterize a box as an occluder. `matrix` transforms the box from its local space to clip space.
+    ///
+    /// Returns `false` if the box crosses the near plane and was skipped.
+    pub fn rasterize_aabb(&mut self, aabb: &Aabb, matrix: &Matrix4<f32>) -> bool {
+        let corners = match self.project_box(matrix, aabb) {
+            Some(corners) => corners,
+            None => return false,
+        };
+
+        for triangle in &BOX_TRIANGLES {
+            self.rasterize_triangle(&corners[triangle[0]], &corners[triangle[1]], &corners[triangle[2]]);
+        }
+
+        true
+    }
+
+    fn rasterize_triangle(&mut self, a: &Point3<f32>, b: &Point3<f32>, c: &Point3<f32>) {
+        fn edge(a: &Point3<f32>, b: &Point3<f32>, x: f32, y: f32) -> f32 {
+            (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x)
+        }
+
+        let area = edge(a, b, c.x, c.y);
+
+        if area.abs() < 1e-8 {
+            return;
+        }
+
+        let x0 = a.x.min(b.x).min(c.x).floor().max(0.0) as usize;
+        let y0 = a.y.min(b.y).min(c.y).floor().max(0.0) as usize;
+        let x1 = (a.x.max(b.x).max(c.x).ceil().max(0.0) as usize).min(self.width);
+        let y1 = (a.y.max(b.y).max(c.y).ceil().max(0.0) as usize).min(self.height);
+
+        for y in y0..y1 {
+            let py = y as f32 + 0.5;
+
+            for x in x0..x1 {
+                let px = x as f32 + 0.5;
+
+                // Barycentric weights, which are all positive inside the triangle for either winding
+                let wa = edge(b, c, px, py) / area;
+                let wb = edge(c, a, px, py) / area;
+                let wc = edge(a, b, px, py) / area;
+
+                if wa < 0.0 || wb < 0.0 || wc < 0.0 {
+                    continue;
+                }
+
+                let z = wa * a.z + wb * b.z + wc * c.z;
+
+                let depth = &mut self.depth[y * self.width + x];
+
+                if z < *depth {
+                    *depth = z;
+                }
+            }
+        }
+    }
+
+    /// Test if any part of a box may be visible. `matrix` transforms the box from its space to clip space.
+    pub fn test_aabb(&self, aabb: &Aabb, matrix: &Matrix4<f32>) -> bool {
+        let corners = match self.project_box(matrix, aabb) {
+            Some(corners) => corners,
+            None => return true,
+        };
+
+        let (mut min_x, mut min_y, mut min_z) = (::std::f32::INFINITY, ::std::f32::INFINITY, ::std::f32::INFINITY);
+        let (mut max_x, mut max_y) = (::std::f32::NEG_INFINITY, ::std::f32::NEG_INFINITY);
+
+        for corner in &corners {
+            min_x = min_x.min(corner.x);
+            min_y = min_y.min(corner.y);
+            min_z = min_z.min(corner.z);
+            max_x = max_x.max(corner.x);
+            max_y = max_y.max(corner.y);
+        }
+
+        if min_z < 0.0 {
+            return true;
+        }
+
+        let x0 = min_x.floor().max(0.0) as usize;
+        let y0 = min_y.floor().max(0.0) as usize;
+        let x1 = (max_x.ceil().max(0.0) as usize).min(self.width);
+        let y1 = (max_y.ceil().max(0.0) as usize).min(self.height);
+
+        // Entirely off-screen, which is left to frustum culling
+        if x0 >= x1 || y0 >= y1 {
+            return true;
+        }
+
+        for y in y0..y1 {
+            for x in x0..x1 {
+                if min_z <= self.depth[y * self.width + x] {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Point3, Matrix4, Eye};
+
+    use protocols::math::data::Aabb;
+
+    use super::*;
+
+    fn cube(x: f32, y: f32, z: f32, half: f32) -> Aabb {
+        Aabb::new(Point3::new(x - half, y - half, z - half), Point3::new(x + half, y + half, z + half))
+    }
+
+    /// Perspective looking down -z from the origin with a 90 degree field of view, near 1 and far 100
+    fn perspective() -> Matrix4<f32> {
+        let (near, far) = (1.0, 100.0);
+
+        Matrix4::new(1.0, 0.0, 0.0, 0.0,
+                     0.0, 1.0, 0.0, 0.0,
+                     0.0, 0.0, (far + near) / (near - far), 2.0 * far * near / (near - far),
+                     0.0, 0.0, -1.0, 0.0)
+    }
+
+    #[test]
+    fn rasterize_fills_the_projected_box() {
+        let mut buffer = OcclusionBuffer::new(64, 64);
+
+        // With the identity matrix, clip space x and y from -0.5 to 0.5 cover the middle half of the buffer
+        assert!(buffer.rasterize_aabb(&cube(0.0, 0.0, 0.0, 0.5), &Matrix4::new_identity(4)));
+
+        // Nearest face is at z = -0.5, or a depth of 0.25
+        assert!((buffer.depth(32, 32).unwrap() - 0.25).abs() < 1e-5);
+        assert!((buffer.depth(16, 16).unwrap() - 0.25).abs() < 1e-5);
+        assert!((buffer.depth(47, 47).unwrap() - 0.25).abs() < 1e-5);
+
+        assert_eq!(buffer.depth(15, 32), Some(1.0));
+        assert_eq!(buffer.depth(48, 32), Some(1.0));
+        assert_eq!(buffer.depth(32, 0), Some(1.0));
+        assert_eq!(buffer.depth(64, 0), None);
+
+        // Closer boxes overwrite, further ones don't
+        buffer.rasterize_aabb(&cube(0.0, 0.0, -0.8, 0.1), &Matrix4::new_identity(4));
+        buffer.rasterize_aabb(&cube(0.0, 0.0, 0.8, 0.1), &Matrix4::new_identity(4));
+
+        assert!((buffer.depth(32, 32).unwrap() - 0.05).abs() < 1e-5);
+        assert!((buffer.depth(20, 20).unwrap() - 0.25).abs() < 1e-5);
+
+        buffer.clear();
+
+        assert_eq!(buffer.depth(32, 32), Some(1.0));
+
+        buffer.resize(8, 4);
+
+        assert_eq!((buffer.width(), buffer.height()), (8, 4));
+        assert_eq!(buffer.depth(7, 3), Some(1.0));
+        assert_eq!(buffer.depth(0, 4), None);
+    }
+
+    #[test]
+    fn boxes_behind_occluders_are_culled() {
+        let mut buffer = OcclusionBuffer::new(64, 64);
+        let m = perspective();
+
+        assert!(buffer.rasterize_aabb(&cube(0.0, 0.0, -5.0, 2.0), &m));
+
+        // Directly behind the occluder
+        assert!(!buffer.test_aabb(&cube(0.0, 0.0, -20.0, 1.0), &m));
+        // Behind it, but large enough to stick out at the sides
+        assert!(buffer.test_aabb(&cube(0.0, 0.0, -20.0, 10.0), &m));
+        // Off to the side
+        assert!(buffer.test_aabb(&cube(20.0, 0.0, -20.0, 1.0), &m));
+        // In front of the occluder
+        assert!(buffer.test_aabb(&cube(0.0, 0.0, -2.0, 0.5), &m));
+        // Entirely off-screen, which is left to frustum culling
+        assert!(buffer.test_aabb(&cube(0.0, 100.0, -20.0, 1.0), &m));
+    }
+
+    #[test]
+    fn empty_buffer_hides_nothing() {
+        let buffer = OcclusionBuffer::new(64, 64);
+
+        assert!(buffer.test_aabb(&cube(0.0, 0.0, -50.0, 1.0), &perspective()));
+    }
+
+    #[test]
+    fn near_plane_is_conservative() {
+        let mut buffer = OcclusionBuffer::new(64, 64);
+        let m = perspective();
+
+        // Crosses the eye, so it can't be projected and doesn't occlude anything
+        assert!(!buffer.rasterize_aabb(&cube(0.0, 0.0, 0.0, 2.0), &m));
+
+        for y in 0..64 {
+            for x in 0..64 {
+                assert_eq!(buffer.depth(x, y), Some(1.0));
+            }
+        }
+
+        buffer.rasterize_aabb(&cube(0.0, 0.0, -5.0, 2.0), &m);
+
+        // Boxes crossing the eye or the near plane are always visible
+        assert!(buffer.test_aabb(&cube(0.0, 0.0, 0.0, 2.0), &m));
+        assert!(buffer.test_aabb(&cube(0.0, 0.0, -1.0, 0.5), &m));
+    }
+}
@@ -0,0 +1,19 @@
+//! Animation sampling system
+//!
+//! Advances animator playback and samples the active clips into joint poses and morph target weights.
+
+use ecs::{self, Join, Delta};
+
+use ::components::animator::Component as Animator;
+
+pub struct System;
+
+impl ecs::System<Delta> for System {
+    fn run(&mut self, arg: ecs::RunArg, delta: Delta) {
+        let mut animators = arg.fetch(|world| world.write::<Animator>());
+
+        for animator in (&mut animators).iter() {
+            animator.update(delta as f32);
+        }
+    }
+}
@@ -0,0 +1,354 @@
+//! Hierarchy propagation
+//!
+//! Propagates local transforms down the scene graph to compute world transforms.
+//! Only subtrees containing a dirty transform are visited.
+//!
+//! This is run by the game's transform system once it has updated the local matrices.
+
+use std::collections::HashSet;
+use std::ops::{Deref, DerefMut};
+
+use petgraph::graph::NodeIndex;
+use nalgebra::{Matrix4, Vector3, Quaternion, Eye};
+
+use ecs::{Join, Storage, MaskedStorage, Allocator, Entities};
+
+use protocols::math::data::{compose_trs, decompose_trs};
+
+use ::Ix;
+use ::edge::SceneEdge;
+use ::graph::SceneGraph;
+use ::node::SceneNodeKind;
+
+use ::components::transform::Component as Transform;
+
+/// Apply only the parts of the parent world matrix the edge inherits
+pub fn inherited(parent: &Matrix4<f32>, edge: &SceneEdge) -> Matrix4<f32> {
+    if edge.inherits_all() {
+        return *parent;
+    }
+
+    let (translation, rotation, scale) = decompose_trs(parent);
+
+    compose_trs(&if edge.inherit_translation { translation } else { Vector3::new(0.0, 0.0, 0.0) },
+                &if edge.inherit_rotation { rotation } else { Quaternion::new(1.0, 0.0, 0.0, 0.0) },
+                &if edge.inherit_scale { scale } else { Vector3::new(1.0, 1.0, 1.0) })
+}
+
+/// Recompute the world matrices of every dirty transform and everything below it in `graph`
+pub fn propagate<A, D>(graph: &SceneGraph, transforms: &mut Storage<Transform, A, D>, entities: &Entities)
+    where A: Deref<Target = Allocator>, D: DerefMut<Target = MaskedStorage<Transform>>
+{
+    // Mark every dirty node and its ancestors, so clean subtrees can be skipped entirely
+    let mut marked: HashSet<NodeIndex<Ix>> = HashSet::new();
+
+    for (transform, entity) in (&mut *transforms, entities).iter() {
+        if !transform.dirty {
+            continue;
+        }
+
+        if let Some(&index) = graph.lookup_index(entity) {
+            if marked.insert(index) {
+                for ancestor in graph.ancestors(index) {
+                    // Anything above a marked node is already marked
+                    if !marked.insert(ancestor) {
+                        break;
+                    }
+                }
+            }
+        } else {
+            // Entities outside the scene graph have no parent
+            transform.world = transform.matrix;
+            transform.dirty = false;
+        }
+    }
+
+    if marked.is_empty() {
+        return;
+    }
+
+    let mut stack = vec![(graph.root(), Matrix4::new_identity(4), false)];
+
+    while let Some((index, parent_world, parent_changed)) = stack.pop() {
+        if !parent_changed && !marked.contains(&index) {
+            continue;
+        }
+
+        let mut world = parent_world;
+        let mut changed = parent_changed;
+
+        if let Some(node) = graph.lookup_node(index) {
+            let edge = graph.parent_edge(index).cloned().unwrap_or_default();
+
+            let parent_world = inherited(&parent_world, &edge);
+
+            let update = |transform: &mut Transform| -> bool {
+                let dirty = parent_changed || transform.dirty;
+
+                if dirty {
+                    transform.world = parent_world * transform.matrix;
+                    transform.dirty = false;
+                }
+
+                dirty
+            };
+
+            match *node.kind() {
+                SceneNodeKind::EntityNode(ref entity_node) => {
+                    if let Some(transform) = transforms.get_mut(entity_node.entity()) {
+                        changed = update(transform);
+                        world = transform.world;
+                    }
+                },
+                SceneNodeKind::MultiEntityNode(ref multi) => {
+                    // Children of multi-entity nodes are relative to the node's parent
+                    for entity in multi.iter() {
+                        if let Some(transform) = transforms.get_mut(*entity) {
+                            update(transform);
+                        }
+                    }
+                },
+                SceneNodeKind::MetaNode => {},
+            }
+        }
+
+        for child in graph.children(index) {
+            stack.push((child, world, changed));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Vector3, Quaternion, Matrix4, Eye, ApproxEq};
+
+    use ecs::{Entity, World};
+
+    use protocols::math::data::compose_trs;
+
+    use ::components;
+    use ::edge::SceneEdge;
+    use ::graph::SceneGraph;
+    use ::node::{SceneNode, SceneNodeExt};
+
+    use super::*;
+
+    fn translation(x: f32, y: f32, z: f32) -> Matrix4<f32> {
+        compose_trs(&Vector3::new(x, y, z), &Quaternion::new(1.0, 0.0, 0.0, 0.0), &Vector3::new(1.0, 1.0, 1.0))
+    }
+
+    fn assert_close(a: &Matrix4<f32>, b: &Matrix4<f32>) {
+        assert!(a.approx_eq_eps(b, &1e-5), "{:?} != {:?}", a, b);
+    }
+
+    struct Scene {
+        world: World,
+    }
+
+    impl Scene {
+        fn new() -> Scene {
+            let mut world = World::new();
+
+            components::register_all(&mut world);
+
+            let graph = SceneGraph::new(&world);
+
+            world.add_resource(graph);
+
+            Scene { world: world }
+        }
+
+        fn spawn(&mut self, parent: Option<Entity>, edge: SceneEdge, matrix: Matrix4<f32>) -> Entity {
+            let world = &mut self.world;
+
+            let entity = world.create_now().with(Transform::from_matrix(matrix)).build();
+
+            let mut graph = world.write_resource::<SceneGraph>();
+
+            let parent = match parent {
+                Some(parent) => *graph.lookup_index(parent).unwrap(),
+                None => graph.root(),
+            };
+
+            graph.add_child_with_edge(parent, SceneNode::new_entity_node(entity), edge).unwrap();
+
+            entity
+        }
+
+        fn run(&mut self) {
+            let graph = self.world.read_resource::<SceneGraph>();
+
+            propagate(&graph, &mut self.world.write::<Transform>(), &self.world.entities());
+        }
+
+        fn with<F, R>(&mut self, entity: Entity, f: F) -> R where F: FnOnce(&mut Transform) -> R {
+            f(self.world.write::<Transform>().get_mut(entity).unwrap())
+        }
+
+        fn world(&mut self, entity: Entity) -> Matrix4<f32> {
+            self.with(entity, |transform| transform.world)
+        }
+    }
+
+    #[test]
+    fn world_transforms_combine_ancestors() {
+        let mut scene = Scene::new();
+
+        let parent = scene.spawn(None, SceneEdge::new(), translation(1.0, 0.0, 0.0));
+        let child = scene.spawn(Some(parent), SceneEdge::new(), translation(0.0, 2.0, 0.0));
+        let grandchild = scene.spawn(Some(child), SceneEdge::new(), translation(0.0, 0.0, 3.0));
+
+        scene.run();
+
+        assert_close(&scene.world(parent), &translation(1.0, 0.0, 0.0));
+        assert_close(&scene.world(child), &translation(1.0, 2.0, 0.0));
+        assert_close(&scene.world(grandchild), &translation(1.0, 2.0, 3.0));
+
+        assert!(!scene.with(grandchild, |transform| transform.dirty));
+    }
+
+    #[test]
+    fn dirty_parent_updates_descendants() {
+        let mut scene = Scene::new();
+
+        let parent = scene.spawn(None, SceneEdge::new(), translation(1.0, 0.0, 0.0));
+        let child = scene.spawn(Some(parent), SceneEdge::new(), translation(0.0, 2.0, 0.0));
+        let grandchild = scene.spawn(Some(child), SceneEdge::new(), translation(0.0, 0.0, 3.0));
+
+        scene.run();
+
+        scene.with(parent, |transform| transform.set_matrix(translation(5.0, 0.0, 0.0)));
+
+        // Only the parent is marked, but its whole subtree follows
+        assert!(!scene.with(grandchild, |transform| transform.dirty));
+
+        scene.run();
+
+        assert_close(&scene.world(child), &translation(5.0, 2.0, 0.0));
+        assert_close(&scene.world(grandchild), &translation(5.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn clean_subtrees_are_skipped() {
+        let mut scene = Scene::new();
+
+        let left = scene.spawn(None, SceneEdge::new(), translation(1.0, 0.0, 0.0));
+        let left_child = scene.spawn(Some(left), SceneEdge::new(), translation(1.0, 0.0, 0.0));
+        let right = scene.spawn(None, SceneEdge::new(), translation(-1.0, 0.0, 0.0));
+        let right_child = scene.spawn(Some(right), SceneEdge::new(), translation(-1.0, 0.0, 0.0));
+
+        scene.run();
+
+        // Overwrite world matrices behind the system's back, which are only recomputed if visited
+        let marker = translation(100.0, 100.0, 100.0);
+
+        for &entity in &[left, left_child, right] {
+            scene.with(entity, |transform| transform.world = marker);
+        }
+
+        // Setting the same matrix again doesn't mark anything
+        scene.with(left, |transform| transform.set_matrix(translation(1.0, 0.0, 0.0)));
+
+        scene.with(right_child, |transform| transform.set_matrix(translation(0.0, -1.0, 0.0)));
+
+        scene.run();
+
+        assert_eq!(scene.world(left), marker);
+        assert_eq!(scene.world(left_child), marker);
+        // Ancestors of a dirty node are visited but not recomputed
+        assert_eq!(scene.world(right), marker);
+
+        assert_close(&scene.world(right_child), &(marker * translation(0.0, -1.0, 0.0)));
+    }
+
+    #[test]
+    fn entities_outside_the_graph() {
+        let mut scene = Scene::new();
+
+        let entity = scene.world.create_now().with(Transform::from_matrix(translation(0.0, 4.0, 0.0))).build();
+
+        scene.run();
+
+        assert_close(&scene.world(entity), &translation(0.0, 4.0, 0.0));
+        assert!(!scene.with(entity, |transform| transform.dirty));
+    }
+
+    #[test]
+    fn multi_entity_nodes_share_their_parent() {
+        let mut scene = Scene::new();
+
+        let parent = scene.spawn(None, SceneEdge::new(), translation(1.0, 0.0, 0.0));
+
+        let (first, second, child) = {
+            let world = &mut scene.world;
+
+            let first = world.create_now().with(Transform::from_matrix(translation(0.0, 1.0, 0.0))).build();
+            let second = world.create_now().with(Transform::from_matrix(translation(0.0, 2.0, 0.0))).build();
+            let child = world.create_now().with(Transform::from_matrix(translation(0.0, 0.0, 1.0))).build();
+
+            let mut graph = world.write_resource::<SceneGraph>();
+
+            let parent_index = *graph.lookup_index(parent).unwrap();
+
+            let multi = graph.add_child(parent_index, SceneNode::new_multi_entity_node(Some(vec![first, second]))).unwrap();
+
+            graph.add_child(multi, SceneNode::new_entity_node(child)).unwrap();
+
+            (first, second, child)
+        };
+
+        scene.run();
+
+        assert_close(&scene.world(first), &translation(1.0, 1.0, 0.0));
+        assert_close(&scene.world(second), &translation(1.0, 2.0, 0.0));
+        assert_close(&scene.world(child), &translation(1.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn inherit_flag_combinations() {
+        let t = Vector3::new(1.0, 2.0, 3.0);
+        let r = Quaternion::new(0.5f32.sqrt(), 0.0, 0.0, 0.5f32.sqrt());
+        let s = Vector3::new(2.0, 3.0, 4.0);
+
+        let identity_rotation = Quaternion::new(1.0, 0.0, 0.0, 0.0);
+
+        let parent = compose_trs(&t, &r, &s);
+        let local = translation(1.0, 1.0, 1.0);
+
+        for &inherit_translation in &[true, false] {
+            for &inherit_rotation in &[true, false] {
+                for &inherit_scale in &[true, false] {
+                    let edge = SceneEdge {
+                        inherit_translation: inherit_translation,
+                        inherit_rotation: inherit_rotation,
+                        inherit_scale: inherit_scale,
+                    };
+
+                    let expected = compose_trs(&if inherit_translation { t } else { Vector3::new(0.0, 0.0, 0.0) },
+                                               &if inherit_rotation { r } else { identity_rotation },
+                                               &if inherit_scale { s } else { Vector3::new(1.0, 1.0, 1.0) });
+
+                    assert_close(&inherited(&parent, &edge), &expected);
+
+                    // The same through propagation
+                    let mut scene = Scene::new();
+
+                    let parent_entity = scene.spawn(None, SceneEdge::new(), parent);
+                    let child = scene.spawn(Some(parent_entity), edge, local);
+
+                    scene.run();
+
+                    assert_close(&scene.world(child), &(expected * local));
+                }
+            }
+        }
+
+        assert_eq!(inherited(&parent, &SceneEdge::new()), parent);
+        assert_close(&inherited(&parent, &SceneEdge::without_scale()), &compose_trs(&t, &r, &Vector3::new(1.0, 1.0, 1.0)));
+
+        // Nothing inherited leaves only the local transform
+        let nothing = SceneEdge { inherit_translation: false, inherit_rotation: false, inherit_scale: false };
+
+        assert_close(&inherited(&parent, &nothing), &Matrix4::new_identity(4));
+    }
+}
@@ -0,0 +1,6 @@
+//! Systems keeping scene components up to date
+
+pub mod animation;
+pub mod skinning;
+pub mod hierarchy;
+pub mod spatial;
@@ -0,0 +1,96 @@
+//! Skinning system
+//!
+//! Converts sampled animator poses into joint palettes for the renderer,
+//! and skins meshes on the CPU for palettes that request it.
+
+use ecs::{self, Join, Delta};
+
+use ::components::animator::Component as Animator;
+use ::components::joint_palette::Component as JointPalette;
+
+pub struct System;
+
+impl ecs::System<Delta> for System {
+    fn run(&mut self, arg: ecs::RunArg, _: Delta) {
+        let (animators, mut palettes) = arg.fetch(|world| {
+            (
+                world.read::<Animator>(),
+                world.write::<JointPalette>(),
+            )
+        });
+
+        for (animator, palette) in (&animators, &mut palettes).iter() {
+            palette.update(animator);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use nalgebra::{Vector3, Quaternion, Matrix4, Eye};
+
+    use ecs::{Planner, World};
+
+    use protocols::model::data::{Model, Skeleton, Joint, AnimationClip, Channel, ChannelKeyframes, Keyframe, Interpolation};
+
+    use ::components;
+    use ::systems::animation::System as AnimationSystem;
+
+    use super::*;
+
+    #[test]
+    fn animation_then_skinning() {
+        let model = Arc::new(Model {
+            skeletons: vec![Skeleton {
+                name: "Root".to_string(),
+                joints: vec![Joint {
+                    name: "root".to_string(),
+                    parent: None,
+                    inverse_bind: Matrix4::new_identity(4),
+                    translation: Vector3::new(0.0, 0.0, 0.0),
+                    rotation: Quaternion::new(1.0, 0.0, 0.0, 0.0),
+                    scale: Vector3::new(1.0, 1.0, 1.0),
+                }],
+            }],
+            animations: vec![AnimationClip {
+                name: "Move".to_string(),
+                duration: 1.0,
+                channels: vec![Channel {
+                    skeleton: 0,
+                    joint: 0,
+                    interpolation: Interpolation::Step,
+                    keyframes: ChannelKeyframes::Translation(vec![
+                        Keyframe::new(0.0, Vector3::new(0.0, 0.0, 0.0)),
+                        Keyframe::new(0.5, Vector3::new(0.0, 0.0, 2.0)),
+                    ]),
+                }],
+            }],
+            ..Model::default()
+        });
+
+        let mut world = World::new();
+
+        components::register_all(&mut world);
+
+        let mut animator = Animator::new(model, 0);
+
+        animator.play(0, false);
+
+        let entity = world.create_now().with(animator).with(JointPalette::new()).build();
+
+        let mut planner = Planner::new(world, 2);
+
+        // Skinning has to see the pose sampled in the same frame
+        planner.add_system(AnimationSystem, "animation", 2);
+        planner.add_system(System, "skinning", 1);
+
+        planner.dispatch(0.75);
+        planner.wait();
+
+        let palettes = planner.mut_world().read::<JointPalette>();
+
+        assert_eq!(palettes.get(entity).unwrap().matrices[0].m34, 2.0);
+    }
+}
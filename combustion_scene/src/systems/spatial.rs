@@ -0,0 +1,234 @@
+//! Spatial system
+//!
+//! Keeps the `Bvh` resource of the world in sync with the world transforms computed by the game's transform system.
+//! Only entities whose world bounding box changed are updated in the index.
+
+use ecs::{self, Join, Delta};
+
+use ::spatial::Bvh;
+
+use ::components::bounds::Component as Bounds;
+use ::components::transform::Component as Transform;
+
+pub struct System {
+    /// Number of tree restructures after which the index is rebuilt from scratch
+    pub rebuild_threshold: usize,
+    restructures: usize,
+}
+
+impl Default for System {
+    #[inline(always)]
+    fn default() -> System { System::new() }
+}
+
+impl System {
+    pub fn new() -> System {
+        System { rebuild_threshold: 1024, restructures: 0 }
+    }
+}
+
+impl ecs::System<Delta> for System {
+    fn run(&mut self, arg: ecs::RunArg, _: Delta) {
+        let (transforms, mut bounds, mut spatial, entities) = arg.fetch(|world| {
+            (
+                world.read::<Transform>(),
+                world.write::<Bounds>(),
+                world.write_resource::<Bvh>(),
+                world.entities(),
+            )
+        });
+
+        for (bounds, transform, entity) in (&mut bounds, &transforms, &entities).iter() {
+            let aabb = bounds.local.aabb.transform(&transform.world);
+
+            if bounds.world != Some(aabb) {
+                bounds.world = Some(aabb);
+
+                if spatial.update(entity, aabb) {
+                    self.restructures += 1;
+                }
+            }
+        }
+
+        // Drop entities which were deleted or lost their bounds or transform
+        spatial.retain(|entity| bounds.get(entity).and_then(|bounds| bounds.world).is_some() && transforms.get(entity).is_some());
+
+        if self.restructures >= self.rebuild_threshold {
+            spatial.rebuild();
+
+            self.restructures = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Vector3, Quaternion, Point3, Matrix4};
+
+    use ecs::{Entity, Planner, World};
+
+    use protocols::math::data::{Aabb, Bounds as Volume, compose_trs};
+
+    use ::components;
+    use ::graph::SceneGraph;
+    use ::node::{SceneNode, SceneNodeExt};
+    use ::systems::hierarchy::propagate;
+
+    use super::*;
+
+    /// Stands in for the game's transform system
+    struct Hierarchy;
+
+    impl ecs::System<Delta> for Hierarchy {
+        fn run(&mut self, arg: ecs::RunArg, _: Delta) {
+            let (graph, mut transforms, entities) = arg.fetch(|world| {
+                (
+                    world.read_resource::<SceneGraph>(),
+                    world.write::<Transform>(),
+                    world.entities(),
+                )
+            });
+
+            propagate(&graph, &mut transforms, &entities);
+        }
+    }
+
+    fn translation(x: f32, y: f32, z: f32) -> Matrix4<f32> {
+        compose_trs(&Vector3::new(x, y, z), &Quaternion::new(1.0, 0.0, 0.0, 0.0), &Vector3::new(1.0, 1.0, 1.0))
+    }
+
+    fn unit_bounds() -> Volume {
+        let corners = [Point3::new(-0.5, -0.5, -0.5), Point3::new(0.5, 0.5, 0.5)];
+
+        Volume::from_points(corners.iter()).unwrap()
+    }
+
+    fn around(x: f32, y: f32, z: f32) -> Aabb {
+        Aabb::new(Point3::new(x - 0.1, y - 0.1, z - 0.1), Point3::new(x + 0.1, y + 0.1, z + 0.1))
+    }
+
+    fn planner(rebuild_threshold: usize) -> Planner {
+        let mut world = World::new();
+
+        components::register_all(&mut world);
+
+        let graph = SceneGraph::new(&world);
+
+        world.add_resource(graph);
+        world.add_resource(Bvh::new());
+
+        let mut planner = Planner::new(world, 1);
+
+        planner.add_system(Hierarchy, "hierarchy", 2);
+        planner.add_system(System { rebuild_threshold: rebuild_threshold, restructures: 0 }, "spatial", 1);
+
+        planner
+    }
+
+    fn spawn(planner: &mut Planner, parent: Option<Entity>, x: f32) -> Entity {
+        let world = planner.mut_world();
+
+        let entity = world.create_now()
+                          .with(Transform::from_matrix(translation(x, 0.0, 0.0)))
+                          .with(Bounds::new(unit_bounds()))
+                          .build();
+
+        let mut graph = world.write_resource::<SceneGraph>();
+
+        let parent = match parent {
+            Some(parent) => *graph.lookup_index(parent).unwrap(),
+            None => graph.root(),
+        };
+
+        graph.add_child(parent, SceneNode::new_entity_node(entity)).unwrap();
+
+        entity
+    }
+
+    fn run(planner: &mut Planner) {
+        planner.dispatch(0.0);
+        planner.wait();
+    }
+
+    fn query(planner: &mut Planner, aabb: &Aabb) -> Vec<Entity> {
+        planner.mut_world().read_resource::<Bvh>().query_aabb(aabb)
+    }
+
+    #[test]
+    fn index_follows_world_transforms() {
+        let mut planner = planner(1024);
+
+        let parent = spawn(&mut planner, None, 0.0);
+        let child = spawn(&mut planner, Some(parent), 10.0);
+
+        run(&mut planner);
+
+        assert_eq!(query(&mut planner, &around(0.0, 0.0, 0.0)), vec![parent]);
+        assert_eq!(query(&mut planner, &around(10.0, 0.0, 0.0)), vec![child]);
+
+        // Moving the parent moves the child's world box with it
+        planner.mut_world().write::<Transform>().get_mut(parent).unwrap().set_matrix(translation(0.0, 5.0, 0.0));
+
+        run(&mut planner);
+
+        assert!(query(&mut planner, &around(10.0, 0.0, 0.0)).is_empty());
+        assert_eq!(query(&mut planner, &around(10.0, 5.0, 0.0)), vec![child]);
+
+        let world = planner.mut_world();
+
+        assert_eq!(world.read::<Bounds>().get(child).unwrap().world, Some(unit_bounds().aabb.transform(&translation(10.0, 5.0, 0.0))));
+    }
+
+    #[test]
+    fn removed_entities_leave_the_index() {
+        let mut planner = planner(1024);
+
+        let first = spawn(&mut planner, None, 0.0);
+        let second = spawn(&mut planner, None, 3.0);
+
+        run(&mut planner);
+
+        assert_eq!(planner.mut_world().read_resource::<Bvh>().len(), 2);
+
+        planner.mut_world().write::<Bounds>().remove(first);
+
+        run(&mut planner);
+
+        assert!(!planner.mut_world().read_resource::<Bvh>().contains(first));
+
+        {
+            let world = planner.mut_world();
+
+            world.delete_later(second);
+            world.maintain();
+        }
+
+        run(&mut planner);
+
+        assert!(planner.mut_world().read_resource::<Bvh>().is_empty());
+    }
+
+    #[test]
+    fn rebuilds_after_restructures() {
+        let mut planner = planner(2);
+
+        let entities: Vec<Entity> = (0..4).map(|i| spawn(&mut planner, None, i as f32 * 2.0)).collect();
+
+        // Inserting counts as restructuring, so the first run already rebuilds and resets the count
+        run(&mut planner);
+
+        for (i, entity) in entities.iter().enumerate() {
+            planner.mut_world().write::<Transform>().get_mut(*entity).unwrap().set_matrix(translation(i as f32 * 2.0, 50.0, 0.0));
+        }
+
+        run(&mut planner);
+
+        let world = planner.mut_world();
+        let bvh = world.read_resource::<Bvh>();
+
+        assert_eq!(bvh.len(), 4);
+        // A rebuilt tree of four entities is perfectly balanced
+        assert_eq!(bvh.height(), 3);
+        assert_eq!(bvh.query_aabb(&around(2.0, 50.0, 0.0)), vec![entities[1]]);
+    }
+}
@@ -0,0 +1,326 @@
+//! The `world` table given to scripts while they run
+//!
+//! * `world.create()` - Create a new entity
+//! * `world.delete(entity)` - Delete an entity at the end of the frame
+//! * `world.is_alive(entity)`
+//! * `world.has(entity, component)`
+//! * `world.get(entity, component, [path])` - Value at the path of a component, or `nil` if the entity doesn't have it
+//! * `world.set(entity, component, path, value)`
+//! * `world.add(entity, component, [fields])` - Add a component with its default value, then set any given fields
+//! * `world.remove(entity, component)` - Remove a component, returning whether the entity had it
+//! * `world.fields(entity, component)` - Array of `{name, type}` tables for the fields of a component
+//! * `world.query(component...)` - Array of every entity which has all of the given components
+//! * `world.components()` - Names of every component scripts can access
+//!
+//! All of these except `create`, `delete` and `query` are also methods on entities, like `entity:get("Light", "color")`.
+//!
+//! Components are accessed by the names they were registered with in `ScriptComponents`.
+
+use rlua::{Lua, Table, Value, Variadic};
+
+use ecs::{Join, World};
+
+use ::components::ScriptComponents;
+use ::error::ScriptResult;
+use ::value::{LuaEntity, WORLD_REGISTRY_KEY, inspect_to_lua, lua_to_inspect, lua_error};
+
+/// Run `f` with the `world` table available to the scripts of a Lua state
+pub fn with_world<F, R>(lua: &Lua, world: &World, components: &ScriptComponents, f: F) -> ScriptResult<R>
+    where F: FnOnce() -> ScriptResult<R> {
+    lua.scope(|scope| {
+        let table = try_throw!(lua.create_table());
+
+        try_throw!(table.set("create", try_throw!(scope.create_function(|_, ()| {
+            Ok(LuaEntity(world.create_later()))
+        }))));
+
+        try_throw!(table.set("delete", try_throw!(scope.create_function(|_, entity: LuaEntity| {
+            world.delete_later(entity.0);
+
+            Ok(())
+        }))));
+
+        try_throw!(table.set("is_alive", try_throw!(scope.create_function(|_, entity: LuaEntity| {
+            Ok(world.is_alive(entity.0))
+        }))));
+
+        try_throw!(table.set("has", try_throw!(scope.create_function(|_, (entity, name): (LuaEntity, String)| {
+            components.has(world, entity.0, &name).map_err(lua_error)
+        }))));
+
+        try_throw!(table.set("get", try_throw!(scope.create_function(|lua, (entity, name, path): (LuaEntity, String, Option<String>)| {
+            let path = path.unwrap_or_default();
+
+            match try!(components.get(world, entity.0, &name, &path).map_err(lua_error)) {
+                Some(value) => inspect_to_lua(lua, value),
+                None => Ok(Value::Nil),
+            }
+        }))));
+
+        try_throw!(table.set("set", try_throw!(scope.create_function(|_, (entity, name, path, value): (LuaEntity, String, String, Value)| {
+            // The current value decides how the new one is converted
+            let current = try!(components.get(world, entity.0, &name, &path).map_err(lua_error));
+
+            let value = try!(lua_to_inspect(value, current.as_ref()));
+
+            components.set(world, entity.0, &name, &path, value).map_err(lua_error)
+        }))));
+
+        try_throw!(table.set("add", try_throw!(scope.create_function(|_, (entity, name, fields): (LuaEntity, String, Option<Table>)| {
+            try!(components.add(world, entity.0, &name).map_err(lua_error));
+
+            if let Some(fields) = fields {
+                for pair in fields.pairs::<String, Value>() {
+                    let (path, value) = try!(pair);
+
+                    let current = try!(components.get(world, entity.0, &name, &path).map_err(lua_error));
+
+                    let value = try!(lua_to_inspect(value, current.as_ref()));
+
+                    try!(components.set(world, entity.0, &name, &path, value).map_err(lua_error));
+                }
+            }
+
+            Ok(())
+        }))));
+
+        try_throw!(table.set("remove", try_throw!(scope.create_function(|_, (entity, name): (LuaEntity, String)| {
+            components.remove(world, entity.0, &name).map_err(lua_error)
+        }))));
+
+        try_throw!(table.set("fields", try_throw!(scope.create_function(|lua, (entity, name): (LuaEntity, String)| {
+            match try!(components.fields(world, entity.0, &name).map_err(lua_error)) {
+                Some(fields) => {
+                    let list = try!(lua.create_table());
+
+                    for (i, field) in fields.into_iter().enumerate() {
+                        let info = try!(lua.create_table());
+
+                        try!(info.set("name", field.name));
+                        try!(info.set("type", field.type_name));
+
+                        try!(list.set(i + 1, info));
+                    }
+
+                    Ok(Value::Table(list))
+                }
+                None => Ok(Value::Nil),
+            }
+        }))));
+
+        try_throw!(table.set("query", try_throw!(scope.create_function(|_, names: Variadic<String>| {
+            let entities = world.entities();
+
+            let mut found = Vec::new();
+
+            for entity in (&entities).iter() {
+                let mut matches = true;
+
+                for name in names.iter() {
+                    if !try!(components.has(world, entity, name).map_err(lua_error)) {
+                        matches = false;
+                        break;
+                    }
+                }
+
+                if matches {
+                    found.push(LuaEntity(entity));
+                }
+            }
+
+            Ok(found)
+        }))));
+
+        try_throw!(table.set("components", try_throw!(scope.create_function(|_, ()| {
+            Ok(components.names().into_iter().map(|name| name.to_string()).collect::<Vec<_>>())
+        }))));
+
+        try_throw!(lua.set_named_registry_value(WORLD_REGISTRY_KEY, table.clone()));
+
+        try_throw!(lua.globals().set("world", table));
+
+        let result = f();
+
+        // Calling any of the functions after this would be an error anyway, but this gives a clearer one
+        try_throw!(lua.set_named_registry_value(WORLD_REGISTRY_KEY, Value::Nil));
+        try_throw!(lua.globals().set("world", Value::Nil));
+
+        result
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use rlua::Lua;
+
+    use common::color::Color;
+
+    use ecs::{self, World};
+
+    use ::components::ScriptComponents;
+    use ::value::LuaEntity;
+
+    use super::*;
+
+    #[derive(Debug, Clone, Default, PartialEq, Inspect)]
+    struct Health {
+        current: i64,
+        max: i64,
+    }
+
+    impl ecs::Component for Health {
+        type Storage = ecs::VecStorage<Health>;
+    }
+
+    #[derive(Debug, Clone, PartialEq, Inspect)]
+    struct Tint {
+        color: Color,
+        strength: f32,
+    }
+
+    impl Default for Tint {
+        fn default() -> Tint {
+            Tint { color: Color::new(1.0, 1.0, 1.0, 1.0), strength: 1.0 }
+        }
+    }
+
+    impl ecs::Component for Tint {
+        type Storage = ecs::VecStorage<Tint>;
+    }
+
+    fn setup() -> (World, ScriptComponents) {
+        let mut world = World::new();
+
+        world.register::<Health>();
+        world.register::<Tint>();
+
+        let mut components = ScriptComponents::new();
+
+        components.register::<Health>("Health");
+        components.register::<Tint>("Tint");
+
+        (world, components)
+    }
+
+    fn run(lua: &Lua, world: &World, components: &ScriptComponents, source: &str) -> ScriptResult<()> {
+        with_world(lua, world, components, || Ok(try_throw!(lua.exec::<()>(source, None))))
+    }
+
+    #[test]
+    fn component_access() {
+        let (mut world, components) = setup();
+
+        let player = world.create_now().with(Health { current: 50, max: 100 }).build();
+        let other = world.create_now().build();
+
+        let lua = Lua::new();
+
+        lua.globals().set("player", LuaEntity(player)).unwrap();
+        lua.globals().set("other", LuaEntity(other)).unwrap();
+
+        run(&lua, &world, &components, r#"
+            assert(world.has(player, "Health"))
+            assert(world.get(player, "Health", "current") == 50)
+
+            world.set(player, "Health", "current", 25)
+            player:set("Health", "max", 80)
+
+            assert(player:get("Health", "max") == 80)
+
+            local fields = world.fields(player, "Health")
+
+            assert(#fields == 2)
+            assert(fields[1].name == "current" and fields[1].type == "i64")
+
+            assert(world.get(other, "Health", "current") == nil)
+            assert(world.fields(other, "Health") == nil)
+
+            world.add(other, "Tint", {strength = 0.5, color = {r = 1, g = 0, b = 0}})
+
+            assert(other:has("Tint"))
+            assert(other:get("Tint", "color.g") == 0)
+            assert(other:get("Tint", "color").a == 1)
+
+            assert(#world.query("Health") == 1)
+            assert(#world.query("Tint") == 1)
+            assert(#world.query("Health", "Tint") == 0)
+
+            assert(world.remove(other, "Tint"))
+            assert(not other:remove("Tint"))
+
+            assert(not pcall(world.get, player, "Mana", "value"))
+            assert(not pcall(world.set, other, "Health", "current", 1))
+            assert(not pcall(world.set, player, "Health", "current", "lots"))
+        "#).unwrap();
+
+        assert_eq!(world.read::<Health>().get(player), Some(&Health { current: 25, max: 80 }));
+        assert!(world.read::<Tint>().get(other).is_none());
+    }
+
+    #[test]
+    fn add_sets_fields_with_matching_types() {
+        let (mut world, components) = setup();
+
+        let entity = world.create_now().build();
+
+        let lua = Lua::new();
+
+        lua.globals().set("entity", LuaEntity(entity)).unwrap();
+
+        run(&lua, &world, &components, r#"world.add(entity, "Tint", {strength = 2, color = {r = 0.5, g = 0.25, b = 0, a = 0.5}})"#).unwrap();
+
+        assert_eq!(world.read::<Tint>().get(entity), Some(&Tint { color: Color::new(0.5, 0.25, 0.0, 0.5), strength: 2.0 }));
+    }
+
+    #[test]
+    fn create_and_delete() {
+        let (mut world, components) = setup();
+
+        let doomed = world.create_now().build();
+
+        let lua = Lua::new();
+
+        lua.globals().set("doomed", LuaEntity(doomed)).unwrap();
+
+        run(&lua, &world, &components, r#"
+            created = world.create()
+
+            assert(world.is_alive(doomed))
+
+            doomed:delete()
+
+            local names = world.components()
+
+            table.sort(names)
+
+            assert(names[1] == "Health" and names[2] == "Tint")
+        "#).unwrap();
+
+        let created: LuaEntity = lua.globals().get("created").unwrap();
+
+        world.maintain();
+
+        assert!(world.is_alive(created.0));
+        assert!(!world.is_alive(doomed));
+    }
+
+    #[test]
+    fn world_is_removed_afterwards() {
+        let (mut world, components) = setup();
+
+        let entity = world.create_now().with(Health::default()).build();
+
+        let lua = Lua::new();
+
+        lua.globals().set("entity", LuaEntity(entity)).unwrap();
+
+        run(&lua, &world, &components, "assert(entity:has('Health'))").unwrap();
+
+        assert!(lua.exec::<()>("assert(world == nil)", None).is_ok());
+        assert!(lua.exec::<()>("entity:has('Health')", None).is_err());
+
+        // Errors from the script are passed through
+        assert!(run(&lua, &world, &components, "error('boom')").is_err());
+        assert!(lua.exec::<()>("assert(world == nil)", None).is_ok());
+    }
+}
@@ -0,0 +1,27 @@
+//! Script component
+
+use std::path::{Path, PathBuf};
+
+use ecs::{Component, HashMapStorage};
+
+/// Script attached to an entity, run each frame by the script system
+#[derive(Debug, Clone)]
+pub struct Script {
+    path: PathBuf,
+    /// Disabled scripts are destroyed, and started again when re-enabled
+    pub enabled: bool,
+}
+
+impl Script {
+    /// Attach the script at the given path in the virtual file system
+    pub fn new<P: AsRef<Path>>(path: P) -> Script {
+        Script { path: path.as_ref().to_path_buf(), enabled: true }
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path { &self.path }
+}
+
+impl Component for Script {
+    type Storage = HashMapStorage<Script>;
+}
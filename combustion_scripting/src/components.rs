@@ -0,0 +1,223 @@
+//! Components which scripts can access by name
+//!
+//! Any component implementing `Inspect` can be registered, and scripts then read and write its fields
+//! by path through the same `Inspect` interface the editor uses.
+
+use fnv::FnvHashMap;
+
+use common::traits::inspect::{FieldInfo, Inspect, InspectResult, InspectValue};
+
+use ecs::{Component, Entity, World};
+
+use ::error::{ScriptError, ScriptResult};
+
+struct ComponentAccessor {
+    has: fn(&World, Entity) -> bool,
+    get: fn(&World, Entity, &str) -> Option<Option<InspectValue>>,
+    set: fn(&World, Entity, &str, InspectValue) -> Option<InspectResult<()>>,
+    fields: fn(&World, Entity) -> Option<Vec<FieldInfo>>,
+    add: fn(&World, Entity),
+    remove: fn(&World, Entity) -> bool,
+}
+
+fn has_component<C: Component>(world: &World, entity: Entity) -> bool {
+    world.read::<C>().get(entity).is_some()
+}
+
+fn get_component<C: Component + Inspect>(world: &World, entity: Entity, path: &str) -> Option<Option<InspectValue>> {
+    world.read::<C>().get(entity).map(|component| component.get(path))
+}
+
+fn set_component<C: Component + Inspect>(world: &World, entity: Entity, path: &str, value: InspectValue) -> Option<InspectResult<()>> {
+    world.write::<C>().get_mut(entity).map(|component| component.set(path, value))
+}
+
+fn component_fields<C: Component + Inspect>(world: &World, entity: Entity) -> Option<Vec<FieldInfo>> {
+    world.read::<C>().get(entity).map(|component| component.fields())
+}
+
+fn add_component<C: Component + Default>(world: &World, entity: Entity) {
+    world.write::<C>().insert(entity, C::default());
+}
+
+fn remove_component<C: Component>(world: &World, entity: Entity) -> bool {
+    world.write::<C>().remove(entity).is_some()
+}
+
+/// Set of components scripts can access, by name
+#[derive(Default)]
+pub struct ScriptComponents {
+    entries: FnvHashMap<String, ComponentAccessor>,
+}
+
+impl ScriptComponents {
+    pub fn new() -> ScriptComponents {
+        ScriptComponents::default()
+    }
+
+    /// Make a component available to scripts under the given name.
+    ///
+    /// Scripts can add the component to entities with its default value, then set its fields.
+    pub fn register<C: Component + Inspect + Default>(&mut self, name: &str) {
+        self.entries.insert(name.to_string(), ComponentAccessor {
+            has: has_component::<C>,
+            get: get_component::<C>,
+            set: set_component::<C>,
+            fields: component_fields::<C>,
+            add: add_component::<C>,
+            remove: remove_component::<C>,
+        });
+    }
+
+    #[inline]
+    pub fn is_registered(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    /// Names of all registered components
+    pub fn names(&self) -> Vec<&str> {
+        self.entries.keys().map(|name| name.as_str()).collect()
+    }
+
+    fn entry(&self, name: &str) -> ScriptResult<&ComponentAccessor> {
+        match self.entries.get(name) {
+            Some(entry) => Ok(entry),
+            None => throw!(ScriptError::UnknownComponent(name.to_string())),
+        }
+    }
+
+    /// Check if an entity has a component
+    pub fn has(&self, world: &World, entity: Entity, name: &str) -> ScriptResult<bool> {
+        Ok((try_rethrow!(self.entry(name)).has)(world, entity))
+    }
+
+    /// Value at the given path of an entity's component, or `None` if the entity doesn't have it
+    pub fn get(&self, world: &World, entity: Entity, name: &str, path: &str) -> ScriptResult<Option<InspectValue>> {
+        Ok((try_rethrow!(self.entry(name)).get)(world, entity, path).and_then(|value| value))
+    }
+
+    /// Set the value at the given path of an entity's component
+    pub fn set(&self, world: &World, entity: Entity, name: &str, path: &str, value: InspectValue) -> ScriptResult<()> {
+        match (try_rethrow!(self.entry(name)).set)(world, entity, path, value) {
+            Some(result) => Ok(try_throw!(result)),
+            None => throw!(ScriptError::MissingComponent(name.to_string())),
+        }
+    }
+
+    /// Fields of an entity's component, or `None` if the entity doesn't have it
+    pub fn fields(&self, world: &World, entity: Entity, name: &str) -> ScriptResult<Option<Vec<FieldInfo>>> {
+        Ok((try_rethrow!(self.entry(name)).fields)(world, entity))
+    }
+
+    /// Add a component with its default value, replacing any existing one
+    pub fn add(&self, world: &World, entity: Entity, name: &str) -> ScriptResult<()> {
+        Ok((try_rethrow!(self.entry(name)).add)(world, entity))
+    }
+
+    /// Remove a component, returning whether the entity had it
+    pub fn remove(&self, world: &World, entity: Entity, name: &str) -> ScriptResult<bool> {
+        Ok((try_rethrow!(self.entry(name)).remove)(world, entity))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use common::traits::inspect::{InspectError, InspectValue};
+
+    use ecs::{self, World};
+
+    use ::error::ScriptError;
+
+    use super::*;
+
+    #[derive(Debug, Clone, Default, PartialEq, Inspect)]
+    struct Health {
+        current: i64,
+        max: i64,
+    }
+
+    impl ecs::Component for Health {
+        type Storage = ecs::VecStorage<Health>;
+    }
+
+    fn setup() -> (World, ScriptComponents, Entity) {
+        let mut world = World::new();
+
+        world.register::<Health>();
+
+        let entity = world.create_now().with(Health { current: 5, max: 10 }).build();
+
+        let mut components = ScriptComponents::new();
+
+        components.register::<Health>("Health");
+
+        (world, components, entity)
+    }
+
+    #[test]
+    fn registered_names() {
+        let (_, components, _) = setup();
+
+        assert!(components.is_registered("Health"));
+        assert!(!components.is_registered("Mana"));
+        assert_eq!(components.names(), vec!["Health"]);
+    }
+
+    #[test]
+    fn get_and_set_fields() {
+        let (world, components, entity) = setup();
+
+        assert!(components.has(&world, entity, "Health").unwrap());
+        assert_eq!(components.get(&world, entity, "Health", "current").unwrap(), Some(InspectValue::Integer(5)));
+
+        components.set(&world, entity, "Health", "current", InspectValue::Integer(7)).unwrap();
+
+        assert_eq!(world.read::<Health>().get(entity), Some(&Health { current: 7, max: 10 }));
+
+        let fields = components.fields(&world, entity, "Health").unwrap().unwrap();
+
+        assert_eq!(fields.iter().map(|field| field.name).collect::<Vec<_>>(), vec!["current", "max"]);
+
+        match *components.set(&world, entity, "Health", "current", InspectValue::Bool(true)).unwrap_err().error() {
+            ScriptError::Inspect(InspectError::InvalidValue("i64")) => {}
+            ref err => panic!("Unexpected error {:?}", err),
+        }
+    }
+
+    #[test]
+    fn add_and_remove() {
+        let (mut world, components, _) = setup();
+
+        let entity = world.create_now().build();
+
+        assert!(!components.has(&world, entity, "Health").unwrap());
+        assert_eq!(components.get(&world, entity, "Health", "current").unwrap(), None);
+        assert!(components.fields(&world, entity, "Health").unwrap().is_none());
+
+        match *components.set(&world, entity, "Health", "current", InspectValue::Integer(1)).unwrap_err().error() {
+            ScriptError::MissingComponent(ref name) => assert_eq!(name, "Health"),
+            ref err => panic!("Unexpected error {:?}", err),
+        }
+
+        components.add(&world, entity, "Health").unwrap();
+
+        assert_eq!(world.read::<Health>().get(entity), Some(&Health::default()));
+
+        assert!(components.remove(&world, entity, "Health").unwrap());
+        assert!(!components.remove(&world, entity, "Health").unwrap());
+        assert!(!components.has(&world, entity, "Health").unwrap());
+    }
+
+    #[test]
+    fn unknown_components() {
+        let (world, components, entity) = setup();
+
+        match *components.has(&world, entity, "Mana").unwrap_err().error() {
+            ScriptError::UnknownComponent(ref name) => assert_eq!(name, "Mana"),
+            ref err => panic!("Unexpected error {:?}", err),
+        }
+
+        assert!(components.get(&world, entity, "Mana", "").is_err());
+        assert!(components.add(&world, entity, "Mana").is_err());
+    }
+}
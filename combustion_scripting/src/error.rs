@@ -0,0 +1,108 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io;
+use std::path::PathBuf;
+
+use rlua;
+
+use trace_error::TraceResult;
+
+use common::traits::inspect::InspectError;
+
+pub type ScriptResult<T> = TraceResult<T, ScriptError>;
+
+#[derive(Debug)]
+pub enum ScriptError {
+    Io(io::Error),
+    Lua(rlua::Error),
+    Inspect(InspectError),
+    /// No component is registered for scripts with the given name
+    UnknownComponent(String),
+    /// The entity doesn't have the given component
+    MissingComponent(String),
+}
+
+/// Split a Lua error message in the `path:line: message` format into its parts
+fn split_location(message: &str) -> Option<(&str, u32, &str)> {
+    for (index, _) in message.match_indices(':') {
+        let rest = &message[index + 1..];
+
+        let digits = rest.find(|c: char| !c.is_digit(10)).unwrap_or(rest.len());
+
+        if digits > 0 && rest[digits..].starts_with(": ") {
+            if let Ok(line) = rest[..digits].parse() {
+                return Some((&message[..index], line, &rest[digits + 2..]));
+            }
+        }
+    }
+
+    None
+}
+
+/// Innermost message of a Lua error, without the errors of the callbacks it passed through
+fn lua_message(err: &rlua::Error) -> String {
+    match *err {
+        rlua::Error::RuntimeError(ref message) |
+        rlua::Error::SyntaxError { ref message, .. } => message.clone(),
+        rlua::Error::CallbackError { ref cause, .. } => lua_message(cause),
+        _ => err.to_string(),
+    }
+}
+
+impl ScriptError {
+    /// File and line the error was raised at, along with the message by itself.
+    ///
+    /// Only errors raised by Lua code have a location.
+    pub fn location(&self) -> Option<(PathBuf, u32, String)> {
+        match *self {
+            ScriptError::Lua(ref err) => {
+                let message = lua_message(err);
+
+                split_location(&message).map(|(file, line, message)| (PathBuf::from(file), line, message.to_string()))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ScriptError {
+    fn from(err: io::Error) -> ScriptError {
+        ScriptError::Io(err)
+    }
+}
+
+impl From<rlua::Error> for ScriptError {
+    fn from(err: rlua::Error) -> ScriptError {
+        ScriptError::Lua(err)
+    }
+}
+
+impl From<InspectError> for ScriptError {
+    fn from(err: InspectError) -> ScriptError {
+        ScriptError::Inspect(err)
+    }
+}
+
+impl Display for ScriptError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            ScriptError::Io(ref err) => err.fmt(f),
+            ScriptError::Lua(ref err) => err.fmt(f),
+            ScriptError::Inspect(ref err) => err.fmt(f),
+            ScriptError::UnknownComponent(ref name) |
+            ScriptError::MissingComponent(ref name) => write!(f, "{}: {}", self.description(), name),
+        }
+    }
+}
+
+impl Error for ScriptError {
+    fn description(&self) -> &str {
+        match *self {
+            ScriptError::Io(ref err) => err.description(),
+            ScriptError::Lua(ref err) => err.description(),
+            ScriptError::Inspect(ref err) => err.description(),
+            ScriptError::UnknownComponent(_) => "Unknown Component",
+            ScriptError::MissingComponent(_) => "Missing Component",
+        }
+    }
+}
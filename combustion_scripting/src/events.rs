@@ -0,0 +1,311 @@
+//! Built-in events as Lua tables
+//!
+//! Every event is a table with a `type` field naming the variant, like `{type = "key", key = 65, action = "press", ...}`.
+//! Scripts subscribe to a kind of event with `events.on(kind, handler)`, where the kinds are
+//! `"input"`, `"entity"`, `"collision"` and `"asset"`.
+
+use rlua::{self, Lua, Table};
+
+use combustion_events::builtin::{Action, AssetEvent, CollisionEvent, EntityEvent, InputEvent};
+
+use ::value::LuaEntity;
+
+/// Event which can be passed to script handlers
+pub trait ScriptEvent {
+    /// Kind of event scripts subscribe to
+    fn kind() -> &'static str where Self: Sized;
+
+    /// Convert the event to a Lua table
+    fn to_lua_table<'lua>(&self, lua: &'lua Lua) -> rlua::Result<Table<'lua>>;
+}
+
+fn event_table<'lua>(lua: &'lua Lua, name: &str) -> rlua::Result<Table<'lua>> {
+    let table = try!(lua.create_table());
+
+    try!(table.set("type", name));
+
+    Ok(table)
+}
+
+fn vector<'lua>(lua: &'lua Lua, v: &[f32; 3]) -> rlua::Result<Table<'lua>> {
+    let table = try!(lua.create_table());
+
+    try!(table.set("x", v[0]));
+    try!(table.set("y", v[1]));
+    try!(table.set("z", v[2]));
+
+    Ok(table)
+}
+
+fn action_name(action: Action) -> &'static str {
+    match action {
+        Action::Press => "press",
+        Action::Release => "release",
+        Action::Repeat => "repeat",
+    }
+}
+
+impl ScriptEvent for InputEvent {
+    fn kind() -> &'static str { "input" }
+
+    fn to_lua_table<'lua>(&self, lua: &'lua Lua) -> rlua::Result<Table<'lua>> {
+        Ok(match *self {
+            InputEvent::Key { key, scancode, action, modifiers } => {
+                let table = try!(event_table(lua, "key"));
+
+                try!(table.set("key", key));
+                try!(table.set("scancode", scancode));
+                try!(table.set("action", action_name(action)));
+                try!(table.set("modifiers", modifiers));
+
+                table
+            }
+            InputEvent::Char(c) => {
+                let table = try!(event_table(lua, "char"));
+
+                try!(table.set("char", c.to_string()));
+
+                table
+            }
+            InputEvent::MouseButton { button, action, modifiers } => {
+                let table = try!(event_table(lua, "mouse_button"));
+
+                try!(table.set("button", button));
+                try!(table.set("action", action_name(action)));
+                try!(table.set("modifiers", modifiers));
+
+                table
+            }
+            InputEvent::CursorMoved(x, y) | InputEvent::Scroll(x, y) => {
+                let name = if let InputEvent::Scroll(..) = *self { "scroll" } else { "cursor_moved" };
+
+                let table = try!(event_table(lua, name));
+
+                try!(table.set("x", x));
+                try!(table.set("y", y));
+
+                table
+            }
+            InputEvent::Resized(width, height) => {
+                let table = try!(event_table(lua, "resized"));
+
+                try!(table.set("width", width));
+                try!(table.set("height", height));
+
+                table
+            }
+            InputEvent::Focus(focused) => {
+                let table = try!(event_table(lua, "focus"));
+
+                try!(table.set("focused", focused));
+
+                table
+            }
+            InputEvent::Close => try!(event_table(lua, "close")),
+        })
+    }
+}
+
+impl ScriptEvent for EntityEvent {
+    fn kind() -> &'static str { "entity" }
+
+    fn to_lua_table<'lua>(&self, lua: &'lua Lua) -> rlua::Result<Table<'lua>> {
+        let (name, entity, component) = match *self {
+            EntityEvent::Created(entity) => ("created", entity, None),
+            EntityEvent::Destroyed(entity) => ("destroyed", entity, None),
+            EntityEvent::ComponentAdded(entity, component) => ("component_added", entity, Some(component)),
+            EntityEvent::ComponentRemoved(entity, component) => ("component_removed", entity, Some(component)),
+        };
+
+        let table = try!(event_table(lua, name));
+
+        try!(table.set("entity", LuaEntity(entity)));
+        try!(table.set("component", component));
+
+        Ok(table)
+    }
+}
+
+impl ScriptEvent for CollisionEvent {
+    fn kind() -> &'static str { "collision" }
+
+    fn to_lua_table<'lua>(&self, lua: &'lua Lua) -> rlua::Result<Table<'lua>> {
+        Ok(match *self {
+            CollisionEvent::Started { a, b, ref point, ref normal, depth } => {
+                let table = try!(event_table(lua, "started"));
+
+                try!(table.set("a", LuaEntity(a)));
+                try!(table.set("b", LuaEntity(b)));
+                try!(table.set("point", try!(vector(lua, point))));
+                try!(table.set("normal", try!(vector(lua, normal))));
+                try!(table.set("depth", depth));
+
+                table
+            }
+            CollisionEvent::Ended { a, b } => {
+                let table = try!(event_table(lua, "ended"));
+
+                try!(table.set("a", LuaEntity(a)));
+                try!(table.set("b", LuaEntity(b)));
+
+                table
+            }
+        })
+    }
+}
+
+impl ScriptEvent for AssetEvent {
+    fn kind() -> &'static str { "asset" }
+
+    fn to_lua_table<'lua>(&self, lua: &'lua Lua) -> rlua::Result<Table<'lua>> {
+        let (name, path, error) = match *self {
+            AssetEvent::Reloaded(ref path) => ("reloaded", path, None),
+            AssetEvent::Removed(ref path) => ("removed", path, None),
+            AssetEvent::Failed(ref path, ref error) => ("failed", path, Some(error.as_str())),
+        };
+
+        let table = try!(event_table(lua, name));
+
+        try!(table.set("path", path.to_string_lossy().into_owned()));
+        try!(table.set("error", error));
+
+        Ok(table)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use rlua::{Lua, Table};
+
+    use ecs::World;
+
+    use combustion_events::builtin::{Action, AssetEvent, CollisionEvent, EntityEvent, InputEvent};
+
+    use ::value::LuaEntity;
+
+    use super::*;
+
+    fn event_type(table: &Table) -> String {
+        table.get("type").unwrap()
+    }
+
+    #[test]
+    fn input_events() {
+        let lua = Lua::new();
+
+        let key = InputEvent::Key { key: 65, scancode: 30, action: Action::Press, modifiers: 2 }.to_lua_table(&lua).unwrap();
+
+        assert_eq!(InputEvent::kind(), "input");
+        assert_eq!(event_type(&key), "key");
+        assert_eq!(key.get::<_, i32>("key").unwrap(), 65);
+        assert_eq!(key.get::<_, i32>("scancode").unwrap(), 30);
+        assert_eq!(key.get::<_, String>("action").unwrap(), "press");
+        assert_eq!(key.get::<_, u32>("modifiers").unwrap(), 2);
+
+        let button = InputEvent::MouseButton { button: 1, action: Action::Release, modifiers: 0 }.to_lua_table(&lua).unwrap();
+
+        assert_eq!(event_type(&button), "mouse_button");
+        assert_eq!(button.get::<_, String>("action").unwrap(), "release");
+
+        let c = InputEvent::Char('é').to_lua_table(&lua).unwrap();
+
+        assert_eq!(event_type(&c), "char");
+        assert_eq!(c.get::<_, String>("char").unwrap(), "é");
+
+        let cursor = InputEvent::CursorMoved(1.5, 2.5).to_lua_table(&lua).unwrap();
+        let scroll = InputEvent::Scroll(0.0, -1.0).to_lua_table(&lua).unwrap();
+
+        assert_eq!(event_type(&cursor), "cursor_moved");
+        assert_eq!(cursor.get::<_, f64>("x").unwrap(), 1.5);
+        assert_eq!(event_type(&scroll), "scroll");
+        assert_eq!(scroll.get::<_, f64>("y").unwrap(), -1.0);
+
+        let resized = InputEvent::Resized(800, 600).to_lua_table(&lua).unwrap();
+
+        assert_eq!(event_type(&resized), "resized");
+        assert_eq!(resized.get::<_, i32>("width").unwrap(), 800);
+        assert_eq!(resized.get::<_, i32>("height").unwrap(), 600);
+
+        let focus = InputEvent::Focus(true).to_lua_table(&lua).unwrap();
+
+        assert_eq!(event_type(&focus), "focus");
+        assert!(focus.get::<_, bool>("focused").unwrap());
+
+        assert_eq!(event_type(&InputEvent::Close.to_lua_table(&lua).unwrap()), "close");
+    }
+
+    #[test]
+    fn entity_events() {
+        let mut world = World::new();
+
+        let entity = world.create_now().build();
+
+        let lua = Lua::new();
+
+        let created = EntityEvent::Created(entity).to_lua_table(&lua).unwrap();
+
+        assert_eq!(EntityEvent::kind(), "entity");
+        assert_eq!(event_type(&created), "created");
+        assert_eq!(created.get::<_, LuaEntity>("entity").unwrap(), LuaEntity(entity));
+        assert_eq!(created.get::<_, Option<String>>("component").unwrap(), None);
+
+        let added = EntityEvent::ComponentAdded(entity, "Light").to_lua_table(&lua).unwrap();
+
+        assert_eq!(event_type(&added), "component_added");
+        assert_eq!(added.get::<_, String>("component").unwrap(), "Light");
+
+        assert_eq!(event_type(&EntityEvent::Destroyed(entity).to_lua_table(&lua).unwrap()), "destroyed");
+        assert_eq!(event_type(&EntityEvent::ComponentRemoved(entity, "Light").to_lua_table(&lua).unwrap()), "component_removed");
+    }
+
+    #[test]
+    fn collision_events() {
+        let mut world = World::new();
+
+        let a = world.create_now().build();
+        let b = world.create_now().build();
+
+        let lua = Lua::new();
+
+        let started = CollisionEvent::Started { a: a, b: b, point: [1.0, 2.0, 3.0], normal: [0.0, 1.0, 0.0], depth: 0.5 }
+            .to_lua_table(&lua).unwrap();
+
+        assert_eq!(CollisionEvent::kind(), "collision");
+        assert_eq!(event_type(&started), "started");
+        assert_eq!(started.get::<_, LuaEntity>("a").unwrap(), LuaEntity(a));
+        assert_eq!(started.get::<_, LuaEntity>("b").unwrap(), LuaEntity(b));
+        assert_eq!(started.get::<_, f32>("depth").unwrap(), 0.5);
+
+        let point: Table = started.get("point").unwrap();
+        let normal: Table = started.get("normal").unwrap();
+
+        assert_eq!((point.get::<_, f32>("x").unwrap(), point.get::<_, f32>("y").unwrap(), point.get::<_, f32>("z").unwrap()), (1.0, 2.0, 3.0));
+        assert_eq!(normal.get::<_, f32>("y").unwrap(), 1.0);
+
+        let ended = CollisionEvent::Ended { a: a, b: b }.to_lua_table(&lua).unwrap();
+
+        assert_eq!(event_type(&ended), "ended");
+        assert_eq!(ended.get::<_, LuaEntity>("b").unwrap(), LuaEntity(b));
+    }
+
+    #[test]
+    fn asset_events() {
+        let lua = Lua::new();
+
+        let reloaded = AssetEvent::Reloaded(PathBuf::from("scripts/player.lua")).to_lua_table(&lua).unwrap();
+
+        assert_eq!(AssetEvent::kind(), "asset");
+        assert_eq!(event_type(&reloaded), "reloaded");
+        assert_eq!(reloaded.get::<_, String>("path").unwrap(), "scripts/player.lua");
+        assert_eq!(reloaded.get::<_, Option<String>>("error").unwrap(), None);
+
+        let failed = AssetEvent::Failed(PathBuf::from("a.png"), "bad header".to_string()).to_lua_table(&lua).unwrap();
+
+        assert_eq!(event_type(&failed), "failed");
+        assert_eq!(failed.get::<_, String>("error").unwrap(), "bad header");
+
+        assert_eq!(event_type(&AssetEvent::Removed(PathBuf::from("a.png")).to_lua_table(&lua).unwrap()), "removed");
+    }
+}
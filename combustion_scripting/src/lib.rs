@@ -1,10 +1,46 @@
-extern crate capnp;
-extern crate capnpc;
+//! Lua scripting
+//!
+//! Scripts are attached to entities with the `Script` component, and run each frame by the `ScriptSystem`.
+//! See the `runtime` module for what scripts can do, and the `sandbox` module for what they can't.
+
+extern crate rlua;
+extern crate fnv;
+extern crate nalgebra;
+
+#[macro_use]
+extern crate slog;
+
+#[macro_use]
+extern crate trace_error;
 
 #[macro_use]
 extern crate combustion_common as common;
 #[macro_use]
 extern crate combustion_backend as backend;
 extern crate combustion_protocols;
+extern crate combustion_ecs as ecs;
+extern crate combustion_events;
+extern crate combustion_log as log;
+
+#[cfg(test)]
+#[macro_use]
+extern crate combustion_macros;
+
+pub mod error;
+pub mod value;
+pub mod components;
+pub mod events;
+pub mod sandbox;
+pub mod state;
+pub mod runtime;
+pub mod bindings;
+pub mod component;
+pub mod system;
 
-extern crate squirrel;
\ No newline at end of file
+pub use error::{ScriptError, ScriptResult};
+pub use components::ScriptComponents;
+pub use runtime::{ScriptRuntime, ScriptInstance};
+pub use sandbox::ScriptLimits;
+pub use state::ScriptValue;
+pub use component::Script;
+pub use system::{ScriptSystem, ScriptFailure};
@@ -0,0 +1,282 @@
+//! Lua runtime and script instances
+//!
+//! Each script attached to an entity is an instance with its own sandboxed Lua state, so its globals are its own.
+//! Along with the parts of the standard library allowed by the sandbox, the globals hold the engine API:
+//!
+//! * `entity` - The entity the script is attached to
+//! * `state` - Table which is kept when the script is reloaded. See the `state` module.
+//! * `events.on(kind, handler)` - Subscribe the instance to a kind of event. See the `events` module.
+//! * `world` - Entity and component access, only available while scripts are running. See the `bindings` module.
+//! * `assets.read(path)`, `assets.exists(path)` and `assets.load(path)` - Read files and load Lua modules
+//!   through the virtual file system
+//!
+//! An instance is started by running the script, then calling its `init` function. Each frame its event handlers
+//! are called, followed by `update(dt)`. When the script is removed from the entity, `destroy` is called.
+//! All of these functions are optional.
+//!
+//! When the script's file changes, it is loaded into a new state and started again with the old `state` table.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use rlua::{self, Lua, Table, Function, Value, RegistryKey, ToLuaMulti};
+
+use common::vfs::BoxedVFS;
+
+use ecs::Entity;
+
+use ::error::{ScriptError, ScriptResult};
+use ::events::ScriptEvent;
+use ::sandbox::{self, InstructionCounter, ScriptLimits};
+use ::state::{ScriptValue, STATE_GLOBAL};
+use ::value::{LuaEntity, lua_error};
+
+/// Name of the registry value holding Lua's own `load` function
+const LOAD_REGISTRY_KEY: &'static str = "combustion.load";
+
+const MAKE_EVENTS: &'static str = r#"
+return function(handlers)
+    return {
+        on = function(kind, handler)
+            local list = handlers[kind]
+
+            if list == nil then
+                list = {}
+                handlers[kind] = list
+            end
+
+            list[#list + 1] = handler
+        end,
+    }
+end
+"#;
+
+/// Read a whole file from the virtual file system
+pub fn read_source(vfs: &BoxedVFS, path: &Path) -> ScriptResult<String> {
+    let mut source = String::new();
+
+    try_throw!(try_throw!(vfs.open(path)).read_to_string(&mut source));
+
+    Ok(source)
+}
+
+/// Create a new environment table for a module, which falls back to the globals
+pub fn new_environment(lua: &Lua) -> rlua::Result<Table> {
+    let env = try!(lua.create_table());
+    let meta = try!(lua.create_table());
+
+    try!(meta.set("__index", lua.globals()));
+
+    env.set_metatable(Some(meta));
+
+    Ok(env)
+}
+
+/// Compile a script with the given environment. Only source code is accepted, never precompiled bytecode.
+///
+/// The chunk is named after the path, so errors read like `scripts/player.lua:12: message`.
+pub fn load_chunk<'lua>(lua: &'lua Lua, source: &str, path: &Path, env: Table<'lua>) -> rlua::Result<Function<'lua>> {
+    let load: Function = try!(lua.named_registry_value(LOAD_REGISTRY_KEY));
+
+    let (chunk, message): (Option<Function>, Option<String>) = try!(load.call((source, format!("@{}", path.display()), "t", env)));
+
+    match chunk {
+        Some(chunk) => Ok(chunk),
+        None => Err(rlua::Error::SyntaxError { message: message.unwrap_or_default(), incomplete_input: false }),
+    }
+}
+
+fn install_assets(lua: &Lua, vfs: Arc<BoxedVFS>) -> rlua::Result<()> {
+    let assets = try!(lua.create_table());
+
+    let read_vfs = vfs.clone();
+
+    try!(assets.set("read", try!(lua.create_function(move |_, path: String| {
+        read_source(&read_vfs, Path::new(&path)).map_err(lua_error)
+    }))));
+
+    let exists_vfs = vfs.clone();
+
+    try!(assets.set("exists", try!(lua.create_function(move |_, path: String| {
+        Ok(exists_vfs.metadata(Path::new(&path)).is_ok())
+    }))));
+
+    let load_vfs = vfs;
+
+    // Modules get their own environment, and return whatever the module returns
+    try!(assets.set("load", try!(lua.create_function(move |lua, path: String| {
+        let path = PathBuf::from(path);
+
+        let source = try!(read_source(&load_vfs, &path).map_err(lua_error));
+
+        let chunk = try!(load_chunk(lua, &source, &path, try!(new_environment(lua))));
+
+        chunk.call::<_, Value>(())
+    }))));
+
+    lua.globals().set("assets", assets)
+}
+
+/// Script attached to an entity, with its own Lua state
+pub struct ScriptInstance {
+    lua: Lua,
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    counter: InstructionCounter,
+    chunk: RegistryKey,
+    handlers: RegistryKey,
+}
+
+impl ScriptInstance {
+    #[inline]
+    pub fn lua(&self) -> &Lua { &self.lua }
+
+    /// Path of the script in the virtual file system
+    #[inline]
+    pub fn path(&self) -> &Path { &self.path }
+
+    /// When the script was last modified, if the file system knows
+    #[inline]
+    pub fn modified(&self) -> Option<SystemTime> { self.modified }
+
+    /// Run the script with the given state, or an empty one, then call its `init` function
+    pub fn start(&self, state: Option<&ScriptValue>) -> ScriptResult<()> {
+        let state = match state {
+            Some(state) => try_throw!(state.to_lua(&self.lua)),
+            None => Value::Table(try_throw!(self.lua.create_table())),
+        };
+
+        try_throw!(self.lua.globals().set(STATE_GLOBAL, state));
+
+        let chunk: Function = try_throw!(self.lua.registry_value(&self.chunk));
+
+        self.counter.reset();
+
+        try_throw!(chunk.call::<_, ()>(()));
+
+        self.call("init", ())
+    }
+
+    /// Call a global function defined by the script, if there is one
+    pub fn call<'lua, A>(&'lua self, name: &str, args: A) -> ScriptResult<()> where A: ToLuaMulti<'lua> {
+        match try_throw!(self.lua.globals().raw_get::<_, Value>(name)) {
+            Value::Function(function) => {
+                self.counter.reset();
+
+                try_throw!(function.call::<_, ()>(args));
+
+                Ok(())
+            }
+            Value::Nil => Ok(()),
+            _ => throw!(ScriptError::Lua(rlua::Error::RuntimeError(format!("\"{}\" is not a function", name)))),
+        }
+    }
+
+    /// Call every handler the script has subscribed to the events' kind with, once for each event
+    pub fn emit<E: ScriptEvent>(&self, events: &[E]) -> ScriptResult<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        let handlers: Table = try_throw!(self.lua.registry_value(&self.handlers));
+
+        let handlers = match try_throw!(handlers.raw_get::<_, Option<Table>>(E::kind())) {
+            Some(handlers) => handlers,
+            None => return Ok(()),
+        };
+
+        for event in events {
+            let event = try_throw!(event.to_lua_table(&self.lua));
+
+            for handler in handlers.clone().sequence_values::<Function>() {
+                self.counter.reset();
+
+                try_throw!(try_throw!(handler).call::<_, ()>(event.clone()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copy the script's `state` table out of its Lua state
+    pub fn save_state(&self) -> ScriptResult<Option<ScriptValue>> {
+        let state = try_throw!(self.lua.globals().raw_get::<_, Value>(STATE_GLOBAL));
+
+        Ok(try_throw!(ScriptValue::from_lua(state)))
+    }
+}
+
+/// Creates script instances
+pub struct ScriptRuntime {
+    vfs: Arc<BoxedVFS>,
+    limits: ScriptLimits,
+}
+
+impl ScriptRuntime {
+    /// Create a runtime which loads scripts and assets from the given file system, with the default limits
+    pub fn new(vfs: Arc<BoxedVFS>) -> ScriptRuntime {
+        ScriptRuntime::with_limits(vfs, ScriptLimits::default())
+    }
+
+    pub fn with_limits(vfs: Arc<BoxedVFS>, limits: ScriptLimits) -> ScriptRuntime {
+        ScriptRuntime { vfs: vfs, limits: limits }
+    }
+
+    #[inline]
+    pub fn vfs(&self) -> &Arc<BoxedVFS> { &self.vfs }
+
+    #[inline]
+    pub fn limits(&self) -> ScriptLimits { self.limits }
+
+    /// Change the limits of scripts loaded from now on
+    #[inline]
+    pub fn set_limits(&mut self, limits: ScriptLimits) { self.limits = limits; }
+
+    /// When a script was last modified, if the file system knows
+    pub fn modified(&self, path: &Path) -> Option<SystemTime> {
+        self.vfs.metadata(path).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    /// Load a script for an entity into a new sandboxed Lua state. The script doesn't run until it's started.
+    pub fn load(&self, path: &Path, entity: Entity) -> ScriptResult<ScriptInstance> {
+        let modified = self.modified(path);
+
+        let source = try_rethrow!(read_source(&self.vfs, path));
+
+        let lua = sandbox::new_state();
+
+        let (counter, chunk, handlers) = {
+            let globals = lua.globals();
+
+            try_throw!(lua.set_named_registry_value(LOAD_REGISTRY_KEY, try_throw!(globals.get::<_, Function>("load"))));
+
+            try_throw!(sandbox::restrict(&lua, path));
+
+            let counter = try_throw!(sandbox::limit(&lua, self.limits));
+
+            try_throw!(install_assets(&lua, self.vfs.clone()));
+
+            let make_events: Function = try_throw!(lua.eval(MAKE_EVENTS, Some("make_events")));
+
+            let handlers = try_throw!(lua.create_table());
+
+            try_throw!(globals.set("events", try_throw!(make_events.call::<_, Table>(handlers.clone()))));
+            try_throw!(globals.set("entity", LuaEntity(entity)));
+
+            let chunk = try_throw!(load_chunk(&lua, &source, path, globals));
+
+            (counter, try_throw!(lua.create_registry_value(chunk)), try_throw!(lua.create_registry_value(handlers)))
+        };
+
+        Ok(ScriptInstance {
+            lua: lua,
+            path: path.to_path_buf(),
+            modified: modified,
+            counter: counter,
+            chunk: chunk,
+            handlers: handlers,
+        })
+    }
+}
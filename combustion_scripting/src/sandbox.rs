@@ -0,0 +1,346 @@
+//! Script sandboxes
+//!
+//! Every script instance runs in its own Lua state, with only a safe subset of the standard library:
+//!
+//! * Base functions like `pairs`, `pcall`, `tostring` and `setmetatable`, but not `load`, `dofile`, `require` or `collectgarbage`
+//! * The `coroutine`, `math`, `string`, `table` and `utf8` libraries
+//! * `os.clock`, `os.date`, `os.difftime` and `os.time`
+//!
+//! The `io`, `debug` and `package` libraries are not available. Files are read through `assets` instead.
+//!
+//! `print` writes to the engine log, prefixed with the script's path.
+//!
+//! Each state also has its own memory limit, and a limit on the number of instructions run by a single
+//! call into the script, so a script stuck in a loop fails instead of hanging the engine.
+//! Both are checked by a debug hook every 1000 instructions, in coroutines too, so a single allocation
+//! like a huge `string.rep` can still go over the memory limit before it is noticed.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rlua::{self, Lua, Table, Function, Value, Variadic};
+
+use log::log::logger;
+
+/// Base functions scripts may use
+const BASE_FUNCTIONS: &'static [&'static str] = &[
+    "assert", "error", "getmetatable", "ipairs", "next", "pairs", "pcall", "rawequal", "rawget", "rawlen",
+    "rawset", "select", "setmetatable", "tonumber", "tostring", "type", "xpcall", "_VERSION",
+];
+
+/// Libraries scripts may use in full
+const LIBRARIES: &'static [&'static str] = &["coroutine", "math", "string", "table", "utf8"];
+
+/// Functions of the `os` library scripts may use
+const OS_FUNCTIONS: &'static [&'static str] = &["clock", "date", "difftime", "time"];
+
+/// Name of the registry value holding Lua's own `tostring` function, used by `print`
+const TOSTRING_REGISTRY_KEY: &'static str = "combustion.tostring";
+
+/// Name of the registry value holding `debug.sethook`, used by `limit`
+const SETHOOK_REGISTRY_KEY: &'static str = "combustion.sethook";
+
+/// Name of the registry value holding `collectgarbage`, used to check the memory limit
+const COLLECTGARBAGE_REGISTRY_KEY: &'static str = "combustion.collectgarbage";
+
+/// Hooks only apply to the coroutine they were set on, so every coroutine created by a script gets the hook as well
+const HOOK_COROUTINES: &'static str = r#"
+return function(sethook, hook, step)
+    local create, resume = coroutine.create, coroutine.resume
+
+    coroutine.create = function(f)
+        local co = create(f)
+
+        sethook(co, hook, "", step)
+
+        return co
+    end
+
+    coroutine.wrap = function(f)
+        local co = coroutine.create(f)
+
+        return function(...)
+            local results = table.pack(resume(co, ...))
+
+            if results[1] then
+                return table.unpack(results, 2, results.n)
+            end
+
+            error(results[2], 0)
+        end
+    end
+end
+"#;
+
+/// Number of instructions between checks of the instruction limit
+const INSTRUCTION_STEP: u32 = 1000;
+
+/// Default instruction limit for a single call into a script
+pub const DEFAULT_INSTRUCTION_LIMIT: u32 = 10_000_000;
+
+/// Default memory limit of a script, in bytes
+pub const DEFAULT_MEMORY_LIMIT: usize = 16 * 1024 * 1024;
+
+/// Resource limits of each script
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScriptLimits {
+    /// Maximum number of instructions run by a single call into the script, like `update`.
+    ///
+    /// This is checked every 1000 instructions, so it isn't exact.
+    pub instructions: Option<u32>,
+    /// Maximum memory used by the script's Lua state, in bytes
+    pub memory: Option<usize>,
+}
+
+impl Default for ScriptLimits {
+    fn default() -> ScriptLimits {
+        ScriptLimits {
+            instructions: Some(DEFAULT_INSTRUCTION_LIMIT),
+            memory: Some(DEFAULT_MEMORY_LIMIT),
+        }
+    }
+}
+
+/// Number of instructions run since the last reset
+#[derive(Debug, Clone, Default)]
+pub struct InstructionCounter(Arc<AtomicUsize>);
+
+impl InstructionCounter {
+    /// Start counting again, before calling into the script
+    #[inline]
+    pub fn reset(&self) {
+        self.0.store(0, Ordering::SeqCst);
+    }
+
+    #[inline]
+    pub fn executed(&self) -> usize {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Create a Lua state for a script.
+///
+/// The state has the debug library, so `limit` can install its hook. It has to be passed to `restrict`
+/// before running any script, which removes the debug library again.
+pub fn new_state() -> Lua {
+    unsafe { Lua::new_with_debug() }
+}
+
+/// Remove everything from the globals except the safe subset of the standard library
+pub fn restrict(lua: &Lua, path: &Path) -> rlua::Result<()> {
+    // Otherwise scripts could modify the string library of the state through any string
+    try!(lua.exec::<()>("getmetatable('').__metatable = false", Some("sandbox")));
+
+    let globals = lua.globals();
+
+    try!(lua.set_named_registry_value(TOSTRING_REGISTRY_KEY, try!(globals.get::<_, Function>("tostring"))));
+    try!(lua.set_named_registry_value(COLLECTGARBAGE_REGISTRY_KEY, try!(globals.get::<_, Function>("collectgarbage"))));
+    try!(lua.set_named_registry_value(SETHOOK_REGISTRY_KEY, try!(try!(globals.get::<_, Table>("debug")).get::<_, Function>("sethook"))));
+
+    let keep = try!(lua.create_table());
+
+    for &name in BASE_FUNCTIONS.iter().chain(LIBRARIES) {
+        try!(keep.set(name, try!(globals.get::<_, Value>(name))));
+    }
+
+    let os = try!(lua.create_table());
+    let full_os: Table = try!(globals.get("os"));
+
+    for &name in OS_FUNCTIONS {
+        try!(os.set(name, try!(full_os.get::<_, Value>(name))));
+    }
+
+    try!(keep.set("os", os));
+
+    let mut names = Vec::new();
+
+    for pair in globals.clone().pairs::<Value, Value>() {
+        names.push(try!(pair).0);
+    }
+
+    for name in names {
+        try!(globals.set(name, Value::Nil));
+    }
+
+    for pair in keep.pairs::<Value, Value>() {
+        let (name, value) = try!(pair);
+
+        try!(globals.set(name, value));
+    }
+
+    let name = path.display().to_string();
+
+    try!(globals.set("print", try!(lua.create_function(move |lua, args: Variadic<Value>| {
+        let tostring: Function = try!(lua.named_registry_value(TOSTRING_REGISTRY_KEY));
+
+        let mut parts = Vec::with_capacity(args.len());
+
+        for arg in args.iter() {
+            parts.push(try!(tostring.call::<_, String>(arg.clone())));
+        }
+
+        info!(logger(), "{}: {}", name, parts.join("\t"));
+
+        Ok(())
+    }))));
+
+    Ok(())
+}
+
+/// Apply resource limits to a Lua state which has been passed to `restrict`
+pub fn limit(lua: &Lua, limits: ScriptLimits) -> rlua::Result<InstructionCounter> {
+    let counter = InstructionCounter::default();
+
+    if limits.instructions.is_none() && limits.memory.is_none() {
+        return Ok(counter);
+    }
+
+    let executed = counter.0.clone();
+
+    let hook = try!(lua.create_function(move |lua, ()| {
+        let executed = executed.fetch_add(INSTRUCTION_STEP as usize, Ordering::SeqCst) + INSTRUCTION_STEP as usize;
+
+        if let Some(limit) = limits.instructions {
+            if executed > limit as usize {
+                return Err(rlua::Error::RuntimeError(format!("instruction limit of {} exceeded", limit)));
+            }
+        }
+
+        if let Some(limit) = limits.memory {
+            let collectgarbage: Function = try!(lua.named_registry_value(COLLECTGARBAGE_REGISTRY_KEY));
+
+            // In kilobytes
+            let used: f64 = try!(collectgarbage.call("count"));
+
+            if used * 1024.0 > limit as f64 {
+                return Err(rlua::Error::RuntimeError(format!("memory limit of {} bytes exceeded", limit)));
+            }
+        }
+
+        Ok(())
+    }));
+
+    let sethook: Function = try!(lua.named_registry_value(SETHOOK_REGISTRY_KEY));
+
+    try!(sethook.call::<_, ()>((hook.clone(), "", INSTRUCTION_STEP)));
+
+    let hook_coroutines: Function = try!(lua.eval(HOOK_COROUTINES, Some("sandbox")));
+
+    try!(hook_coroutines.call::<_, ()>((sethook, hook, INSTRUCTION_STEP)));
+
+    Ok(counter)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use rlua::Lua;
+
+    use super::*;
+
+    fn sandboxed(limits: ScriptLimits) -> (Lua, InstructionCounter) {
+        let lua = new_state();
+
+        restrict(&lua, Path::new("scripts/test.lua")).unwrap();
+
+        let counter = limit(&lua, limits).unwrap();
+
+        (lua, counter)
+    }
+
+    fn is_nil(lua: &Lua, expression: &str) -> bool {
+        lua.eval::<bool>(&format!("return {} == nil", expression), None).unwrap()
+    }
+
+    #[test]
+    fn unsafe_globals_are_removed() {
+        let (lua, _) = sandboxed(ScriptLimits::default());
+
+        for name in &["io", "load", "loadstring", "dofile", "loadfile", "require", "package", "debug", "collectgarbage",
+                      "os.execute", "os.exit", "os.getenv", "os.remove", "os.rename", "os.tmpname"] {
+            assert!(is_nil(&lua, name), "{} is still available", name);
+        }
+
+        assert!(lua.exec::<()>("require('os')", None).is_err());
+        assert!(lua.exec::<()>("io.open('/etc/passwd')", None).is_err());
+    }
+
+    #[test]
+    fn safe_globals_are_kept() {
+        let (lua, _) = sandboxed(ScriptLimits::default());
+
+        for name in BASE_FUNCTIONS.iter().chain(LIBRARIES) {
+            assert!(!is_nil(&lua, name), "{} was removed", name);
+        }
+
+        for name in OS_FUNCTIONS {
+            assert!(!is_nil(&lua, &format!("os.{}", name)), "os.{} was removed", name);
+        }
+
+        assert!(!is_nil(&lua, "print"));
+
+        assert_eq!(lua.eval::<String>("return string.upper('abc') .. tostring(math.floor(2.5))", None).unwrap(), "ABC2");
+    }
+
+    #[test]
+    fn string_metatable_is_locked() {
+        let (lua, _) = sandboxed(ScriptLimits::default());
+
+        assert!(lua.eval::<bool>("return getmetatable('') == false", None).unwrap());
+
+        // Methods on strings still work
+        assert_eq!(lua.eval::<String>("return ('abc'):upper()", None).unwrap(), "ABC");
+    }
+
+    #[test]
+    fn instruction_limit_stops_infinite_loops() {
+        let (lua, counter) = sandboxed(ScriptLimits { instructions: Some(100_000), memory: None });
+
+        match lua.exec::<()>("while true do end", None) {
+            Err(rlua::Error::CallbackError { ref cause, .. }) => {
+                assert!(cause.to_string().contains("instruction limit of 100000 exceeded"), "{}", cause)
+            }
+            Err(err) => panic!("Unexpected error: {}", err),
+            Ok(()) => panic!("Infinite loop finished"),
+        }
+
+        assert!(counter.executed() > 100_000);
+
+        // The count starts again for the next call
+        counter.reset();
+
+        assert_eq!(counter.executed(), 0);
+
+        assert!(lua.exec::<()>("local x = 0 for i = 1, 1000 do x = x + i end", None).is_ok());
+    }
+
+    #[test]
+    fn instruction_limit_applies_to_coroutines() {
+        let (lua, _) = sandboxed(ScriptLimits { instructions: Some(100_000), memory: None });
+
+        let (ok, message): (bool, String) = lua.eval(r#"
+            local ok, err = coroutine.resume(coroutine.create(function() while true do end end))
+
+            return ok, tostring(err)
+        "#, None).unwrap();
+
+        assert!(!ok);
+        assert!(message.contains("instruction limit of 100000 exceeded"), "{}", message);
+
+        assert!(lua.exec::<()>("coroutine.wrap(function() while true do end end)()", None).is_err());
+    }
+
+    #[test]
+    fn memory_limit_is_enforced() {
+        let (lua, _) = sandboxed(ScriptLimits { instructions: None, memory: Some(1024 * 1024) });
+
+        assert!(lua.exec::<()>("local t = {} for i = 1, 1000 do t[i] = i end", None).is_ok());
+
+        assert!(lua.exec::<()>("local t = {} for i = 1, 10000000 do t[i] = tostring(i) end", None).is_err());
+
+        // The state is still usable after running out of memory
+        assert_eq!(lua.eval::<i64>("return 1 + 1", None).unwrap(), 2);
+    }
+}
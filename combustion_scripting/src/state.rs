@@ -0,0 +1,172 @@
+//! Script state kept across reloads
+//!
+//! Scripts keep data which should survive being reloaded in the global `state` table. When a script is reloaded,
+//! the table is copied out of the old Lua state and into the new one before the script runs again.
+//!
+//! Only data is copied: booleans, numbers, strings, entities and tables of those. Functions, coroutines
+//! and other userdata are left out, as are metatables.
+
+use rlua::{self, Lua, Value};
+
+use ecs::Entity;
+
+use ::value::LuaEntity;
+
+/// Name of the global holding a script's state
+pub const STATE_GLOBAL: &'static str = "state";
+
+/// Deepest nesting of tables copied, which also stops cycles
+pub const MAX_STATE_DEPTH: usize = 32;
+
+/// Value copied out of a script's state
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptValue {
+    Bool(bool),
+    Integer(i64),
+    Number(f64),
+    String(String),
+    Entity(Entity),
+    Table(Vec<(ScriptValue, ScriptValue)>),
+}
+
+impl ScriptValue {
+    /// Copy a Lua value, or `None` if it isn't data
+    pub fn from_lua(value: Value) -> rlua::Result<Option<ScriptValue>> {
+        ScriptValue::from_lua_depth(value, 0)
+    }
+
+    fn from_lua_depth(value: Value, depth: usize) -> rlua::Result<Option<ScriptValue>> {
+        Ok(Some(match value {
+            Value::Boolean(value) => ScriptValue::Bool(value),
+            Value::Integer(value) => ScriptValue::Integer(value),
+            Value::Number(value) => ScriptValue::Number(value),
+            Value::String(value) => ScriptValue::String(try!(value.to_str()).to_string()),
+            Value::UserData(data) => match data.borrow::<LuaEntity>() {
+                Ok(entity) => ScriptValue::Entity(entity.0),
+                Err(_) => return Ok(None),
+            },
+            Value::Table(table) => {
+                if depth >= MAX_STATE_DEPTH {
+                    return Err(rlua::Error::RuntimeError("script state is nested too deeply, or contains a cycle".to_string()));
+                }
+
+                let mut pairs = Vec::new();
+
+                for pair in table.pairs::<Value, Value>() {
+                    let (key, value) = try!(pair);
+
+                    let key = try!(ScriptValue::from_lua_depth(key, depth + 1));
+                    let value = try!(ScriptValue::from_lua_depth(value, depth + 1));
+
+                    if let (Some(key), Some(value)) = (key, value) {
+                        pairs.push((key, value));
+                    }
+                }
+
+                ScriptValue::Table(pairs)
+            }
+            _ => return Ok(None),
+        }))
+    }
+
+    /// Create the value in a Lua state
+    pub fn to_lua<'lua>(&self, lua: &'lua Lua) -> rlua::Result<Value<'lua>> {
+        Ok(match *self {
+            ScriptValue::Bool(value) => Value::Boolean(value),
+            ScriptValue::Integer(value) => Value::Integer(value),
+            ScriptValue::Number(value) => Value::Number(value),
+            ScriptValue::String(ref value) => Value::String(try!(lua.create_string(value))),
+            ScriptValue::Entity(entity) => Value::UserData(try!(lua.create_userdata(LuaEntity(entity)))),
+            ScriptValue::Table(ref pairs) => {
+                let table = try!(lua.create_table());
+
+                for &(ref key, ref value) in pairs {
+                    try!(table.set(try!(key.to_lua(lua)), try!(value.to_lua(lua))));
+                }
+
+                Value::Table(table)
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rlua::{Lua, Value};
+
+    use ecs::World;
+
+    use super::*;
+
+    fn copy(lua: &Lua, source: &str) -> Option<ScriptValue> {
+        ScriptValue::from_lua(lua.eval::<Value>(source, None).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn data_round_trips() {
+        let mut world = World::new();
+
+        let entity = world.create_now().build();
+
+        let value = ScriptValue::Table(vec![
+            (ScriptValue::String("alive".to_string()), ScriptValue::Bool(true)),
+            (ScriptValue::String("score".to_string()), ScriptValue::Integer(12)),
+            (ScriptValue::String("speed".to_string()), ScriptValue::Number(1.5)),
+            (ScriptValue::String("target".to_string()), ScriptValue::Entity(entity)),
+            (ScriptValue::Integer(1), ScriptValue::Table(vec![(ScriptValue::String("x".to_string()), ScriptValue::Number(2.0))])),
+        ]);
+
+        // Copy into one state and back out through another, like a reload does
+        let old = Lua::new();
+
+        old.globals().set(STATE_GLOBAL, value.to_lua(&old).unwrap()).unwrap();
+
+        let saved = ScriptValue::from_lua(old.globals().get::<_, Value>(STATE_GLOBAL).unwrap()).unwrap().unwrap();
+
+        let new = Lua::new();
+
+        new.globals().set(STATE_GLOBAL, saved.to_lua(&new).unwrap()).unwrap();
+
+        assert!(new.eval::<bool>("return state.alive and state.score == 12 and state.speed == 1.5 and state[1].x == 2.0", None).unwrap());
+
+        let target: LuaEntity = new.eval("return state.target", None).unwrap();
+
+        assert_eq!(target.0, entity);
+
+        let mut pairs = match ScriptValue::from_lua(new.globals().get::<_, Value>(STATE_GLOBAL).unwrap()).unwrap() {
+            Some(ScriptValue::Table(pairs)) => pairs,
+            other => panic!("Expected a table, got {:?}", other),
+        };
+
+        let mut expected = match value {
+            ScriptValue::Table(pairs) => pairs,
+            _ => unreachable!(),
+        };
+
+        // Table order isn't kept
+        let key = |pair: &(ScriptValue, ScriptValue)| format!("{:?}", pair.0);
+
+        pairs.sort_by_key(&key);
+        expected.sort_by_key(&key);
+
+        assert_eq!(pairs, expected);
+    }
+
+    #[test]
+    fn functions_are_left_out() {
+        let lua = Lua::new();
+
+        assert_eq!(copy(&lua, "return function() end"), None);
+        assert_eq!(copy(&lua, "return {a = 1, f = print}"),
+                   Some(ScriptValue::Table(vec![(ScriptValue::String("a".to_string()), ScriptValue::Integer(1))])));
+    }
+
+    #[test]
+    fn cycles_are_errors() {
+        let lua = Lua::new();
+
+        let value = lua.eval::<Value>("local t = {} t.self = t return t", None).unwrap();
+
+        assert!(ScriptValue::from_lua(value).is_err());
+    }
+}
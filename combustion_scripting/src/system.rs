@@ -0,0 +1,395 @@
+//! System running the scripts attached to entities
+//!
+//! The system accesses arbitrary components through scripts, so it should be added to the planner
+//! without any other systems running in parallel with it.
+//!
+//! Errors in a script never stop the system. They are logged with the file and line they were raised at,
+//! and the failed script is stopped until its file changes, if hot reloading is enabled, or it is replaced.
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use fnv::FnvHashMap;
+
+use ecs::{Delta, Entity, Join, RunArg, System, World};
+
+use combustion_events::{EventChannel, ReaderId};
+use combustion_events::builtin::{AssetEvent, CollisionEvent, EntityEvent, InputEvent};
+
+use log::log::logger;
+
+use ::bindings::with_world;
+use ::component::Script;
+use ::components::ScriptComponents;
+use ::error::{ScriptError, ScriptResult};
+use ::runtime::{ScriptInstance, ScriptRuntime};
+use ::state::ScriptValue;
+
+/// Default time between checks for changed scripts, in seconds
+pub const DEFAULT_RELOAD_INTERVAL: Delta = 0.5;
+
+struct Readers {
+    input: ReaderId,
+    entity: ReaderId,
+    collision: ReaderId,
+    asset: ReaderId,
+}
+
+/// Script which failed to start, reload or run
+#[derive(Debug, Clone)]
+pub struct ScriptFailure {
+    /// Path of the script attached to the entity
+    pub script: PathBuf,
+    /// File the error was raised in, which may be a module loaded by the script
+    pub file: PathBuf,
+    pub line: Option<u32>,
+    pub message: String,
+    /// When the script was last modified before failing
+    modified: Option<SystemTime>,
+    /// State of the script when it failed, given to it when it starts again
+    state: Option<ScriptValue>,
+}
+
+impl ScriptFailure {
+    fn new(script: &Path, modified: Option<SystemTime>, err: &ScriptError, state: Option<ScriptValue>) -> ScriptFailure {
+        let (file, line, message) = match err.location() {
+            Some((file, line, message)) => (file, Some(line), message),
+            None => (script.to_path_buf(), None, err.to_string()),
+        };
+
+        ScriptFailure {
+            script: script.to_path_buf(),
+            file: file,
+            line: line,
+            message: message,
+            modified: modified,
+            state: state,
+        }
+    }
+
+    fn log(&self, entity: Entity) {
+        let script = self.script.display().to_string();
+
+        match self.line {
+            Some(line) => error!(logger(), "{}:{}: {}", self.file.display(), line, self.message; "script" => script, "entity" => entity.get_id()),
+            None => error!(logger(), "{}: {}", self.file.display(), self.message; "script" => script, "entity" => entity.get_id()),
+        }
+    }
+}
+
+/// Load a script into a new instance and start it
+fn start(runtime: &ScriptRuntime, world: &World, components: &ScriptComponents, path: &Path, entity: Entity,
+         state: Option<&ScriptValue>) -> ScriptResult<ScriptInstance> {
+    let instance = try_rethrow!(runtime.load(path, entity));
+
+    try_rethrow!(with_world(instance.lua(), world, components, || instance.start(state)));
+
+    Ok(instance)
+}
+
+/// Runs every enabled `Script` component each frame.
+///
+/// The `EventChannel`s for `InputEvent`, `EntityEvent`, `CollisionEvent` and `AssetEvent`
+/// must be added to the world as resources before the system first runs.
+pub struct ScriptSystem {
+    runtime: ScriptRuntime,
+    components: ScriptComponents,
+    instances: FnvHashMap<Entity, ScriptInstance>,
+    failed: FnvHashMap<Entity, ScriptFailure>,
+    readers: Option<Readers>,
+    hot_reload: bool,
+    reload_interval: Delta,
+    since_reload_check: Delta,
+}
+
+impl ScriptSystem {
+    pub fn new(runtime: ScriptRuntime, components: ScriptComponents) -> ScriptSystem {
+        ScriptSystem {
+            runtime: runtime,
+            components: components,
+            instances: FnvHashMap::default(),
+            failed: FnvHashMap::default(),
+            readers: None,
+            hot_reload: true,
+            reload_interval: DEFAULT_RELOAD_INTERVAL,
+            since_reload_check: 0.0,
+        }
+    }
+
+    #[inline]
+    pub fn runtime(&self) -> &ScriptRuntime { &self.runtime }
+
+    #[inline]
+    pub fn components(&self) -> &ScriptComponents { &self.components }
+
+    /// Scripts which have failed, by entity.
+    ///
+    /// A script which failed to reload keeps running the old version, so it may also be running.
+    #[inline]
+    pub fn failed(&self) -> &FnvHashMap<Entity, ScriptFailure> { &self.failed }
+
+    /// Reload scripts when their files change. Enabled by default.
+    #[inline]
+    pub fn set_hot_reload(&mut self, enabled: bool) { self.hot_reload = enabled; }
+
+    #[inline]
+    pub fn is_hot_reload(&self) -> bool { self.hot_reload }
+
+    /// Set the time between checks for changed scripts, in seconds
+    #[inline]
+    pub fn set_reload_interval(&mut self, interval: Delta) { self.reload_interval = interval; }
+}
+
+impl System<Delta> for ScriptSystem {
+    fn run(&mut self, arg: RunArg, delta: Delta) {
+        let ScriptSystem {
+            ref runtime, ref components, ref mut instances, ref mut failed, ref mut readers,
+            hot_reload, reload_interval, ref mut since_reload_check
+        } = *self;
+
+        let check_reload = hot_reload && {
+            *since_reload_check += delta;
+
+            *since_reload_check >= reload_interval
+        };
+
+        if check_reload {
+            *since_reload_check = 0.0;
+        }
+
+        arg.fetch(|world| {
+            let scripts: Vec<(Entity, PathBuf)> = {
+                let scripts = world.read::<Script>();
+                let entities = world.entities();
+
+                (&scripts, &entities).iter()
+                                     .filter(|&(script, _)| script.enabled)
+                                     .map(|(script, entity)| (entity, script.path().to_path_buf()))
+                                     .collect()
+            };
+
+            if readers.is_none() {
+                *readers = Some(Readers {
+                    input: world.read_resource::<EventChannel<InputEvent>>().register_reader(),
+                    entity: world.read_resource::<EventChannel<EntityEvent>>().register_reader(),
+                    collision: world.read_resource::<EventChannel<CollisionEvent>>().register_reader(),
+                    asset: world.read_resource::<EventChannel<AssetEvent>>().register_reader(),
+                });
+            }
+
+            let readers = readers.as_mut().unwrap();
+
+            let input: Vec<InputEvent> = world.read_resource::<EventChannel<InputEvent>>().read(&mut readers.input).cloned().collect();
+            let entity_events: Vec<EntityEvent> = world.read_resource::<EventChannel<EntityEvent>>().read(&mut readers.entity).cloned().collect();
+            let collisions: Vec<CollisionEvent> = world.read_resource::<EventChannel<CollisionEvent>>().read(&mut readers.collision).cloned().collect();
+            let assets: Vec<AssetEvent> = world.read_resource::<EventChannel<AssetEvent>>().read(&mut readers.asset).cloned().collect();
+
+            // Forget failures of scripts which have since been removed or replaced
+            failed.retain(|entity, failure| scripts.iter().any(|&(e, ref path)| e == *entity && *path == failure.script));
+
+            // Destroy instances whose script was removed, disabled or replaced
+            let removed: Vec<Entity> = instances.iter()
+                                                .filter(|&(entity, instance)| {
+                                                    !scripts.iter().any(|&(e, ref path)| e == *entity && path == instance.path())
+                                                })
+                                                .map(|(entity, _)| *entity)
+                                                .collect();
+
+            for entity in removed {
+                if let Some(instance) = instances.remove(&entity) {
+                    if let Err(err) = with_world(instance.lua(), world, components, || instance.call("destroy", ())) {
+                        ScriptFailure::new(instance.path(), instance.modified(), err.error(), None).log(entity);
+                    }
+                }
+            }
+
+            if check_reload {
+                // Skip scripts which already failed to reload since they last changed
+                let changed: Vec<(Entity, PathBuf, Option<SystemTime>)> = instances.iter().filter_map(|(&entity, instance)| {
+                    let modified = runtime.modified(instance.path());
+
+                    let retry = failed.get(&entity).map_or(true, |failure| failure.modified != modified);
+
+                    if modified.is_some() && modified != instance.modified() && retry {
+                        Some((entity, instance.path().to_path_buf(), modified))
+                    } else {
+                        None
+                    }
+                }).collect();
+
+                for (entity, path, modified) in changed {
+                    let reloaded = instances[&entity].save_state().and_then(|state| {
+                        start(runtime, world, components, &path, entity, state.as_ref())
+                    });
+
+                    // If the new version fails, the old one keeps running
+                    match reloaded {
+                        Ok(instance) => {
+                            info!(logger(), "Reloaded script {}", path.display());
+
+                            failed.remove(&entity);
+                            instances.insert(entity, instance);
+                        }
+                        Err(err) => {
+                            let failure = ScriptFailure::new(&path, modified, err.error(), None);
+
+                            failure.log(entity);
+                            failed.insert(entity, failure);
+                        }
+                    }
+                }
+            }
+
+            for &(entity, ref path) in &scripts {
+                if instances.contains_key(&entity) {
+                    continue;
+                }
+
+                // Scripts which failed are only started again once they change
+                let state = match failed.get(&entity) {
+                    Some(failure) if check_reload && runtime.modified(path) != failure.modified => failure.state.clone(),
+                    Some(_) => continue,
+                    None => None,
+                };
+
+                match start(runtime, world, components, path, entity, state.as_ref()) {
+                    Ok(instance) => {
+                        failed.remove(&entity);
+                        instances.insert(entity, instance);
+                    }
+                    Err(err) => {
+                        let failure = ScriptFailure::new(path, runtime.modified(path), err.error(), state);
+
+                        failure.log(entity);
+                        failed.insert(entity, failure);
+                    }
+                }
+            }
+
+            let mut errors = Vec::new();
+
+            for (&entity, instance) in instances.iter() {
+                let result = with_world(instance.lua(), world, components, || {
+                    try_rethrow!(instance.emit(&input));
+                    try_rethrow!(instance.emit(&entity_events));
+                    try_rethrow!(instance.emit(&collisions));
+                    try_rethrow!(instance.emit(&assets));
+
+                    instance.call("update", delta)
+                });
+
+                if let Err(err) = result {
+                    // Keep what state can be kept, for when the script starts again
+                    let state = instance.save_state().ok().and_then(|state| state);
+
+                    errors.push((entity, ScriptFailure::new(instance.path(), instance.modified(), err.error(), state)));
+                }
+            }
+
+            for (entity, failure) in errors {
+                instances.remove(&entity);
+
+                failure.log(entity);
+                failed.insert(entity, failure);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use common::vfs::BoxedVFS;
+    use common::vfs::default::DefaultFS;
+
+    use ecs::{self, Planner};
+
+    use combustion_events::system::register_channels;
+
+    use super::*;
+
+    #[derive(Debug, Clone, Default, PartialEq, Inspect)]
+    struct Counter {
+        count: i64,
+    }
+
+    impl ecs::Component for Counter {
+        type Storage = ecs::VecStorage<Counter>;
+    }
+
+    fn write_script(path: &Path, step: i64) {
+        let mut file = File::create(path).unwrap();
+
+        write!(file, r#"
+            state.count = state.count or 0
+
+            function update(dt)
+                state.count = state.count + {}
+
+                entity:set("Counter", "count", state.count)
+            end
+        "#, step).unwrap();
+    }
+
+    fn count(planner: &mut Planner, entity: Entity) -> i64 {
+        planner.mut_world().read::<Counter>().get(entity).unwrap().count
+    }
+
+    #[test]
+    fn reload_keeps_state() {
+        let dir = env::temp_dir().join("combustion_scripting_reload_test");
+
+        fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("counter.lua");
+
+        write_script(&path, 1);
+
+        let mut world = World::new();
+
+        register_channels(&mut world);
+
+        world.register::<Script>();
+        world.register::<Counter>();
+
+        let entity = world.create_now().with(Script::new(&path)).with(Counter::default()).build();
+
+        let mut components = ScriptComponents::new();
+
+        components.register::<Counter>("Counter");
+
+        let mut system = ScriptSystem::new(ScriptRuntime::new(Arc::new(Box::new(DefaultFS) as BoxedVFS)), components);
+
+        system.set_reload_interval(0.0);
+
+        let mut planner = Planner::new(world, 1);
+
+        planner.add_system(system, "scripts", 0);
+
+        for _ in 0..2 {
+            planner.dispatch(0.1);
+            planner.wait();
+        }
+
+        assert_eq!(count(&mut planner, entity), 2);
+
+        // Make sure the modification time changes, even on file systems with coarse timestamps
+        thread::sleep(Duration::from_millis(1100));
+
+        write_script(&path, 10);
+
+        planner.dispatch(0.1);
+        planner.wait();
+
+        // The new version continues from the old state instead of starting over
+        assert_eq!(count(&mut planner, entity), 12);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,262 @@
+//! Conversion between Lua values and engine values
+//!
+//! Entities are userdata which compare equal by identity. Component values use the same representation
+//! as `InspectValue`, with vectors and points as `{x, y, z, w}` tables, colors as `{r, g, b, a}` tables
+//! and enum variants as strings.
+
+use std::fmt::Display;
+
+use nalgebra::{Vector2, Vector3, Vector4, Point2, Point3};
+
+use rlua::{self, Lua, Table, Value, MultiValue, Function, ToLua, UserData, UserDataMethods, MetaMethod};
+
+use common::color::Color;
+use common::traits::inspect::InspectValue;
+
+use ecs::Entity;
+
+/// Name of the registry value holding the `world` table while scripts are running
+pub const WORLD_REGISTRY_KEY: &'static str = "combustion.world";
+
+/// Entity handle given to scripts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LuaEntity(pub Entity);
+
+/// Methods on entities which call the function of the same name in the `world` table, with the entity first
+const ENTITY_METHODS: &'static [&'static str] = &["get", "set", "has", "add", "remove", "fields", "delete", "is_alive"];
+
+impl UserData for LuaEntity {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("id", |_, this, ()| Ok(this.0.get_id()));
+
+        methods.add_meta_method(MetaMethod::Eq, |_, this, other: LuaEntity| Ok(*this == other));
+
+        methods.add_meta_method(MetaMethod::ToString, |_, this, ()| {
+            Ok(format!("Entity({})", this.0.get_id()))
+        });
+
+        for &name in ENTITY_METHODS {
+            methods.add_method(name, move |lua, this, args: MultiValue| {
+                let world = match try!(lua.named_registry_value::<Value>(WORLD_REGISTRY_KEY)) {
+                    Value::Table(world) => world,
+                    _ => return Err(rlua::Error::RuntimeError("the world is only available while scripts are running".to_string())),
+                };
+
+                let function: Function = try!(world.get(name));
+
+                let mut args = args.into_vec();
+
+                args.insert(0, try!((*this).to_lua(lua)));
+
+                function.call::<_, MultiValue>(MultiValue::from_vec(args))
+            });
+        }
+    }
+}
+
+/// Turn any error into a Lua runtime error
+pub fn lua_error<E: Display>(err: E) -> rlua::Error {
+    rlua::Error::RuntimeError(err.to_string())
+}
+
+fn vector_table<'lua>(lua: &'lua Lua, values: &[(&str, f32)]) -> rlua::Result<Value<'lua>> {
+    let table = try!(lua.create_table());
+
+    for &(key, value) in values {
+        try!(table.set(key, value));
+    }
+
+    Ok(Value::Table(table))
+}
+
+/// Convert a component value to Lua
+pub fn inspect_to_lua<'lua>(lua: &'lua Lua, value: InspectValue) -> rlua::Result<Value<'lua>> {
+    Ok(match value {
+        InspectValue::None => Value::Nil,
+        InspectValue::Bool(value) => Value::Boolean(value),
+        InspectValue::Integer(value) => Value::Integer(value),
+        InspectValue::Float(value) => Value::Number(value),
+        InspectValue::String(value) | InspectValue::Enum(value) => try!(value.to_lua(lua)),
+        InspectValue::Vector2(v) => try!(vector_table(lua, &[("x", v.x), ("y", v.y)])),
+        InspectValue::Vector3(v) => try!(vector_table(lua, &[("x", v.x), ("y", v.y), ("z", v.z)])),
+        InspectValue::Vector4(v) => try!(vector_table(lua, &[("x", v.x), ("y", v.y), ("z", v.z), ("w", v.w)])),
+        InspectValue::Point2(p) => try!(vector_table(lua, &[("x", p.x), ("y", p.y)])),
+        InspectValue::Point3(p) => try!(vector_table(lua, &[("x", p.x), ("y", p.y), ("z", p.z)])),
+        InspectValue::Color(c) => try!(vector_table(lua, &[("r", c.r), ("g", c.g), ("b", c.b), ("a", c.a)])),
+    })
+}
+
+fn table_to_inspect(table: Table, hint: Option<&InspectValue>) -> rlua::Result<InspectValue> {
+    let (has_r, has_w, has_z, has_x) = {
+        let has = |key: &str| table.contains_key(key).unwrap_or(false);
+
+        (has("r"), has("w"), has("z"), has("x"))
+    };
+
+    Ok(match hint {
+        Some(&InspectValue::Vector2(_)) => InspectValue::Vector2(Vector2::new(try!(table.get("x")), try!(table.get("y")))),
+        Some(&InspectValue::Point2(_)) => InspectValue::Point2(Point2::new(try!(table.get("x")), try!(table.get("y")))),
+        Some(&InspectValue::Point3(_)) => {
+            InspectValue::Point3(Point3::new(try!(table.get("x")), try!(table.get("y")), try!(table.get("z"))))
+        }
+        Some(&InspectValue::Color(_)) => {
+            let alpha: Option<f32> = try!(table.get("a"));
+
+            InspectValue::Color(Color::new(try!(table.get("r")), try!(table.get("g")), try!(table.get("b")), alpha.unwrap_or(1.0)))
+        }
+        // Without a hint, go by which keys the table has
+        _ if has_r => return table_to_inspect(table, Some(&InspectValue::Color(Color::new(0.0, 0.0, 0.0, 1.0)))),
+        _ if has_w => {
+            InspectValue::Vector4(Vector4::new(try!(table.get("x")), try!(table.get("y")), try!(table.get("z")), try!(table.get("w"))))
+        }
+        _ if has_z => InspectValue::Vector3(Vector3::new(try!(table.get("x")), try!(table.get("y")), try!(table.get("z")))),
+        _ if has_x => InspectValue::Vector2(Vector2::new(try!(table.get("x")), try!(table.get("y")))),
+        _ => return Err(rlua::Error::RuntimeError("table is not a vector, point or color".to_string())),
+    })
+}
+
+/// Convert a Lua value to a component value.
+///
+/// `hint` is the current value being replaced, if any, which decides between types Lua can't tell apart,
+/// like vectors and points.
+pub fn lua_to_inspect(value: Value, hint: Option<&InspectValue>) -> rlua::Result<InspectValue> {
+    Ok(match value {
+        Value::Nil => InspectValue::None,
+        Value::Boolean(value) => InspectValue::Bool(value),
+        Value::Integer(value) => match hint {
+            Some(&InspectValue::Float(_)) => InspectValue::Float(value as f64),
+            _ => InspectValue::Integer(value),
+        },
+        Value::Number(value) => match hint {
+            Some(&InspectValue::Integer(_)) if value.fract() == 0.0 => InspectValue::Integer(value as i64),
+            _ => InspectValue::Float(value),
+        },
+        Value::String(value) => {
+            let value = try!(value.to_str()).to_string();
+
+            match hint {
+                Some(&InspectValue::Enum(_)) => InspectValue::Enum(value),
+                _ => InspectValue::String(value),
+            }
+        }
+        Value::Table(table) => try!(table_to_inspect(table, hint)),
+        _ => return Err(rlua::Error::RuntimeError("value can't be stored in a component".to_string())),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{Vector2, Vector3, Vector4, Point2, Point3};
+
+    use rlua::{Lua, Value};
+
+    use common::color::Color;
+    use common::traits::inspect::InspectValue;
+
+    use ecs::World;
+
+    use super::*;
+
+    /// Convert to Lua and back, using the original value as the hint
+    fn round_trip(value: InspectValue) {
+        let lua = Lua::new();
+
+        let lua_value = inspect_to_lua(&lua, value.clone()).unwrap();
+
+        assert_eq!(lua_to_inspect(lua_value, Some(&value)).unwrap(), value);
+    }
+
+    /// Convert to Lua and back without a hint
+    fn unhinted(value: InspectValue) -> InspectValue {
+        let lua = Lua::new();
+
+        let lua_value = inspect_to_lua(&lua, value).unwrap();
+
+        lua_to_inspect(lua_value, None).unwrap()
+    }
+
+    #[test]
+    fn plain_values_round_trip() {
+        round_trip(InspectValue::None);
+        round_trip(InspectValue::Bool(true));
+        round_trip(InspectValue::Integer(-42));
+        round_trip(InspectValue::Float(0.25));
+        round_trip(InspectValue::String("hello".to_string()));
+        round_trip(InspectValue::Enum("Directional".to_string()));
+    }
+
+    #[test]
+    fn vectors_and_colors_round_trip() {
+        round_trip(InspectValue::Vector2(Vector2::new(1.0, 2.0)));
+        round_trip(InspectValue::Vector3(Vector3::new(1.0, 2.0, 3.0)));
+        round_trip(InspectValue::Vector4(Vector4::new(1.0, 2.0, 3.0, 4.0)));
+        round_trip(InspectValue::Point2(Point2::new(-1.0, 0.5)));
+        round_trip(InspectValue::Point3(Point3::new(-1.0, 0.5, 8.0)));
+        round_trip(InspectValue::Color(Color::new(1.0, 0.5, 0.25, 0.75)));
+    }
+
+    #[test]
+    fn tables_without_hints_go_by_their_keys() {
+        assert_eq!(unhinted(InspectValue::Vector2(Vector2::new(1.0, 2.0))), InspectValue::Vector2(Vector2::new(1.0, 2.0)));
+        assert_eq!(unhinted(InspectValue::Vector3(Vector3::new(1.0, 2.0, 3.0))), InspectValue::Vector3(Vector3::new(1.0, 2.0, 3.0)));
+        assert_eq!(unhinted(InspectValue::Vector4(Vector4::new(1.0, 2.0, 3.0, 4.0))), InspectValue::Vector4(Vector4::new(1.0, 2.0, 3.0, 4.0)));
+        assert_eq!(unhinted(InspectValue::Color(Color::new(1.0, 0.5, 0.25, 0.75))), InspectValue::Color(Color::new(1.0, 0.5, 0.25, 0.75)));
+
+        // Points look like vectors without a hint, and enum variants like strings
+        assert_eq!(unhinted(InspectValue::Point3(Point3::new(1.0, 2.0, 3.0))), InspectValue::Vector3(Vector3::new(1.0, 2.0, 3.0)));
+        assert_eq!(unhinted(InspectValue::Enum("Point".to_string())), InspectValue::String("Point".to_string()));
+    }
+
+    #[test]
+    fn numbers_follow_the_hint() {
+        let lua = Lua::new();
+
+        assert_eq!(lua_to_inspect(Value::Integer(2), Some(&InspectValue::Float(0.0))).unwrap(), InspectValue::Float(2.0));
+        assert_eq!(lua_to_inspect(Value::Number(2.0), Some(&InspectValue::Integer(0))).unwrap(), InspectValue::Integer(2));
+        assert_eq!(lua_to_inspect(Value::Number(2.5), Some(&InspectValue::Integer(0))).unwrap(), InspectValue::Float(2.5));
+
+        // Colors default to opaque
+        let color = lua.eval::<Value>("return {r = 1, g = 0, b = 0}", None).unwrap();
+
+        assert_eq!(lua_to_inspect(color, None).unwrap(), InspectValue::Color(Color::new(1.0, 0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn invalid_values_are_errors() {
+        let lua = Lua::new();
+
+        let function = lua.eval::<Value>("return function() end", None).unwrap();
+        let table = lua.eval::<Value>("return {1, 2, 3}", None).unwrap();
+
+        assert!(lua_to_inspect(function, None).is_err());
+        assert!(lua_to_inspect(table, None).is_err());
+    }
+
+    #[test]
+    fn entities_compare_by_identity() {
+        let mut world = World::new();
+
+        let first = world.create_now().build();
+        let second = world.create_now().build();
+
+        let lua = Lua::new();
+
+        let globals = lua.globals();
+
+        globals.set("a", LuaEntity(first)).unwrap();
+        globals.set("b", LuaEntity(first)).unwrap();
+        globals.set("c", LuaEntity(second)).unwrap();
+
+        assert!(lua.eval::<bool>("return a == b", None).unwrap());
+        assert!(!lua.eval::<bool>("return a == c", None).unwrap());
+        assert_eq!(lua.eval::<u32>("return c:id()", None).unwrap(), second.get_id());
+        assert_eq!(lua.eval::<String>("return tostring(a)", None).unwrap(), format!("Entity({})", first.get_id()));
+
+        let entity: LuaEntity = globals.get("c").unwrap();
+
+        assert_eq!(entity, LuaEntity(second));
+
+        // Entity methods need the world table
+        assert!(lua.exec::<()>("a:get('Health')", None).is_err());
+    }
+}
@@ -0,0 +1,54 @@
+#[macro_use]
+extern crate combustion_macros;
+
+extern crate combustion_ecs as ecs;
+extern crate combustion_scene as scene;
+
+use scene::prefab::{PrefabComponent, NodeComponent, Value};
+
+#[derive(Component, Debug, PartialEq)]
+#[ecs(prefab = "health")]
+pub struct Health {
+    current: i32,
+    max: i32,
+}
+
+#[derive(Component, Debug, PartialEq)]
+#[ecs(prefab)]
+pub struct Speed(f32);
+
+#[test]
+fn prefab_round_trip() {
+    let health = Health { current: 50, max: 100 };
+
+    let component = health.to_node_component();
+
+    assert_eq!(component.name, "health");
+    assert_eq!(component.get("current"), Some(&Value::Integer(50)));
+
+    assert_eq!(Health::from_node_component(&component), Some(health));
+
+    let speed = Speed(2.5);
+
+    assert_eq!(Speed::prefab_name(), "Speed");
+    assert_eq!(Speed::from_node_component(&speed.to_node_component()), Some(speed));
+}
+
+#[test]
+fn prefab_partial_apply() {
+    let mut component = NodeComponent::new("health");
+
+    component.properties.insert("current".to_string(), Value::Integer(10));
+
+    // Missing fields can't create a component, but can be applied to an existing one
+    assert_eq!(Health::from_node_component(&component), None);
+
+    let mut health = Health { current: 50, max: 100 };
+
+    assert!(health.apply_node_component(&component));
+    assert_eq!(health, Health { current: 10, max: 100 });
+
+    component.properties.insert("max".to_string(), Value::String("lots".to_string()));
+
+    assert!(!health.apply_node_component(&component));
+}
@@ -0,0 +1,87 @@
+#[macro_use]
+extern crate combustion_macros;
+#[macro_use]
+extern crate serde_derive;
+
+extern crate serde;
+extern crate serde_json;
+extern crate combustion_ecs as ecs;
+
+use serde::de::DeserializeSeed;
+
+use ecs::World;
+use ecs::serialize::{SerializeComponent, ComponentRegistry, stable_id};
+
+#[derive(Component, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[ecs(serialize = "health")]
+pub struct Health {
+    current: i32,
+    max: i32,
+}
+
+#[derive(Component, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[ecs(serialize)]
+pub struct Speed(f32);
+
+fn registry() -> ComponentRegistry {
+    let mut registry = ComponentRegistry::new();
+
+    registry.register::<Health>();
+    registry.register::<Speed>();
+
+    registry
+}
+
+#[test]
+fn stable_names() {
+    assert_eq!(Health::serialize_name(), "health");
+    assert_eq!(Speed::serialize_name(), "Speed");
+    assert_eq!(Health::serialize_id(), stable_id("health"));
+
+    let registry = registry();
+
+    assert_eq!(registry.name_of(stable_id("Speed")), Some("Speed"));
+    assert_eq!(registry.id_of("health"), Some(Health::serialize_id()));
+    assert!(!registry.is_registered("missing"));
+}
+
+#[test]
+fn world_round_trip() {
+    let registry = registry();
+
+    let mut world = World::new();
+
+    registry.register_storages(&mut world);
+
+    world.create_now().build();
+
+    let first = world.create_now().with(Health { current: 5, max: 10 }).with(Speed(1.5)).build();
+    let second = world.create_now().with(Speed(3.0)).build();
+
+    let json = serde_json::to_string(&registry.serializer(&world)).unwrap();
+
+    // Load into a world which already has entities, so the IDs have to be remapped
+    let mut loaded = World::new();
+
+    registry.register_storages(&mut loaded);
+
+    for _ in 0..5 {
+        loaded.create_now().build();
+    }
+
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+    let entities = registry.deserializer(&mut loaded).deserialize(value).unwrap();
+
+    assert_eq!(entities.len(), 3);
+
+    let loaded_first = entities[&(first.get_id() as u32)];
+    let loaded_second = entities[&(second.get_id() as u32)];
+
+    assert!(loaded_first != first);
+
+    assert_eq!(loaded.read::<Health>().get(loaded_first), Some(&Health { current: 5, max: 10 }));
+    assert_eq!(loaded.read::<Speed>().get(loaded_first), Some(&Speed(1.5)));
+    assert_eq!(loaded.read::<Speed>().get(loaded_second), Some(&Speed(3.0)));
+    assert_eq!(loaded.read::<Health>().get(loaded_second), None);
+}